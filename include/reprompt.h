@@ -0,0 +1,80 @@
+#ifndef REPROMPT_H
+#define REPROMPT_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A cleaner built once from the user's config, for hosts that clean often.
+// Opaque to C; use it from one thread at a time.
+typedef struct RepromptCleaner RepromptCleaner;
+
+// Options for `reprompt_clean` and `reprompt_cleaner_new`. A null pointer means all defaults.
+typedef struct RepromptOptions {
+  // Profile name (e.g. "claude-code"), or null to detect it from the text
+  const char *profile;
+  // Only keep cleaning that clearly improves the text, like `--conservative`
+  bool conservative;
+  // Skip user-defined `[[substitutions]]`
+  bool no_substitutions;
+} RepromptOptions;
+
+// Loads the user's config and builds a cleaner with `options`, to pass to
+// `reprompt_cleaner_clean` and release with `reprompt_cleaner_free`. Returns
+// null on failure (see `reprompt_last_error`). Config changes need a new one.
+//
+// # Safety
+//
+// `options` must be null or point to a valid `RepromptOptions`, whose
+// `profile` is null or NUL-terminated.
+struct RepromptCleaner *reprompt_cleaner_new(const struct RepromptOptions *options);
+
+// Cleans `text` with `cleaner`. Returns a new string to release with
+// `reprompt_free`, or null on failure (see `reprompt_last_error`).
+//
+// # Safety
+//
+// `cleaner` must come from `reprompt_cleaner_new` and not be freed yet.
+// `text` must be a NUL-terminated string.
+char *reprompt_cleaner_clean(const struct RepromptCleaner *cleaner, const char *text);
+
+// Releases a cleaner from `reprompt_cleaner_new`. Null is ignored.
+//
+// # Safety
+//
+// `cleaner` must be null or come from `reprompt_cleaner_new`, and not be freed twice.
+void reprompt_cleaner_free(struct RepromptCleaner *cleaner);
+
+// Cleans `text` with the user's config, as the `reprompt` command would.
+// Returns a new string to release with `reprompt_free`, or null on failure
+// (see `reprompt_last_error`). Loads the config on every call; to clean
+// more than once, keep a `reprompt_cleaner_new` handle instead.
+//
+// # Safety
+//
+// `text` must be a NUL-terminated string. `options` must be null or point to a
+// valid `RepromptOptions`, whose `profile` is null or NUL-terminated.
+char *reprompt_clean(const char *text, const struct RepromptOptions *options);
+
+// Releases a string returned by `reprompt_clean` or `reprompt_cleaner_clean`.
+// Null is ignored.
+//
+// # Safety
+//
+// `text` must be null or come from `reprompt_clean` or `reprompt_cleaner_clean`,
+// and not be freed twice.
+void reprompt_free(char *text);
+
+// Why the last `reprompt_*` call on this thread returned null, or null if none
+// failed. The string stays valid until the next failing call on this thread.
+const char *reprompt_last_error(void);
+
+// What kind of failure `reprompt_last_error` describes, e.g. "unknown_profile"
+// or "too_large", or null if none failed. Valid for as long as that message.
+const char *reprompt_last_error_kind(void);
+
+#endif  /* REPROMPT_H */
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use reprompt::cleaner::Cleaner;
+use reprompt::clipboard::{set_clipboard, with_retry};
+use reprompt::lock::InstanceLock;
+use reprompt::paths;
+
+
+/// Adds `text` to the buffer at `path`, after `separator` unless the buffer is
+/// empty. Returns the buffer's new size in bytes.
+fn push(path: &Path, text: &str, separator: &str) -> Result<u64> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    if file.metadata()?.len() > 0 {
+        file.write_all(separator.as_bytes())?;
+    }
+    file.write_all(text.as_bytes())?;
+    Ok(file.metadata()?.len())
+}
+
+/// Cleans the clipboard and adds it to the buffer, leaving the clipboard as it
+/// is. `separator` overrides `[append] separator`.
+pub fn append(cleaner: &Cleaner, separator: Option<&str>) -> Result<()> {
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    let report = crate::read_cleaned(cleaner)?;
+
+    let separator = separator.unwrap_or(&cleaner.config().append.separator);
+    let path = paths::append_file()?;
+    let size = push(&path, &report.output, separator)?;
+    tracing::info!("Appended {} bytes; {} bytes collected (`reprompt flush` to paste them)", report.output.len(), size);
+    Ok(())
+}
+
+/// Puts everything collected on the clipboard and empties the buffer.
+pub fn flush() -> Result<()> {
+    let path = paths::append_file()?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) if !text.is_empty() => text,
+        Ok(_) => anyhow::bail!("Nothing to flush; collect snippets with `reprompt append` first"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("Nothing to flush; collect snippets with `reprompt append` first")
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    with_retry(|| set_clipboard(&text)).context("Failed to write clipboard")?;
+    // Only empty the buffer once its text is safely on the clipboard
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    tracing::info!("Copied {} collected bytes to the clipboard", text.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_separates_snippets() {
+        let path = std::env::temp_dir().join(format!("reprompt-append-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        push(&path, "first", "\n---\n").unwrap();
+        let size = push(&path, "second", "\n---\n").unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(text, "first\n---\nsecond");
+        assert_eq!(size, text.len() as u64);
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use reprompt::clipboard::{set_clipboard, with_retry};
+use reprompt::config::{Backup, Encrypt};
+use reprompt::files::{remove_securely, write_private};
+use reprompt::lock::InstanceLock;
+use reprompt::paths;
+
+use crate::vault;
+
+static POLICY: RwLock<Option<Backup>> = RwLock::new(None);
+
+/// Sets the `[backup]` policy, replacing any earlier one (a config reload).
+pub fn configure(backup: Backup) {
+    *POLICY.write().unwrap_or_else(PoisonError::into_inner) = Some(backup);
+}
+
+fn policy() -> Backup {
+    POLICY.read().unwrap_or_else(PoisonError::into_inner).clone().unwrap_or_default()
+}
+
+/// Backup number `index` in `dir`; 0 is the newest. Encrypted ones end in `.age`.
+fn backup_path(dir: &Path, index: usize, encrypted: bool) -> PathBuf {
+    let name = match index {
+        0 => "last-original.txt".to_string(),
+        _ => format!("last-original.{}.txt", index),
+    };
+    if encrypted {
+        dir.join(format!("{}.age", name))
+    } else {
+        dir.join(name)
+    }
+}
+
+/// Every backup in `dir`, plain or encrypted, in no particular order.
+fn backups(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("last-original.")))
+        .filter(|path| !path.to_string_lossy().ends_with(".tmp"))
+        .collect()
+}
+
+fn remove(path: &Path, secure: bool) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    if secure {
+        Ok(remove_securely(path)?)
+    } else {
+        Ok(std::fs::remove_file(path)?)
+    }
+}
+
+/// Shifts existing backups up by one, dropping those beyond `keep`, then writes
+/// `text` as the newest. Returns its path, or `None` if the policy skips it.
+/// Encrypted backups replace any plain ones, which are deleted; when
+/// encryption fails nothing is written.
+fn save_in(dir: &Path, policy: &Backup, text: &str) -> Result<Option<PathBuf>> {
+    if !policy.enabled || policy.keep == 0 {
+        return Ok(None);
+    }
+    if text.len() > policy.max_mb.saturating_mul(1024 * 1024) {
+        tracing::warn!("Clipboard is over [backup] max_mb = {}; not backed up", policy.max_mb);
+        return Ok(None);
+    }
+    let encrypted = policy.encrypt != Encrypt::Off;
+    let content = if encrypted { vault::encrypt(text)? } else { text.to_string() };
+    if encrypted {
+        for path in backups(dir).iter().filter(|path| path.extension().is_some_and(|ext| ext == "txt")) {
+            remove(path, policy.secure_delete)?;
+        }
+    }
+    // Anything past the last kept slot is left over from a larger `keep`
+    let mut index = policy.keep - 1;
+    while backup_path(dir, index, encrypted).exists() {
+        remove(&backup_path(dir, index, encrypted), policy.secure_delete)?;
+        index += 1;
+    }
+    for index in (0..policy.keep - 1).rev() {
+        let from = backup_path(dir, index, encrypted);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(dir, index + 1, encrypted))?;
+        }
+    }
+    let path = backup_path(dir, 0, encrypted);
+    write_private(&path, &content)?;
+    Ok(Some(path))
+}
+
+/// Where the newest backup is written.
+pub fn latest_path() -> Result<PathBuf> {
+    Ok(backup_path(&paths::state_dir()?, 0, policy().encrypt != Encrypt::Off))
+}
+
+/// Backs up `text` (the clipboard about to be overwritten) in the state
+/// directory. Failures only warn: a backup must never block a cleanup.
+pub fn save(text: &str) -> Option<PathBuf> {
+    match paths::state_dir().map_err(Into::into).and_then(|dir| save_in(&dir, &policy(), text)) {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!("Could not back up the clipboard: {:#}", e);
+            None
+        }
+    }
+}
+
+/// `reprompt history restore`: puts backup `index` (0 is the newest) back on
+/// the clipboard, decrypting it if it's encrypted.
+pub fn restore(index: usize) -> Result<()> {
+    let dir = paths::state_dir()?;
+    let (encrypted, plain) = (backup_path(&dir, index, true), backup_path(&dir, index, false));
+    let text = if encrypted.exists() {
+        vault::decrypt(&encrypted)?
+    } else {
+        std::fs::read_to_string(&plain).with_context(|| format!("No backup {} in {}", index, dir.display()))?
+    };
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    with_retry(|| set_clipboard(&text)).context("Failed to write clipboard")?;
+    tracing::info!("Restored backup {} ({} bytes)", index, text.len());
+    Ok(())
+}
+
+/// `reprompt history purge`: deletes every stored copy of clipboard text —
+/// backups, slots and the `append` buffer — overwriting them first with
+/// `secure_delete`. The backup key is kept.
+pub fn purge() -> Result<()> {
+    let secure = policy().secure_delete;
+    let mut files = backups(&paths::state_dir()?);
+    if let Ok(entries) = std::fs::read_dir(paths::slots_dir()?) {
+        files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()));
+    }
+    files.push(paths::append_file()?);
+    let mut removed = 0;
+    for path in files.iter().filter(|path| path.exists()) {
+        remove(path, secure).with_context(|| format!("Failed to remove {}", path.display()))?;
+        removed += 1;
+    }
+    tracing::info!("Removed {} file(s) of clipboard history", removed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("reprompt-backup-{}", std::process::id()));
+        let policy = Backup { keep: 2, secure_delete: true, ..Backup::default() };
+        for text in ["one", "two", "three"] {
+            save_in(&dir, &policy, text).unwrap();
+        }
+        let newest = std::fs::read_to_string(backup_path(&dir, 0, false)).unwrap();
+        let older = std::fs::read_to_string(backup_path(&dir, 1, false)).unwrap();
+        let dropped = backup_path(&dir, 2, false).exists();
+        let found = backups(&dir).len();
+
+        let skipped = save_in(&dir, &Backup { max_mb: 0, ..policy }, "four").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((newest.as_str(), older.as_str(), dropped), ("three", "two", false));
+        assert_eq!((skipped, found), (None, 2));
+    }
+}
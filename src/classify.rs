@@ -0,0 +1,253 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{RepromptError, Result};
+
+/// Share of lines that must look like log records
+const LOG_THRESHOLD: f64 = 0.5;
+/// Share of lines that must split into columns
+const TABLE_THRESHOLD: f64 = 0.6;
+/// Share of lines that must look like source code
+const CODE_THRESHOLD: f64 = 0.4;
+/// Share of unprintable characters that marks text as binary data
+const BINARY_THRESHOLD: f64 = 0.1;
+/// Characters sampled from the start of the text by `is_binary`
+const BINARY_SAMPLE: usize = 8192;
+/// Lengths of a single token `looks_secret` checks for a password or API key
+const SECRET_LENGTH: std::ops::RangeInclusive<usize> = 8..=128;
+/// Bits of entropy per character from which a token looks randomly generated
+const SECRET_ENTROPY: f64 = 3.0;
+
+lazy_static! {
+    static ref RE_LOG_LINE: Regex = Regex::new(r"(?x)
+        ^\s*\[?
+        (?:
+            \d{4}-\d{2}-\d{2}[T\x20]\d{2}:\d{2}     # ISO date + time
+          | \d{2}:\d{2}:\d{2}                       # bare time
+          | (?:TRACE|DEBUG|INFO|WARN|WARNING|ERROR|FATAL|CRITICAL)\b
+        )
+    ").expect("Invalid Log Line Regex");
+
+    // Two or more gaps of 2+ spaces between words, as in aligned columns
+    static ref RE_ALIGNED_COLUMNS: Regex = Regex::new(r"\S\s{2,}\S.*?\s{2,}\S").expect("Invalid Aligned Columns Regex");
+
+    static ref RE_CODE_LINE: Regex = Regex::new(r"(?x)
+        [;{}]\s*$                                   # statement or block ending
+      | ^\s*(?:fn|pub|let|const|use|impl|struct|enum|def|class|import|from|return
+              |if|for|while|func|var|package|\#include|\#!)\b
+      | ^\s*(?://|\#\x20|/\*|\*\x20)                # comments
+      | ^\s*[\w.]+\s*(?:=|\+=|-=|:=)\s*\S           # assignment
+      | \)\s*(?::|=>|->.*)\s*$                      # signature ending
+    ").expect("Invalid Code Line Regex");
+
+    // Single tokens that are addresses rather than secrets: URLs, paths, emails
+    static ref RE_NOT_SECRET: Regex = Regex::new(r"^(?:[a-zA-Z][a-zA-Z0-9+.-]*://|[/~.]|[a-zA-Z]:\\|[\w.+-]+@[\w-]+\.)")
+        .expect("Invalid Not Secret Regex");
+}
+
+/// Broad kind of cleaned text, used by `--json` and `[templates]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Code,
+    Prose,
+    Table,
+    Log,
+    /// Binary data pasted as text; never cleaned
+    Binary,
+}
+
+impl std::fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ContentKind::Code => "code",
+            ContentKind::Prose => "prose",
+            ContentKind::Table => "table",
+            ContentKind::Log => "log",
+            ContentKind::Binary => "binary",
+        })
+    }
+}
+
+/// Labels text by the share of its non-blank lines that look like log records,
+/// table rows or code; anything else is prose.
+pub fn classify(text: &str) -> ContentKind {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return ContentKind::Prose;
+    }
+
+    let share = |predicate: fn(&str) -> bool| {
+        lines.iter().filter(|line| predicate(line)).count() as f64 / lines.len() as f64
+    };
+
+    if share(|line| RE_LOG_LINE.is_match(line)) >= LOG_THRESHOLD {
+        ContentKind::Log
+    } else if lines.len() >= 2 && share(is_table_row) >= TABLE_THRESHOLD {
+        ContentKind::Table
+    } else if share(|line| RE_CODE_LINE.is_match(line)) >= CODE_THRESHOLD {
+        ContentKind::Code
+    } else {
+        ContentKind::Prose
+    }
+}
+
+/// Whether `text` is binary data rather than text: NULs, other control
+/// characters and `U+FFFD` make up too much of its start. Tabs, newlines,
+/// escapes and backspaces are left out, since ANSI and man output use them.
+pub fn is_binary(text: &str) -> bool {
+    let mut sampled = 0usize;
+    let mut unprintable = 0usize;
+    for c in text.chars().take(BINARY_SAMPLE) {
+        sampled += 1;
+        if c == '\u{FFFD}' || (c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x1b' | '\x08' | '\x0c')) {
+            unprintable += 1;
+        }
+    }
+    sampled > 0 && unprintable as f64 / sampled as f64 > BINARY_THRESHOLD
+}
+
+/// Whether `text` looks like a password, API token or one-time code rather
+/// than output worth cleaning: a 6-8 digit code, or one token without spaces
+/// that mixes three kinds of character (lower, upper, digit, symbol) at high
+/// entropy. URLs, paths and email addresses don't count.
+pub fn looks_secret(text: &str) -> bool {
+    let token = text.trim();
+    if token.contains(|c: char| c.is_whitespace() || c.is_control()) || RE_NOT_SECRET.is_match(token) {
+        return false;
+    }
+    let length = token.chars().count();
+    if (6..=8).contains(&length) && token.bytes().all(|b| b.is_ascii_digit()) {
+        return true;
+    }
+    if !SECRET_LENGTH.contains(&length) {
+        return false;
+    }
+    let kinds = [
+        token.chars().any(char::is_lowercase),
+        token.chars().any(char::is_uppercase),
+        token.chars().any(|c| c.is_ascii_digit()),
+        token.chars().any(|c| !c.is_alphanumeric()),
+    ];
+    if kinds.iter().filter(|&&kind| kind).count() < 3 {
+        return false;
+    }
+    let mut counts = std::collections::HashMap::new();
+    for c in token.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    let entropy: f64 = counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length as f64;
+            -p * p.log2()
+        })
+        .sum();
+    entropy >= SECRET_ENTROPY
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.matches('|').count() >= 2 || line.contains('\t') || RE_ALIGNED_COLUMNS.is_match(line)
+}
+
+/// Per-kind wrappers for the cleaned text (`[templates]`); `{text}` marks where
+/// it goes and `{lang}` the guessed language, for fence tags.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Templates {
+    pub code: Option<String>,
+    pub prose: Option<String>,
+    pub table: Option<String>,
+    pub log: Option<String>,
+}
+
+impl Templates {
+    pub fn check(&self) -> Result<()> {
+        for template in [&self.code, &self.prose, &self.table, &self.log].into_iter().flatten() {
+            if !template.contains("{text}") {
+                return Err(RepromptError::InvalidSetting(format!("Template '{}' has no {{text}} placeholder", template)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Wraps `text` in the template for `kind`, if one is configured.
+    pub fn render(&self, kind: ContentKind, text: &str) -> String {
+        let template = match kind {
+            ContentKind::Code => &self.code,
+            ContentKind::Prose => &self.prose,
+            ContentKind::Table => &self.table,
+            ContentKind::Log => &self.log,
+            ContentKind::Binary => &None,
+        };
+        match template {
+            Some(template) if template.contains("{lang}") => {
+                let language = crate::language::detect(text).unwrap_or_default();
+                template.replace("{lang}", language).replace("{text}", text)
+            }
+            Some(template) => template.replace("{text}", text),
+            None => text.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("fn main() {\n    println!(\"hi\");\n}"), ContentKind::Code);
+        assert_eq!(classify("def f(x):\n    return x + 1"), ContentKind::Code);
+        assert_eq!(classify("| a | b |\n|---|---|\n| 1 | 2 |"), ContentKind::Table);
+        assert_eq!(classify("NAME     READY   STATUS\nweb-1    1/1     Running"), ContentKind::Table);
+        assert_eq!(
+            classify("2024-05-01 12:00:01 INFO started\n2024-05-01 12:00:02 WARN slow disk"),
+            ContentKind::Log
+        );
+        assert_eq!(
+            classify("The build failed because the feature flag was missing.\nAdding it fixes the error."),
+            ContentKind::Prose
+        );
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(is_binary("\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x02\0>\0\x01\0\0\0"));
+        assert!(is_binary("PNG\u{FFFD}\u{FFFD}\u{FFFD}\x1a\u{FFFD}IHDR"));
+        assert!(!is_binary("\x1b[1mbold\x1b[0m\tand N\x08NA\x08AM\x08ME\x08E"));
+        assert!(!is_binary(""));
+    }
+
+    #[test]
+    fn test_looks_secret() {
+        for secret in ["hunter2!Pass", "kX9#mQ2$vL7p", "sk-ant-api03-Xy7Qp2LmZk9W", "482913", "  12345678\n"] {
+            assert!(looks_secret(secret), "{}", secret);
+        }
+        for text in [
+            "12345",
+            "reprompt",
+            "snake_case_name",
+            "0123456789abcdef0123456789abcdef01234567",
+            "https://example.com/a?B=1",
+            "/usr/Local/bin2",
+            "C:\\Users\\Me2",
+            "Me.2@example.com",
+            "two Words!2",
+            "\x1b[1mBold1\x1b[0m",
+        ] {
+            assert!(!looks_secret(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_templates_by_kind() {
+        let templates = Templates { code: Some("```\n{text}\n```".into()), ..Templates::default() };
+        assert_eq!(templates.render(ContentKind::Code, "x = 1;"), "```\nx = 1;\n```");
+        assert_eq!(templates.render(ContentKind::Prose, "hello"), "hello");
+        let templates = Templates { code: Some("```{lang}\n{text}\n```".into()), ..Templates::default() };
+        assert_eq!(templates.render(ContentKind::Code, "def f():\n    return 1"), "```python\ndef f():\n    return 1\n```");
+        assert!(Templates { log: Some("no placeholder".into()), ..Templates::default() }.check().is_err());
+    }
+}
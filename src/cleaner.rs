@@ -0,0 +1,699 @@
+use std::borrow::Cow;
+use std::io::{BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::classify;
+use crate::config::{Config, LineEndings, Oversize};
+use crate::crop::{self, Crop, Window};
+use crate::detect;
+use crate::error::{RepromptError, Result};
+use crate::format::{self, OutputFormat};
+use crate::header::{self, Context, HeaderFormat};
+use crate::invariants;
+use crate::pipeline::{Pipeline, Stage, StageKind};
+use crate::profiles::{self, Profile};
+use crate::provenance;
+use crate::report::{Candidate, Level, Report, Selection};
+use crate::score::{Conservative, Fallback, Scorer};
+use crate::search;
+use crate::stages::{count_invisible, ImageMode, InvisibleCounts, Screen};
+use crate::tables::{self, TableFormat};
+use crate::timing::Timings;
+
+/// Variants kept at each branching stage unless `beam_width` is set
+const DEFAULT_BEAM_WIDTH: usize = 4;
+
+/// Inputs larger than this (in bytes) are searched a piece at a time, since
+/// every variant in the beam holds its own copy of the text
+const SEARCH_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Piece size for inputs over `SEARCH_LIMIT`, `oversize = "chunk"` and [`Cleaner::clean_stream`]
+const CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Whether cleaning changed the text. Line endings don't count: the
+/// clipboard may hand back LF for the CRLF written to it (WSL), and a clean
+/// that only converts them would otherwise never be "unchanged".
+fn differs(output: &str, input: &str) -> bool {
+    output != input && output.replace("\r\n", "\n") != input.replace("\r\n", "\n")
+}
+
+/// Config, known profiles and command-line overrides, resolved once per run.
+/// A pipeline is assembled per text so the profile can be detected from it.
+pub struct Cleaner {
+    config: Config,
+    profiles: Vec<Profile>,
+    /// Profile named with `--profile`; `None` enables detection
+    forced: Option<Profile>,
+    order: Vec<StageKind>,
+    /// Whether `order` came from `--stages`, which beats a profile's `stages`
+    order_given: bool,
+    /// Opt-in stages turned on by flags, added to a profile's `stages` too
+    enabled: Vec<StageKind>,
+    no_substitutions: bool,
+    scorer: Scorer,
+    conservative: Conservative,
+    crop: Crop,
+    /// Export the cleaned table in this format (`--tables`)
+    tables: Option<TableFormat>,
+    /// Trace each output line back to the input (`--json`)
+    line_map: bool,
+    /// Time each stage and report it (`--profile-stages`)
+    timed: bool,
+}
+
+impl Cleaner {
+    /// Stage order comes from `stages`, then the profile, then the config, then
+    /// the built-in default.
+    pub fn new(
+        config: Config,
+        profile: Option<&str>,
+        stages: Option<&[StageKind]>,
+        no_substitutions: bool,
+    ) -> Result<Self> {
+        let forced = profile.or(config.profile.as_deref()).map(profiles::find).transpose()?;
+        let mut order = stages
+            .or(config.stages.as_deref())
+            .unwrap_or(StageKind::DEFAULT_ORDER)
+            .to_vec();
+        let mut enabled = Vec::new();
+        if config.markdown {
+            StageKind::enable(&mut order, StageKind::Markdown);
+            enabled.push(StageKind::Markdown);
+        }
+        Ok(Self {
+            scorer: Scorer::new(config.scoring.clone()),
+            conservative: config.conservative.clone(),
+            config,
+            profiles: profiles::discover(),
+            forced,
+            order,
+            order_given: stages.is_some(),
+            enabled,
+            no_substitutions,
+            crop: Crop::default(),
+            tables: None,
+            line_map: false,
+            timed: false,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The profile every clean uses, or `None` when it's detected from the text.
+    pub fn forced_profile(&self) -> Option<&str> {
+        self.forced.as_ref().map(|profile| profile.name.as_str())
+    }
+
+    /// Turns on an opt-in stage (e.g. from a command-line flag).
+    pub fn enable(&mut self, kind: StageKind) {
+        StageKind::enable(&mut self.order, kind);
+        self.enabled.push(kind);
+    }
+
+    /// Overrides `line_endings` from the config (`--line-endings`).
+    pub fn set_line_endings(&mut self, line_endings: LineEndings) {
+        self.config.line_endings = line_endings;
+    }
+
+    /// Overrides `[indent] tab_width` from the config (`--expand-tabs N`).
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.config.indent.tab_width = width;
+    }
+
+    /// Overrides `screen` from the config (`--screen`).
+    pub fn set_screen(&mut self, screen: Screen) {
+        self.config.screen = screen;
+    }
+
+    /// Overrides `images` from the config (`--images`).
+    pub fn set_images(&mut self, mode: ImageMode) {
+        self.config.images = mode;
+    }
+
+    /// Overrides `header` from the config (`--header`).
+    pub fn set_header(&mut self, format: HeaderFormat) {
+        self.config.header = Some(format);
+    }
+
+    /// Keeps only the section of the cleaned text that `pattern` picks (`--select`).
+    pub fn set_select(&mut self, pattern: &str) {
+        self.crop.select = Some(crop::pattern(pattern));
+    }
+
+    /// Keeps only a window of the cleaned lines (`--head`, `--tail`, `--lines`).
+    pub fn set_window(&mut self, window: Window) {
+        self.crop.window = Some(window);
+    }
+
+    /// Turns the cleaned table into CSV, TSV or JSON (`--tables`).
+    pub fn set_tables(&mut self, format: TableFormat) {
+        self.tables = Some(format);
+    }
+
+    /// Adds a per-line provenance map to reports (`--json`).
+    pub fn set_line_map(&mut self) {
+        self.line_map = true;
+    }
+
+    /// Records how long each stage, detection and scoring take in reports (`--profile-stages`).
+    pub fn set_timed(&mut self) {
+        self.timed = true;
+    }
+
+    /// Whether reports carry timings.
+    pub fn timed(&self) -> bool {
+        self.timed
+    }
+
+    /// Overrides `format` from the config (`--format`).
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.config.format = format;
+    }
+
+    /// Protects Markdown structure while cleaning and makes unbalanced fences
+    /// fail validation (`--markdown`).
+    pub fn set_markdown(&mut self) {
+        self.config.markdown = true;
+        self.enable(StageKind::Markdown);
+    }
+
+    /// Only keep cleaned results that clearly beat the original (`--conservative`).
+    pub fn set_conservative(&mut self) {
+        self.conservative.enabled = true;
+    }
+
+    /// Cleaning pipeline for one profile.
+    pub fn pipeline(&self, profile: &Profile) -> Pipeline {
+        match profile.stages.as_ref().filter(|_| !self.order_given) {
+            Some(stages) => {
+                let mut order = stages.clone();
+                for kind in &self.enabled {
+                    StageKind::enable(&mut order, *kind);
+                }
+                self.pipeline_with(profile, &order)
+            }
+            None => self.pipeline_with(profile, &self.order),
+        }
+    }
+
+    fn pipeline_with(&self, profile: &Profile, order: &[StageKind]) -> Pipeline {
+        let mut options = self.config.stage_options(profile);
+        if self.no_substitutions {
+            options.substitutions.clear();
+        }
+        Pipeline::new(order, options)
+    }
+
+    /// Pipeline used when no profile is detected (or the forced one).
+    pub fn default_pipeline(&self) -> Pipeline {
+        self.pipeline(self.forced.as_ref().unwrap_or(&Profile::default()))
+    }
+
+    /// Largest input `clean` accepts, or `None` when oversized input is chunked.
+    pub fn max_input_bytes(&self) -> Option<usize> {
+        let limit = &self.config.input;
+        (limit.oversize == Oversize::Refuse).then(|| limit.max_bytes())
+    }
+
+    /// Fails when `input` is over `[input] max_mb` and oversized input is refused.
+    pub fn check_size(&self, input: &str) -> Result<()> {
+        match self.max_input_bytes() {
+            Some(limit) if input.len() > limit => Err(RepromptError::TooLarge { bytes: input.len(), limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Picks the profile for `input`, cleans it, wraps it in the template for
+    /// its content kind, and reports what happened.
+    pub fn clean(&self, input: &str) -> Report {
+        self.clean_as(input, self.forced.as_ref())
+    }
+
+    /// Like [`Cleaner::clean`], but with the profile named `profile` for this text only.
+    pub fn clean_with_profile(&self, input: &str, profile: &str) -> Result<Report> {
+        Ok(self.clean_as(input, Some(&profiles::find(profile)?)))
+    }
+
+    /// Like [`Cleaner::clean`] (with `profile` for this text only, when set),
+    /// but for long-running hosts: oversized input and unknown profiles are
+    /// errors, and a panic in any stage is caught and returned instead of
+    /// unwinding into the caller.
+    pub fn clean_checked(&self, input: &str, profile: Option<&str>) -> Result<Report> {
+        self.check_size(input)?;
+        let profile = profile.map(profiles::find).transpose()?;
+        panic::catch_unwind(AssertUnwindSafe(|| self.clean_as(input, profile.as_ref().or(self.forced.as_ref()))))
+            .map_err(|payload| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                RepromptError::Panicked(message)
+            })
+    }
+
+    /// Cleans text from `reader` into `writer` a line-aligned piece at a time,
+    /// so memory stays flat however long the input is. The profile is
+    /// detected from the first piece and each piece is searched on its own.
+    /// Writes only the cleaned text, with `line_endings` applied: no
+    /// template, header or table export.
+    pub fn clean_stream(&self, reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+        let newline = if self.config.line_endings.crlf() { "\r\n" } else { "\n" };
+        let width = self.config.beam_width.unwrap_or(DEFAULT_BEAM_WIDTH);
+        let generic = Profile::default();
+        let timings = Timings::default();
+        let mut pipeline = None;
+        // The last line with text and any blank lines after it: trailing whitespace is dropped at the end
+        let mut pending: Vec<String> = Vec::new();
+        let mut started = false;
+        for piece in search::pieces(reader, CHUNK_BYTES) {
+            let piece = piece?;
+            let pipeline = pipeline.get_or_insert_with(|| {
+                let detected = || {
+                    let detection = detect::detect(&piece, &self.profiles)?;
+                    self.profiles.iter().find(|profile| profile.name == detection.profile)
+                };
+                self.pipeline(self.forced.as_ref().or_else(detected).unwrap_or(&generic))
+            });
+            let best = search::search(pipeline, &piece, &self.scorer, width, &timings).into_iter().next();
+            for line in best.map(|variant| variant.lines).unwrap_or_default() {
+                if !line.text.trim().is_empty() {
+                    for text in pending.drain(..) {
+                        if std::mem::replace(&mut started, true) {
+                            writer.write_all(newline.as_bytes())?;
+                        }
+                        writer.write_all(text.as_bytes())?;
+                    }
+                }
+                pending.push(line.text);
+            }
+        }
+        let last = pending.join(newline);
+        if started && !last.trim_end().is_empty() {
+            writer.write_all(newline.as_bytes())?;
+        }
+        writer.write_all(last.trim_end().as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Re-cleans `input` at a weaker `level`, for when `full` (its full clean)
+    /// fails validation. Keeps the profile and detection from `full`.
+    pub fn clean_partial(&self, input: &str, full: &Report, level: Level) -> Report {
+        let stage = match level {
+            Level::Full => return full.clone(),
+            Level::Ansi => StageKind::Ansi,
+            Level::Mojibake => StageKind::Mojibake,
+        };
+        let pipeline = Pipeline::new(&[stage], Default::default());
+        let output = pipeline.run(input);
+        let changed = differs(&output, input);
+        let output = self.config.line_endings.apply(output);
+        let score = self.scorer.score(&output);
+        let removed = match input.len().saturating_sub(output.len()) {
+            0 => Vec::new(),
+            bytes => vec![(pipeline.stage_names()[0], bytes)],
+        };
+        Report {
+            profile: full.profile.clone(),
+            selection: full.selection,
+            detection: full.detection.clone(),
+            stages: pipeline.stage_names(),
+            kind: classify::classify(&output),
+            invisible: InvisibleCounts::default(),
+            removed,
+            margin: score.total - self.scorer.score(input).total,
+            score,
+            candidates: Vec::new(),
+            held_back: false,
+            level,
+            cropped: None,
+            lines_in: input.lines().count(),
+            lines_out: output.lines().count(),
+            changed,
+            committed: false,
+            output,
+            html: None,
+            line_map: None,
+            violations: Vec::new(),
+            timings: Vec::new(),
+            recovered: None,
+        }
+    }
+
+    fn clean_as(&self, input: &str, forced: Option<&Profile>) -> Report {
+        let generic = Profile::default();
+        if classify::is_binary(input) {
+            return Report::binary(&generic.name, input);
+        }
+        let timings = Timings::default();
+        let (profile, selection, detection) = match forced {
+            Some(profile) => (profile, Selection::Flag, None),
+            None => match timings.time("detection", || detect::detect(input, &self.profiles)) {
+                Some(detection) => {
+                    let profile = self
+                        .profiles
+                        .iter()
+                        .find(|profile| profile.name == detection.profile)
+                        .unwrap_or(&generic);
+                    (profile, Selection::Detected, Some(detection))
+                }
+                None => (&generic, Selection::Default, None),
+            },
+        };
+
+        tracing::debug!(profile = %profile.name, ?selection, "profile selected");
+
+        // Explore with/without the judgement-call stages and keep the best result
+        let pipeline = self.pipeline(profile);
+        let width = self.config.beam_width.unwrap_or(DEFAULT_BEAM_WIDTH);
+        let beam = if input.len() > SEARCH_LIMIT.min(self.config.input.max_bytes()) {
+            vec![search::search_chunked(&pipeline, input, &self.scorer, width, CHUNK_BYTES, &timings)]
+        } else {
+            search::search(&pipeline, input, &self.scorer, width, &timings)
+        };
+        let candidates = beam
+            .iter()
+            .map(|variant| Candidate { name: variant.name(), score: variant.score.total })
+            .collect();
+        let best = beam.into_iter().next().unwrap_or_else(|| {
+            tracing::warn!("The search kept no variant; leaving the text as it was");
+            search::Variant::new(input)
+        });
+        let stages: Vec<&'static str> = pipeline
+            .stage_names()
+            .into_iter()
+            .filter(|name| !best.skipped.contains(name))
+            .collect();
+        // Cleaning has to beat what --conservative would fall back to
+        let fallback = match self.conservative.fallback {
+            Fallback::None => Cow::Borrowed(input),
+            Fallback::Ansi => Cow::Owned(Pipeline::new(&[StageKind::Ansi], Default::default()).run(input)),
+        };
+        let margin = best.score.total - timings.time("scoring", || self.scorer.score(&fallback)).total;
+        let held_back = self.conservative.enabled && margin < self.conservative.threshold;
+        tracing::debug!(variant = %best.name(), score = best.score.total, margin, held_back, "variant chosen");
+        let cleaned = if held_back { fallback.into_owned() } else { Pipeline::finish(&best.lines) };
+        drop(best.lines);
+        let score = best.score;
+        let removed = if held_back { Vec::new() } else { best.removed };
+
+        // Invariant checks and the line map re-run the stages one at a time, so big inputs go without;
+        // external stages already ran once and aren't run again for them
+        let applied: Vec<&dyn Stage> = if held_back || input.len() > SEARCH_LIMIT {
+            Vec::new()
+        } else {
+            pipeline
+                .stages()
+                .iter()
+                .filter(|stage| stages.contains(&stage.name()) && !stage.external())
+                .map(|stage| stage.as_ref())
+                .collect()
+        };
+        let violations = if invariants::enabled() && input.len() <= SEARCH_LIMIT {
+            invariants::check(&applied, input, &cleaned)
+        } else {
+            Vec::new()
+        };
+        for violation in &violations {
+            tracing::warn!(%violation, "invariant violated");
+        }
+
+        // Verbatim lines keep theirs, so count what's gone from the output
+        let invisible = if stages.contains(&"invisible") {
+            count_invisible(input).minus(count_invisible(&cleaned))
+        } else {
+            InvisibleCounts::default()
+        };
+        let (cleaned, cropped) = match self.crop.apply(&cleaned) {
+            Some((text, kept)) => (text, Some(kept)),
+            None => (cleaned, None),
+        };
+        let table = self.tables.filter(|_| !held_back).and_then(|format| match tables::parse(&cleaned) {
+            Some(table) => Some(table.render(format)),
+            None => {
+                tracing::warn!("No table found for --tables; keeping the cleaned text");
+                None
+            }
+        });
+        let kind = classify::classify(&cleaned);
+        let format = if held_back || table.is_some() { OutputFormat::Plain } else { self.config.format };
+        let html = (format == OutputFormat::Html).then(|| format::html(kind, &cleaned));
+        // Exported data goes out as-is, without a template around it
+        let mut output = match table {
+            Some(table) => table,
+            None if held_back => cleaned,
+            None if format == OutputFormat::Markdown => format::markdown(kind, &cleaned),
+            None => self.config.templates.render(kind, &cleaned),
+        };
+        if let Some(format) = self.config.header.filter(|_| !held_back && !header::has_header(&output)) {
+            let source = (selection != Selection::Default).then_some(profile.name.as_str());
+            output.insert_str(0, &Context::gather(source).render(format));
+        }
+        let changed = differs(&output, input);
+        let output = self.config.line_endings.apply(output);
+        let line_map = (self.line_map && input.len() <= SEARCH_LIMIT).then(|| provenance::trace(&applied, input, &output));
+        let mut report = Report {
+            profile: profile.name.clone(),
+            selection,
+            detection,
+            stages,
+            kind,
+            invisible,
+            removed,
+            score,
+            candidates,
+            margin,
+            held_back,
+            level: Level::Full,
+            cropped,
+            lines_in: input.lines().count(),
+            lines_out: output.lines().count(),
+            changed,
+            committed: false,
+            output,
+            html,
+            line_map,
+            violations,
+            timings: if self.timed { timings.into_vec() } else { Vec::new() },
+            recovered: None,
+        };
+        if format == OutputFormat::Json {
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => {
+                    report.output = self.config.line_endings.apply(json);
+                    report.lines_out = report.output.lines().count();
+                    report.changed = true;
+                }
+                Err(e) => tracing::warn!("Failed to serialize report for --format json: {}", e),
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::ContentKind;
+
+    #[test]
+    fn test_profile_stages_unless_given() {
+        let profile = Profile::parse("name = \"t\"\nversion = \"1\"\nstages = [\"ansi\"]\n").unwrap();
+        let mut cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        cleaner.enable(StageKind::Trailing);
+        assert_eq!(cleaner.pipeline(&profile).stage_names(), ["ansi", "trailing"]);
+        let given = Cleaner::new(Config::default(), None, Some(&[StageKind::Border]), false).unwrap();
+        assert_eq!(given.pipeline(&profile).stage_names(), ["border"]);
+    }
+
+    #[test]
+    fn test_flag_overrides_detection() {
+        let text = "⏺ Bash(ls)\n  ⎿  a.txt\n\n⏺ Done.";
+
+        let auto = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let report = auto.clean(text);
+        assert_eq!(report.profile, "claude-code");
+        assert_eq!(report.selection, Selection::Detected);
+        assert_eq!(report.output, "Done.");
+
+        let forced = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let report = forced.clean(text);
+        assert_eq!(report.profile, "generic");
+        assert_eq!(report.selection, Selection::Flag);
+        assert!(report.detection.is_none());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let mut cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        assert_eq!(cleaner.clean("│ one │\r\n│ two │").output, "one\ntwo");
+        cleaner.set_line_endings(LineEndings::Crlf);
+        let report = cleaner.clean("│ one │\n│ two │");
+        assert_eq!(report.output, "one\r\ntwo");
+        assert_eq!(report.lines_out, 2);
+        assert!(!cleaner.clean("one\ntwo").changed, "Converting line endings alone is no change");
+    }
+
+    #[test]
+    fn test_arbitrary_text_never_fails() {
+        // Pieces the stages look for, mixed at random: a cheap fuzz of the whole pipeline
+        const PIECES: &[&str] = &[
+            "a", "Z", "9", " ", "  ", "\t", "\n", "\r\n", "\r", "\x1b[", "\x1b[1m", "\x1b]8;;", "\x07", "m", "│", "─", "╭", "╯",
+            "┼", "|", "+", "-", "=", "`", "```", "#", ">", "*", "Ã", "©", "â”", "€", "\u{200B}", "\u{FEFF}", "\u{FFFD}", "\u{0}",
+            "é", "e\u{301}", "😀", "\u{1F468}\u{200D}\u{1F469}", "…", "“", "[[verbatim]]", "⠋", "$ ", "diff --git", "@@",
+        ];
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        for _ in 0..1000 {
+            let length = next(120);
+            let text: String = (0..length).map(|_| PIECES[next(PIECES.len())]).collect();
+            if let Err(e) = cleaner.clean_checked(&text, None) {
+                panic!("{:?} failed to clean: {}", text, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verbatim_keeps_invisible_characters() {
+        let mut cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        cleaner.enable(StageKind::Invisible);
+        let report = cleaner.clean("a\u{200B}b\n```\nc\u{200B}d\n```");
+        assert_eq!(report.output, "ab\n```\nc\u{200B}d\n```");
+        assert_eq!(report.invisible.zero_width, 1);
+    }
+
+    #[test]
+    fn test_stream_matches_clean() {
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let input = "\n│ one │\n\n│ two │  \n  \n\n";
+        let mut streamed = Vec::new();
+        cleaner.clean_stream(input.as_bytes(), &mut streamed).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), cleaner.clean(input).output);
+    }
+
+    #[test]
+    fn test_partial_levels() {
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let input = "\x1b[1m│ The cafÃ© is open │\x1b[0m";
+        let full = cleaner.clean(input);
+        let ansi = cleaner.clean_partial(input, &full, Level::Ansi);
+        assert_eq!((ansi.output.as_str(), ansi.level), ("│ The cafÃ© is open │", Level::Ansi));
+        assert_eq!(ansi.profile, "generic");
+
+        let input = "The cafÃ© is open";
+        let mojibake = cleaner.clean_partial(input, &cleaner.clean(input), Level::Mojibake);
+        assert_eq!(mojibake.output, "The café is open");
+        assert!(mojibake.explain().contains("level: mojibake repair only"));
+    }
+
+    #[test]
+    fn test_best_scoring_variant_wins() {
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let report = cleaner.clean("The cafÃ© is open");
+        assert_eq!(report.output, "The café is open");
+        let names: Vec<&str> = report.candidates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["all stages", "without mojibake"]);
+
+        // Weights come from `[scoring]`: rewarding mojibake pairs flips the choice
+        let config = Config::parse("beam_width = 1\n[scoring]\nmojibake = 5.0\n").unwrap();
+        let cleaner = Cleaner::new(config, Some("generic"), None, false).unwrap();
+        let report = cleaner.clean("The cafÃ© is open");
+        assert_eq!(report.output, "The cafÃ© is open");
+        assert_eq!(report.candidates.len(), 1);
+        assert!(!report.stages.contains(&"mojibake"));
+    }
+
+    #[test]
+    fn test_conservative_holds_back_small_gains() {
+        let config = Config::parse("[conservative]\nfallback = \"ansi\"\n").unwrap();
+        let mut cleaner = Cleaner::new(config, Some("generic"), None, false).unwrap();
+        let text = "\x1b[1ma long sentence with words\x1b[0m │ and more";
+        assert_eq!(cleaner.clean(text).output, "a long sentence with words   and more");
+
+        cleaner.set_conservative();
+        let report = cleaner.clean(text);
+        assert!(report.held_back && report.margin < 0.1, "margin {}", report.margin);
+        assert_eq!(report.output, "a long sentence with words │ and more");
+
+        // A box is a clear improvement and still gets cleaned
+        let report = cleaner.clean("╭──────────╮\n│ See this │\n╰──────────╯");
+        assert!(!report.held_back);
+        assert_eq!(report.output, "See this");
+    }
+
+    #[test]
+    fn test_binary_is_left_alone() {
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let binary = "\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x02\0>\0\x01│";
+        let report = cleaner.clean(binary);
+        assert_eq!(report.kind, ContentKind::Binary);
+        assert!(!report.changed && report.stages.is_empty());
+        assert_eq!(report.output, binary);
+    }
+
+    #[test]
+    fn test_clean_checked_survives_adversarial_input() {
+        let mut cleaner = Cleaner::new(Config::parse("[input]\nmax_mb = 1\n").unwrap(), None, None, false).unwrap();
+        for kind in [StageKind::Logprefix, StageKind::Repeats, StageKind::Quotes, StageKind::Markdown, StageKind::Grid, StageKind::Indent] {
+            cleaner.enable(kind);
+        }
+        cleaner.set_line_map();
+        let inputs = [
+            "\x1b", "\x1b[", "\x1b]8;;", "\u{9b}\u{9d}\u{90}", "é\x1b[3", "日本\x1b[31", "````\n```", "> ```\n>", "|\n|---|\n|",
+            "\t\t \t│", "\u{301}\u{200b}\u{feff}", "\r\r\n\r", "╭\n│\n", "+--+\n|é|\n+", ">>>>>\n> >",
+        ];
+        for input in inputs {
+            assert!(cleaner.clean_checked(input, None).is_ok(), "{input:?}");
+        }
+        let huge = "x".repeat(2 * 1024 * 1024);
+        assert!(matches!(cleaner.clean_checked(&huge, None), Err(RepromptError::TooLarge { .. })));
+        assert!(matches!(cleaner.clean_checked("text", Some("nope")), Err(RepromptError::UnknownProfile(name)) if name == "nope"));
+    }
+
+    #[test]
+    fn test_timed_reports() {
+        let mut cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        assert!(cleaner.clean("│ hello │").timings.is_empty());
+        cleaner.set_timed();
+        let report = cleaner.clean("\x1b[1m│ hello │\x1b[0m");
+        let names: Vec<&str> = report.timings.iter().map(|timing| timing.name).collect();
+        for name in ["detection", "ansi", "border", "scoring"] {
+            assert!(names.contains(&name), "{name} missing from {names:?}");
+        }
+    }
+
+    #[test]
+    fn test_oversized_input() {
+        let text = "│ some text │\n".repeat(100_000);
+        let cleaner = Cleaner::new(Config::parse("[input]\nmax_mb = 1\n").unwrap(), None, None, false).unwrap();
+        assert!(cleaner.check_size(&text).is_err());
+        assert!(cleaner.check_size("│ some text │").is_ok());
+
+        let config = Config::parse("[input]\nmax_mb = 1\noversize = \"chunk\"\n").unwrap();
+        let cleaner = Cleaner::new(config, Some("generic"), None, false).unwrap();
+        assert!(cleaner.check_size(&text).is_ok());
+        let report = cleaner.clean(&text);
+        assert_eq!(report.lines_out, 100_000);
+        assert!(report.output.lines().all(|line| line == "some text"));
+    }
+
+    #[test]
+    fn test_template_follows_content_kind() {
+        let config = Config::parse("[templates]\ncode = \"```\\n{text}\\n```\"\n").unwrap();
+        let cleaner = Cleaner::new(config, Some("generic"), None, false).unwrap();
+
+        let report = cleaner.clean("│ let x = 1; │");
+        assert_eq!(report.kind, ContentKind::Code);
+        assert_eq!(report.output, "```\nlet x = 1;\n```");
+        assert_eq!(cleaner.clean("Just a sentence.").output, "Just a sentence.");
+    }
+}
@@ -0,0 +1,389 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+use std::fmt::Display;
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+
+use crate::error::Result;
+
+// The system clipboard itself is behind the `clipboard` feature; the
+// `[retry]`/`verify` policies are plain config types and always available.
+#[cfg(feature = "clipboard")]
+use {
+    crate::error::RepromptError,
+    crate::process::run_with_timeout,
+    base64::prelude::*,
+    std::io::Write,
+    std::process::{Command, Output, Stdio},
+};
+
+/// How long a `powershell.exe` clipboard call may take unless configured
+pub const DEFAULT_POWERSHELL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "clipboard")]
+/// Raw bytes per Base64 line across the WSL bridge. A multiple of 3, so each
+/// line decodes on its own and no single write or argument gets huge.
+pub const FRAME_BYTES: usize = 48 * 1024;
+
+// Settings from the config; a long-running command sets them again on reload
+static POWERSHELL_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+static RETRY: RwLock<Option<Retry>> = RwLock::new(None);
+static VERIFY: RwLock<Option<Verify>> = RwLock::new(None);
+
+fn setting<T: Copy>(lock: &RwLock<Option<T>>) -> Option<T> {
+    *lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn set_setting<T>(lock: &RwLock<Option<T>>, value: Option<T>) {
+    *lock.write().unwrap_or_else(PoisonError::into_inner) = value;
+}
+
+/// How a commit checks the clipboard read back after writing (`verify`).
+/// CRLF versus LF never counts as a mismatch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verify {
+    /// Identical apart from trailing whitespace at the very end
+    #[default]
+    Exact,
+    /// Same words; any whitespace differences are ignored
+    Whitespace,
+    /// Same SHA-256 after NFC normalization and trimming every line's end
+    Hash,
+    /// Don't read back at all
+    Off,
+}
+
+impl Verify {
+    /// Whether `actual` counts as the `expected` text under this mode.
+    pub fn matches(&self, expected: &str, actual: &str) -> bool {
+        match self {
+            Verify::Exact => {
+                expected.replace("\r\n", "\n").trim_end() == actual.replace("\r\n", "\n").trim_end()
+            }
+            Verify::Whitespace => expected.split_whitespace().eq(actual.split_whitespace()),
+            Verify::Hash => normalized_hash(expected) == normalized_hash(actual),
+            Verify::Off => true,
+        }
+    }
+}
+
+fn normalized_hash(text: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for line in text.trim_end().lines() {
+        hasher.update(line.trim_end().nfc().collect::<String>().as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finalize().into()
+}
+
+/// Sets the readback `verify` mode used by commits, replacing any earlier one.
+pub fn set_verify(verify: Verify) {
+    set_setting(&VERIFY, Some(verify));
+}
+
+/// The configured readback `verify` mode.
+pub fn verify_mode() -> Verify {
+    setting(&VERIFY).unwrap_or_default()
+}
+
+/// `[retry]`: how clipboard reads and writes are retried while another app
+/// (a clipboard manager, RDP) briefly holds the clipboard. The delay doubles
+/// after every failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retry {
+    /// Tries in total, including the first
+    pub attempts: u32,
+    pub delay_ms: u64,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self { attempts: 3, delay_ms: 50 }
+    }
+}
+
+impl Retry {
+    /// Runs `op` until it succeeds or the attempts run out, returning the last error.
+    pub fn run<T, E: Display>(&self, mut op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        let mut delay = Duration::from_millis(self.delay_ms);
+        for _ in 1..self.attempts {
+            match op() {
+                Ok(value) => return Ok(value),
+                Err(e) => tracing::warn!("{e}; retrying in {}ms", delay.as_millis()),
+            }
+            std::thread::sleep(delay);
+            delay = delay.saturating_mul(2);
+        }
+        op()
+    }
+}
+
+/// Sets the `[retry]` policy used by `with_retry`, replacing any earlier one.
+pub fn set_retry(retry: Retry) {
+    set_setting(&RETRY, Some(retry));
+}
+
+/// Runs a clipboard operation under the configured `[retry]` policy.
+pub fn with_retry<T, E: Display>(op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    setting(&RETRY).unwrap_or_default().run(op)
+}
+
+/// Sets the `powershell.exe` timeout (`powershell_timeout_ms`), replacing any
+/// earlier one; `None` goes back to `DEFAULT_POWERSHELL_TIMEOUT`.
+pub fn set_powershell_timeout(timeout: Option<Duration>) {
+    set_setting(&POWERSHELL_TIMEOUT, timeout);
+}
+
+#[cfg(feature = "clipboard")]
+/// Checks if the program is running inside WSL.
+pub fn is_wsl_custom() -> bool {
+    is_wsl::is_wsl()
+}
+
+#[cfg(feature = "clipboard")]
+/// Which clipboard `get_clipboard` and `set_clipboard` talk to here.
+pub fn backend() -> &'static str {
+    if is_wsl_custom() {
+        "powershell.exe (WSL)"
+    } else if cfg!(target_os = "macos") {
+        "NSPasteboard"
+    } else if cfg!(windows) {
+        "Win32 clipboard"
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        "Wayland"
+    } else {
+        "X11"
+    }
+}
+
+#[cfg(feature = "clipboard")]
+/// Base64-encodes `data` as newline-separated frames of `FRAME_BYTES` each.
+fn encode_frames(data: &[u8]) -> String {
+    let mut frames = String::with_capacity(data.len() / 3 * 4 + data.len() / FRAME_BYTES + 8);
+    for chunk in data.chunks(FRAME_BYTES) {
+        BASE64_STANDARD.encode_string(chunk, &mut frames);
+        frames.push('\n');
+    }
+    frames
+}
+
+#[cfg(feature = "clipboard")]
+/// Decodes frames written by `encode_frames` (or the PowerShell reader) back into bytes.
+pub fn decode_frames(frames: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(frames.len() / 4 * 3);
+    for frame in frames.lines().map(str::trim).filter(|frame| !frame.is_empty()) {
+        BASE64_STANDARD
+            .decode_vec(frame, &mut data)
+            .map_err(|e| RepromptError::InteropProtocol(format!("Failed to decode Base64 from PowerShell: {}", e)))?;
+    }
+    Ok(data)
+}
+
+#[cfg(feature = "clipboard")]
+/// Runs a PowerShell script with `input` on stdin, killing it if it hangs
+/// (profile scripts, antivirus scans). `None` means `powershell.exe` is missing.
+pub fn run_powershell(script: &str, input: &[u8]) -> Result<Option<Output>> {
+    let timeout = setting(&POWERSHELL_TIMEOUT).unwrap_or(DEFAULT_POWERSHELL_TIMEOUT);
+    let mut command = Command::new("powershell.exe");
+    command.args(["-NoProfile", "-Command", script]);
+    match run_with_timeout(&mut command, input, timeout) {
+        Ok(output) => Ok(Some(output)),
+        Err(RepromptError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(RepromptError::InteropUnavailable(format!(
+            "powershell.exe failed: {e} (a hang usually means a slow profile or antivirus scan; \
+             raise powershell_timeout_ms in config.toml if it is just slow)"
+        ))),
+    }
+}
+
+#[cfg(feature = "clipboard")]
+/// How long `powershell.exe` takes to start and exit; `None` if it's missing.
+pub fn powershell_latency() -> Result<Option<Duration>> {
+    let started = std::time::Instant::now();
+    Ok(run_powershell("exit 0", b"")?.map(|_| started.elapsed()))
+}
+
+#[cfg(feature = "clipboard")]
+fn write_error(e: impl Display) -> RepromptError {
+    RepromptError::ClipboardWrite(e.to_string())
+}
+
+#[cfg(feature = "clipboard")]
+fn read_native() -> Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| RepromptError::ClipboardRead(e.to_string()))
+}
+
+#[cfg(feature = "clipboard")]
+fn write_native(data: &str) -> Result<()> {
+    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(data)).map_err(write_error)
+}
+
+#[cfg(feature = "clipboard")]
+/// Reads text from the system clipboard with proper encoding handling.
+/// Handles Native (arboard) and WSL (powershell) environments.
+pub fn get_clipboard() -> Result<String> {
+    if is_wsl_custom() {
+        // Try PowerShell first (WSL interop) with explicit UTF-8 encoding via Base64 transfer
+        // This avoids all code page issues by transferring ASCII Base64 over the pipe.
+        // Frames of FRAME_BYTES come back one per line
+        let script = format!(
+            "$bytes = [System.Text.Encoding]::UTF8.GetBytes(($OFS=\"`n\"; \"$(Get-Clipboard)\")); \
+             for ($i = 0; $i -lt $bytes.Length; $i += {FRAME_BYTES}) {{ \
+             [Console]::Out.WriteLine([Convert]::ToBase64String($bytes, $i, [Math]::Min({FRAME_BYTES}, $bytes.Length - $i))) }}"
+        );
+        match run_powershell(&script, b"")? {
+            Some(output) if output.status.success() => {
+                // Decode the Base64 frames
+                let decoded_bytes = decode_frames(&String::from_utf8_lossy(&output.stdout))?;
+
+                let text = String::from_utf8(decoded_bytes)
+                    .map_err(|e| RepromptError::InteropProtocol(format!("Decoded Base64 is not valid UTF-8: {}", e)))?;
+
+                // Normalize line endings from CRLF to LF
+                let normalized = text.replace("\r\n", "\n");
+
+                // Trim trailing whitespace that PowerShell often adds
+                let trimmed = normalized.trim_end().to_string();
+
+                Ok(trimmed)
+            }
+            Some(output) => {
+                // PowerShell ran but failed
+                Err(RepromptError::ClipboardRead(format!(
+                    "PowerShell Get-Clipboard failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )))
+            }
+            None => {
+                // powershell.exe not found - WSL interop likely disabled
+                // Fall back to arboard
+                tracing::warn!(
+                    "WSL detected but powershell.exe not found. Windows interop may be disabled; \
+                     falling back to native clipboard. To fix: check /etc/wsl.conf has [interop] enabled=true"
+                );
+                read_native()
+            }
+        }
+    } else {
+        read_native()
+    }
+}
+
+#[cfg(feature = "clipboard")]
+/// Writes text to the system clipboard with proper encoding handling.
+/// Handles Native (arboard) and WSL (clip.exe) environments.
+pub fn set_clipboard(data: &str) -> Result<()> {
+    if is_wsl_custom() {
+        // Use PowerShell with Base64 transfer for reliable encoding, streamed
+        // in frames that are decoded and reassembled on the Windows side
+        let frames = encode_frames(data.as_bytes());
+        match run_powershell(
+            "$ms = New-Object System.IO.MemoryStream; \
+             foreach ($frame in $input) { if ($frame) { $b = [System.Convert]::FromBase64String($frame.Trim()); $ms.Write($b, 0, $b.Length) } }; \
+             if ($ms.Length -gt 0) { [System.Text.Encoding]::UTF8.GetString($ms.ToArray()) | Set-Clipboard }",
+            frames.as_bytes(),
+        )? {
+            Some(output) => {
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(RepromptError::ClipboardWrite(format!("PowerShell Set-Clipboard failed: {}", stderr)));
+                }
+
+                Ok(())
+            }
+            None => {
+                // powershell.exe not found - fallback logic
+                // Try clip.exe (legacy, unreliable for utf-8 but better than nothing)
+                if data.is_ascii() {
+                    tracing::warn!("powershell.exe not found, trying clip.exe...");
+                    if let Ok(mut child) = Command::new("clip.exe").stdin(Stdio::piped()).spawn() {
+                        let mut stdin = child.stdin.take().unwrap();
+                        stdin.write_all(data.as_bytes()).map_err(write_error)?;
+                        drop(stdin);
+                        let status = child.wait().map_err(write_error)?;
+                        if !status.success() { return Err(RepromptError::ClipboardWrite("clip.exe failed".to_string())); }
+                        return Ok(());
+                    }
+                }
+
+                // Fall back to native clipboard (arboard)
+                tracing::warn!("WSL detected but Windows interop not available.");
+                write_native(data)
+            }
+        }
+    } else {
+        write_native(data)
+    }
+}
+
+#[cfg(feature = "clipboard")]
+/// Writes `html` as the clipboard's rich-text flavor with `text` as the plain
+/// one, for paste targets such as docs and chat apps. Under WSL only the plain
+/// text is written.
+pub fn set_clipboard_html(html: &str, text: &str) -> Result<()> {
+    if is_wsl_custom() {
+        tracing::warn!("HTML clipboard content isn't supported under WSL; writing plain text");
+        return set_clipboard(text);
+    }
+    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_html(html, Some(text))).map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RepromptError;
+
+    #[test]
+    fn test_retry_until_success() {
+        let retry = Retry { attempts: 3, delay_ms: 1 };
+        let mut calls = 0;
+        let result = retry.run(|| {
+            calls += 1;
+            if calls < 3 { Err("clipboard locked") } else { Ok(calls) }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut calls = 0;
+        let result: Result<()> = Retry { attempts: 2, delay_ms: 1 }.run(|| {
+            calls += 1;
+            Err(RepromptError::ClipboardRead(format!("locked {calls}")))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Failed to read clipboard: locked 2");
+    }
+
+    #[test]
+    fn test_verify_modes() {
+        let expected = "caf\u{e9}  au lait\nsecond line";
+        let crlf = "caf\u{e9}  au lait\r\nsecond line\r\n";
+        for mode in [Verify::Exact, Verify::Whitespace, Verify::Hash, Verify::Off] {
+            assert!(mode.matches(expected, crlf), "{:?}", mode);
+        }
+
+        let reflowed = "caf\u{e9} au lait second line";
+        assert!(!Verify::Exact.matches(expected, reflowed));
+        assert!(Verify::Whitespace.matches(expected, reflowed));
+
+        let decomposed = "cafe\u{301}  au lait   \nsecond line";
+        assert!(!Verify::Exact.matches(expected, decomposed));
+        assert!(Verify::Hash.matches(expected, decomposed));
+        assert!(!Verify::Hash.matches(expected, "something else"));
+    }
+
+    #[test]
+    #[cfg(feature = "clipboard")]
+    fn test_frames_round_trip() {
+        let data = "naïve café ─ ".repeat(20_000);
+        let frames = encode_frames(data.as_bytes());
+        assert!(frames.lines().count() > 1);
+        assert!(frames.lines().all(|frame| frame.len() <= FRAME_BYTES / 3 * 4));
+        assert_eq!(decode_frames(&frames).unwrap(), data.as_bytes());
+        // PowerShell writes CRLF line endings
+        assert_eq!(decode_frames(&frames.replace('\n', "\r\n")).unwrap(), data.as_bytes());
+        assert!(decode_frames("").unwrap().is_empty());
+    }
+}
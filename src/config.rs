@@ -0,0 +1,545 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::classify::Templates;
+use crate::format::OutputFormat;
+use crate::header::HeaderFormat;
+use crate::clipboard::{Retry, Verify};
+use crate::error::{RepromptError, Result};
+use crate::ocr::OcrSpec;
+use crate::paths;
+use crate::pipeline::{StageKind, StageOptions};
+use crate::profiles::Profile;
+use crate::rules::Rule;
+use crate::score::{Conservative, Weights};
+use crate::stages::{ExecSpec, GlyphSpec, ImageMode, IndentSpec, Screen, Substitution, VerbatimRegion};
+use crate::validate::Policy;
+
+/// A regex compiled while the config is parsed, so bad patterns fail at load time.
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Regex::new(&source).map(Pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// What to do with a clipboard over `[input] max_mb`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Oversize {
+    /// Leave the clipboard alone and report an error
+    #[default]
+    Refuse,
+    /// Clean it in line-aligned pieces, each searched on its own
+    Chunk,
+}
+
+/// Line endings of the cleaned text (`line_endings`, `--line-endings`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    #[default]
+    Lf,
+    Crlf,
+    /// CRLF on Windows and under WSL (where the clipboard belongs to Windows), LF elsewhere
+    Native,
+}
+
+impl LineEndings {
+    /// Whether this style ends lines with CRLF here.
+    pub fn crlf(self) -> bool {
+        match self {
+            LineEndings::Lf => false,
+            LineEndings::Crlf => true,
+            #[cfg(feature = "clipboard")]
+            LineEndings::Native => cfg!(windows) || crate::clipboard::is_wsl_custom(),
+            #[cfg(not(feature = "clipboard"))]
+            LineEndings::Native => cfg!(windows),
+        }
+    }
+
+    /// Rewrites every line ending in `text` (LF or CRLF) to this style.
+    pub fn apply(self, text: String) -> String {
+        let crlf = self.crlf();
+        if !text.contains('\r') && !crlf {
+            return text;
+        }
+        let lf = text.replace("\r\n", "\n");
+        if crlf {
+            lf.replace('\n', "\r\n")
+        } else {
+            lf
+        }
+    }
+}
+
+/// `[input]` limits that keep a pathological clipboard from hanging a run,
+/// and how to read a damaged one.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputLimit {
+    pub max_mb: usize,
+    pub oversize: Oversize,
+    /// Rebuild the text from the clipboard's RTF flavor when the plain text lost characters to a code page
+    pub rtf: bool,
+}
+
+impl Default for InputLimit {
+    fn default() -> Self {
+        Self { max_mb: 20, oversize: Oversize::Refuse, rtf: true }
+    }
+}
+
+impl InputLimit {
+    pub fn max_bytes(&self) -> usize {
+        self.max_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// `[append]` settings for `reprompt append`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Append {
+    /// Put between snippets in the accumulation buffer
+    pub separator: String,
+}
+
+impl Default for Append {
+    fn default() -> Self {
+        Self { separator: "\n\n---\n\n".to_string() }
+    }
+}
+
+/// `[watch]`: which copies `reprompt watch` cleans, by the window focused when
+/// they were made and by their content, and how often. Window patterns match
+/// the application (X11 class, Wayland app id, executable or app name) or the
+/// window title.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchScope {
+    /// Only clean copies made in a matching window (empty: any window)
+    pub only: Vec<Pattern>,
+    /// Never clean copies made in a matching window
+    pub skip: Vec<Pattern>,
+    /// Never clean copies whose text matches, e.g. `^-----BEGIN PGP`
+    pub ignore: Vec<Pattern>,
+    /// Changes past this many clipboard writes in a minute are left alone (0: no limit)
+    pub max_per_minute: usize,
+    /// Seconds to leave the clipboard alone after a clean fails validation
+    pub cooldown_secs: u64,
+}
+
+impl Default for WatchScope {
+    fn default() -> Self {
+        Self { only: Vec::new(), skip: Vec::new(), ignore: Vec::new(), max_per_minute: 60, cooldown_secs: 10 }
+    }
+}
+
+impl WatchScope {
+    /// Whether any window is excluded at all, i.e. the focus is worth looking up.
+    pub fn is_set(&self) -> bool {
+        !self.only.is_empty() || !self.skip.is_empty()
+    }
+
+    /// Whether `text` matches an `ignore` pattern.
+    pub fn ignores(&self, text: &str) -> bool {
+        self.ignore.iter().any(|pattern| pattern.0.is_match(text))
+    }
+
+    /// Whether a copy made in `app`'s window titled `title` may be cleaned.
+    pub fn allows(&self, app: &str, title: &str) -> bool {
+        let matches = |patterns: &[Pattern]| patterns.iter().any(|pattern| pattern.0.is_match(app) || pattern.0.is_match(title));
+        (self.only.is_empty() || matches(&self.only)) && !matches(&self.skip)
+    }
+}
+
+/// How backups are stored (`[backup] encrypt`). Encrypted ones are written
+/// with `age` to a key made by `reprompt history key`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encrypt {
+    /// Plain text, readable by the owner only
+    #[default]
+    Off,
+    /// The key's secret half is kept in the OS keychain
+    Keychain,
+    /// The key's secret half is kept in a file locked with a passphrase
+    Passphrase,
+}
+
+/// `[backup]`: copies of the original clipboard written before each cleanup.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Backup {
+    pub enabled: bool,
+    /// Backups kept, newest first (`last-original.txt`, `last-original.1.txt`, …)
+    pub keep: usize,
+    /// Larger clipboards are not backed up
+    pub max_mb: usize,
+    /// Overwrite rotated-out backups with zeros before deleting them
+    pub secure_delete: bool,
+    pub encrypt: Encrypt,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self { enabled: true, keep: 3, max_mb: 20, secure_delete: false, encrypt: Encrypt::Off }
+    }
+}
+
+/// Top-level `config.toml` keys, which `REPROMPT_*` variables may override
+const KEYS: &[&str] = &[
+    "stages", "rules", "exec", "plugins", "substitutions", "templates", "glyphs", "verbatim", "scoring",
+    "beam_width", "powershell_timeout_ms", "retry", "verify", "conservative", "input", "append", "backup",
+    "validation", "line_endings", "indent", "header", "screen", "images", "markdown", "format", "profile", "watch",
+    "ocr",
+];
+
+static OVERRIDES: OnceLock<Vec<Override>> = OnceLock::new();
+
+/// One config value set from outside `config.toml`: `--set key=value` or a
+/// `REPROMPT_KEY=value` variable. Dotted keys (`backup.keep`) reach into
+/// tables; the value is read as TOML, or as a plain string if it isn't.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Override {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, String> {
+        match text.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Self { key: key.trim().to_string(), value: value.to_string() }),
+            _ => Err(format!("expected key=value, got '{}'", text)),
+        }
+    }
+}
+
+impl Override {
+    /// `REPROMPT_BACKUP__MAX_MB=5` → `backup.max_mb = 5`: lowercase, with `__`
+    /// between table and key. Variables that don't name a config key are ignored.
+    fn from_env(name: &str, value: String) -> Option<Self> {
+        let key = name.strip_prefix("REPROMPT_")?.to_lowercase().replace("__", ".");
+        let top = key.split('.').next()?;
+        KEYS.contains(&top).then_some(Self { key, value })
+    }
+
+    fn value(&self) -> toml::Value {
+        format!("value = {}", self.value)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(self.value.clone()))
+    }
+
+    fn apply(&self, table: &mut toml::Table) -> Result<()> {
+        let mut parts: Vec<&str> = self.key.split('.').collect();
+        let last = parts.pop().unwrap_or_default();
+        let mut table = table;
+        for part in parts {
+            let entry = table.entry(part).or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            table = entry
+                .as_table_mut()
+                .ok_or_else(|| RepromptError::InvalidSetting(format!("Can't set '{}': '{}' is not a table", self.key, part)))?;
+        }
+        table.insert(last.to_string(), self.value());
+        Ok(())
+    }
+}
+
+/// Sets the `--set` overrides applied on top of `config.toml` and `REPROMPT_*`
+/// variables whenever the config is loaded; only the first call counts.
+pub fn set_overrides(overrides: Vec<Override>) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+/// Overrides in effect: `REPROMPT_*` variables, then `--set` (which wins).
+fn overrides() -> Vec<Override> {
+    let mut overrides: Vec<Override> = std::env::vars().filter_map(|(name, value)| Override::from_env(&name, value)).collect();
+    overrides.sort_by(|a, b| a.key.cmp(&b.key));
+    overrides.extend(OVERRIDES.get().into_iter().flatten().cloned());
+    overrides
+}
+
+/// User configuration loaded from `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Cleaning stages to run, in order (defaults to all built-in stages)
+    #[serde(default)]
+    pub stages: Option<Vec<StageKind>>,
+    /// Ordered line rules, evaluated after `verbatim` and before the built-in heuristics
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Default external filter for the `exec` stage (profiles may override)
+    #[serde(default)]
+    pub exec: Option<ExecSpec>,
+    /// WASM cleaner modules run by the `wasm` stage (relative to `plugins/`)
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+    /// Regex rewrites applied after cleaning
+    #[serde(default)]
+    pub substitutions: Vec<Substitution>,
+    /// Wrappers for the final text, chosen by its content kind
+    #[serde(default)]
+    pub templates: Templates,
+    /// Icon glyphs the `glyphs` stage should leave alone
+    #[serde(default)]
+    pub glyphs: GlyphSpec,
+    /// Marker-delimited regions passed through untouched
+    #[serde(default)]
+    pub verbatim: Vec<VerbatimRegion>,
+    /// Weights the scorer uses to compare repair and cleaning variants
+    #[serde(default)]
+    pub scoring: Weights,
+    /// Variants kept at each branching stage of the search (default 4)
+    #[serde(default)]
+    pub beam_width: Option<usize>,
+    /// How long WSL clipboard calls through `powershell.exe` may take (default 5000)
+    #[serde(default)]
+    pub powershell_timeout_ms: Option<u64>,
+    /// How clipboard reads and writes are retried when the clipboard is busy
+    #[serde(default)]
+    pub retry: Retry,
+    /// How the clipboard is checked after a write
+    #[serde(default)]
+    pub verify: Verify,
+    /// When `--conservative` holds back a cleaned result
+    #[serde(default)]
+    pub conservative: Conservative,
+    /// Size limit for clipboard input
+    #[serde(default)]
+    pub input: InputLimit,
+    /// Command `reprompt ocr` reads screenshots with
+    #[serde(default)]
+    pub ocr: OcrSpec,
+    /// How `reprompt append` joins snippets
+    #[serde(default)]
+    pub append: Append,
+    /// Safety copies of the clipboard before it is overwritten
+    #[serde(default)]
+    pub backup: Backup,
+    /// Windows whose copies `reprompt watch` cleans or leaves alone
+    #[serde(default)]
+    pub watch: WatchScope,
+    /// Checks the cleaned text must pass before it is committed
+    #[serde(default)]
+    pub validation: Policy,
+    /// Line endings of the cleaned text (default LF)
+    #[serde(default)]
+    pub line_endings: LineEndings,
+    /// Tab stops for the `tabs` and `indent` stages
+    #[serde(default)]
+    pub indent: IndentSpec,
+    /// Provenance header put before the cleaned text (off by default)
+    #[serde(default)]
+    pub header: Option<HeaderFormat>,
+    /// Screen to keep when the text switches to the alternate screen (default both)
+    #[serde(default)]
+    pub screen: Screen,
+    /// What replaces inline images in the cleaned text (default nothing)
+    #[serde(default)]
+    pub images: ImageMode,
+    /// Keep Markdown structure intact and abort on unbalanced fences, as with `--markdown`
+    #[serde(default)]
+    pub markdown: bool,
+    /// What the cleaned text is turned into (default plain)
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Profile to use instead of detecting one, as with `--profile`
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+impl Config {
+    /// Loads the user's config file; a missing file, or no home directory to
+    /// keep one in, means defaults.
+    pub fn load() -> Result<Self> {
+        match paths::config_file() {
+            Ok(path) => Self::load_from(&path),
+            Err(_) => Self::parse_at("", &overrides(), None),
+        }
+    }
+
+    /// Loads `path` with the `REPROMPT_*` and `--set` overrides applied.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(RepromptError::io("Failed to read", path, e)),
+        };
+        Self::parse_at(&text, &overrides(), Some(path))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::parse_with(text, &[])
+    }
+
+    /// Parses `text` with `overrides` set on top of it.
+    pub fn parse_with(text: &str, overrides: &[Override]) -> Result<Self> {
+        Self::parse_at(text, overrides, None)
+    }
+
+    /// `parse_with`, naming `path` in errors when the text came from a file.
+    fn parse_at(text: &str, overrides: &[Override], path: Option<&Path>) -> Result<Self> {
+        let context = match path {
+            Some(path) => format!("Invalid config {}", path.display()),
+            None => "Invalid config".to_string(),
+        };
+        let invalid = |context: String| move |error| RepromptError::InvalidConfig { context, error };
+        let config: Config = if overrides.is_empty() {
+            toml::from_str(text).map_err(invalid(context.clone()))?
+        } else {
+            let mut table: toml::Table = text.parse().map_err(invalid(context.clone()))?;
+            for item in overrides {
+                item.apply(&mut table)?;
+            }
+            let keys: Vec<&str> = overrides.iter().map(|item| item.key.as_str()).collect();
+            table.try_into().map_err(invalid(format!("{} with overrides for {}", context, keys.join(", "))))?
+        };
+        let check = || -> Result<()> {
+            for rule in &config.rules {
+                rule.check()?;
+            }
+            if let Some(exec) = &config.exec {
+                exec.check()?;
+            }
+            config.templates.check()
+        };
+        match check() {
+            Ok(()) => Ok(config),
+            Err(RepromptError::InvalidSetting(message)) if path.is_some() => {
+                Err(RepromptError::InvalidSetting(format!("{}: {}", context, message)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stage settings in effect for a profile.
+    pub fn stage_options(&self, profile: &Profile) -> StageOptions {
+        StageOptions {
+            rules: self.rules_for(profile),
+            exec: profile.exec.clone().or_else(|| self.exec.clone()),
+            plugins: self
+                .plugins
+                .iter()
+                .chain(&profile.plugins)
+                .map(|path| paths::plugins_dir().map_or_else(|_| path.clone(), |dir| dir.join(path)))
+                .collect(),
+            substitutions: self
+                .substitutions
+                .iter()
+                .filter(|sub| sub.profile.as_ref().is_none_or(|name| *name == profile.name))
+                .chain(&profile.substitutions)
+                .cloned()
+                .collect(),
+            status_line: profile.status_line.clone(),
+            glyphs: self.glyphs.clone(),
+            verbatim: self
+                .verbatim
+                .iter()
+                .filter(|region| region.profile.as_ref().is_none_or(|name| *name == profile.name))
+                .chain(&profile.verbatim)
+                .cloned()
+                .collect(),
+            indent: self.indent.clone(),
+            screen: self.screen,
+            images: self.images,
+        }
+    }
+
+    /// Rules in effect for a profile: unscoped and matching config rules first,
+    /// then the profile's own rules.
+    pub fn rules_for(&self, profile: &Profile) -> Vec<Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.profile.as_ref().is_none_or(|name| *name == profile.name))
+            .chain(profile.rules.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_scoped_by_profile() {
+        let config = Config::parse(r#"
+            [[rules]]
+            match = '^⏺'
+            action = "drop"
+            profile = "claude-code"
+
+            [[rules]]
+            match = '^\$ '
+            action = "keep"
+        "#).unwrap();
+
+        let generic = config.rules_for(&Profile::default());
+        assert_eq!(generic.len(), 1, "Scoped rule should not apply to other profiles");
+
+        let claude = Profile::parse("name = \"claude-code\"\nversion = \"1\"\n").unwrap();
+        assert_eq!(config.rules_for(&claude).len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        assert!(Config::parse("[[rules]]\nmatch = '('\naction = \"drop\"\n").is_err());
+        assert!(Config::parse("[[rules]]\nmatch = 'x'\naction = \"replace\"\n").is_err(),
+            "replace without replacement should be rejected");
+        assert!(Config::parse("[[rules]]\nmatch = 'x'\naction = \"explode\"\n").is_err());
+    }
+
+    #[test]
+    fn test_watch_scope_by_app_and_title() {
+        let config = Config::parse("[watch]\nonly = ['(?i)kitty|terminal']\nskip = ['(?i)password']\nignore = ['^-----BEGIN PGP']\n").unwrap();
+        let scope = config.watch;
+        assert!(scope.allows("kitty", "~/src"));
+        assert!(scope.allows("code", "bash - Terminal"));
+        assert!(!scope.allows("firefox", "GitHub"));
+        assert!(!scope.allows("kitty", "Password Manager"));
+        assert!(scope.ignores("-----BEGIN PGP MESSAGE-----\n") && !scope.ignores("see -----BEGIN PGP"));
+        assert!(!WatchScope::default().is_set() && WatchScope::default().allows("firefox", ""));
+    }
+
+    #[test]
+    fn test_overrides_set_nested_keys() {
+        let overrides: Vec<Override> = ["backup.keep=7", "stages=[\"ansi\", \"border\"]", "profile=claude-code"]
+            .iter()
+            .map(|text| text.parse().unwrap())
+            .collect();
+        let config = Config::parse_with("[backup]\nkeep = 1\nmax_mb = 2\n", &overrides).unwrap();
+        assert_eq!((config.backup.keep, config.backup.max_mb), (7, 2));
+        assert_eq!(config.stages, Some(vec![StageKind::Ansi, StageKind::Border]));
+        assert_eq!(config.profile.as_deref(), Some("claude-code"));
+
+        let env = Override::from_env("REPROMPT_INPUT__MAX_MB", "5".to_string()).unwrap();
+        assert_eq!(env.key, "input.max_mb");
+        assert!(Override::from_env("REPROMPT_CHECK_INVARIANTS", "1".to_string()).is_none());
+        assert!("novalue".parse::<Override>().is_err());
+        assert!(Config::parse_with("", &["backup.keep=many".parse().unwrap()]).is_err());
+        for key in KEYS {
+            let error = Config::parse_with("", &[Override { key: key.to_string(), value: "{}".to_string() }]).err();
+            assert!(!error.is_some_and(|e| format!("{:#}", e).contains("unknown field")), "{} is not a config key", key);
+        }
+        // serde lists every field it expected; each must be overridable
+        let error = format!("{:#}", Config::parse("not_a_key = 1").unwrap_err());
+        let expected = error.split_once("expected one of").expect("serde names the fields").1;
+        let fields: Vec<&str> = expected.split('`').skip(1).step_by(2).collect();
+        assert!(fields.len() > 20, "Couldn't read the fields from {:?}", error);
+        for field in fields {
+            assert!(KEYS.contains(&field), "{} is missing from KEYS", field);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::clipboard::{get_clipboard, with_retry};
+
+const INPUT_SUFFIX: &str = ".input.txt";
+const EXPECTED_SUFFIX: &str = ".expected.txt";
+
+/// Longest fixture name, so a name always makes a valid file name
+const MAX_NAME: usize = 64;
+
+/// Changed lines printed per failing fixture
+const MAX_DIFF_LINES: usize = 20;
+
+/// Above this many line pairs, the middle of a diff is shown whole rather than aligned
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// How one `<name>.input.txt` compared with its `<name>.expected.txt`.
+#[derive(Debug, Serialize)]
+pub struct Fixture {
+    pub name: String,
+    /// Profile the input was cleaned with
+    pub profile: String,
+    pub passed: bool,
+    /// `-` expected and `+` cleaned lines that differ, with their line numbers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diff: Vec<String>,
+    /// Why the fixture couldn't be compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `*.input.txt` files in `dir`, by name.
+fn inputs(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_suffix(INPUT_SUFFIX));
+        if let Some(name) = name.filter(|name| !name.is_empty()) {
+            inputs.push((name.to_string(), path.clone()));
+        }
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// The lines that turn `expected` into `actual`: `-n: line` for removed ones
+/// and `+n: line` for added ones, aligned on their longest common subsequence.
+fn diff(expected: &[&str], actual: &[&str]) -> Vec<String> {
+    let prefix = expected.iter().zip(actual).take_while(|(a, b)| a == b).count();
+    let suffix = expected[prefix..].iter().rev().zip(actual[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&expected[prefix..expected.len() - suffix], &actual[prefix..actual.len() - suffix]);
+    let removed = |i: usize| format!("-{}: {}", prefix + i + 1, old[i]);
+    let added = |j: usize| format!("+{}: {}", prefix + j + 1, new[j]);
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return (0..old.len()).map(removed).chain((0..new.len()).map(added)).collect();
+    }
+
+    // common[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(removed(i));
+            i += 1;
+        } else {
+            lines.push(added(j));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Cleans every `<name>.input.txt` in `dir` and compares it, line by line and
+/// ignoring trailing newlines, with `<name>.expected.txt`.
+pub fn check(cleaner: &Cleaner, dir: &Path) -> Result<Vec<Fixture>> {
+    let mut fixtures = Vec::new();
+    for (name, path) in inputs(dir)? {
+        let input = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let report = cleaner.clean(&input);
+        let mut fixture = Fixture { name, profile: report.profile, passed: false, diff: Vec::new(), error: None };
+        let expected_path = dir.join(format!("{}{EXPECTED_SUFFIX}", fixture.name));
+        match std::fs::read_to_string(&expected_path) {
+            Ok(expected) => {
+                let expected: Vec<&str> = expected.lines().collect();
+                let actual: Vec<&str> = report.output.lines().collect();
+                let trim = |lines: &[&str]| lines.len() - lines.iter().rev().take_while(|line| line.is_empty()).count();
+                fixture.diff = diff(&expected[..trim(&expected)], &actual[..trim(&actual)]);
+                fixture.passed = fixture.diff.is_empty();
+            }
+            Err(e) => fixture.error = Some(format!("{}: {}", expected_path.display(), e)),
+        }
+        fixtures.push(fixture);
+    }
+    Ok(fixtures)
+}
+
+/// Fixture name for a recording of `file`: its name without `.input.txt` or
+/// its extension, with anything but letters, digits, `-` and `_` made `-`.
+fn name_for(file: &Path) -> Option<String> {
+    let file_name = file.file_name()?.to_str()?;
+    let stem = file_name.strip_suffix(INPUT_SUFFIX).or_else(|| file.file_stem()?.to_str())?;
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .take(MAX_NAME)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Writes `input` as `<name>.input.txt` and what `cleaner` makes of it as
+/// `<name>.expected.txt` in `dir`, refusing to replace either unless `force`.
+pub fn record_text(cleaner: &Cleaner, dir: &Path, name: &str, input: &str, force: bool) -> Result<(PathBuf, PathBuf)> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && name.len() <= MAX_NAME
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Invalid fixture name '{}': use up to {} letters, digits, '-' or '_'",
+        name,
+        MAX_NAME
+    );
+    let input_path = dir.join(format!("{name}{INPUT_SUFFIX}"));
+    let expected_path = dir.join(format!("{name}{EXPECTED_SUFFIX}"));
+    if !force {
+        if let Some(existing) = [&input_path, &expected_path].into_iter().find(|path| path.exists()) {
+            bail!("{} already exists; pick another --name or pass --force", existing.display());
+        }
+    }
+
+    let report = cleaner.clean(input);
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&input_path, input).with_context(|| format!("Failed to write {}", input_path.display()))?;
+    std::fs::write(&expected_path, format!("{}\n", report.output))
+        .with_context(|| format!("Failed to write {}", expected_path.display()))?;
+    Ok((input_path, expected_path))
+}
+
+/// `reprompt corpus record`: records `file`, or the clipboard without one, as
+/// a fixture named `name` (by default the file's name, or the detected profile
+/// and the time).
+pub fn record(cleaner: &Cleaner, dir: &Path, file: Option<&Path>, name: Option<&str>, force: bool) -> Result<()> {
+    let input = match file {
+        Some(file) => std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?,
+        None => {
+            let text = with_retry(get_clipboard).context("Failed to read clipboard")?;
+            crate::sensitive::ensure_safe(&text)?;
+            text
+        }
+    };
+    anyhow::ensure!(!input.trim().is_empty(), "Nothing to record: the input is empty");
+    let name = match (name, file) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(file)) => name_for(file).with_context(|| format!("No fixture name in {}; pass --name", file.display()))?,
+        (None, None) => format!("{}-{}", cleaner.clean(&input).profile, crate::stats::now()),
+    };
+    let (input_path, expected_path) = record_text(cleaner, dir, &name, &input, force)?;
+    tracing::info!(
+        "Recorded {} and {}; fix up the expected output if it's wrong before sharing it",
+        input_path.display(),
+        expected_path.display()
+    );
+    Ok(())
+}
+
+/// `reprompt corpus run`: prints each fixture's result and the diffs of the
+/// failing ones (with `json`, all of it as JSON), and fails if any did.
+pub fn run(cleaner: &Cleaner, dir: &Path, json: bool) -> Result<()> {
+    let fixtures = check(cleaner, dir)?;
+    if fixtures.is_empty() {
+        bail!("No *{INPUT_SUFFIX} fixtures in {}", dir.display());
+    }
+    let failed = fixtures.iter().filter(|fixture| !fixture.passed).count();
+
+    if json {
+        let summary = serde_json::json!({ "passed": fixtures.len() - failed, "failed": failed, "fixtures": fixtures });
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        for fixture in &fixtures {
+            let status = if fixture.passed { "ok  " } else { "FAIL" };
+            println!("{status} {} ({})", fixture.name, fixture.profile);
+            if let Some(error) = &fixture.error {
+                println!("     {error}");
+            }
+            for line in fixture.diff.iter().take(MAX_DIFF_LINES) {
+                println!("     {line}");
+            }
+            if fixture.diff.len() > MAX_DIFF_LINES {
+                println!("     … {} more changed lines", fixture.diff.len() - MAX_DIFF_LINES);
+            }
+        }
+        println!("{} passed, {} failed", fixtures.len() - failed, failed);
+    }
+
+    if failed > 0 {
+        bail!("{} of {} fixtures failed", failed, fixtures.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::config::Config;
+
+    #[test]
+    fn test_corpus_fixtures() {
+        let dir = std::env::temp_dir().join(format!("reprompt-corpus-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, text: &str| std::fs::write(dir.join(name), text).unwrap();
+        write("box.input.txt", "╭──────╮\n│ kept │\n╰──────╯\n");
+        write("box.expected.txt", "kept\n");
+        write("drift.input.txt", "one\n\x1b[1mtwo\x1b[0m\nthree\nfour");
+        write("drift.expected.txt", "one\n2\nthree\nfour\nfive\n");
+        write("orphan.input.txt", "text");
+        write("notes.txt", "not a fixture");
+
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let fixtures = check(&cleaner, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let results: Vec<(&str, bool)> = fixtures.iter().map(|fixture| (fixture.name.as_str(), fixture.passed)).collect();
+        assert_eq!(results, [("box", true), ("drift", false), ("orphan", false)]);
+        assert_eq!(fixtures[1].diff, ["-2: 2", "+2: two", "-5: five"]);
+        assert!(fixtures[2].error.is_some());
+    }
+
+    #[test]
+    fn test_recorded_fixture_passes() {
+        let dir = std::env::temp_dir().join(format!("reprompt-record-{}", std::process::id()));
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        assert_eq!(name_for(Path::new("captures/k9s pods.log")).as_deref(), Some("k9s-pods"));
+        assert_eq!(name_for(Path::new("old.input.txt")).as_deref(), Some("old"));
+        assert!(record_text(&cleaner, &dir, "../escape", "text", false).is_err());
+
+        let (input, expected) = record_text(&cleaner, &dir, "box", "╭────╮\n│ hi │\n╰────╯", false).unwrap();
+        let again = record_text(&cleaner, &dir, "box", "other", false);
+        let saved = (std::fs::read_to_string(&input).unwrap(), std::fs::read_to_string(&expected).unwrap());
+        let fixtures = check(&cleaner, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(again.is_err());
+        assert_eq!(saved, ("╭────╮\n│ hi │\n╰────╯".to_string(), "hi\n".to_string()));
+        assert!(fixtures.len() == 1 && fixtures[0].passed);
+    }
+}
@@ -0,0 +1,182 @@
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+lazy_static! {
+    // `## Plan`; the capture is the level
+    static ref RE_MARKDOWN_HEADING: Regex = Regex::new(r"^\s{0,3}(#{1,6})\s+\S").expect("Invalid Markdown Heading Regex");
+
+    // `**Plan**`, `**Plan:**` or `Plan:` on a line of its own
+    static ref RE_LABEL_HEADING: Regex = Regex::new(r"^\s*(?:\*\*[^*]+\*\*:?|__[^_]+__:?|[A-Z][^.!?:]{0,60}:)\s*$").expect("Invalid Label Heading Regex");
+}
+
+/// Markdown level of a `#` heading, 7 for other heading-like lines.
+fn heading_level(line: &str) -> Option<usize> {
+    match RE_MARKDOWN_HEADING.captures(line) {
+        Some(caps) => Some(caps[1].len()),
+        None => RE_LABEL_HEADING.is_match(line).then_some(7),
+    }
+}
+
+/// Compiles a `--select` pattern: a case-insensitive regex, or the literal text
+/// if it isn't a valid one.
+pub fn pattern(source: &str) -> Regex {
+    let build = |source: &str| RegexBuilder::new(source).case_insensitive(true).build();
+    build(source).unwrap_or_else(|_| build(&regex::escape(source)).expect("Invalid Escaped Regex"))
+}
+
+/// Which lines of the cleaned text to keep (`--head`, `--tail`, `--lines`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Head(usize),
+    Tail(usize),
+    /// 1-based and inclusive; `None` runs to the end
+    Lines(usize, Option<usize>),
+}
+
+impl Window {
+    /// Zero-based half-open range of `count` lines that the window keeps.
+    fn range(self, count: usize) -> (usize, usize) {
+        match self {
+            Window::Head(n) => (0, n.min(count)),
+            Window::Tail(n) => (count.saturating_sub(n), count),
+            Window::Lines(first, last) => {
+                let end = last.unwrap_or(count).min(count);
+                (first.saturating_sub(1).min(end), end)
+            }
+        }
+    }
+}
+
+/// Parses `--lines A:B` (either end may be left out: `A:`, `:B`).
+pub fn parse_lines(source: &str) -> Result<Window, String> {
+    let (first, last) = source.split_once(':').ok_or("expected A:B, e.g. 10:20")?;
+    let number = |text: &str| text.trim().parse::<usize>().map_err(|_| format!("'{}' is not a line number", text));
+    let first = if first.trim().is_empty() { 1 } else { number(first)? };
+    let last = if last.trim().is_empty() { None } else { Some(number(last)?) };
+    if first == 0 || last.is_some_and(|last| last < first) {
+        return Err("lines are numbered from 1 and A must not be after B".to_string());
+    }
+    Ok(Window::Lines(first, last))
+}
+
+/// Note standing in for `count` elided lines.
+fn elided(count: usize) -> String {
+    match count {
+        1 => "[1 line elided]".to_string(),
+        _ => format!("[{} lines elided]", count),
+    }
+}
+
+/// Narrows the cleaned text to the part the user asked for.
+#[derive(Debug, Clone, Default)]
+pub struct Crop {
+    /// Keep only the section starting at the first line that matches (`--select`)
+    pub select: Option<Regex>,
+    /// Then keep only these lines, noting how many were left out
+    pub window: Option<Window>,
+}
+
+impl Crop {
+    /// Applies the crop to `text`, returning the result and a description of
+    /// what was kept, or `None` when there's nothing to crop (or no match).
+    pub fn apply(&self, text: &str) -> Option<(String, String)> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        let mut kept = Vec::new();
+        if let Some(pattern) = &self.select {
+            match section(&lines, pattern) {
+                Some((start, end)) => {
+                    kept.push(format!("section '{}' (lines {}-{})", lines[start].trim(), start + 1, end));
+                    lines = lines[start..end].to_vec();
+                }
+                None => tracing::warn!("No section matches --select '{}'; keeping all of the text", pattern.as_str()),
+            }
+        }
+        let mut output = lines.join("\n");
+        if let Some(window) = self.window {
+            let (start, end) = window.range(lines.len());
+            if (start, end) != (0, lines.len()) {
+                kept.push(format!("lines {}-{} of {}", start + 1, end, lines.len()));
+                let mut parts = Vec::with_capacity(3);
+                if start > 0 {
+                    parts.push(elided(start));
+                }
+                parts.extend(lines[start..end].iter().map(|line| line.to_string()));
+                if end < lines.len() {
+                    parts.push(elided(lines.len() - end));
+                }
+                output = parts.join("\n");
+            }
+        }
+        (!kept.is_empty()).then(|| (output, kept.join(", then ")))
+    }
+}
+
+/// Line range of the section opened by the first heading matching `pattern`,
+/// or failing that the first matching line. A `#` heading runs to the next
+/// heading of the same or a higher level; anything else runs to the next
+/// heading or the first blank line after some content.
+fn section(lines: &[&str], pattern: &Regex) -> Option<(usize, usize)> {
+    let matches = |i: &usize| pattern.is_match(lines[*i]);
+    let start = (0..lines.len())
+        .filter(|i| heading_level(lines[*i]).is_some())
+        .find(matches)
+        .or_else(|| (0..lines.len()).find(matches))?;
+    let level = heading_level(lines[start]).unwrap_or(7);
+
+    let mut end = start + 1;
+    let mut content = false;
+    while end < lines.len() {
+        let line = lines[end];
+        if heading_level(line).is_some_and(|next| next <= level || level == 7) {
+            break;
+        }
+        if line.trim().is_empty() {
+            if level == 7 && content {
+                break;
+            }
+        } else {
+            content = true;
+        }
+        end += 1;
+    }
+    // Blank lines before the next section aren't part of this one
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = "I looked at the parser.\n\nNotes:\nkeep the API\n\n## Plan\n\n1. Add a test\n\n### Details\nUse the fixture.\n\n## Risks\nNone.";
+
+    fn select(source: &str) -> Option<String> {
+        Crop { select: Some(pattern(source)), window: None }.apply(RESPONSE).map(|(text, _)| text)
+    }
+
+    #[test]
+    fn test_markdown_section_keeps_subsections() {
+        assert_eq!(select("plan").unwrap(), "## Plan\n\n1. Add a test\n\n### Details\nUse the fixture.");
+        assert_eq!(select("^## risks").unwrap(), "## Risks\nNone.");
+    }
+
+    #[test]
+    fn test_label_and_marker_sections_end_at_blank_line() {
+        assert_eq!(select("notes").unwrap(), "Notes:\nkeep the API");
+        assert_eq!(select("looked").unwrap(), "I looked at the parser.");
+        assert_eq!(select("[unclosed").map(|_| ()), None);
+    }
+
+    #[test]
+    fn test_windows_note_elided_lines() {
+        let text = (1..=10).map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+        let crop = |window| Crop { select: None, window: Some(window) }.apply(&text).map(|(text, _)| text);
+        assert_eq!(crop(Window::Head(2)).unwrap(), "1\n2\n[8 lines elided]");
+        assert_eq!(crop(Window::Tail(1)).unwrap(), "[9 lines elided]\n10");
+        assert_eq!(crop(parse_lines("4:5").unwrap()).unwrap(), "[3 lines elided]\n4\n5\n[5 lines elided]");
+        assert_eq!(crop(parse_lines(":20").unwrap()), None);
+        assert!(parse_lines("5:4").is_err() && parse_lines("0:").is_err() && parse_lines("7").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::Result;
+use std::path::PathBuf;
+#[cfg(unix)]
+use {
+    anyhow::Context,
+    reprompt::clipboard::{self, get_clipboard, set_clipboard, with_retry},
+    reprompt::lock::InstanceLock,
+    reprompt::report::Report,
+    std::io::{Read, Write},
+    std::time::{Duration, Instant},
+};
+
+use reprompt::paths;
+use crate::reload::Reloader;
+use crate::Reporting;
+
+/// Largest frame a client may send, so a bad length prefix can't exhaust memory
+#[cfg(unix)]
+const MAX_FRAME: usize = 256 * 1024 * 1024;
+
+/// How long a client may take to send its request or read the reply, so a
+/// stalled client can't hold up every other one
+#[cfg(unix)]
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the daemon listens.
+pub fn socket_path() -> PathBuf {
+    paths::runtime_file("daemon.sock")
+}
+
+/// No daemon answered on the socket, so the request was never sent.
+#[derive(Debug, thiserror::Error)]
+#[error("No daemon listening on {} (start one with `reprompt daemon`)", path.display())]
+pub struct Unreachable {
+    path: PathBuf,
+    #[source]
+    error: std::io::Error,
+}
+
+/// Writes one frame: a big-endian `u32` length, then the payload.
+#[cfg(unix)]
+fn write_frame(mut writer: impl Write, payload: &[u8]) -> std::io::Result<()> {
+    let length = u32::try_from(payload.len()).map_err(|_| std::io::Error::other("Frame too large"))?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+#[cfg(unix)]
+fn read_frame(mut reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME {
+        return Err(std::io::Error::other(format!("Frame of {} bytes is over the limit", length)));
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// State kept between requests: counters for `STATUS` and the text `UNDO` restores.
+#[cfg(unix)]
+struct Daemon<'a> {
+    reloader: Reloader<'a>,
+    reporting: Reporting,
+    started: Instant,
+    requests: u64,
+    /// Clipboard text from before the last `CLEAN_CLIPBOARD` that changed it,
+    /// and the text that clean wrote
+    undo: Option<(String, String)>,
+    /// When the last clean ran, how it ended and with which profile
+    last_clean: Option<serde_json::Value>,
+}
+
+#[cfg(unix)]
+impl<'a> Daemon<'a> {
+    fn new(reloader: Reloader<'a>, reporting: Reporting) -> Self {
+        Self { reloader, reporting, started: Instant::now(), requests: 0, undo: None, last_clean: None }
+    }
+
+    fn record(&mut self, result: &str, report: Option<&Report>) {
+        self.last_clean = Some(serde_json::json!({
+            "at": crate::stats::now(),
+            "result": result,
+            "profile": report.map(|report| report.profile.as_str()),
+        }));
+    }
+
+    /// Runs one command and returns the payload of its `OK` reply:
+    /// - `CLEAN <text>` → the cleaned text
+    /// - `CLEAN_CLIPBOARD` → the JSON report, after cleaning the clipboard in
+    ///   place, or `null` if it was left alone (empty, sensitive, too large)
+    /// - `UNDO` → restores the clipboard from before the last `CLEAN_CLIPBOARD`,
+    ///   unless something else has been copied since
+    /// - `PAUSE [seconds]` → pauses the watcher, for that long or until `RESUME`
+    /// - `RESUME` → ends a pause
+    /// - `STATUS` → JSON with pid, uptime, request count, whether undo is
+    ///   possible, the clipboard backend, forced profile, last clean and pause
+    fn handle(&mut self, request: &str) -> Result<String> {
+        self.requests += 1;
+        let (command, argument) = request.split_once(' ').unwrap_or((request, ""));
+        match command {
+            "CLEAN" => {
+                let report = self.reloader.current().clean_checked(argument, None)?;
+                self.record(if report.changed { "cleaned" } else { "unchanged" }, Some(&report));
+                Ok(report.output)
+            }
+            "CLEAN_CLIPBOARD" => {
+                let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+                let cleaner = self.reloader.current();
+                let started = Instant::now();
+                // Undo restores exactly what this clean read, not a second read
+                let transaction = crate::ClipboardTransaction::snapshot(cleaner)?;
+                let before = transaction.original.clone();
+                let read_time = started.elapsed();
+                let cleaned = crate::clean_transaction(cleaner, transaction, read_time, None, self.reporting)?;
+                let Some((report, _)) = cleaned else {
+                    self.record("skipped", None);
+                    return Ok("null".to_string());
+                };
+                let result = match (report.committed, report.changed) {
+                    (true, _) => "cleaned",
+                    (false, false) => "unchanged",
+                    (false, true) => "failed",
+                };
+                self.record(result, Some(&report));
+                if report.committed {
+                    self.undo = Some((before, report.output.clone()));
+                }
+                Ok(serde_json::to_string(&report)?)
+            }
+            "UNDO" => {
+                let (before, written) = self.undo.take().context("Nothing to undo")?;
+                let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+                let current = with_retry(get_clipboard).context("Failed to read clipboard")?;
+                if !crate::watch::same_write(&current, &written) {
+                    anyhow::bail!("The clipboard changed since the last clean; not restoring over it");
+                }
+                with_retry(|| set_clipboard(&before)).context("Failed to restore clipboard")?;
+                Ok(format!("Restored {} bytes", before.len()))
+            }
+            "PAUSE" => {
+                let duration = match argument {
+                    "" => None,
+                    seconds => Some(Duration::from_secs(seconds.parse().context("PAUSE takes a number of seconds")?)),
+                };
+                let message = crate::pause::pause(duration)?;
+                tracing::info!("{}", message);
+                Ok(message)
+            }
+            "RESUME" => {
+                let message = crate::pause::resume()?;
+                tracing::info!("{}", message);
+                Ok(message)
+            }
+            "STATUS" => Ok(serde_json::json!({
+                "pid": std::process::id(),
+                "uptime_secs": self.started.elapsed().as_secs(),
+                "requests": self.requests,
+                "undo": self.undo.is_some(),
+                "backend": clipboard::backend(),
+                "profile": self.reloader.current().forced_profile(),
+                "last_clean": self.last_clean,
+                "paused": crate::pause::state().map(|until| serde_json::json!({ "until": until })),
+            })
+            .to_string()),
+            _ => anyhow::bail!(
+                "Unknown command '{}' (expected CLEAN, CLEAN_CLIPBOARD, UNDO, PAUSE, RESUME or STATUS)",
+                command
+            ),
+        }
+    }
+
+    /// Serves one connection: one request frame, one `OK …`/`ERR …` reply frame.
+    fn serve(&mut self, mut stream: impl Read + Write) -> std::io::Result<()> {
+        let request = read_frame(&mut stream)?;
+        let reply = match std::str::from_utf8(&request) {
+            Ok(request) => match self.handle(request) {
+                Ok(payload) => format!("OK {}", payload),
+                Err(e) => format!("ERR {:#}", e),
+            },
+            Err(_) => "ERR Request is not UTF-8".to_string(),
+        };
+        write_frame(&mut stream, reply.as_bytes())
+    }
+}
+
+/// Listens on the daemon socket until killed, one connection at a time.
+#[cfg(unix)]
+pub fn run(reloader: Reloader, reporting: Reporting) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+
+    let Some(_instance) = InstanceLock::try_acquire("daemon")? else {
+        anyhow::bail!("Another reprompt daemon is already running");
+    };
+    let path = socket_path();
+    // Holding the instance lock means any existing socket is stale
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    tracing::info!("Listening on {}", path.display());
+
+    let mut daemon = Daemon::new(reloader, reporting);
+    for stream in listener.incoming() {
+        let served = stream.and_then(|stream| {
+            stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+            stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+            daemon.serve(stream)
+        });
+        if let Err(e) = served {
+            tracing::warn!("Request failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_reloader: Reloader, _reporting: Reporting) -> Result<()> {
+    anyhow::bail!("The daemon needs Unix domain sockets; on Windows use `reprompt serve` instead")
+}
+
+/// Sends one command to a running daemon and returns its `OK` payload. Fails
+/// with [`Unreachable`] when no daemon accepted the connection; any other
+/// error means the daemon got the request.
+#[cfg(unix)]
+pub fn request(command: &str) -> Result<String> {
+    let path = socket_path();
+    let mut stream = match std::os::unix::net::UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(error) => return Err(Unreachable { path, error }.into()),
+    };
+    write_frame(&mut stream, command.as_bytes())?;
+    let reply = String::from_utf8(read_frame(&mut stream)?).context("Daemon reply is not UTF-8")?;
+    match reply.split_once(' ').unwrap_or((&reply, "")) {
+        ("OK", payload) => Ok(payload.to_string()),
+        ("ERR", message) => anyhow::bail!("Daemon: {}", message),
+        _ => anyhow::bail!("Malformed daemon reply"),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn request(_command: &str) -> Result<String> {
+    let error = std::io::Error::new(std::io::ErrorKind::Unsupported, "The daemon needs Unix domain sockets");
+    Err(Unreachable { path: socket_path(), error }.into())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use reprompt::cleaner::Cleaner;
+    use reprompt::config::Config;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frames_and_commands() {
+        let reloader = Reloader::new(|| Ok(Cleaner::new(Config::default(), Some("generic"), None, false)?)).unwrap();
+        let mut daemon = Daemon::new(reloader, Reporting::default());
+
+        let mut request = Vec::new();
+        write_frame(&mut request, "CLEAN │ hello │".as_bytes()).unwrap();
+        let mut stream = Cursor::new(request);
+        daemon.serve(&mut stream).unwrap();
+        let written = stream.into_inner();
+        let reply = read_frame(&written[4 + "CLEAN │ hello │".len()..]).unwrap();
+        assert_eq!(reply, b"OK hello");
+
+        assert_eq!(daemon.handle("UNDO").unwrap_err().to_string(), "Nothing to undo");
+        assert!(daemon.handle("FROB").is_err());
+        let status: serde_json::Value = serde_json::from_str(&daemon.handle("STATUS").unwrap()).unwrap();
+        assert_eq!(status["requests"], 4);
+        assert_eq!(status["undo"], false);
+        assert_eq!((status["profile"].as_str(), status["last_clean"]["result"].as_str()), (Some("generic"), Some("cleaned")));
+        assert_eq!(daemon.handle("PAUSE soon").unwrap_err().to_string(), "PAUSE takes a number of seconds");
+    }
+}
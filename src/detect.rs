@@ -0,0 +1,124 @@
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::config::Pattern;
+use crate::profiles::Profile;
+
+/// Points for each distinct signature found in the text
+const SIGNATURE_SCORE: u32 = 2;
+/// Points when the text is framed mostly with the profile's border characters
+const BORDER_SCORE: u32 = 1;
+/// Minimum score to auto-select a profile; borders alone are never enough
+const MIN_SCORE: u32 = SIGNATURE_SCORE;
+/// Lines that must use a profile's border characters before they count
+const MIN_BORDER_LINES: usize = 2;
+
+/// How a profile recognises its tool's output (`[detect]` in the profile file).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fingerprint {
+    /// Patterns characteristic of the tool (banners, bullets, footers)
+    #[serde(default)]
+    pub signatures: Vec<Pattern>,
+    /// Characters the tool draws its frames with, e.g. "╭╮╰╯" for rounded boxes
+    #[serde(default)]
+    pub borders: String,
+    /// `signatures` as one set, built the first time the profile is scored
+    #[serde(skip)]
+    set: OnceLock<Option<RegexSet>>,
+}
+
+impl Fingerprint {
+    /// Which signatures match some line of `text`, checking each line against
+    /// all of them in one pass and stopping once every one has matched.
+    fn matched_signatures(&self, text: &str) -> Vec<bool> {
+        let set = self.set.get_or_init(|| RegexSet::new(self.signatures.iter().map(|signature| signature.0.as_str())).ok());
+        let mut matched = vec![false; self.signatures.len()];
+        let Some(set) = set else {
+            for (found, signature) in matched.iter_mut().zip(&self.signatures) {
+                *found = text.lines().any(|line| signature.0.is_match(line));
+            }
+            return matched;
+        };
+        let mut left = matched.len();
+        for line in text.lines() {
+            if left == 0 {
+                break;
+            }
+            for i in set.matches(line).iter() {
+                if !matched[i] {
+                    matched[i] = true;
+                    left -= 1;
+                }
+            }
+        }
+        matched
+    }
+}
+
+/// The profile picked for a text, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct Detection {
+    pub profile: String,
+    pub score: u32,
+    pub signals: Vec<String>,
+}
+
+/// Picks the profile whose fingerprint best matches `text`, if any scores high enough.
+/// Ties go to the profile listed first.
+pub fn detect(text: &str, profiles: &[Profile]) -> Option<Detection> {
+    let mut best: Option<Detection> = None;
+    for profile in profiles {
+        let (score, signals) = score(text, &profile.detect);
+        if score >= MIN_SCORE && best.as_ref().is_none_or(|b| score > b.score) {
+            best = Some(Detection { profile: profile.name.clone(), score, signals });
+        }
+    }
+    best
+}
+
+fn score(text: &str, fingerprint: &Fingerprint) -> (u32, Vec<String>) {
+    let mut score = 0;
+    let mut signals = Vec::new();
+
+    let matched = fingerprint.matched_signatures(text);
+    for (signature, _) in fingerprint.signatures.iter().zip(matched).filter(|(_, found)| *found) {
+        score += SIGNATURE_SCORE;
+        signals.push(format!("signature '{}'", signature.0));
+    }
+
+    if !fingerprint.borders.is_empty() {
+        let framed = text
+            .lines()
+            .filter(|line| line.chars().any(|c| fingerprint.borders.contains(c)))
+            .count();
+        if framed >= MIN_BORDER_LINES {
+            score += BORDER_SCORE;
+            signals.push(format!("border style {}", fingerprint.borders));
+        }
+    }
+
+    (score, signals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles;
+
+    #[test]
+    fn test_detects_builtin_tools() {
+        let builtin = profiles::builtin();
+        let detected = |text: &str| detect(text, &builtin).map(|d| d.profile);
+
+        assert_eq!(detected("╭────╮\n│ ✻ Welcome to Claude Code! │\n╰────╯\n⏺ Done."), Some("claude-code".into()));
+        assert_eq!(detected("Aider v0.86.1\nMain model: gpt-4o\n<<<<<<< SEARCH"), Some("aider".into()));
+        assert_eq!(detected("✦ Sure.\n(98% context left)"), Some("gemini-cli".into()));
+        assert_eq!(detected("┌─Files─┐\nCommit: c | Stash: s | Keybindings: ?"), Some("lazygit".into()));
+        assert_eq!(detected("mysql> select 1;\n+---+\n| 1 |\n+---+\n1 row in set (0.00 sec)"), Some("cli-tables".into()));
+        assert_eq!(detected(" K9s Rev: v0.32.5\n<ctrl-d> Delete"), Some("k9s".into()));
+        assert_eq!(detected("  0[|||   25.3%]   Tasks: 12\n  PID USER      PRI  NI  VIRT   RES"), Some("htop".into()));
+        assert_eq!(detected("╭──╮\n│ plain box │\n╰──╯"), None, "Borders alone should not pick a tool");
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use reprompt::clipboard::{self, get_clipboard, is_wsl_custom, set_clipboard};
+use reprompt::lock::{self, InstanceLock};
+use reprompt::paths;
+
+/// Written to the clipboard (and then replaced with the original) by the round-trip check
+const PROBE: &str = "reprompt doctor ✓ round-trip — ünïcödé";
+
+/// PowerShell calls slower than this make every hotkey press feel sluggish
+const SLOW_POWERSHELL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One diagnostic result, with a suggested fix when it isn't fine.
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<&'static str>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: &'static str) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: &'static str) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix) }
+    }
+
+    fn render(&self) -> String {
+        let mark = match self.status {
+            Status::Ok => "✓",
+            Status::Warn => "!",
+            Status::Fail => "✗",
+        };
+        let mut text = format!("{} {}: {}", mark, self.name, self.detail);
+        if let Some(fix) = self.fix {
+            text.push_str(&format!("\n    fix: {}", fix));
+        }
+        text
+    }
+}
+
+fn check_config() -> Check {
+    let path = match paths::config_file() {
+        Ok(path) => path,
+        Err(e) => return Check::fail("config", e.to_string(), "set HOME, or XDG_CONFIG_HOME"),
+    };
+    match crate::load_config() {
+        Ok(_) if !path.exists() => Check::ok("config", format!("{} not found, using defaults", path.display())),
+        Ok(_) => Check::ok("config", format!("{} is valid", path.display())),
+        Err(e) => Check::fail(
+            "config",
+            format!("{:#}", e),
+            "run `reprompt config check` for line numbers, then fix config.toml, --set or REPROMPT_* variables",
+        ),
+    }
+}
+
+fn check_environment() -> Vec<Check> {
+    if is_wsl_custom() {
+        let powershell = match clipboard::powershell_latency() {
+            Ok(Some(elapsed)) if elapsed > SLOW_POWERSHELL => Check::warn(
+                "powershell.exe",
+                format!("responds in {}ms", elapsed.as_millis()),
+                "speed up your PowerShell profile or exclude powershell.exe from antivirus scans",
+            ),
+            Ok(Some(elapsed)) => Check::ok("powershell.exe", format!("responds in {}ms", elapsed.as_millis())),
+            Ok(None) => Check::fail(
+                "powershell.exe",
+                "not found on PATH",
+                "enable interop in /etc/wsl.conf ([interop] enabled=true, appendWindowsPath=true), then wsl.exe --shutdown",
+            ),
+            Err(e) => Check::fail("powershell.exe", format!("{:#}", e), "raise powershell_timeout_ms in config.toml"),
+        };
+        return vec![Check::ok("platform", "WSL, clipboard via powershell.exe"), powershell];
+    }
+
+    let mut checks = Vec::new();
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY");
+        let x11 = std::env::var_os("DISPLAY");
+        checks.push(match (&wayland, &x11) {
+            (Some(display), _) => Check::ok("display", format!("Wayland ({})", display.to_string_lossy())),
+            (None, Some(display)) => Check::ok("display", format!("X11 ({})", display.to_string_lossy())),
+            (None, None) => Check::fail(
+                "display",
+                "neither WAYLAND_DISPLAY nor DISPLAY is set",
+                "run inside a desktop session, or start a virtual display: Xvfb :99 & export DISPLAY=:99",
+            ),
+        });
+    }
+    checks.push(match arboard::Clipboard::new() {
+        Ok(_) => Check::ok("clipboard backend", "arboard initialized"),
+        Err(e) => Check::fail("clipboard backend", e.to_string(), "see the display check above"),
+    });
+    checks
+}
+
+/// Writes `PROBE`, reads it back, and puts the original text back.
+fn check_round_trip() -> Check {
+    const NAME: &str = "round-trip";
+    let _lock = match InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT) {
+        Ok(lock) => lock,
+        Err(e) => return Check::warn(NAME, format!("skipped: {}", e), "run doctor again when the clipboard is free"),
+    };
+    let original = match get_clipboard() {
+        Ok(text) => text,
+        Err(e) => return Check::fail(NAME, format!("read failed: {:#}", e), "see the checks above"),
+    };
+    let started = Instant::now();
+    let result = set_clipboard(PROBE).and_then(|()| get_clipboard());
+    let elapsed = started.elapsed();
+    let restored = set_clipboard(&original);
+    match (result, restored) {
+        (_, Err(e)) => Check::fail(NAME, format!("could not restore the clipboard: {:#}", e), "copy your text again"),
+        (Ok(readback), _) if readback == PROBE => {
+            Check::ok(NAME, format!("wrote and read back Unicode text in {}ms", elapsed.as_millis()))
+        }
+        (Ok(readback), _) => Check::fail(
+            NAME,
+            format!("read back {:?}", readback),
+            "another app may be rewriting the clipboard; try verify = \"whitespace\" in config.toml",
+        ),
+        (Err(e), _) => Check::fail(NAME, format!("{:#}", e), "see the checks above"),
+    }
+}
+
+fn check_watcher() -> Check {
+    match lock::holder_pid("watch") {
+        Some(pid) if InstanceLock::try_acquire("watch").ok().flatten().is_none() => {
+            Check::ok("watcher", format!("running (pid {})", pid))
+        }
+        _ => Check::ok("watcher", "not running"),
+    }
+}
+
+/// Runs every check, printing each result and a fix for anything that failed.
+pub fn run() -> Result<()> {
+    let mut checks = vec![check_config()];
+    checks.extend(check_environment());
+    checks.push(check_watcher());
+    checks.push(check_round_trip());
+
+    for check in &checks {
+        println!("{}", check.render());
+    }
+    let failed = checks.iter().filter(|check| check.status == Status::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::path::Path;
+
+const MB: f64 = 1024.0 * 1024.0;
+
+/// Everything the library can fail with. `kind()` names the variant for JSON
+/// output and callers that handle some failures differently (HTTP status
+/// codes, retries); the message is for people.
+#[derive(Debug, thiserror::Error)]
+pub enum RepromptError {
+    /// The system clipboard couldn't be read
+    #[error("Failed to read clipboard: {0}")]
+    ClipboardRead(String),
+    /// The system clipboard couldn't be written
+    #[error("Failed to write clipboard: {0}")]
+    ClipboardWrite(String),
+    /// `powershell.exe` hung or failed under WSL
+    #[error("{0}")]
+    InteropUnavailable(String),
+    /// Cleaned text failed a `[validation]` check set to abort
+    #[error("{reason}")]
+    ValidationFailed { reason: String },
+    /// Text whose broken encoding (mojibake) couldn't be repaired
+    #[error("{0}")]
+    EncodingRepairFailed(String),
+    /// Text handed to the library that it can't take, e.g. bytes that aren't UTF-8
+    #[error("{0}")]
+    InvalidInput(String),
+    /// A profile file that can't be read as one, e.g. because it isn't UTF-8
+    #[error("{0}")]
+    InvalidProfile(String),
+    /// `powershell.exe` answered under WSL, but with data that doesn't decode
+    #[error("{0}")]
+    InteropProtocol(String),
+    /// Over `[input] max_mb` with `oversize = "refuse"`
+    #[error(
+        "Input is {:.1} MB, over the {} MB limit; raise [input] max_mb or set oversize = \"chunk\"",
+        *.bytes as f64 / MB,
+        .limit / (1024 * 1024)
+    )]
+    TooLarge { bytes: usize, limit: usize },
+    /// No built-in or user profile has this name
+    #[error("Unknown profile '{0}' (see `reprompt profile list`)")]
+    UnknownProfile(String),
+    /// A config or profile file that isn't valid TOML or has a bad value
+    #[error("{context}: {error}")]
+    InvalidConfig { context: String, error: toml::de::Error },
+    /// A setting that parses but can't work, e.g. a replace rule without a replacement
+    #[error("{0}")]
+    InvalidSetting(String),
+    /// A file that couldn't be read or written
+    #[error("{context}: {error}")]
+    Io { context: String, error: std::io::Error },
+    /// An external program (a filter, `curl`, a keychain tool) failed or timed out
+    #[error("{0}")]
+    Command(String),
+    /// A WASM plugin that failed to load or run
+    #[error("{0}")]
+    Plugin(String),
+    /// Another reprompt instance holds the lock
+    #[error("Another reprompt instance{} is using the clipboard", .holder.map(|pid| format!(" (pid {})", pid)).unwrap_or_default())]
+    Busy { holder: Option<u32> },
+    /// A stage panicked; the message is the panic's
+    #[error("Cleaning panicked: {0}")]
+    Panicked(String),
+}
+
+impl RepromptError {
+    /// Short snake_case name of the variant, e.g. `clipboard_read`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ClipboardRead(_) => "clipboard_read",
+            Self::ClipboardWrite(_) => "clipboard_write",
+            Self::InteropUnavailable(_) => "interop_unavailable",
+            Self::ValidationFailed { .. } => "validation_failed",
+            Self::EncodingRepairFailed(_) => "encoding_repair_failed",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::InvalidProfile(_) => "invalid_profile",
+            Self::InteropProtocol(_) => "interop_protocol",
+            Self::TooLarge { .. } => "too_large",
+            Self::UnknownProfile(_) => "unknown_profile",
+            Self::InvalidConfig { .. } => "invalid_config",
+            Self::InvalidSetting(_) => "invalid_setting",
+            Self::Io { .. } => "io",
+            Self::Command(_) => "command",
+            Self::Plugin(_) => "plugin",
+            Self::Busy { .. } => "busy",
+            Self::Panicked(_) => "panicked",
+        }
+    }
+
+    /// An I/O error on `path`, e.g. `io("Failed to read", path, e)`.
+    pub fn io(action: &str, path: &Path, error: std::io::Error) -> Self {
+        Self::Io { context: format!("{} {}", action, path.display()), error }
+    }
+}
+
+impl From<std::io::Error> for RepromptError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io { context: "I/O error".to_string(), error }
+    }
+}
+
+pub type Result<T, E = RepromptError> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds_and_messages() {
+        let busy = RepromptError::Busy { holder: Some(42) };
+        assert_eq!((busy.kind(), busy.to_string().as_str()), ("busy", "Another reprompt instance (pid 42) is using the clipboard"));
+        let missing = RepromptError::io("Failed to read", Path::new("/tmp/x.toml"), std::io::ErrorKind::NotFound.into());
+        assert_eq!(missing.kind(), "io");
+        assert!(missing.to_string().starts_with("Failed to read /tmp/x.toml: "));
+        assert_eq!(RepromptError::InteropProtocol("bad frame".to_string()).kind(), "interop_protocol");
+    }
+}
@@ -0,0 +1,93 @@
+use std::process::ExitCode;
+
+use crate::stats::Outcome;
+
+/// What `reprompt` exits with, so a hotkey script can show a different
+/// notification for each outcome of cleaning the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Cleaned, or nothing to clean without `--fail-on-unchanged`
+    Cleaned = 0,
+    /// Nothing to clean with `--fail-on-unchanged`
+    Unchanged = 1,
+    /// `[validation]` or an invariant check rejected the cleaned text
+    Rejected = 2,
+    /// The clipboard couldn't be read or written, or another instance held it
+    Clipboard = 3,
+    /// The config, a profile or a `--set` value is invalid
+    Config = 4,
+    /// The clipboard was left alone: empty, a password or code, or over `max_mb`
+    Skipped = 5,
+    /// Any other error
+    Failed = 6,
+}
+
+impl Exit {
+    /// The status for cleaning the clipboard once; `None` when it was skipped
+    /// (empty, sensitive, or over `max_mb`).
+    pub fn of(outcome: Option<Outcome>, fail_on_unchanged: bool) -> Self {
+        match outcome {
+            None => Exit::Skipped,
+            Some(Outcome::Cleaned) => Exit::Cleaned,
+            Some(Outcome::ValidationFailed) => Exit::Rejected,
+            Some(Outcome::CommitFailed) => Exit::Clipboard,
+            _ if fail_on_unchanged => Exit::Unchanged,
+            _ => Exit::Cleaned,
+        }
+    }
+
+    /// The status for a command that failed with `e`.
+    pub fn of_error(e: &anyhow::Error) -> Self {
+        match crate::error_kind(e) {
+            "validation_failed" => Exit::Rejected,
+            "clipboard_read" | "clipboard_write" | "interop_unavailable" | "interop_protocol" | "busy" => Exit::Clipboard,
+            "invalid_config" | "invalid_setting" | "invalid_profile" | "unknown_profile" => Exit::Config,
+            "too_large" => Exit::Skipped,
+            _ => Exit::Failed,
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::error::RepromptError;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(Exit::of(Some(Outcome::Cleaned), true), Exit::Cleaned);
+        assert_eq!(Exit::of(Some(Outcome::Unchanged), false), Exit::Cleaned);
+        assert_eq!(Exit::of(Some(Outcome::HeldBack), true), Exit::Unchanged);
+        assert_eq!(Exit::of(None, false), Exit::Skipped);
+        assert_eq!(Exit::of(Some(Outcome::ValidationFailed), false), Exit::Rejected);
+
+        let busy = anyhow::Error::from(RepromptError::Busy { holder: None }).context("Failed to clean");
+        assert_eq!(Exit::of_error(&busy), Exit::Clipboard);
+        assert_eq!(Exit::of_error(&RepromptError::UnknownProfile("x".to_string()).into()), Exit::Config);
+        assert_eq!(Exit::of_error(&anyhow::anyhow!("something else")), Exit::Failed);
+    }
+
+    #[test]
+    fn test_too_large_clipboard_is_skipped() {
+        let dir = std::env::temp_dir().join(format!("reprompt-exit-{}", std::process::id()));
+        reprompt::paths::set_state_dir(dir.clone());
+        let mut config = reprompt::config::Config::default();
+        config.input.max_mb = 1;
+        let cleaner = reprompt::cleaner::Cleaner::new(config, Some("generic"), None, false).unwrap();
+        let transaction = crate::ClipboardTransaction {
+            original: "│ text │\n".repeat(200_000),
+            recovered: None,
+            modified: None,
+            html: None,
+        };
+        let cleaned = crate::clean_transaction(&cleaner, transaction, Default::default(), None, Default::default()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(Exit::of(cleaned.map(|(_, outcome)| outcome), false), Exit::Skipped);
+    }
+}
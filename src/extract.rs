@@ -0,0 +1,272 @@
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::pipeline::{Pipeline, StageKind, StageOptions};
+use crate::stages::{is_borderish, is_vertical_divider};
+
+lazy_static! {
+    static ref RE_COLON_PAIR: Regex = Regex::new(r"^([^:]{1,40}?):\s+(\S.*)$").expect("Invalid Colon Pair Regex");
+
+    // Key and value separated by a gap of two or more spaces
+    static ref RE_ALIGNED_PAIR: Regex = Regex::new(r"^(\S(?:.*?\S)?)\s{2,}(\S.*)$").expect("Invalid Aligned Pair Regex");
+}
+
+/// Per-pane cleanup before sections are split out
+const PANE_STAGES: &[StageKind] = &[StageKind::Glyphs, StageKind::Art, StageKind::Invisible];
+
+/// Output format for `reprompt extract`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+/// A section's content: key/value pairs when every line is one, else plain lines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Lines(Vec<String>),
+    Pairs(Vec<(String, String)>),
+}
+
+impl Value {
+    fn from_lines(lines: Vec<String>) -> Self {
+        match key_values(&lines) {
+            Some(pairs) => Value::Pairs(pairs),
+            None => Value::Lines(lines),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Lines(lines) => lines.serialize(serializer),
+            Value::Pairs(pairs) => {
+                let mut map = serializer.serialize_map(Some(pairs.len()))?;
+                for (key, value) in pairs {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Titled sections in the order they appear; serializes as a title → content map.
+#[derive(Debug, Default)]
+pub struct Sections(Vec<(String, Value)>);
+
+impl Serialize for Sections {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (title, value) in &self.0 {
+            map.serialize_entry(title, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Sections {
+    pub fn render(&self, format: Format) -> serde_json::Result<String> {
+        Ok(match format {
+            Format::Json => serde_json::to_string_pretty(self)?,
+            Format::Yaml => self.to_yaml(),
+        })
+    }
+
+    /// YAML with every scalar double-quoted (JSON string syntax is valid YAML).
+    fn to_yaml(&self) -> String {
+        let quote = |text: &str| serde_json::Value::from(text).to_string();
+        let mut output = String::new();
+        for (title, value) in &self.0 {
+            output.push_str(&format!("{}:", quote(title)));
+            match value {
+                Value::Lines(lines) if lines.is_empty() => output.push_str(" []\n"),
+                Value::Lines(lines) => {
+                    output.push('\n');
+                    for line in lines {
+                        output.push_str(&format!("  - {}\n", quote(line)));
+                    }
+                }
+                Value::Pairs(pairs) => {
+                    output.push('\n');
+                    for (key, value) in pairs {
+                        output.push_str(&format!("  {}: {}\n", quote(key), quote(value)));
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Builds sections title by title, merging lines under repeated titles.
+#[derive(Default)]
+struct Builder(Vec<(String, Vec<String>)>);
+
+impl Builder {
+    fn add(&mut self, title: &str, lines: impl IntoIterator<Item = String>) {
+        match self.0.iter_mut().find(|(existing, _)| existing == title) {
+            Some((_, existing)) => existing.extend(lines),
+            None => self.0.push((title.to_string(), lines.into_iter().collect())),
+        }
+    }
+
+    /// Splits a pane into blank-separated blocks. A block of two or more lines
+    /// is titled by its first line; single lines go under `fallback`.
+    fn add_pane(&mut self, fallback: &str, pane: Vec<String>) {
+        let lines = Pipeline::new(PANE_STAGES, StageOptions::default()).run(&pane.join("\n"));
+        let lines: Vec<String> = lines.lines().map(|line| line.trim().to_string()).collect();
+        for block in lines.split(|line| line.is_empty()).filter(|block| !block.is_empty()) {
+            match block {
+                [single] => self.add(fallback, [single.clone()]),
+                [title, rest @ ..] => self.add(title, rest.iter().cloned()),
+                [] => {}
+            }
+        }
+    }
+
+    fn finish(self) -> Sections {
+        Sections(
+            self.0
+                .into_iter()
+                .filter(|(_, lines)| !lines.is_empty())
+                .map(|(title, lines)| (title, Value::from_lines(lines)))
+                .collect(),
+        )
+    }
+}
+
+/// Lines made only of box-drawing characters (borders, `├──┤` separators).
+fn is_frame(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && text.chars().all(|c| c.is_whitespace() || is_borderish(c))
+}
+
+/// The text of a titled border (`╭─── Title ───╮`), if any.
+fn border_title(text: &str) -> String {
+    let title: String = text.chars().filter(|&c| !is_borderish(c)).collect();
+    title.trim().to_string()
+}
+
+/// Splits `│ a │ b │` into its cells, dropping the outer frame.
+fn cells(text: &str) -> Vec<String> {
+    let text = text.trim();
+    let mut chars = text.chars();
+    chars.next();
+    let inner = chars.as_str();
+    let inner = inner.strip_suffix(is_vertical_divider).unwrap_or(inner);
+    inner
+        .split(is_vertical_divider)
+        .map(|cell| if is_frame(cell) { String::new() } else { cell.to_string() })
+        .collect()
+}
+
+/// Reads every line as `key: value`, or as aligned `key   value` columns.
+fn key_values(lines: &[String]) -> Option<Vec<(String, String)>> {
+    let pair = |caps: regex::Captures| (caps[1].trim().to_string(), caps[2].trim().to_string());
+    if let Some(pairs) = lines.iter().map(|line| RE_COLON_PAIR.captures(line).map(pair)).collect() {
+        return Some(pairs);
+    }
+    if lines.len() < 2 {
+        return None;
+    }
+    // Aligned pairs must start their values in the same column
+    let columns: Vec<usize> = lines
+        .iter()
+        .map(|line| Some(line[..RE_ALIGNED_PAIR.captures(line)?.get(2)?.start()].chars().count()))
+        .collect::<Option<_>>()?;
+    if columns.iter().any(|&column| column != columns[0]) {
+        return None;
+    }
+    lines.iter().map(|line| RE_ALIGNED_PAIR.captures(line).map(pair)).collect()
+}
+
+/// Finds boxes and side-by-side panes in `text` and turns them into sections.
+/// Untitled lines are filed under their box's title, or `""` outside boxes.
+pub fn extract(text: &str) -> Sections {
+    let text = Pipeline::new(&[StageKind::Mojibake, StageKind::Ansi], StageOptions::default()).run(text);
+    let mut builder = Builder::default();
+
+    // The open box's title and panes, or lines outside any box
+    let mut boxed: Option<(String, Vec<Vec<String>>)> = None;
+    let mut plain: Vec<String> = Vec::new();
+
+    let flush_box = |builder: &mut Builder, boxed: &mut Option<(String, Vec<Vec<String>>)>| {
+        if let Some((title, panes)) = boxed.take() {
+            for pane in panes {
+                builder.add_pane(&title, pane);
+            }
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let first = trimmed.chars().next();
+        if matches!(first, Some('╭' | '┌' | '╔' | '┏')) {
+            flush_box(&mut builder, &mut boxed);
+            builder.add_pane("", std::mem::take(&mut plain));
+            boxed = Some((border_title(line), Vec::new()));
+        } else if matches!(first, Some('╰' | '└' | '╚' | '┗')) {
+            flush_box(&mut builder, &mut boxed);
+        } else if first.is_some_and(is_vertical_divider) {
+            if boxed.is_none() {
+                builder.add_pane("", std::mem::take(&mut plain));
+            }
+            let (_, panes) = boxed.get_or_insert_with(|| (String::new(), Vec::new()));
+            let row = cells(line);
+            if panes.len() < row.len() {
+                // A pane appearing mid-box starts out blank alongside the others
+                let height = panes.first().map_or(0, Vec::len);
+                panes.resize(row.len(), vec![String::new(); height]);
+            }
+            for (i, pane) in panes.iter_mut().enumerate() {
+                pane.push(row.get(i).cloned().unwrap_or_default());
+            }
+        } else if let Some((_, panes)) = boxed.as_mut().filter(|_| is_frame(line)) {
+            panes.iter_mut().for_each(|pane| pane.push(String::new()));
+        } else {
+            flush_box(&mut builder, &mut boxed);
+            plain.push(line.to_string());
+        }
+    }
+    flush_box(&mut builder, &mut boxed);
+    builder.add_pane("", plain);
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_side_by_side_panes() {
+        let input = "╭─── Claude Code v2.0.47 ────────────────────────────────╮\n\
+                     │                          │ Recent activity             │\n\
+                     │    Welcome back Ain!     │ No recent activity          │\n\
+                     │                          │ ─────────────────────────── │\n\
+                     │         ▐▛███▜▌          │ What's new                  │\n\
+                     │        ▝▜█████▛▘         │ Added /context              │\n\
+                     │                          │ Fixed paste on Windows      │\n\
+                     │      /home/ain3sh        │                             │\n\
+                     ╰────────────────────────────────────────────────────────╯";
+        let sections = extract(input);
+        let json = serde_json::to_string(&sections).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Claude Code v2.0.47":["Welcome back Ain!","/home/ain3sh"],"Recent activity":["No recent activity"],"What's new":["Added /context","Fixed paste on Windows"]}"#
+        );
+    }
+
+    #[test]
+    fn test_key_values_and_yaml() {
+        let sections = extract("Context\nCluster:   prod-eu\nUser:      admin\n\nPods\nNAME   READY\napi-1  1/1");
+        let yaml = sections.render(Format::Yaml).unwrap();
+        assert_eq!(
+            yaml,
+            "\"Context\":\n  \"Cluster\": \"prod-eu\"\n  \"User\": \"admin\"\n\"Pods\":\n  \"NAME\": \"READY\"\n  \"api-1\": \"1/1\"\n"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! C interface for embedding the cleaner, e.g. in clipboard managers. The
+//! header is `include/reprompt.h`, generated with `cbindgen` (see `cbindgen.toml`).
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::cleaner::Cleaner;
+use crate::config::Config;
+use crate::error::{RepromptError, Result};
+
+/// Options for `reprompt_clean` and `reprompt_cleaner_new`. A null pointer means all defaults.
+#[repr(C)]
+pub struct RepromptOptions {
+    /// Profile name (e.g. "claude-code"), or null to detect it from the text
+    pub profile: *const c_char,
+    /// Only keep cleaning that clearly improves the text, like `--conservative`
+    pub conservative: bool,
+    /// Skip user-defined `[[substitutions]]`
+    pub no_substitutions: bool,
+}
+
+thread_local! {
+    /// Message and `RepromptError::kind` of the last failure
+    static LAST_ERROR: RefCell<Option<(CString, CString)>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String, kind: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    let kind = CString::new(kind).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((message, kind)));
+}
+
+fn not_utf8(name: &str) -> impl FnOnce(std::str::Utf8Error) -> RepromptError + '_ {
+    move |e| RepromptError::InvalidInput(format!("{} is not UTF-8: {}", name, e))
+}
+
+/// Runs `f`, recording its error or panic for `reprompt_last_error` and
+/// returning `failed` instead.
+fn guard<T>(f: impl FnOnce() -> Result<T>, failed: T) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string(), e.kind());
+            failed
+        }
+        Err(_) => {
+            set_last_error("reprompt panicked while cleaning".to_string(), "panicked");
+            failed
+        }
+    }
+}
+
+unsafe fn new_cleaner(options: *const RepromptOptions) -> Result<Cleaner> {
+    let options = options.as_ref();
+    let profile = match options.map_or(ptr::null(), |options| options.profile) {
+        profile if profile.is_null() => None,
+        profile => Some(CStr::from_ptr(profile).to_str().map_err(not_utf8("profile"))?),
+    };
+    let mut cleaner = Cleaner::new(
+        Config::load()?,
+        profile,
+        None,
+        options.is_some_and(|options| options.no_substitutions),
+    )?;
+    if options.is_some_and(|options| options.conservative) {
+        cleaner.set_conservative();
+    }
+    Ok(cleaner)
+}
+
+unsafe fn clean(cleaner: &Cleaner, text: *const c_char) -> Result<CString> {
+    if text.is_null() {
+        return Err(RepromptError::InvalidSetting("text is null".to_string()));
+    }
+    let text = CStr::from_ptr(text).to_str().map_err(not_utf8("text"))?;
+    // Cleaned text never gains NUL bytes the input didn't have, and C strings can't hold any
+    CString::new(cleaner.clean_checked(text, None)?.output)
+        .map_err(|e| RepromptError::InvalidInput(e.to_string()))
+}
+
+/// A cleaner built once from the user's config, for hosts that clean often.
+/// Opaque to C; use it from one thread at a time.
+pub struct RepromptCleaner(Cleaner);
+
+/// Loads the user's config and builds a cleaner with `options`, to pass to
+/// `reprompt_cleaner_clean` and release with `reprompt_cleaner_free`. Returns
+/// null on failure (see `reprompt_last_error`). Config changes need a new one.
+///
+/// # Safety
+///
+/// `options` must be null or point to a valid `RepromptOptions`, whose
+/// `profile` is null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn reprompt_cleaner_new(options: *const RepromptOptions) -> *mut RepromptCleaner {
+    guard(
+        || Ok(Box::into_raw(Box::new(RepromptCleaner(new_cleaner(options)?)))),
+        ptr::null_mut(),
+    )
+}
+
+/// Cleans `text` with `cleaner`. Returns a new string to release with
+/// `reprompt_free`, or null on failure (see `reprompt_last_error`).
+///
+/// # Safety
+///
+/// `cleaner` must come from `reprompt_cleaner_new` and not be freed yet.
+/// `text` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn reprompt_cleaner_clean(cleaner: *const RepromptCleaner, text: *const c_char) -> *mut c_char {
+    guard(
+        || match cleaner.as_ref() {
+            Some(cleaner) => Ok(clean(&cleaner.0, text)?.into_raw()),
+            None => Err(RepromptError::InvalidSetting("cleaner is null".to_string())),
+        },
+        ptr::null_mut(),
+    )
+}
+
+/// Releases a cleaner from `reprompt_cleaner_new`. Null is ignored.
+///
+/// # Safety
+///
+/// `cleaner` must be null or come from `reprompt_cleaner_new`, and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn reprompt_cleaner_free(cleaner: *mut RepromptCleaner) {
+    if !cleaner.is_null() {
+        drop(Box::from_raw(cleaner));
+    }
+}
+
+/// Cleans `text` with the user's config, as the `reprompt` command would.
+/// Returns a new string to release with `reprompt_free`, or null on failure
+/// (see `reprompt_last_error`). Loads the config on every call; to clean
+/// more than once, keep a `reprompt_cleaner_new` handle instead.
+///
+/// # Safety
+///
+/// `text` must be a NUL-terminated string. `options` must be null or point to a
+/// valid `RepromptOptions`, whose `profile` is null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn reprompt_clean(text: *const c_char, options: *const RepromptOptions) -> *mut c_char {
+    guard(|| Ok(clean(&new_cleaner(options)?, text)?.into_raw()), ptr::null_mut())
+}
+
+/// Releases a string returned by `reprompt_clean` or `reprompt_cleaner_clean`.
+/// Null is ignored.
+///
+/// # Safety
+///
+/// `text` must be null or come from `reprompt_clean` or `reprompt_cleaner_clean`,
+/// and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn reprompt_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Why the last `reprompt_*` call on this thread returned null, or null if none
+/// failed. The string stays valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn reprompt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(message, _)| message.as_ptr()))
+}
+
+/// What kind of failure `reprompt_last_error` describes, e.g. "unknown_profile"
+/// or "too_large", or null if none failed. Valid for as long as that message.
+#[no_mangle]
+pub extern "C" fn reprompt_last_error_kind() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(_, kind)| kind.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_and_errors() {
+        let text = CString::new("│ hello │").unwrap();
+        let profile = CString::new("generic").unwrap();
+        let options = RepromptOptions { profile: profile.as_ptr(), conservative: false, no_substitutions: false };
+        unsafe {
+            let cleaned = reprompt_clean(text.as_ptr(), &options);
+            assert_eq!(CStr::from_ptr(cleaned).to_str().unwrap(), "hello");
+            reprompt_free(cleaned);
+
+            let missing = CString::new("no-such-profile").unwrap();
+            let options = RepromptOptions { profile: missing.as_ptr(), ..options };
+            assert!(reprompt_clean(text.as_ptr(), &options).is_null());
+            assert!(!reprompt_last_error().is_null());
+            assert_eq!(CStr::from_ptr(reprompt_last_error_kind()).to_str().unwrap(), "unknown_profile");
+        }
+    }
+
+    #[test]
+    fn test_cleaner_handle() {
+        let profile = CString::new("generic").unwrap();
+        let options = RepromptOptions { profile: profile.as_ptr(), conservative: false, no_substitutions: false };
+        unsafe {
+            let cleaner = reprompt_cleaner_new(&options);
+            assert!(!cleaner.is_null());
+            for (text, expected) in [("│ one │", "one"), ("│ two │", "two")] {
+                let text = CString::new(text).unwrap();
+                let cleaned = reprompt_cleaner_clean(cleaner, text.as_ptr());
+                assert_eq!(CStr::from_ptr(cleaned).to_str().unwrap(), expected);
+                reprompt_free(cleaned);
+            }
+            assert!(reprompt_cleaner_clean(cleaner, ptr::null()).is_null());
+            reprompt_cleaner_free(cleaner);
+        }
+    }
+}
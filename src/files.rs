@@ -0,0 +1,50 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{RepromptError, Result};
+
+/// Writes `text` to `path` readable only by the owner, through a temporary file
+/// so a crash can't leave it half-written. Creates the parent directory.
+pub fn write_private(path: &Path, text: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| RepromptError::io("Failed to create", dir, e))?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = Path::new(&temp);
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let write = || -> std::io::Result<()> {
+        let mut file = options.open(temp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(temp, path)
+    };
+    write().map_err(|e| RepromptError::io("Failed to write", path, e))
+}
+
+/// Overwrites a file with zeros before deleting it. Best effort: SSDs and
+/// copy-on-write file systems may keep the old blocks anyway.
+pub fn remove_securely(path: &Path) -> Result<()> {
+    overwrite_and_remove(path).map_err(|e| RepromptError::io("Failed to remove", path, e))
+}
+
+fn overwrite_and_remove(path: &Path) -> std::io::Result<()> {
+    let length = std::fs::metadata(path)?.len();
+    {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        let zeros = [0u8; 8192];
+        let mut left = length;
+        while left > 0 {
+            let count = left.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..count])?;
+            left -= count as u64;
+        }
+        file.sync_all()?;
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+#[cfg(unix)]
+use {reprompt::process::run_with_timeout, std::process::Command, std::time::Duration};
+
+/// How long `hyprctl`, `swaymsg` or `osascript` may take to name the focused
+/// window, so a hung one can't stall the watcher
+#[cfg(unix)]
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The window that had focus when a copy was seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Focus {
+    /// X11 class, Wayland app id, executable or application name
+    pub app: String,
+    pub title: String,
+}
+
+/// Stdout of `program args`, if it ran and succeeded within `TIMEOUT`.
+#[cfg(unix)]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = run_with_timeout(Command::new(program).args(args), b"", TIMEOUT)
+        .inspect_err(|e| tracing::debug!("Can't ask {} for the focused window: {}", program, e))
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Hyprland: `hyprctl activewindow -j`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn hyprland(json: &str) -> Option<Focus> {
+    let window: serde_json::Value = serde_json::from_str(json).ok()?;
+    let field = |name: &str| window[name].as_str().unwrap_or_default().to_string();
+    Some(Focus { app: field("class"), title: field("title") })
+}
+
+/// Sway: the focused node in `swaymsg -t get_tree`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn sway(node: &serde_json::Value) -> Option<Focus> {
+    if node["focused"].as_bool() == Some(true) {
+        let app = node["app_id"].as_str().or_else(|| node["window_properties"]["class"].as_str());
+        return Some(Focus { app: app.unwrap_or_default().to_string(), title: node["name"].as_str().unwrap_or_default().to_string() });
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(sway)
+}
+
+/// The focused window's application and title, or `None` where this
+/// desktop offers no way to ask (most Wayland compositors, WSL).
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn focused() -> Option<Focus> {
+    if reprompt::clipboard::is_wsl_custom() {
+        return None;
+    }
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        return hyprland(&output("hyprctl", &["activewindow", "-j"])?);
+    }
+    if std::env::var_os("SWAYSOCK").is_some() {
+        return sway(&serde_json::from_str(&output("swaymsg", &["-t", "get_tree"])?).ok()?);
+    }
+    // Under other Wayland compositors, X11 only knows about XWayland windows
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() && std::env::var_os("DISPLAY").is_some() {
+        return x11::active_window().ok().flatten();
+    }
+    None
+}
+
+#[cfg(target_os = "macos")]
+pub fn focused() -> Option<Focus> {
+    let script = "tell application \"System Events\" to get name of first application process whose frontmost is true";
+    let app = output("osascript", &["-e", script])?.trim().to_string();
+    (!app.is_empty()).then(|| Focus { app, title: String::new() })
+}
+
+#[cfg(windows)]
+pub fn focused() -> Option<Focus> {
+    windows::foreground_window()
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn focused() -> Option<Focus> {
+    None
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11 {
+    use super::Focus;
+    use anyhow::Result;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _};
+
+    /// `_NET_ACTIVE_WINDOW` on the root window, with its `WM_CLASS` class and
+    /// `_NET_WM_NAME` (or `WM_NAME`) title.
+    pub fn active_window() -> Result<Option<Focus>> {
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let atom = |name: &[u8]| -> Result<u32> { Ok(conn.intern_atom(false, name)?.reply()?.atom) };
+        let (active, net_name, utf8) = (atom(b"_NET_ACTIVE_WINDOW")?, atom(b"_NET_WM_NAME")?, atom(b"UTF8_STRING")?);
+
+        let reply = conn.get_property(false, root, active, AtomEnum::WINDOW, 0, 1)?.reply()?;
+        let Some(window) = reply.value32().and_then(|mut ids| ids.next()).filter(|&id| id != 0) else {
+            return Ok(None);
+        };
+        let text = |property: u32, kind: u32| -> Result<Vec<u8>> {
+            Ok(conn.get_property(false, window, property, kind, 0, 1024)?.reply()?.value)
+        };
+        // WM_CLASS is "instance\0class\0"; the class is the one tools show
+        let class = text(AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?;
+        let app = class.split(|&b| b == 0).rfind(|part| !part.is_empty()).unwrap_or_default();
+        let mut title = text(net_name, utf8)?;
+        if title.is_empty() {
+            title = text(AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())?;
+        }
+        Ok(Some(Focus { app: String::from_utf8_lossy(app).into_owned(), title: String::from_utf8_lossy(&title).into_owned() }))
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::Focus;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+    /// The foreground window's title and its process's executable name.
+    pub fn foreground_window() -> Option<Focus> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+            let mut title = [0u16; 512];
+            let len = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32).max(0) as usize;
+
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            let mut app = String::new();
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if !process.is_null() {
+                let mut path = [0u16; 1024];
+                let mut size = path.len() as u32;
+                if QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, path.as_mut_ptr(), &mut size) != 0 {
+                    let path = String::from_utf16_lossy(&path[..size as usize]);
+                    app = path.rsplit(['\\', '/']).next().unwrap_or_default().to_string();
+                }
+                CloseHandle(process);
+            }
+            Some(Focus { app, title: String::from_utf16_lossy(&title[..len]) })
+        }
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compositor_replies() {
+        let focus = |app: &str, title: &str| Some(Focus { app: app.to_string(), title: title.to_string() });
+        assert_eq!(hyprland(r#"{"class": "kitty", "title": "vim"}"#), focus("kitty", "vim"));
+
+        let tree = serde_json::json!({
+            "nodes": [{"focused": false, "nodes": [
+                {"focused": false, "app_id": "firefox", "name": "GitHub"},
+                {"focused": true, "app_id": null, "window_properties": {"class": "URxvt"}, "name": "~"},
+            ]}],
+            "floating_nodes": [],
+        });
+        assert_eq!(sway(&tree), focus("URxvt", "~"));
+    }
+}
@@ -0,0 +1,167 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::classify::ContentKind;
+use crate::language;
+use crate::tables::{self, Table, TableFormat};
+
+/// What the cleaned text is turned into, on the clipboard and on stdout
+/// (`format`, `--format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The cleaned text, through `[templates]`
+    #[default]
+    Plain,
+    /// Code and logs in fences tagged with their language, tables as Markdown tables
+    Markdown,
+    /// The `--json` report, with the cleaned text as `output`
+    Json,
+    /// HTML, committed as the clipboard's rich-text flavor next to the plain text
+    Html,
+}
+
+/// A fence longer than any backtick fence inside `text`.
+fn fence_for(text: &str) -> String {
+    let longest = text
+        .lines()
+        .map(|line| line.trim_start().chars().take_while(|&c| c == '`').count())
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// The table `text` is made of, when it's a single table and nothing else.
+fn whole_table(text: &str) -> Option<Table> {
+    let text = text.trim();
+    if text.lines().any(|line| line.trim().is_empty()) {
+        return None;
+    }
+    tables::parse(text)
+}
+
+/// Cleaned text as Markdown: code and logs fenced (tagged with the guessed
+/// language), a table as a Markdown table, prose as it is.
+pub fn markdown(kind: ContentKind, text: &str) -> String {
+    let fenced = |language: &str| {
+        let fence = fence_for(text);
+        format!("{fence}{language}\n{text}\n{fence}")
+    };
+    match kind {
+        ContentKind::Code => fenced(language::detect(text).unwrap_or_default()),
+        ContentKind::Log => fenced(""),
+        ContentKind::Table => match whole_table(text) {
+            Some(table) => table.render(TableFormat::Markdown),
+            None => fenced(""),
+        },
+        ContentKind::Prose | ContentKind::Binary => text.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn pre(text: &str, language: Option<&str>) -> String {
+    match language.filter(|language| !language.is_empty()) {
+        Some(language) => format!("<pre><code class=\"language-{}\">{}</code></pre>", escape(language), escape(text)),
+        None => format!("<pre><code>{}</code></pre>", escape(text)),
+    }
+}
+
+fn html_table(table: &Table) -> String {
+    let row = |cells: &[String], tag: &str| {
+        let cells: String = cells.iter().map(|cell| format!("<{tag}>{}</{tag}>", escape(cell))).collect();
+        format!("<tr>{cells}</tr>")
+    };
+    let body: Vec<String> = table.rows.iter().map(|cells| row(cells, "td")).collect();
+    format!("<table>\n<thead>{}</thead>\n<tbody>\n{}\n</tbody>\n</table>", row(&table.header, "th"), body.join("\n"))
+}
+
+/// Prose as HTML: paragraphs split at blank lines, line breaks kept, and
+/// fenced blocks as `<pre>`.
+fn html_prose(text: &str) -> String {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = text.lines();
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<String>| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.drain(..).map(escape).collect();
+            blocks.push(format!("<p>{}</p>", lines.join("<br>\n")));
+        }
+    };
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush(&mut paragraph, &mut blocks);
+            let marker = &trimmed[..3];
+            let language = trimmed.trim_start_matches(['`', '~']).trim();
+            let code: Vec<&str> = lines.by_ref().take_while(|line| !line.trim_start().starts_with(marker)).collect();
+            blocks.push(pre(&code.join("\n"), Some(language)));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks.join("\n")
+}
+
+/// Cleaned text as an HTML fragment for rich-text paste targets: code and logs
+/// in `<pre>`, a table as `<table>`, prose as paragraphs.
+pub fn html(kind: ContentKind, text: &str) -> String {
+    match kind {
+        ContentKind::Code => pre(text, language::detect(text)),
+        ContentKind::Log | ContentKind::Binary => pre(text, None),
+        ContentKind::Table => match whole_table(text) {
+            Some(table) => html_table(&table),
+            None => pre(text, None),
+        },
+        ContentKind::Prose => html_prose(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_fences_and_tables() {
+        let code = "fn main() {\n    println!(\"```\");\n}";
+        assert_eq!(markdown(ContentKind::Code, code), format!("```rust\n{code}\n```"));
+        let nested = "```sh\nls\n```";
+        assert_eq!(markdown(ContentKind::Code, nested), format!("````\n{nested}\n````"));
+
+        let table = "NAME   READY   STATUS\nweb    1/1     Running\ndb     0/1     Pending";
+        assert_eq!(
+            markdown(ContentKind::Table, table),
+            "| NAME | READY | STATUS |\n| --- | --- | --- |\n| web | 1/1 | Running |\n| db | 0/1 | Pending |"
+        );
+        assert_eq!(markdown(ContentKind::Prose, "Just *prose*."), "Just *prose*.");
+    }
+
+    #[test]
+    fn test_html() {
+        let prose = "Run <this>:\nnow\n\n```sh\nls && pwd\n```\nDone.";
+        assert_eq!(
+            html(ContentKind::Prose, prose),
+            "<p>Run &lt;this&gt;:<br>\nnow</p>\n<pre><code class=\"language-sh\">ls &amp;&amp; pwd</code></pre>\n<p>Done.</p>"
+        );
+        let table = "id | name\n---+-----\n1  | ann";
+        assert_eq!(
+            html(ContentKind::Table, table),
+            "<table>\n<thead><tr><th>id</th><th>name</th></tr></thead>\n<tbody>\n<tr><td>1</td><td>ann</td></tr>\n</tbody>\n</table>"
+        );
+    }
+}
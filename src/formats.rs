@@ -0,0 +1,579 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+use reprompt::error::RepromptError;
+
+/// One flavor of what's on the clipboard, e.g. `text/html` or `CF_UNICODETEXT`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Format {
+    pub name: String,
+    /// Size of its data, when the backend can tell
+    pub bytes: Option<u64>,
+}
+
+/// One `bytes  name` line per format; `?` where the size is unknown.
+fn render(formats: &[Format]) -> String {
+    formats
+        .iter()
+        .map(|format| {
+            let bytes = format.bytes.map_or_else(|| "?".to_string(), |bytes| bytes.to_string());
+            format!("{:>10}  {}", bytes, format.name)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `reprompt formats`: every format the clipboard holds right now, with sizes,
+/// for finding out why a clean saw different text than the app pasted.
+pub fn run(json: bool) -> Result<()> {
+    let formats = list().map_err(|e| RepromptError::ClipboardRead(format!("{:#}", e)))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&formats)?);
+    } else if formats.is_empty() {
+        eprintln!("The clipboard is empty");
+    } else {
+        println!("{}", render(&formats));
+    }
+    Ok(())
+}
+
+/// `reprompt read --format <name>`: the raw bytes of one format, as the
+/// clipboard owner provides them, on stdout.
+pub fn read(name: &str) -> Result<()> {
+    let data = fetch(name).map_err(|e| RepromptError::ClipboardRead(format!("{:#}", e)))?;
+    let Some(data) = data else {
+        anyhow::bail!("The clipboard has no '{}' format (see `reprompt formats`)", name);
+    };
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&data)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Names RTF goes by on this clipboard, most common first.
+fn rtf_names() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &["public.rtf", "NeXT Rich Text Format v1.0 pasteboard type"]
+    } else if cfg!(windows) || reprompt::clipboard::is_wsl_custom() {
+        &["Rich Text Format"]
+    } else {
+        &["text/rtf", "application/rtf", "text/richtext"]
+    }
+}
+
+/// The clipboard's RTF flavor, if it holds one.
+pub fn fetch_rtf() -> Result<Option<Vec<u8>>> {
+    for name in rtf_names() {
+        if let Some(rtf) = fetch(name)? {
+            return Ok(Some(rtf));
+        }
+    }
+    Ok(None)
+}
+
+/// The clipboard's image, as a file an OCR program reads (PNG, TIFF or BMP).
+#[cfg(feature = "ocr")]
+pub fn fetch_image() -> Result<Option<Vec<u8>>> {
+    // Device-independent bitmaps lack the BMP file header
+    let names: &[(&str, bool)] = if cfg!(target_os = "macos") {
+        &[("public.png", false), ("public.tiff", false)]
+    } else if cfg!(windows) {
+        &[("PNG", false), ("CF_DIBV5", true), ("CF_DIB", true)]
+    } else if reprompt::clipboard::is_wsl_custom() {
+        &[("PNG", false), ("DeviceIndependentBitmap", true)]
+    } else {
+        &[("image/png", false), ("image/bmp", false), ("image/tiff", false), ("image/jpeg", false)]
+    };
+    for &(name, dib) in names {
+        if let Some(image) = fetch(name)? {
+            if !dib {
+                return Ok(Some(image));
+            }
+            return reprompt::ocr::dib_to_bmp(&image)
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("The clipboard's {} is too short to be a bitmap", name));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn list() -> Result<Vec<Format>> {
+    if reprompt::clipboard::is_wsl_custom() {
+        wsl::list()
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        wayland::list()
+    } else {
+        x11::list()
+    }
+}
+
+/// The data of format `name`; `None` when the clipboard doesn't offer it.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+    if reprompt::clipboard::is_wsl_custom() {
+        wsl::fetch(name)
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        wayland::fetch(name)
+    } else {
+        x11::fetch(name)
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn list() -> Result<Vec<Format>> {
+    use objc2_app_kit::NSPasteboard;
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    let Some(types) = pasteboard.types() else {
+        return Ok(Vec::new());
+    };
+    let formats = types
+        .iter()
+        .map(|kind| Format { name: kind.to_string(), bytes: pasteboard.dataForType(&kind).map(|data| data.length() as u64) })
+        .collect();
+    Ok(formats)
+}
+
+#[cfg(target_os = "macos")]
+fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::NSString;
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    let kind = NSString::from_str(name);
+    if !pasteboard.types().is_some_and(|types| types.containsObject(&kind)) {
+        return Ok(None);
+    }
+    Ok(pasteboard.dataForType(&kind).map(|data| data.to_vec()))
+}
+
+#[cfg(windows)]
+use windows::{fetch, list};
+
+#[cfg(not(any(unix, windows)))]
+fn list() -> Result<Vec<Format>> {
+    anyhow::bail!("Listing clipboard formats isn't supported on this platform")
+}
+
+#[cfg(not(any(unix, windows)))]
+fn fetch(_name: &str) -> Result<Option<Vec<u8>>> {
+    anyhow::bail!("Reading clipboard formats isn't supported on this platform")
+}
+
+/// The Windows clipboard seen from WSL, through `powershell.exe`. Formats are
+/// those .NET reports; text comes back as UTF-8.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod wsl {
+    use anyhow::Result;
+    use reprompt::clipboard::{decode_frames, run_powershell, FRAME_BYTES};
+
+    use super::Format;
+
+    /// Exit status of the read script when the format isn't on the clipboard
+    const ABSENT: i32 = 3;
+    /// Exit status of the read script when the format holds neither text nor bytes
+    const OPAQUE: i32 = 4;
+
+    /// Reads `<bytes>\t<name>` lines; a negative size means unknown.
+    pub(super) fn parse_listing(text: &str) -> Vec<Format> {
+        text.lines()
+            .filter_map(|line| line.trim_end().split_once('\t'))
+            .map(|(bytes, name)| Format { name: name.to_string(), bytes: bytes.parse::<u64>().ok() })
+            .collect()
+    }
+
+    /// Sizes are those of the .NET objects, so text counts as UTF-16.
+    pub fn list() -> Result<Vec<Format>> {
+        const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+            $data = [System.Windows.Forms.Clipboard]::GetDataObject(); \
+            if ($data) { foreach ($format in $data.GetFormats($false)) { \
+            try { $value = $data.GetData($format) } catch { $value = $null }; \
+            $bytes = if ($value -is [string]) { [System.Text.Encoding]::Unicode.GetByteCount($value) } \
+            elseif ($value -is [System.IO.MemoryStream]) { $value.Length } else { -1 }; \
+            [Console]::Out.WriteLine(\"$bytes`t$format\") } }";
+        match run_powershell(SCRIPT, b"")? {
+            Some(output) if output.status.success() => Ok(parse_listing(&String::from_utf8_lossy(&output.stdout))),
+            Some(output) => anyhow::bail!("PowerShell failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            None => anyhow::bail!("powershell.exe not found; is WSL interop enabled?"),
+        }
+    }
+
+    /// The name goes over stdin so it needs no quoting; the data comes back
+    /// as Base64 frames like `get_clipboard`'s.
+    pub fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+        let script = format!(
+            "$name = [Console]::In.ReadToEnd(); \
+             Add-Type -AssemblyName System.Windows.Forms; \
+             $data = [System.Windows.Forms.Clipboard]::GetDataObject(); \
+             if (-not $data -or -not $data.GetDataPresent($name, $false)) {{ exit {ABSENT} }}; \
+             $value = $data.GetData($name, $false); \
+             if ($value -is [string]) {{ $bytes = [System.Text.Encoding]::UTF8.GetBytes($value) }} \
+             elseif ($value -is [System.IO.MemoryStream]) {{ $bytes = $value.ToArray() }} else {{ exit {OPAQUE} }}; \
+             for ($i = 0; $i -lt $bytes.Length; $i += {FRAME_BYTES}) {{ \
+             [Console]::Out.WriteLine([Convert]::ToBase64String($bytes, $i, [Math]::Min({FRAME_BYTES}, $bytes.Length - $i))) }}"
+        );
+        match run_powershell(&script, name.as_bytes())? {
+            Some(output) if output.status.success() => Ok(Some(decode_frames(&String::from_utf8_lossy(&output.stdout))?)),
+            Some(output) if output.status.code() == Some(ABSENT) => Ok(None),
+            Some(output) if output.status.code() == Some(OPAQUE) => {
+                anyhow::bail!("'{}' holds a .NET object, not text or bytes", name)
+            }
+            Some(output) => anyhow::bail!("PowerShell failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            None => anyhow::bail!("powershell.exe not found; is WSL interop enabled?"),
+        }
+    }
+}
+
+/// Wayland through `wl-paste`, which lists MIME types and fetches any of them.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod wayland {
+    use anyhow::{Context, Result};
+    use reprompt::process::run_with_timeout;
+    use std::process::Command;
+    use std::time::Duration;
+
+    use super::Format;
+
+    /// How long each `wl-paste` gets, including the owner sending its data
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn types() -> Result<Vec<String>> {
+        let listed = run_with_timeout(Command::new("wl-paste").arg("--list-types"), b"", TIMEOUT)
+            .context("Failed to run wl-paste; is wl-clipboard installed?")?;
+        // wl-paste fails with "No selection" when the clipboard is empty
+        if !listed.status.success() {
+            return Ok(Vec::new());
+        }
+        let types = String::from_utf8_lossy(&listed.stdout).lines().map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect();
+        Ok(types)
+    }
+
+    fn paste(name: &str) -> Result<Vec<u8>> {
+        let output = run_with_timeout(Command::new("wl-paste").args(["--no-newline", "--type", name]), b"", TIMEOUT)?;
+        anyhow::ensure!(output.status.success(), "wl-paste failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        Ok(output.stdout)
+    }
+
+    /// Every type is fetched once to measure it.
+    pub fn list() -> Result<Vec<Format>> {
+        let formats = types()?
+            .into_iter()
+            .map(|name| {
+                let bytes = paste(&name).ok().map(|data| data.len() as u64);
+                Format { name, bytes }
+            })
+            .collect();
+        Ok(formats)
+    }
+
+    /// Only exact names: `wl-paste --type` alone would fall back to a
+    /// similar type.
+    pub fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+        if !types()?.iter().any(|offered| offered == name) {
+            return Ok(None);
+        }
+        paste(name).map(Some)
+    }
+}
+
+/// The X11 CLIPBOARD selection, asked about directly rather than through
+/// `arboard`, which only ever fetches text.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod x11 {
+    use anyhow::Result;
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, Property, Window, WindowClass,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+
+    use super::Format;
+
+    /// How long the CLIPBOARD owner gets to answer each request or send each chunk
+    const TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Targets that describe the selection rather than hold its content
+    const META: &[&str] = &["TARGETS", "MULTIPLE", "TIMESTAMP", "SAVE_TARGETS"];
+
+    /// A hidden window that CLIPBOARD conversions are delivered to.
+    struct Requestor {
+        conn: RustConnection,
+        window: Window,
+        clipboard: Atom,
+        property: Atom,
+    }
+
+    impl Requestor {
+        fn new() -> Result<Self> {
+            let (conn, screen) = x11rb::connect(None)?;
+            let window = conn.generate_id()?;
+            let root = conn.setup().roots[screen].root;
+            conn.create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                x11rb::COPY_FROM_PARENT,
+                // INCR transfers announce each chunk with a PropertyNotify
+                &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )?;
+            let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+            let property = conn.intern_atom(false, b"REPROMPT_SELECTION")?.reply()?.atom;
+            Ok(Self { conn, window, clipboard, property })
+        }
+
+        fn atom(&self, name: &str, only_if_exists: bool) -> Result<Atom> {
+            Ok(self.conn.intern_atom(only_if_exists, name.as_bytes())?.reply()?.atom)
+        }
+
+        /// Waits for the first event `matches` accepts; `None` on timeout.
+        fn wait<T>(&self, mut matches: impl FnMut(Event) -> Option<T>) -> Result<Option<T>> {
+            let started = Instant::now();
+            while started.elapsed() < TIMEOUT {
+                match self.conn.poll_for_event()? {
+                    Some(event) => {
+                        if let Some(found) = matches(event) {
+                            return Ok(Some(found));
+                        }
+                    }
+                    None => std::thread::sleep(Duration::from_millis(5)),
+                }
+            }
+            Ok(None)
+        }
+
+        /// Asks the owner to put the clipboard as `target` in our property.
+        /// False when it refuses or doesn't answer in time.
+        fn convert(&self, target: Atom) -> Result<bool> {
+            self.conn.convert_selection(self.window, self.clipboard, target, self.property, x11rb::CURRENT_TIME)?;
+            self.conn.flush()?;
+            let answered = self.wait(|event| match event {
+                Event::SelectionNotify(event) if event.requestor == self.window => Some(event.property != x11rb::NONE),
+                _ => None,
+            })?;
+            Ok(answered.unwrap_or(false))
+        }
+
+        /// Reads and deletes our property.
+        fn take(&self) -> Result<(Atom, Vec<u8>)> {
+            let reply = self.conn.get_property(true, self.window, self.property, AtomEnum::ANY, 0, u32::MAX / 4)?.reply()?;
+            self.conn.flush()?;
+            Ok((reply.type_, reply.value))
+        }
+
+        fn targets(&self) -> Result<Vec<Atom>> {
+            if !self.convert(self.atom("TARGETS", false)?)? {
+                return Ok(Vec::new());
+            }
+            let reply = self.conn.get_property(true, self.window, self.property, AtomEnum::ATOM, 0, 4096)?.reply()?;
+            Ok(reply.value32().map(|atoms| atoms.collect()).unwrap_or_default())
+        }
+
+        /// The clipboard as `target`, following an `INCR` transfer chunk by
+        /// chunk when the owner sends it that way.
+        fn fetch(&self, target: Atom, incr: Atom) -> Result<Option<Vec<u8>>> {
+            if !self.convert(target)? {
+                return Ok(None);
+            }
+            let (kind, value) = self.take()?;
+            if kind != incr {
+                return Ok(Some(value));
+            }
+            // Deleting the INCR property asked for the first chunk; an empty one ends the transfer
+            let mut data = Vec::new();
+            loop {
+                let sent = self.wait(|event| match event {
+                    Event::PropertyNotify(event)
+                        if event.window == self.window && event.atom == self.property && event.state == Property::NEW_VALUE =>
+                    {
+                        Some(())
+                    }
+                    _ => None,
+                })?;
+                anyhow::ensure!(sent.is_some(), "The clipboard owner stopped sending after {} bytes", data.len());
+                let (_, chunk) = self.take()?;
+                if chunk.is_empty() {
+                    return Ok(Some(data));
+                }
+                data.extend(chunk);
+            }
+        }
+    }
+
+    /// Whether the CLIPBOARD owner lists `target` among its TARGETS.
+    pub fn offers(target: &str) -> Result<bool> {
+        let requestor = Requestor::new()?;
+        // No client has ever named the target, so none can be offering it
+        let wanted = requestor.atom(target, true)?;
+        if wanted == x11rb::NONE {
+            return Ok(false);
+        }
+        Ok(requestor.targets()?.contains(&wanted))
+    }
+
+    /// Every target the CLIPBOARD owner offers, each fetched once to measure it.
+    pub fn list() -> Result<Vec<Format>> {
+        let requestor = Requestor::new()?;
+        let incr = requestor.atom("INCR", false)?;
+        let mut formats = Vec::new();
+        for target in requestor.targets()? {
+            let name = String::from_utf8_lossy(&requestor.conn.get_atom_name(target)?.reply()?.name).into_owned();
+            let bytes = if META.contains(&name.as_str()) {
+                None
+            } else {
+                requestor.fetch(target, incr)?.map(|data| data.len() as u64)
+            };
+            formats.push(Format { name, bytes });
+        }
+        Ok(formats)
+    }
+
+    pub fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+        let requestor = Requestor::new()?;
+        let wanted = requestor.atom(name, true)?;
+        if wanted == x11rb::NONE || !requestor.targets()?.contains(&wanted) {
+            return Ok(None);
+        }
+        requestor.fetch(wanted, requestor.atom("INCR", false)?)
+    }
+}
+
+/// The Win32 clipboard, format by format.
+#[cfg(windows)]
+mod windows {
+    use anyhow::Result;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, EnumClipboardFormats, GetClipboardData, GetClipboardFormatNameW, IsClipboardFormatAvailable,
+        OpenClipboard, RegisterClipboardFormatW,
+    };
+    use windows_sys::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+
+    use super::Format;
+
+    /// Formats Windows defines itself, which have no registered name
+    const STANDARD: &[(u32, &str)] = &[
+        (1, "CF_TEXT"),
+        (2, "CF_BITMAP"),
+        (3, "CF_METAFILEPICT"),
+        (4, "CF_SYLK"),
+        (5, "CF_DIF"),
+        (6, "CF_TIFF"),
+        (7, "CF_OEMTEXT"),
+        (8, "CF_DIB"),
+        (9, "CF_PALETTE"),
+        (10, "CF_PENDATA"),
+        (11, "CF_RIFF"),
+        (12, "CF_WAVE"),
+        (13, "CF_UNICODETEXT"),
+        (14, "CF_ENHMETAFILE"),
+        (15, "CF_HDROP"),
+        (16, "CF_LOCALE"),
+        (17, "CF_DIBV5"),
+    ];
+    /// Bitmaps, palettes and metafiles are GDI handles, not memory with a size
+    const GDI: &[u32] = &[2, 9, 14, 0x82, 0x8E];
+
+    /// The clipboard, open until dropped.
+    struct Opened;
+
+    impl Opened {
+        fn new() -> Result<Self> {
+            if unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+                anyhow::bail!("Failed to open the clipboard: {}", std::io::Error::last_os_error());
+            }
+            Ok(Opened)
+        }
+    }
+
+    impl Drop for Opened {
+        fn drop(&mut self) {
+            unsafe { CloseClipboard() };
+        }
+    }
+
+    /// `CF_` names for standard formats, registered names otherwise, `#<id>` for the rest.
+    fn name(format: u32) -> String {
+        if let Some((_, name)) = STANDARD.iter().find(|(id, _)| *id == format) {
+            return name.to_string();
+        }
+        let mut buffer = [0u16; 256];
+        let length = unsafe { GetClipboardFormatNameW(format, buffer.as_mut_ptr(), buffer.len() as i32) };
+        match length {
+            0 => format!("#{}", format),
+            length => String::from_utf16_lossy(&buffer[..length as usize]),
+        }
+    }
+
+    /// The reverse of `name`.
+    fn id(name: &str) -> u32 {
+        if let Some((id, _)) = STANDARD.iter().find(|(_, standard)| *standard == name) {
+            return *id;
+        }
+        if let Some(id) = name.strip_prefix('#').and_then(|id| id.parse().ok()) {
+            return id;
+        }
+        let wide: Vec<u16> = name.encode_utf16().chain([0]).collect();
+        unsafe { RegisterClipboardFormatW(wide.as_ptr()) }
+    }
+
+    pub fn list() -> Result<Vec<Format>> {
+        let _open = Opened::new()?;
+        let mut formats = Vec::new();
+        let mut format = unsafe { EnumClipboardFormats(0) };
+        while format != 0 {
+            let data = unsafe { GetClipboardData(format) };
+            let bytes = (!data.is_null() && !GDI.contains(&format)).then(|| unsafe { GlobalSize(data) } as u64);
+            formats.push(Format { name: name(format), bytes });
+            format = unsafe { EnumClipboardFormats(format) };
+        }
+        Ok(formats)
+    }
+
+    /// Text formats come back as stored: `CF_UNICODETEXT` is UTF-16 with its
+    /// terminating NUL.
+    pub fn fetch(name: &str) -> Result<Option<Vec<u8>>> {
+        let format = id(name);
+        anyhow::ensure!(!GDI.contains(&format), "'{}' is a GDI handle, not data that can be printed", name);
+        let _open = Opened::new()?;
+        if format == 0 || unsafe { IsClipboardFormatAvailable(format) } == 0 {
+            return Ok(None);
+        }
+        unsafe {
+            let data = GetClipboardData(format);
+            anyhow::ensure!(!data.is_null(), "Failed to get '{}': {}", name, std::io::Error::last_os_error());
+            let locked = GlobalLock(data) as *const u8;
+            anyhow::ensure!(!locked.is_null(), "Failed to lock '{}': {}", name, std::io::Error::last_os_error());
+            let bytes = std::slice::from_raw_parts(locked, GlobalSize(data)).to_vec();
+            GlobalUnlock(data);
+            Ok(Some(bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_formats() {
+        let formats = [
+            Format { name: "text/plain;charset=utf-8".to_string(), bytes: Some(1234) },
+            Format { name: "TARGETS".to_string(), bytes: None },
+        ];
+        assert_eq!(render(&formats), "      1234  text/plain;charset=utf-8\n         ?  TARGETS");
+        #[cfg(all(unix, not(target_os = "macos")))]
+        assert_eq!(wsl::parse_listing("26\tUnicodeText\r\n-1\tBitmap\r\n"), [
+            Format { name: "UnicodeText".to_string(), bytes: Some(26) },
+            Format { name: "Bitmap".to_string(), bytes: None },
+        ]);
+    }
+}
@@ -0,0 +1,171 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+#[cfg(not(target_family = "wasm"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Style of the provenance header (`header`, `--header`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderFormat {
+    /// One `<!-- reprompt: … -->` line, invisible once Markdown is rendered
+    Comment,
+    /// YAML front matter between `---` lines
+    Yaml,
+}
+
+/// Where and when the text was cleaned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Context {
+    /// Detected (or `--profile`) tool, if not the generic profile
+    pub source: Option<String>,
+    /// Seconds since the Unix epoch; unknown on WebAssembly
+    pub time: Option<u64>,
+    pub cwd: Option<PathBuf>,
+    /// Git branch of `cwd`, or the short commit on a detached HEAD
+    pub branch: Option<String>,
+}
+
+impl Context {
+    /// Context of the current process.
+    pub fn gather(source: Option<&str>) -> Self {
+        let cwd = std::env::current_dir().ok();
+        // There's no clock to read in the browser, and asking panics
+        #[cfg(not(target_family = "wasm"))]
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|elapsed| elapsed.as_secs());
+        #[cfg(target_family = "wasm")]
+        let time = None;
+        Self {
+            source: source.map(str::to_string),
+            time,
+            branch: cwd.as_deref().and_then(git_branch),
+            cwd,
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(source) = &self.source {
+            fields.push(("source", source.clone()));
+        }
+        if let Some(time) = self.time {
+            fields.push(("time", iso8601(time)));
+        }
+        if let Some(cwd) = &self.cwd {
+            fields.push(("cwd", cwd.display().to_string()));
+        }
+        if let Some(branch) = &self.branch {
+            fields.push(("branch", branch.clone()));
+        }
+        fields
+    }
+
+    /// The header, ending with a blank line.
+    pub fn render(&self, format: HeaderFormat) -> String {
+        let fields = self.fields();
+        match format {
+            HeaderFormat::Comment => {
+                let fields: Vec<String> = fields.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+                format!("<!-- reprompt: {} -->\n\n", fields.join(", ").replace("-->", "--&gt;"))
+            }
+            HeaderFormat::Yaml => {
+                // JSON strings are valid YAML scalars and need no further escaping
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}\n", key, serde_json::Value::from(value.as_str())))
+                    .collect();
+                format!("---\n{}---\n\n", fields.concat())
+            }
+        }
+    }
+}
+
+/// Whether `text` already starts with a header in either format.
+pub fn has_header(text: &str) -> bool {
+    if text.starts_with("<!-- reprompt: ") {
+        return true;
+    }
+    let Some(rest) = text.strip_prefix("---\n") else {
+        return false;
+    };
+    let block: Vec<&str> = rest.lines().take_while(|line| *line != "---").collect();
+    !block.is_empty()
+        && block.len() < rest.lines().count()
+        && block.iter().all(|line| ["source: ", "time: ", "cwd: ", "branch: "].iter().any(|key| line.starts_with(key)))
+}
+
+/// Formats Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`.
+fn iso8601(seconds: u64) -> String {
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+/// Branch checked out in the repository containing `dir`, read from `HEAD`
+/// without running git. Worktrees and submodules (a `.git` file) are followed.
+fn git_branch(dir: &Path) -> Option<String> {
+    let dot_git = dir.ancestors().map(|dir| dir.join(".git")).find(|path| path.exists())?;
+    let git_dir = if dot_git.is_file() {
+        let pointer = std::fs::read_to_string(&dot_git).ok()?;
+        let target = Path::new(pointer.strip_prefix("gitdir:")?.trim());
+        dot_git.parent()?.join(target)
+    } else {
+        dot_git
+    };
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string()),
+        None => head.get(..7).map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_formats() {
+        let context = Context {
+            source: Some("claude-code".to_string()),
+            time: Some(1_791_115_200),
+            cwd: Some(PathBuf::from("/home/ain/app: v2")),
+            branch: Some("main".to_string()),
+        };
+        let comment = context.render(HeaderFormat::Comment);
+        assert_eq!(
+            comment,
+            "<!-- reprompt: source=claude-code, time=2026-10-04T12:00:00Z, cwd=/home/ain/app: v2, branch=main -->\n\n"
+        );
+        let yaml = context.render(HeaderFormat::Yaml);
+        assert_eq!(
+            yaml,
+            "---\nsource: \"claude-code\"\ntime: \"2026-10-04T12:00:00Z\"\ncwd: \"/home/ain/app: v2\"\nbranch: \"main\"\n---\n\n"
+        );
+        assert!(has_header(&comment) && has_header(&format!("{}text", yaml)));
+        assert!(!has_header("---\ntitle: notes\n---\n") && !has_header("---\n"));
+    }
+
+    #[test]
+    fn test_git_branch_from_head() {
+        let dir = std::env::temp_dir().join(format!("reprompt-git-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join(".git/HEAD"), "ref: refs/heads/feature/x\n").unwrap();
+        let branch = git_branch(&dir.join("src"));
+        std::fs::write(dir.join(".git/HEAD"), "0123456789abcdef\n").unwrap();
+        let detached = git_branch(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(branch.as_deref(), Some("feature/x"));
+        assert_eq!(detached.as_deref(), Some("0123456"));
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use reprompt::clipboard::{get_clipboard, with_retry};
+use reprompt::config::Config;
+use reprompt::paths;
+
+/// SHA-256 of `text`'s words, line by line, leaving out blank lines, so a copy
+/// that differs only in spacing, indentation or line endings matches too.
+pub fn fingerprint(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    for line in text.lines() {
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+        for word in words {
+            hasher.update(word.as_bytes());
+            hasher.update(b" ");
+        }
+        hasher.update(b"\n");
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Fingerprints in `path`, one per line; none when it's missing.
+fn load(path: &Path) -> BTreeSet<String> {
+    std::fs::read_to_string(path)
+        .map(|text| text.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Adds `fingerprint` to `path`; false if it was already there.
+fn add(path: &Path, fingerprint: String) -> Result<bool> {
+    let mut known = load(path);
+    if !known.insert(fingerprint) {
+        return Ok(false);
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let text: String = known.iter().map(|line| format!("{}\n", line)).collect();
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Why `reprompt watch` should leave `text` alone, if it should: it was
+/// marked with `reprompt ignore`, or it matches a `[watch] ignore` pattern.
+pub fn reason(config: &Config, text: &str) -> Option<&'static str> {
+    if config.watch.ignores(text) {
+        Some("it matches a [watch] ignore pattern")
+    } else if paths::ignore_file().is_ok_and(|path| load(&path).contains(&fingerprint(text))) {
+        Some("it was marked with `reprompt ignore`")
+    } else {
+        None
+    }
+}
+
+/// `reprompt ignore`: remembers the clipboard's current content so watch mode
+/// never cleans it, or anything differing only in whitespace, again. With
+/// `clear`, forgets everything remembered so far.
+pub fn run(clear: bool) -> Result<()> {
+    let path = paths::ignore_file()?;
+    if clear {
+        match std::fs::remove_file(&path) {
+            Ok(()) => tracing::info!("Forgot all ignored clipboard contents"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => tracing::info!("Nothing was ignored"),
+            Err(e) => return Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+        }
+        return Ok(());
+    }
+    let text = with_retry(get_clipboard).context("Failed to read clipboard")?;
+    if text.trim().is_empty() {
+        bail!("Clipboard is empty; nothing to ignore");
+    }
+    if add(&path, fingerprint(&text))? {
+        tracing::info!("Watch mode will leave this clipboard content alone from now on");
+    } else {
+        tracing::info!("This clipboard content was already ignored");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_spacing() {
+        let text = "fn main() {\n    println!(\"hi\");\n}\n";
+        assert_eq!(fingerprint(text), fingerprint("fn main()  {\r\n\n  println!(\"hi\");  \r\n}"));
+        assert_ne!(fingerprint(text), fingerprint("fn main() {\n    println!(\"bye\");\n}\n"));
+        assert_ne!(fingerprint("a b"), fingerprint("a\nb"));
+
+        let path = std::env::temp_dir().join(format!("reprompt-ignore-{}", std::process::id())).join("ignored.txt");
+        assert!(add(&path, fingerprint(text)).unwrap());
+        assert!(!add(&path, fingerprint(text)).unwrap());
+        let known = load(&path);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(known.len(), 1);
+    }
+}
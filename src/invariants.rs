@@ -0,0 +1,148 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::fmt;
+
+use crate::pipeline::{Line, Stage};
+
+/// Stages that write text of their own (annotations, diff headers, Markdown
+/// markers, spelled-out punctuation, user replacements), so text they changed
+/// may come out longer than it went in.
+const GROWING: &[&str] = &["emphasis", "exec", "images", "punctuation", "repeats", "rules", "sidebyside", "substitute", "wasm"];
+
+/// Stages that rewrite verbatim lines too, on purpose: escapes, charset shifts
+/// and image payloads are never meant text, even when pasted, and mixed
+/// indentation is what `indent` exists to fix inside code blocks
+const THROUGH_VERBATIM: &[&str] = &["ansi", "charset", "images", "indent"];
+
+lazy_static! {
+    static ref ENABLED: bool = cfg!(feature = "invariants")
+        || std::env::var_os("REPROMPT_CHECK_INVARIANTS").is_some_and(|value| !value.is_empty() && value != "0");
+}
+
+/// Whether cleans are checked against their guarantees: built with the
+/// `invariants` feature, or `REPROMPT_CHECK_INVARIANTS=1` in the environment.
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+/// A documented guarantee a clean broke.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "invariant", rename_all = "snake_case")]
+pub enum Violation {
+    /// An escape sequence is left after `ansi` ran (1-based output line)
+    Escape { line: usize },
+    /// U+FFFD appeared though the input had none (1-based output line)
+    Replacement { line: usize },
+    /// More visible characters came out than went in
+    Grew { input: usize, output: usize },
+    /// A stage changed or dropped a line an earlier stage marked verbatim
+    Verbatim { stage: &'static str, text: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Escape { line } => write!(f, "escape sequence left on line {line}"),
+            Self::Replacement { line } => write!(f, "U+FFFD introduced on line {line}"),
+            Self::Grew { input, output } => write!(f, "output has {output} visible characters, input {input}"),
+            Self::Verbatim { stage, text } => write!(f, "stage '{stage}' altered verbatim line {text:?}"),
+        }
+    }
+}
+
+fn visible(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// The first verbatim line of `before` that `after` doesn't still hold, in order.
+fn lost_verbatim<'a>(before: &'a [Line], after: &[Line]) -> Option<&'a str> {
+    let mut after = after.iter();
+    before
+        .iter()
+        .filter(|line| line.verbatim)
+        .find(|line| !after.any(|kept| kept.text == line.text))
+        .map(|line| line.text.as_str())
+}
+
+/// Checks `cleaned`, what `stages` made of `input` before templates, headers
+/// and `--format`, against what cleaning promises: no escape sequences once
+/// `ansi` ran, no U+FFFD the input didn't have, no more visible characters
+/// than the input (unless a stage that writes its own text changed it), and
+/// verbatim lines passed on unchanged by every stage after the one that
+/// marked them (but those documented to rewrite them). Runs the stages again one at a time to see the last.
+pub fn check(stages: &[&dyn Stage], input: &str, cleaned: &str) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut lines: Vec<Line> = input.lines().map(Line::new).collect();
+    let mut grows = false;
+    for stage in stages {
+        let after = stage.apply(lines.clone());
+        if after != lines {
+            grows |= GROWING.contains(&stage.name());
+            let lost = lost_verbatim(&lines, &after).filter(|_| !THROUGH_VERBATIM.contains(&stage.name()));
+            if let Some(text) = lost {
+                violations.push(Violation::Verbatim { stage: stage.name(), text: text.to_string() });
+            }
+        }
+        lines = after;
+    }
+
+    if stages.iter().any(|stage| stage.name() == "ansi") {
+        if let Some(at) = cleaned.lines().position(|line| line.contains(['\x1b', '\u{9b}', '\u{9d}'])) {
+            violations.push(Violation::Escape { line: at + 1 });
+        }
+    }
+    if !input.contains('\u{FFFD}') {
+        if let Some(at) = cleaned.lines().position(|line| line.contains('\u{FFFD}')) {
+            violations.push(Violation::Replacement { line: at + 1 });
+        }
+    }
+    let (before, after) = (visible(input), visible(cleaned));
+    if after > before && !grows {
+        violations.push(Violation::Grew { input: before, output: after });
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Pipeline;
+
+    struct Corrupt;
+
+    impl Stage for Corrupt {
+        fn name(&self) -> &'static str {
+            "corrupt"
+        }
+
+        fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+            lines.into_iter().map(|line| Line { text: format!("{}\u{FFFD}\x1b[0m", line.text), ..line }).collect()
+        }
+    }
+
+    #[test]
+    fn test_default_pipeline_keeps_its_guarantees() {
+        let pipeline = Pipeline::default();
+        let stages: Vec<&dyn Stage> = pipeline.stages().iter().map(|stage| stage.as_ref()).collect();
+        let input = "╭──────────╮\n│ \x1b[1mhello\x1b[0m │\n╰──────────╯\n```\n│ keep │\n```";
+        assert_eq!(check(&stages, input, &pipeline.run(input)), []);
+    }
+
+    #[test]
+    fn test_reports_each_violation() {
+        let pipeline = Pipeline::default();
+        let mut stages: Vec<&dyn Stage> = pipeline.stages().iter().map(|stage| stage.as_ref()).collect();
+        stages.push(&Corrupt);
+        let input = "```\nfenced\n```";
+        let cleaned = "```\u{FFFD}\x1b[0m\nfenced\u{FFFD}\x1b[0m\n```\u{FFFD}\x1b[0m";
+        assert_eq!(
+            check(&stages, input, cleaned),
+            [
+                Violation::Verbatim { stage: "corrupt", text: "```".to_string() },
+                Violation::Escape { line: 1 },
+                Violation::Replacement { line: 1 },
+                Violation::Grew { input: 12, output: 27 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Lowest score that names a language; below it the fence stays untagged
+const MIN_SCORE: u32 = 3;
+
+/// Lines looked at, so a huge paste doesn't cost a full scan per language
+const SAMPLE_LINES: usize = 200;
+
+lazy_static! {
+    static ref RE_SHEBANG: Regex = Regex::new(r"^#!\S*?(?:/env\s+)?\b(python[\d.]*|node|bash|sh|zsh|ruby|perl)\b").expect("Invalid Shebang Regex");
+
+    // Per language, patterns (matched against the whole sample) and their weights.
+    // Patterns shared by several languages are weighted low; distinctive ones high.
+    static ref SIGNALS: Vec<(&'static str, Vec<(Regex, u32)>)> = [
+        ("rust", &[
+            (r"(?m)^\s*(?:pub(?:\(crate\))?\s+)?fn\s+\w+.*(?:->|\{)", 3),
+            (r"(?m)^\s*let\s+(?:mut\s+)?\w+", 1),
+            (r"(?m)^\s*(?:use\s+\w+(?:::\w+)+|impl\b|#\[derive)", 3),
+            (r"\w+!\(|&mut\s|Option<|Result<|::new\(", 2),
+        ][..]),
+        ("python", &[
+            (r"(?m)^\s*def\s+\w+\(.*\)\s*(?:->\s*[\w\[\], ]+)?:\s*$", 4),
+            (r"(?m)^\s*(?:from\s+[\w.]+\s+import\s|import\s+\w+\s*$)", 2),
+            (r"(?m)^\s*(?:elif\b.*:|class\s+\w+.*:\s*$|if __name__ ==)", 3),
+            (r"\bself\.\w+|\bNone\b|\bTrue\b|print\(", 1),
+        ][..]),
+        ("typescript", &[
+            (r"(?m)^\s*(?:export\s+)?(?:interface|type)\s+\w+\s*(?:=|\{|<)", 4),
+            (r":\s*(?:string|number|boolean|void|any)\b", 3),
+            (r"(?m)^\s*import\s+.*\s+from\s+['\x22]", 1),
+        ][..]),
+        ("javascript", &[
+            (r"(?m)^\s*(?:const|let|var)\s+\w+\s*=", 1),
+            (r"=>\s*[{(]|\bfunction\s*\w*\(|console\.log\(", 2),
+            (r"(?m)^\s*import\s+.*\s+from\s+['\x22]|require\(['\x22]|module\.exports", 2),
+        ][..]),
+        ("go", &[
+            (r"(?m)^package\s+\w+\s*$", 4),
+            (r"(?m)^\s*func\s+(?:\(\w+\s+\*?\w+\)\s*)?\w+\(", 3),
+            (r":=|\bfmt\.\w+\(|\berr != nil\b", 2),
+        ][..]),
+        ("java", &[
+            (r"(?m)^\s*(?:public|private|protected)\s+(?:static\s+)?(?:final\s+)?(?:class|void|[A-Z]\w*(?:<.*>)?)\s+\w+", 3),
+            (r"(?m)System\.out\.print|@Override|^import\s+java\.", 4),
+        ][..]),
+        ("c", &[
+            (r"(?m)^\s*#include\s*<\w+\.h>", 4),
+            (r"\b(?:printf|malloc|free|sizeof)\(|\bint\s+main\s*\(", 2),
+        ][..]),
+        ("cpp", &[
+            (r"(?m)^\s*#include\s*<\w+>", 3),
+            (r"\bstd::\w+|\bcout\s*<<|\btemplate\s*<|\bnamespace\s+\w+", 3),
+        ][..]),
+        ("ruby", &[
+            (r"(?m)^\s*(?:def\s+\w+[?!]?(?:\(.*\))?\s*$|end\s*$)", 2),
+            (r"(?m)^\s*require\s+['\x22]|\bputs\s|\bdo\s*\|\w+\|", 3),
+        ][..]),
+        ("bash", &[
+            (r"(?m)^\s*\$\s+\w+", 2),
+            (r"(?m)^\s*(?:if\s+\[\[?\s|fi\s*$|done\s*$|esac\s*$|export\s+[A-Z_]+=)", 3),
+            (r"(?m)^\s*(?:\$\s+)?(?:sudo|apt(?:-get)?|brew|npm|cargo|git|cd|echo|curl|pip|docker)\s", 2),
+        ][..]),
+        ("sql", &[
+            (r"(?im)^\s*(?:select\s+.+\s+from\s|insert\s+into\s|create\s+table\s|update\s+\w+\s+set\s)", 4),
+            (r"(?i)\b(?:where|join|group by|order by)\b", 1),
+        ][..]),
+        ("html", &[
+            (r"(?i)<!doctype html|<html\b|</(?:div|span|body|head|p|a)>", 4),
+        ][..]),
+        ("css", &[
+            (r"(?m)^\s*[.#]?[\w-]+(?:\s*[,>]\s*[.#]?[\w-]+)*\s*\{\s*$", 2),
+            (r"(?m)^\s*[\w-]+:\s*[^;{]+;\s*$", 1),
+            (r"\b(?:margin|padding|display|color|font-size):", 2),
+        ][..]),
+        ("yaml", &[
+            (r"(?m)^[\w-]+:\s*$", 1),
+            (r"(?m)^\s+[\w-]+:\s+\S", 1),
+            (r"(?m)^\s*-\s+[\w-]+:\s", 2),
+            (r"(?m)^(?:apiVersion|kind|services|steps|jobs):", 3),
+        ][..]),
+        ("toml", &[
+            (r"(?m)^\[[\w.-]+\]\s*$|^\[\[[\w.-]+\]\]\s*$", 3),
+            (r#"(?m)^[\w-]+\s*=\s*(?:"|\d|true|false|\[|\{)"#, 1),
+        ][..]),
+        ("dockerfile", &[
+            (r"(?m)^FROM\s+\S+", 3),
+            (r"(?m)^(?:RUN|COPY|WORKDIR|ENTRYPOINT|CMD|EXPOSE)\s", 2),
+        ][..]),
+    ]
+    .into_iter()
+    .map(|(language, signals)| {
+        let signals = signals
+            .iter()
+            .map(|(pattern, weight)| (Regex::new(pattern).expect("Invalid Language Signal Regex"), *weight))
+            .collect();
+        (language, signals)
+    })
+    .collect();
+}
+
+/// Guesses the language of a code snippet for a fence tag (`rust`, `python`, …)
+/// from a shebang, JSON syntax, diff headers, or weighted keyword patterns.
+/// `None` when nothing scores clearly enough.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let text = text.trim();
+    if let Some(caps) = RE_SHEBANG.captures(text) {
+        return Some(match &caps[1] {
+            "node" => "javascript",
+            "ruby" => "ruby",
+            "perl" => "perl",
+            interpreter if interpreter.starts_with("python") => "python",
+            _ => "bash",
+        });
+    }
+    if (text.starts_with('{') || text.starts_with('[')) && serde_json::from_str::<serde_json::Value>(text).is_ok() {
+        return Some("json");
+    }
+    if text.starts_with("diff --git ") || (text.starts_with("--- ") && text.contains("\n+++ ")) {
+        return Some("diff");
+    }
+
+    let sample: String = text.lines().take(SAMPLE_LINES).collect::<Vec<_>>().join("\n");
+    let mut best = None;
+    let mut best_score = MIN_SCORE - 1;
+    for (language, signals) in SIGNALS.iter() {
+        let score: u32 = signals.iter().filter(|(pattern, _)| pattern.is_match(&sample)).map(|(_, weight)| weight).sum();
+        // Ties go to the earlier language, so TypeScript wins over JavaScript
+        if score > best_score {
+            best = Some(*language);
+            best_score = score;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_common_languages() {
+        let cases = [
+            ("fn main() {\n    let mut x = Vec::new();\n    println!(\"{:?}\", x);\n}", Some("rust")),
+            ("import os\n\ndef walk(path):\n    return os.listdir(path)", Some("python")),
+            ("package main\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}", Some("go")),
+            ("interface User {\n  name: string;\n}\nconst u: User = { name: 'a' };", Some("typescript")),
+            ("const add = (a, b) => {\n  return a + b;\n};\nconsole.log(add(1, 2));", Some("javascript")),
+            ("#include <stdio.h>\nint main(void) {\n    printf(\"hi\\n\");\n}", Some("c")),
+            ("SELECT id, name FROM users WHERE active = 1 ORDER BY name;", Some("sql")),
+            ("$ cargo build --release\n$ git push origin main", Some("bash")),
+            ("#!/usr/bin/env python3\nprint('x')", Some("python")),
+            ("{\"a\": [1, 2], \"b\": null}", Some("json")),
+            ("[package]\nname = \"reprompt\"\nversion = \"0.1.0\"", Some("toml")),
+            ("FROM rust:1.80\nWORKDIR /app\nRUN cargo build", Some("dockerfile")),
+            ("The build failed because a flag was missing.", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(detect(text), expected, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Core cleaning logic behind the `reprompt` binary: profiles, detection, the
+//! stage pipeline and variant search, plus the clipboard backends.
+
+pub mod classify;
+pub mod cleaner;
+pub mod clipboard;
+pub mod config;
+pub mod crop;
+pub mod detect;
+pub mod error;
+pub mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod files;
+pub mod format;
+pub mod header;
+pub mod invariants;
+pub mod language;
+pub mod lock;
+pub mod ocr;
+pub mod paths;
+pub mod pipeline;
+pub mod process;
+pub mod provenance;
+pub mod profiles;
+pub mod report;
+pub mod rtf;
+pub mod rules;
+pub mod score;
+pub mod search;
+pub mod stages;
+pub mod tables;
+pub mod timing;
+pub mod urls;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod width;
@@ -0,0 +1,99 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::error::{RepromptError, Result};
+use crate::paths;
+
+/// How often a waiting instance re-checks a contended lock
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Exclusive cross-process lock backed by an advisory file lock.
+/// Released automatically when dropped or when the process exits (even on crash).
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Attempts to take the named lock without blocking.
+    /// Returns `Ok(None)` if another process currently holds it.
+    pub fn try_acquire(name: &str) -> Result<Option<Self>> {
+        let path = lock_path(name);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| RepromptError::io("Failed to open lock file", &path, e))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => {
+                return Err(RepromptError::io("Failed to lock", &path, e));
+            }
+        }
+
+        // Record our PID so contenders can report who holds the lock
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+
+        Ok(Some(Self { _file: file }))
+    }
+
+    /// Waits up to `timeout` for the named lock to become available.
+    pub fn acquire_timeout(name: &str, timeout: Duration) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(lock) = Self::try_acquire(name)? {
+                return Ok(lock);
+            }
+            if Instant::now() >= deadline {
+                return Err(RepromptError::Busy { holder: holder_pid(name) });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Reads the PID recorded by the current holder of a lock, if any.
+/// Best effort: Windows denies reads of locked files, so this may return `None`.
+pub fn holder_pid(name: &str) -> Option<u32> {
+    let mut contents = String::new();
+    File::open(lock_path(name)).ok()?.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn lock_path(name: &str) -> PathBuf {
+    paths::runtime_file(&format!("{}.lock", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let name = format!("test-exclusive-{}", std::process::id());
+
+        let first = InstanceLock::try_acquire(&name).unwrap();
+        assert!(first.is_some(), "First acquire should succeed");
+        assert_eq!(holder_pid(&name), Some(std::process::id()));
+
+        let second = InstanceLock::try_acquire(&name).unwrap();
+        assert!(second.is_none(), "Second acquire should see the lock as held");
+
+        let err = InstanceLock::acquire_timeout(&name, Duration::from_millis(100));
+        assert!(err.is_err(), "Waiting should time out while the lock is held");
+
+        drop(first);
+        let third = InstanceLock::try_acquire(&name).unwrap();
+        assert!(third.is_some(), "Lock should be free after drop");
+
+        drop(third);
+        let _ = std::fs::remove_file(lock_path(&name));
+    }
+}
@@ -0,0 +1,22 @@
+use tracing_subscriber::EnvFilter;
+
+/// Installs the stderr logger. `verbosity` counts `-v` flags: `info` by
+/// default (`warn` with `quiet`), `debug` with `-v`, `trace` with `-vv`.
+/// `RUST_LOG` overrides it, e.g. `RUST_LOG=reprompt::clipboard=debug`.
+/// Targets are module paths (`reprompt::clipboard`, `reprompt::search`, …).
+pub fn init(verbosity: u8, quiet: bool, json: bool) {
+    let level = match verbosity {
+        0 if quiet => "warn",
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(format!("reprompt={level}")));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    if json {
+        builder.json().init();
+    } else {
+        // Level and message only; module targets once debugging
+        builder.without_time().with_target(verbosity > 0).init();
+    }
+}
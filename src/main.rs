@@ -1,92 +1,145 @@
 use anyhow::{Context, Result};
-use regex::Regex;
-use lazy_static::lazy_static;
-use std::process::{Command, Stdio};
-use std::io::Write;
-use base64::prelude::*;
-
-lazy_static! {
-    static ref RE_BORDER_LINE: Regex = Regex::new(r"^[\s╭╮╰╯─═━┌┐└┘]+$").expect("Invalid Border Line Regex");
-
-    // Handles borders that have text embedded, e.g., "╭─── Title ───╮"
-    static ref RE_TITLED_BORDER: Regex = Regex::new(r"(?x)
-        ^[\s╭┌╰└]           # Start with corner or space
-        (?:.*?)             # Content (title, etc.)
-        [─═━]{3,}           # Must contain at least 3 horizontal bars
-        (?:.*?)             # More content
-        [╮┐╯┘]\s*$          # End with corner
-    ").expect("Invalid Titled Border Regex");
-
-    static ref RE_CONTENT_WRAPPER: Regex = Regex::new(r"(?x)
-        ^
-        \s*           # Start of line, optional indentation
-        [│║]          # The border character
-        \x20?         # Optional single padding space
-        (?P<content>.*?) # Lazy capture of the actual content
-        \x20?         # Optional single padding space
-        [│║]?         # Optional trailing border
-        \s*           # End of line
-        $
-    ").expect("Invalid Content Wrapper Regex");
-
-    // Improved ANSI escape codes regex
-    // Matches standard CSI sequences and some common others
-    static ref RE_ANSI: Regex = Regex::new(r"[\x1b\x9b][\[()#;?]*(?:[0-9]{1,4}(?:;[0-9]{0,4})*)?[0-9A-ORZcf-nqry=><]").expect("Invalid ANSI Regex");
-}
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+use std::process::ExitCode;
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+
+mod append;
+mod backup;
+mod corpus;
+mod daemon;
+mod doctor;
+mod exit;
+mod focus;
+mod formats;
+mod ignore;
+mod logging;
+mod mcp;
+mod pause;
+mod register;
+mod reload;
+#[cfg(feature = "ocr")]
+mod screenshot;
+mod sensitive;
+mod serve;
+mod settings;
+mod slots;
+mod stats;
+mod status;
+mod vault;
+mod watch;
+
+use reprompt::{clipboard, extract, paths, profiles, rtf, timing, urls};
+
+use reprompt::classify::ContentKind;
+use reprompt::cleaner::Cleaner;
+use clipboard::{get_clipboard, set_clipboard, set_clipboard_html, with_retry, Verify};
+use reprompt::config::{self, Config, LineEndings, Override};
+use reprompt::crop::{self, Window};
+use reprompt::error::RepromptError;
+use reprompt::format::OutputFormat;
+use reprompt::header::HeaderFormat;
+use reprompt::lock::InstanceLock;
+use reprompt::pipeline::StageKind;
+use reprompt::report::{Level, Report};
+use reprompt::stages::{ImageMode, Screen};
+use reprompt::tables::TableFormat;
+use reprompt::timing::Timing;
+use reprompt::validate::{Action, Policy};
+use reload::Reloader;
+use exit::Exit;
+use stats::Outcome;
+use watch::RecentWrites;
+
+/// How long an invocation waits for another instance to finish its transaction
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Represents a clipboard transaction with rollback capability
 struct ClipboardTransaction {
     original: String,
+    /// `original` rebuilt from the clipboard's RTF flavor, when its plain text was damaged
+    recovered: Option<String>,
     modified: Option<String>,
+    /// Rich-text flavor written next to `modified`
+    html: Option<String>,
 }
 
 impl ClipboardTransaction {
     /// Creates a new transaction by reading the current clipboard
     fn new() -> Result<Self> {
-        let original = get_clipboard().context("Failed to read clipboard for transaction")?;
+        let original = with_retry(get_clipboard).context("Failed to read clipboard for transaction")?;
         Ok(Self {
             original,
+            recovered: None,
             modified: None,
+            html: None,
         })
     }
 
-    /// Gets the original clipboard content
-    fn original(&self) -> &str {
-        &self.original
+    /// Reads the clipboard like `new`, rebuilding the text from RTF when
+    /// `[input] rtf` is on and the plain text looks damaged
+    fn snapshot(cleaner: &Cleaner) -> Result<Self> {
+        let mut transaction = Self::new()?;
+        if cleaner.config().input.rtf && rtf::suspect(transaction.source()) {
+            transaction.recover();
+        }
+        Ok(transaction)
     }
 
-    /// Sets the modified content (doesn't commit yet)
-    fn set_modified(&mut self, modified: String) {
-        self.modified = Some(modified);
+    /// Gets the text to clean: the clipboard's, or the one rebuilt from RTF
+    fn source(&self) -> &str {
+        self.recovered.as_deref().unwrap_or(&self.original)
     }
 
-    /// Validates that the modified content is not corrupted
-    fn validate(&self) -> Result<()> {
-        let modified = self.modified.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No modified content to validate"))?;
-
-        // Bail on Unicode replacement character (U+FFFD indicates encoding corruption)
-        if modified.contains('\u{FFFD}') {
-            anyhow::bail!("Unicode replacement character (U+FFFD) detected");
+    /// Rebuilds the text from the clipboard's RTF flavor when the plain text
+    /// lost characters it kept, e.g. a terminal copying through a code page.
+    /// Rollback still restores the original.
+    fn recover(&mut self) {
+        let rtf = match formats::fetch_rtf() {
+            Ok(Some(rtf)) => rtf,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::debug!("Couldn't read the RTF flavor: {:#}", e);
+                return;
+            }
+        };
+        let Some(mut text) = rtf::to_text(&rtf).filter(|text| rtf::repairs(&self.original, text)) else {
+            return;
+        };
+        // RTF ends paragraphs with `\par`; keep the plain text's ending
+        if !self.original.ends_with('\n') {
+            text.truncate(text.trim_end_matches('\n').len());
         }
+        tracing::info!("The plain text lost characters; cleaning the clipboard's RTF flavor instead");
+        self.recovered = Some(text);
+    }
 
-        // Sanity check: if original had substantial content but cleaned is empty,
-        // we likely over-cleaned (false positive on content detection)
-        let original_has_content = self.original.trim().len() > 10;
-        let cleaned_is_empty = modified.trim().is_empty();
-
-        if original_has_content && cleaned_is_empty {
-            anyhow::bail!("Cleaning removed all content (likely false positive)");
-        }
+    /// Sets the modified content from a report (doesn't commit yet)
+    fn set_modified(&mut self, report: &Report) {
+        self.modified = Some(report.output.clone());
+        self.html = report.html.clone();
+    }
 
-        // Sanity check: if cleaned text is dramatically shorter (>90% reduction),
-        // and original was substantial, we might have over-cleaned
-        if self.original.len() > 200 && modified.len() < self.original.len() / 10 {
-            eprintln!("Warning: Cleaning reduced content by >90% ({} -> {} bytes)",
-                     self.original.len(), modified.len());
-            eprintln!("This might indicate over-aggressive cleaning.");
+    /// Runs the `[validation]` checks on the modified content. Fails if one
+    /// says to abort, or says to prompt and the answer is no.
+    fn validate(&self, policy: &Policy) -> Result<()> {
+        let modified = self.modified.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No modified content to validate"))?;
+        let failed = |reason: String| Err(RepromptError::ValidationFailed { reason }.into());
+
+        let mut violations = policy.check(self.source(), modified);
+        // Abort before asking anything that wouldn't matter anyway
+        violations.sort_by_key(|violation| violation.action != Action::Abort);
+        for violation in violations {
+            match violation.action {
+                Action::Abort => return failed(violation.message),
+                Action::Warn => tracing::warn!("{}", violation.message),
+                Action::Prompt if confirm(&violation.message)? => {}
+                Action::Prompt => return failed(format!("{} (not confirmed)", violation.message)),
+                Action::Off => {}
+            }
         }
-
         Ok(())
     }
 
@@ -100,47 +153,55 @@ impl ClipboardTransaction {
             return Ok(());
         }
 
+        // Keep a copy on disk in case both the write and the rollback fail
+        let backup = backup::save(&self.original);
+        let saved = match &backup {
+            Some(path) => format!("The original text is saved in {} (`reprompt history restore`)", path.display()),
+            None => "Original clipboard content may be lost!".to_string(),
+        };
+
         // Attempt to write with proper encoding
-        if let Err(e) = set_clipboard(&modified) {
+        let write = || match &self.html {
+            Some(html) => set_clipboard_html(html, &modified),
+            None => set_clipboard(&modified),
+        };
+        if let Err(e) = with_retry(write) {
             // Attempt rollback on write failure
-            eprintln!("Write failed: {}. Attempting rollback...", e);
-            if let Err(rollback_err) = set_clipboard(&self.original) {
-                eprintln!("CRITICAL: Rollback failed: {}", rollback_err);
-                eprintln!("Original clipboard content may be lost!");
-                return Err(anyhow::anyhow!(
-                    "Write failed and rollback failed: {} -> {}",
-                    e,
-                    rollback_err
-                ));
+            tracing::warn!("Write failed: {}. Attempting rollback...", e);
+            if let Err(rollback_err) = with_retry(|| set_clipboard(&self.original)) {
+                tracing::error!("Rollback failed: {}. {}", rollback_err, saved);
+                return Err(RepromptError::ClipboardWrite(format!("rollback failed too: {} -> {}", e, rollback_err)).into());
             }
-            eprintln!("Rollback successful. Clipboard restored to original state.");
-            return Err(anyhow::anyhow!("Transaction aborted: {}", e));
+            tracing::info!("Rollback successful. Clipboard restored to original state.");
+            return Err(anyhow::Error::new(e).context("Transaction aborted"));
         }
 
         // Verify the write by reading back
-        match get_clipboard() {
+        let verify = clipboard::verify_mode();
+        if verify == Verify::Off {
+            return Ok(());
+        }
+        match with_retry(get_clipboard) {
             Ok(readback) => {
-                // Normalize both strings for comparison to handle platform differences
-                // (PowerShell might add trailing newline, etc.)
-                let expected_normalized = modified.trim_end();
-                let readback_normalized = readback.trim_end();
-
-                if readback_normalized != expected_normalized {
-                    eprintln!("Verification failed: Clipboard content doesn't match expected result");
-                    eprintln!("Expected {} bytes, got {} bytes",
-                             expected_normalized.len(), readback_normalized.len());
-                    eprintln!("Attempting rollback...");
-                    if let Err(rollback_err) = set_clipboard(&self.original) {
-                        eprintln!("CRITICAL: Rollback failed: {}", rollback_err);
-                        return Err(anyhow::anyhow!("Verification and rollback both failed"));
+                // Clipboard managers and PowerShell may change line endings,
+                // trailing newlines or more, depending on the `verify` mode
+                if !verify.matches(&modified, &readback) {
+                    tracing::warn!(
+                        "Verification failed: expected {} bytes, got {} bytes (verify = {:?}). Attempting rollback...",
+                        modified.len(),
+                        readback.len(),
+                        verify
+                    );
+                    if let Err(rollback_err) = with_retry(|| set_clipboard(&self.original)) {
+                        tracing::error!("Rollback failed: {}. {}", rollback_err, saved);
+                        return Err(RepromptError::ClipboardWrite("verification and rollback both failed".to_string()).into());
                     }
-                    eprintln!("Rollback successful.");
-                    return Err(anyhow::anyhow!("Transaction aborted: Verification failed"));
+                    tracing::info!("Rollback successful.");
+                    return Err(RepromptError::ClipboardWrite("transaction aborted, verification failed".to_string()).into());
                 }
             }
             Err(e) => {
-                eprintln!("Warning: Could not verify write: {}", e);
-                eprintln!("Clipboard may have been updated, but verification failed.");
+                tracing::warn!("Could not verify write: {}. Clipboard may have been updated, but verification failed.", e);
             }
         }
 
@@ -149,311 +210,994 @@ impl ClipboardTransaction {
 
 }
 
-/// Checks if the program is running inside WSL.
-fn is_wsl_custom() -> bool {
-    is_wsl::is_wsl()
+#[derive(Parser)]
+#[command(name = "reprompt", version, about = "Strip TUI artifacts from clipboard text")]
+struct Cli {
+    /// Cleaning profile to apply (see `reprompt profile list`); detected from the text if omitted
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Comma-separated cleaning stages to run, in order (overrides config)
+    #[arg(long, global = true, value_delimiter = ',')]
+    stages: Option<Vec<StageKind>>,
+
+    /// Override a config key for this run, e.g. `--set backup.keep=5` (repeatable; beats `REPROMPT_*` variables)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<Override>,
+
+    /// Skip user-defined `[[substitutions]]` for this run
+    #[arg(long, global = true)]
+    no_substitutions: bool,
+
+    /// Strip timestamps, log levels and pid/thread tags from the start of log lines
+    #[arg(long, global = true)]
+    strip_log_prefixes: bool,
+
+    /// Fold runs of repeated (or nearly repeated) lines into one
+    #[arg(long, global = true)]
+    collapse_repeats: bool,
+
+    /// Keep bold/italic as Markdown emphasis and mark red/green diff lines with -/+
+    #[arg(long, global = true)]
+    ansi_markdown: bool,
+
+    /// Normalize the output to Unicode NFC
+    #[arg(long, global = true)]
+    nfc: bool,
+
+    /// Fold full-width ASCII, curly quotes and non-breaking spaces to plain ASCII (implies --nfc)
+    #[arg(long, global = true)]
+    fold_confusables: bool,
+
+    /// Spell curly quotes, en/em dashes, ellipses and non-breaking spaces in ASCII
+    #[arg(long, global = true)]
+    ascii_punctuation: bool,
+
+    /// Expand tabs to spaces, N columns per tab stop (default `[indent] tab_width`, 4)
+    #[arg(long, global = true, value_name = "N", num_args = 0..=1)]
+    expand_tabs: Option<Option<usize>>,
+
+    /// Turn leading tabs into spaces in code blocks that mix tab and space indentation
+    #[arg(long, global = true)]
+    normalize_indent: bool,
+
+    /// Trim trailing whitespace from every line
+    #[arg(long, global = true)]
+    strip_trailing_whitespace: bool,
+
+    /// Unframe ASCII tables (`+----+`, `| cell |`) from MySQL, psql and similar CLIs
+    #[arg(long, global = true)]
+    ascii_borders: bool,
+
+    /// Remove `>` quote markers from copied mail or chat text and re-flow the quoted paragraphs
+    #[arg(long, global = true)]
+    strip_quotes: bool,
+
+    /// Keep Markdown fences, tables, headings and lists intact, and refuse output whose fences no longer balance
+    #[arg(long, global = true)]
+    markdown: bool,
+
+    /// Keep only the section starting at the heading (or line) matching this regex, e.g. `Plan`
+    #[arg(long, global = true, value_name = "PATTERN")]
+    select: Option<String>,
+
+    /// Keep only the first N lines of the cleaned text, noting how many were elided
+    #[arg(long, global = true, value_name = "N", conflicts_with_all = ["tail", "lines"])]
+    head: Option<usize>,
+
+    /// Keep only the last N lines of the cleaned text, noting how many were elided
+    #[arg(long, global = true, value_name = "N", conflicts_with = "lines")]
+    tail: Option<usize>,
+
+    /// Keep only lines A to B (1-based, inclusive; `A:` or `:B` for open ends)
+    #[arg(long, global = true, value_name = "A:B", value_parser = crop::parse_lines)]
+    lines: Option<Window>,
+
+    /// Turn the table in the cleaned text (psql, MySQL, `docker ps`, `kubectl get`) into CSV, TSV or JSON
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    tables: Option<TableFormat>,
+
+    /// What the cleaned text becomes: plain text, Markdown with fences and tables, the JSON report, or HTML (overrides config)
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = possible::<OutputFormat>())]
+    format: Option<String>,
+
+    /// Put a provenance header (tool, time, directory, git branch) before the cleaned text
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    header: Option<HeaderFormat>,
+
+    /// Screen to keep when the text switches to the alternate screen, where full-screen programs draw (overrides config)
+    #[arg(long, global = true, value_enum)]
+    screen: Option<Screen>,
+
+    /// What to leave where an inline image (sixel, iTerm2, kitty) was removed (overrides config)
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    images: Option<ImageMode>,
+
+    /// Line endings of the cleaned text; `native` is CRLF on Windows and WSL (overrides config)
+    #[arg(long, global = true, value_enum)]
+    line_endings: Option<LineEndings>,
+
+    /// Only apply cleaning that clearly improves the text (see `[conservative]` in the config)
+    #[arg(long, global = true)]
+    conservative: bool,
+
+    /// Explain the chosen profile and what cleaning did (on stderr)
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Time each stage, detection, scoring and clipboard access, and print the times (on stderr; in the report with --json)
+    #[arg(long, global = true)]
+    profile_stages: bool,
+
+    /// Print a JSON report of the run instead of the ✨ confirmation
+    #[arg(long, global = true, conflicts_with = "explain")]
+    json: bool,
+
+    /// Print nothing but warnings and errors: no ✨, no progress messages
+    #[arg(short, long, global = true, conflicts_with_all = ["json", "verbose"])]
+    quiet: bool,
+
+    /// Confirm with the word "cleaned" instead of ✨
+    #[arg(long, global = true)]
+    no_emoji: bool,
+
+    /// More log detail: -v for debug, -vv for trace (`RUST_LOG` overrides)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Keep state (stats, slots, backups, the append buffer) and the cache in this directory instead
+    #[arg(long, global = true, value_name = "DIR")]
+    state_dir: Option<std::path::PathBuf>,
+
+    /// Write logs as JSON lines, e.g. for a `watch` daemon's log collector
+    #[arg(long, global = true)]
+    log_json: bool,
+
+    /// Ask a running `reprompt daemon` to clean the clipboard instead of doing it here
+    #[arg(long)]
+    client: bool,
+
+    /// Exit with status 1 when there was nothing to clean (see README for every exit status)
+    #[arg(long, conflicts_with = "register")]
+    fail_on_unchanged: bool,
+
+    /// Editor mode: clean stdin to stdout, preceded by a JSON metadata line (see README)
+    #[arg(long, conflicts_with = "client")]
+    register: bool,
+
+    /// With --register, write the metadata line to this inherited file descriptor instead
+    #[arg(long, value_name = "FD", requires = "register")]
+    meta_fd: Option<i32>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Stay running and clean every new clipboard copy automatically
+    Watch {
+        /// Polling interval (ms) used where native change notifications are unavailable
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// Stop `watch` cleaning for a while, e.g. to copy box drawings on purpose
+    Pause {
+        /// How long, like 30s, 5m or 2h (default: until `reprompt resume`)
+        #[arg(value_parser = pause::parse_duration)]
+        duration: Option<Duration>,
+    },
+    /// Let a paused `watch` clean again
+    Resume,
+    /// Clean the clipboard and add it to a buffer of collected snippets (clipboard unchanged)
+    Append {
+        /// Put this between snippets instead of `[append] separator`
+        #[arg(long)]
+        separator: Option<String>,
+    },
+    /// Put all snippets collected by `append` on the clipboard and empty the buffer
+    Flush,
+    /// Never let `watch` clean the clipboard's current content (or the same text respaced) again
+    Ignore {
+        /// Forget every content ignored so far
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Clean the clipboard and keep it in a named slot (clipboard unchanged)
+    Save {
+        /// Slot name: letters, digits, `-` and `_`
+        name: String,
+    },
+    /// Put a slot saved with `save` on the clipboard
+    Load {
+        name: String,
+    },
+    /// Print the clipboard's boxes and panes as structured sections (title → lines)
+    Extract {
+        /// Output format
+        #[arg(long, value_parser = possible::<extract::Format>(), default_value = "json")]
+        format: String,
+    },
+    /// Print the URLs in the cleaned clipboard, one per line (with --json, as an array)
+    Urls {
+        /// Replace the clipboard with the list instead of printing it
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Manage cleaning profiles from profiles.d
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Validate or create the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Restore or delete clipboard backups, and make the key that encrypts them
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Check cleaning against a directory of captured fixtures
+    #[command(subcommand)]
+    Corpus(CorpusCommand),
+    /// Show cleanup totals and recent activity (with --json, the raw numbers)
+    Stats,
+    /// Show whether the daemon and watcher run, the backend, profile, last clean and pause (with --json, as an object)
+    Status,
+    /// List every format on the clipboard with its size in bytes (with --json, as an array)
+    Formats,
+    /// Read the screenshot on the clipboard with OCR, clean the text and put it in place of the image
+    #[cfg(feature = "ocr")]
+    Ocr,
+    /// Print one clipboard format's raw data, e.g. `--format text/html` (names from `formats`)
+    Read {
+        /// Format name: a MIME type, X11 target, macOS pasteboard type or Windows format (`CF_UNICODETEXT`)
+        #[arg(long)]
+        format: String,
+    },
+    /// Check the clipboard environment and config, and suggest fixes
+    Doctor,
+    /// Show where config, state and cache files live (with --json, as an object)
+    Paths,
+    /// Serve `clean_text`, `get_clean_clipboard` and `set_clipboard` as MCP tools over stdio
+    Mcp,
+    /// Serve a local HTTP API: POST /clean, POST /clipboard/clean, GET /health
+    Serve {
+        /// Port to listen on (127.0.0.1 only)
+        #[arg(long, default_value_t = 7457)]
+        port: u16,
+        /// Require `Authorization: Bearer <token>` on every request, with the
+        /// token on the first line of FILE (or in `REPROMPT_TOKEN`)
+        #[arg(long, value_name = "FILE")]
+        token_file: Option<std::path::PathBuf>,
+    },
+    /// Keep a cleaner loaded behind a Unix socket for `--client` and editor plugins
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonCommand>,
+    },
+    /// Print a shell completion script (profile names are those installed now)
+    Completions {
+        shell: clap_complete::Shell,
+    },
 }
 
-/// Reads text from the system clipboard with proper encoding handling.
-/// Handles Native (arboard) and WSL (powershell) environments.
-fn get_clipboard() -> Result<String> {
-    if is_wsl_custom() {
-        // Try PowerShell first (WSL interop) with explicit UTF-8 encoding via Base64 transfer
-        // This avoids all code page issues by transferring ASCII Base64 over the pipe.
-        match Command::new("powershell.exe")
-            .args([
-                "-NoProfile",
-                "-Command",
-                "$b64 = [Convert]::ToBase64String([System.Text.Encoding]::UTF8.GetBytes(($OFS=\"`n\"; \"$(Get-Clipboard)\"))); Write-Output $b64"
-            ])
-            .output()
-        {
-            Ok(output) if output.status.success() => {
-                let base64_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-                // Decode Base64
-                let decoded_bytes = BASE64_STANDARD.decode(&base64_str)
-                    .context("Failed to decode Base64 from PowerShell")?;
-
-                let text = String::from_utf8(decoded_bytes)
-                    .context("Decoded Base64 is not valid UTF-8")?;
-
-                // Normalize line endings from CRLF to LF
-                let normalized = text.replace("\r\n", "\n");
-
-                // Trim trailing whitespace that PowerShell often adds
-                let trimmed = normalized.trim_end().to_string();
-
-                return Ok(trimmed);
-            }
-            Ok(output) => {
-                // PowerShell ran but failed
-                return Err(anyhow::anyhow!(
-                    "PowerShell Get-Clipboard failed: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                ));
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                // powershell.exe not found - WSL interop likely disabled
-                // Fall back to arboard
-                eprintln!("Warning: WSL detected but powershell.exe not found.");
-                eprintln!("Windows interop may be disabled. Falling back to native clipboard.");
-                eprintln!("To fix: Check /etc/wsl.conf has [interop] enabled=true");
-                let mut clipboard = arboard::Clipboard::new()?;
-                return Ok(clipboard.get_text()?);
-            }
-            Err(e) => {
-                // Other error running powershell.exe
-                return Err(e.into());
-            }
-        }
-    } else {
-        let mut clipboard = arboard::Clipboard::new()?;
-        Ok(clipboard.get_text()?)
-    }
+/// Checks a flag against `T`'s values but keeps it a string. `--format` is
+/// both global and defined by `extract` and `read` with other values, and clap
+/// hands a subcommand's value up to the global flag, so their types must match.
+fn possible<T: ValueEnum>() -> PossibleValuesParser {
+    PossibleValuesParser::new(T::value_variants().iter().filter_map(ValueEnum::to_possible_value))
 }
 
-/// Writes text to the system clipboard with proper encoding handling.
-/// Handles Native (arboard) and WSL (clip.exe) environments.
-fn set_clipboard(data: &str) -> Result<()> {
-    if is_wsl_custom() {
-        // Use PowerShell with Base64 transfer for reliable encoding
-        match Command::new("powershell.exe")
-            .args([
-                "-NoProfile",
-                "-Command",
-                "$b64 = $input | Out-String; if (-not [string]::IsNullOrWhiteSpace($b64)) { $bytes = [System.Convert]::FromBase64String($b64.Trim()); [System.Text.Encoding]::UTF8.GetString($bytes) | Set-Clipboard }"
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-        {
-            Ok(mut child) => {
-                {
-                    let mut stdin = child.stdin.take()
-                        .ok_or_else(|| anyhow::anyhow!("Failed to open stdin for PowerShell"))?;
-
-                    // Encode to Base64 in Rust
-                    let base64_str = BASE64_STANDARD.encode(data);
-
-                    // Write Base64 string (safe ASCII)
-                    stdin.write_all(base64_str.as_bytes())
-                        .context("Failed to write to PowerShell stdin")?;
-
-                    // Explicitly drop stdin to close the pipe and signal EOF
-                    drop(stdin);
-                }
+/// The `T` a flag checked by `possible` names.
+fn parse_value<T: ValueEnum>(value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(anyhow::Error::msg)
+}
+
+#[derive(Subcommand)]
+enum DaemonCommand {
+    /// Show the running daemon's pid, uptime and request count
+    Status,
+    /// Restore the clipboard from before the daemon's last cleanup
+    Undo,
+}
 
-                let output = child.wait_with_output()
-                    .context("Failed to wait for PowerShell process")?;
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List built-in and installed profiles
+    List,
+    /// Install a profile TOML file from a URL or local path
+    Install {
+        /// http(s) URL or path of the profile file
+        source: String,
+        /// Expected SHA-256 of the file; installation fails without it or on mismatch
+        #[arg(long)]
+        sha256: Option<String>,
+        /// Install without a --sha256 to check the file against
+        #[arg(long, conflicts_with = "sha256")]
+        insecure: bool,
+        /// Allow replacing a newer installed version
+        #[arg(long)]
+        force: bool,
+    },
+}
 
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(anyhow::anyhow!("PowerShell Set-Clipboard failed: {}", stderr));
-                }
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Parse the config and profiles, compile every regex, and report problems with line numbers
+    Check,
+    /// Write a commented config with every setting at its default
+    Init {
+        /// Replace an existing config
+        #[arg(long)]
+        force: bool,
+    },
+}
 
-                Ok(())
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                // powershell.exe not found - fallback logic
-                // Try clip.exe (legacy, unreliable for utf-8 but better than nothing)
-                 if data.is_ascii() {
-                    eprintln!("Warning: powershell.exe not found, trying clip.exe...");
-                    match Command::new("clip.exe").stdin(Stdio::piped()).spawn() {
-                        Ok(mut child) => {
-                            let mut stdin = child.stdin.take().unwrap();
-                            stdin.write_all(data.as_bytes())?;
-                            drop(stdin);
-                            let status = child.wait()?;
-                            if !status.success() { return Err(anyhow::anyhow!("clip.exe failed")); }
-                            return Ok(());
-                        }
-                        Err(_) => {}
-                    }
-                }
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// Put a backup back on the clipboard, decrypting it if needed
+    Restore {
+        /// Which backup: 0 is the newest
+        #[arg(default_value_t = 0)]
+        index: usize,
+    },
+    /// Delete every backup, slot and the append buffer
+    Purge,
+    /// Create the key backups are encrypted to with `[backup] encrypt`
+    Key {
+        /// Replace an existing key; backups made with it can't be read afterwards
+        #[arg(long)]
+        force: bool,
+    },
+}
 
-                // Fall back to native clipboard (arboard)
-                eprintln!("Warning: WSL detected but Windows interop not available.");
-                let mut clipboard = arboard::Clipboard::new()?;
-                clipboard.set_text(data)?;
-                Ok(())
-            }
-            Err(e) => {
-                Err(e.into())
+#[derive(Subcommand)]
+enum CorpusCommand {
+    /// Clean each `<name>.input.txt` in DIR and compare it with `<name>.expected.txt`
+    Run {
+        dir: std::path::PathBuf,
+    },
+    /// Save FILE (or the clipboard) and its cleaned output to DIR as a new fixture
+    Record {
+        dir: std::path::PathBuf,
+        /// Record this file instead of the clipboard
+        file: Option<std::path::PathBuf>,
+        /// Fixture name (default: the file's name, or the detected profile and the time)
+        #[arg(long)]
+        name: Option<String>,
+        /// Replace an existing fixture of the same name
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    // A bad command line is a config error too; --help and --version still exit 0
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if e.use_stderr() => {
+            let _ = e.print();
+            return Exit::Config.into();
+        }
+        Err(e) => e.exit(),
+    };
+    let json = cli.json;
+    match run(cli) {
+        Ok(exit) => exit.into(),
+        Err(e) => {
+            if json {
+                println!("{}", serde_json::json!({ "error": format!("{:#}", e), "kind": error_kind(&e) }));
+            } else {
+                eprintln!("Error: {:?}", e);
             }
+            Exit::of_error(&e).into()
         }
-    } else {
-        let mut clipboard = arboard::Clipboard::new()?;
-        clipboard.set_text(data)?;
-        Ok(())
     }
 }
 
-/// Cleans the input text by removing TUI artifacts (borders, ANSI codes).
-fn clean_text(input: &str) -> String {
-    // First pass: strip ANSI escape codes (colors, cursor movement, etc.)
-    // Many TUI applications add these for visual formatting
-    let ansi_stripped = RE_ANSI.replace_all(input, "");
+/// `RepromptError::kind` of the first library error behind `e`, or "other".
+pub fn error_kind(e: &anyhow::Error) -> &'static str {
+    e.chain().find_map(|cause| cause.downcast_ref::<RepromptError>()).map_or("other", RepromptError::kind)
+}
 
-    let mut output = String::new();
-    let mut first = true;
-    let mut consecutive_empty = 0;
+fn run(cli: Cli) -> Result<Exit> {
+    logging::init(cli.verbose, cli.quiet, cli.log_json);
+    config::set_overrides(cli.set.clone());
+    if let Some(dir) = &cli.state_dir {
+        paths::set_state_dir(dir.clone());
+    }
 
-    for line in ansi_stripped.lines() {
-        // Check if this is a pure border line (top/bottom of box)
-        if RE_BORDER_LINE.is_match(line) {
-            continue;
+    let result = match cli.command {
+        Some(Commands::Watch { interval }) => {
+            let mut reloader = Reloader::new(|| build_cleaner(&cli))?;
+            watch::run(Duration::from_millis(interval), &mut reloader, Reporting::from(&cli))
         }
-
-        // Check if this is a titled border line (top/bottom with text)
-        if RE_TITLED_BORDER.is_match(line) {
-            continue;
+        Some(Commands::Pause { duration }) => {
+            let command = duration.map_or("PAUSE".to_string(), |duration| format!("PAUSE {}", duration.as_secs()));
+            pause::signal(&command, || pause::pause(duration))
         }
-
-        // Check if this is a content line wrapped in borders
-        if let Some(caps) = RE_CONTENT_WRAPPER.captures(line) {
-            if let Some(content) = caps.name("content") {
-                let content_str = content.as_str();
-
-                // Only trim trailing spaces (TUI padding), preserve leading spaces (indentation)
-                // trim_end() removes the padding spaces that TUIs add to reach the right border
-                let trimmed = content_str.trim_end();
-
-                // Track consecutive empty lines to avoid bloat (apply limit globally)
-                if trimmed.is_empty() {
-                    consecutive_empty += 1;
-                    if consecutive_empty > 2 {
-                        continue; // Skip excessive empty lines from wrapped content too
-                    }
-                } else {
-                    consecutive_empty = 0;
-                }
-
-                if !first {
-                    output.push('\n');
-                }
-                output.push_str(trimmed);
-                first = false;
+        Some(Commands::Resume) => pause::signal("RESUME", pause::resume),
+        Some(Commands::Extract { ref format }) => {
+            let format = parse_value::<extract::Format>(format)?;
+            load_config()?;
+            let text = get_clipboard().context("Failed to read clipboard")?;
+            print!("{}", extract::extract(&text).render(format)?);
+            if format == extract::Format::Json {
+                println!();
             }
-        } else {
-            // Line doesn't match any TUI pattern - preserve as-is
-            // This handles regular text, markdown, code, etc.
-
-            // Limit consecutive empty lines to avoid bloat from TUI spacing
-            if line.trim().is_empty() {
-                consecutive_empty += 1;
-                if consecutive_empty > 2 {
-                    continue; // Skip excessive empty lines
-                }
+            Ok(())
+        }
+        Some(Commands::Urls { copy }) => {
+            let cleaner = build_cleaner(&cli)?;
+            print_urls(&cleaner, copy, cli.json)
+        }
+        Some(Commands::Append { ref separator }) => {
+            let cleaner = build_cleaner(&cli)?;
+            append::append(&cleaner, separator.as_deref())
+        }
+        Some(Commands::Flush) => {
+            load_config()?;
+            append::flush()
+        }
+        Some(Commands::Ignore { clear }) => {
+            load_config()?;
+            ignore::run(clear)
+        }
+        Some(Commands::Save { ref name }) => {
+            let cleaner = build_cleaner(&cli)?;
+            slots::save(&cleaner, name)
+        }
+        Some(Commands::Load { ref name }) => {
+            load_config()?;
+            slots::load(name)
+        }
+        Some(Commands::Doctor) => doctor::run(),
+        Some(Commands::Config(ConfigCommand::Check)) => settings::check(),
+        Some(Commands::Config(ConfigCommand::Init { force })) => settings::init(force),
+        Some(Commands::History(HistoryCommand::Restore { index })) => {
+            load_config()?;
+            backup::restore(index)
+        }
+        Some(Commands::History(HistoryCommand::Purge)) => {
+            load_config()?;
+            backup::purge()
+        }
+        Some(Commands::History(HistoryCommand::Key { force })) => vault::create_key(load_config()?.backup.encrypt, force),
+        Some(Commands::Status) => {
+            let config = load_config()?;
+            status::run(cli.profile.as_deref().or(config.profile.as_deref()), cli.json)
+        }
+        Some(Commands::Formats) => {
+            load_config()?;
+            formats::run(cli.json)
+        }
+        #[cfg(feature = "ocr")]
+        Some(Commands::Ocr) => {
+            let cleaner = build_cleaner(&cli)?;
+            screenshot::run(&cleaner, Reporting::from(&cli))
+        }
+        Some(Commands::Read { ref format }) => {
+            load_config()?;
+            formats::read(format)
+        }
+        Some(Commands::Paths) => print_paths(cli.json),
+        Some(Commands::Serve { port, ref token_file }) => {
+            let token = serve::token(token_file.as_deref())?;
+            let cleaner = build_cleaner(&cli)?;
+            serve::run(&cleaner, port, token.as_deref(), Reporting::from(&cli))
+        }
+        Some(Commands::Daemon { action: None }) => {
+            daemon::run(Reloader::new(|| build_cleaner(&cli))?, Reporting::from(&cli))
+        }
+        Some(Commands::Daemon { action: Some(DaemonCommand::Status) }) => {
+            println!("{}", daemon::request("STATUS")?);
+            Ok(())
+        }
+        Some(Commands::Daemon { action: Some(DaemonCommand::Undo) }) => {
+            tracing::info!("{}", daemon::request("UNDO")?);
+            Ok(())
+        }
+        Some(Commands::Mcp) => {
+            let cleaner = build_cleaner(&cli)?;
+            mcp::serve(&cleaner, std::io::stdin().lock(), std::io::stdout().lock())
+        }
+        Some(Commands::Completions { shell }) => {
+            print_completions(shell);
+            Ok(())
+        }
+        Some(Commands::Stats) => {
+            let stats = stats::Stats::load(&paths::stats_file()?);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
             } else {
-                consecutive_empty = 0;
+                println!("{}", stats.render(stats::now()));
+            }
+            Ok(())
+        }
+        Some(Commands::Profile(ProfileCommand::List)) => {
+            for profile in profiles::discover() {
+                let origin = match (&profile.source, &profile.extends) {
+                    (None, _) => " (built-in)".to_string(),
+                    (Some(_), Some(parent)) => format!(" (extends {})", parent),
+                    (Some(_), None) => String::new(),
+                };
+                println!("{} {}{}\t{}", profile.name, profile.version, origin, profile.description);
             }
+            Ok(())
+        }
+        Some(Commands::Profile(ProfileCommand::Install { source, sha256, insecure, force })) => {
+            let path = profiles::install(&source, sha256.as_deref(), insecure, force)?;
+            tracing::info!("Installed {}", path.display());
+            Ok(())
+        }
+        Some(Commands::Corpus(CorpusCommand::Run { ref dir })) => {
+            let cleaner = build_cleaner(&cli)?;
+            corpus::run(&cleaner, dir, cli.json)
+        }
+        Some(Commands::Corpus(CorpusCommand::Record { ref dir, ref file, ref name, force })) => {
+            let cleaner = build_cleaner(&cli)?;
+            corpus::record(&cleaner, dir, file.as_deref(), name.as_deref(), force)
+        }
+        None if cli.register => {
+            let cleaner = build_cleaner(&cli)?;
+            let meta = cli.meta_fd.map(register::open_fd).transpose()?;
+            register::run(&cleaner, std::io::stdin().lock(), std::io::stdout().lock(), meta)
+        }
+        None => return clean_once(&cli),
+    };
+    result.map(|()| Exit::Cleaned)
+}
 
-            if !first {
-                output.push('\n');
+/// Plain `reprompt`: cleans the clipboard once, through the daemon with `--client`.
+fn clean_once(cli: &Cli) -> Result<Exit> {
+    if cli.client {
+        match daemon::request("CLEAN_CLIPBOARD") {
+            Ok(report) => {
+                let outcome = print_daemon_report(&report, Reporting::from(cli))?;
+                return Ok(Exit::of(outcome, cli.fail_on_unchanged));
             }
-            output.push_str(line);
-            first = false;
+            // Only when no daemon got the request; otherwise it may already have cleaned
+            Err(e) if e.is::<daemon::Unreachable>() => tracing::warn!("{:#}; cleaning locally", e),
+            Err(e) => return Err(e),
         }
     }
+    let cleaner = build_cleaner(cli)?;
+    // Serialize with any other reprompt instance (e.g. a watch daemon) so two
+    // processes never interleave read/write cycles on the clipboard
+    let _lock = match InstanceLock::acquire_timeout("clipboard", LOCK_TIMEOUT) {
+        Ok(lock) => lock,
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Ok(Exit::Clipboard);
+        }
+    };
+    let cleaned = clean_clipboard(&cleaner, None, Reporting::from(cli))?;
+    Ok(Exit::of(cleaned.map(|(_, outcome)| outcome), cli.fail_on_unchanged))
+}
 
-    // Final cleanup: remove any trailing whitespace the TUI might have added
-    output.trim_end().to_string()
+/// Prints the daemon's JSON report the way a local run would print its own,
+/// and tells how the clean went as far as the report shows.
+fn print_daemon_report(json: &str, reporting: Reporting) -> Result<Option<Outcome>> {
+    let report: serde_json::Value = serde_json::from_str(json).context("Malformed daemon report")?;
+    // `null`: the daemon left the clipboard alone
+    if report.is_null() {
+        return Ok(None);
+    }
+    if reporting.json {
+        println!("{}", json);
+    } else if report["committed"] == true {
+        reporting.done();
+    }
+    Ok(Some(match (report["committed"] == true, report["changed"] == true) {
+        (true, _) => Outcome::Cleaned,
+        (false, false) => Outcome::Unchanged,
+        (false, true) => Outcome::ValidationFailed,
+    }))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Writes a completion script for `shell` to stdout. `--profile` completes to
+/// the built-in and installed profile names, as of generating the script.
+fn print_completions(shell: clap_complete::Shell) {
+    let generic = profiles::Profile::default();
+    let names: Vec<PossibleValue> = std::iter::once(generic)
+        .chain(profiles::discover())
+        .map(|profile| PossibleValue::new(profile.name).help(profile.description))
+        .collect();
+    let mut command = Cli::command().mut_arg("profile", |arg| arg.value_parser(PossibleValuesParser::new(names)));
+    clap_complete::generate(shell, &mut command, "reprompt", &mut std::io::stdout());
+}
 
-    #[test]
-    fn test_claude_code_titled_border() {
-        let input = "╭─── Claude Code v2.0.47 ──────────────────────────────────────────────────────────────────────────╮\n\
-                     │                             │ Recent activity                                                    │\n\
-                     │     Welcome back Ainesh!    │ No recent activity                                                 │\n\
-                     │                             │ ────────────────────────────────────────────────────────────────── │\n\
-                     │           ▐▛███▜▌           │ What's new                                                         │\n\
-                     ╰──────────────────────────────────────────────────────────────────────────────────────────────────╯";
+/// Loads the config and applies its process-wide settings.
+fn load_config() -> Result<Config> {
+    let config = Config::load()?;
+    apply_settings(&config);
+    Ok(config)
+}
 
-        // The expected output should have the top and bottom lines removed,
-        // and the side borders removed from the content lines.
+/// Makes `config`'s clipboard and backup settings the ones in effect, in
+/// place of any applied before (a reload in `watch` or the daemon).
+fn apply_settings(config: &Config) {
+    clipboard::set_powershell_timeout(config.powershell_timeout_ms.map(Duration::from_millis));
+    clipboard::set_retry(config.retry);
+    clipboard::set_verify(config.verify);
+    backup::configure(config.backup.clone());
+}
 
-        let expected_contains = "Welcome back Ainesh!";
-        let cleaned = clean_text(input);
+/// Loads the config and profiles and applies the command-line overrides. The
+/// config's process-wide settings take effect only once the cleaner builds,
+/// so a reload that fails leaves the previous ones in place.
+fn build_cleaner(cli: &Cli) -> Result<Cleaner> {
+    let mut cleaner = Cleaner::new(Config::load()?, cli.profile.as_deref(), cli.stages.as_deref(), cli.no_substitutions)?;
+    if cli.strip_log_prefixes {
+        cleaner.enable(StageKind::Logprefix);
+    }
+    if cli.collapse_repeats {
+        cleaner.enable(StageKind::Repeats);
+    }
+    if cli.ansi_markdown {
+        cleaner.enable(StageKind::Emphasis);
+    }
+    if cli.nfc || cli.fold_confusables {
+        cleaner.enable(StageKind::Nfc);
+    }
+    if cli.fold_confusables {
+        cleaner.enable(StageKind::Fold);
+    }
+    if cli.ascii_punctuation {
+        cleaner.enable(StageKind::Punctuation);
+    }
+    if let Some(width) = cli.expand_tabs {
+        cleaner.enable(StageKind::Tabs);
+        if let Some(width) = width {
+            cleaner.set_tab_width(width);
+        }
+    }
+    if cli.normalize_indent {
+        cleaner.enable(StageKind::Indent);
+    }
+    if cli.strip_trailing_whitespace {
+        cleaner.enable(StageKind::Trailing);
+    }
+    if cli.ascii_borders {
+        cleaner.enable(StageKind::Grid);
+    }
+    if cli.strip_quotes {
+        cleaner.enable(StageKind::Quotes);
+    }
+    if cli.markdown {
+        cleaner.set_markdown();
+    }
+    if cli.conservative {
+        cleaner.set_conservative();
+    }
+    if cli.json {
+        cleaner.set_line_map();
+    }
+    if cli.profile_stages {
+        cleaner.set_timed();
+    }
+    if let Some(pattern) = &cli.select {
+        cleaner.set_select(pattern);
+    }
+    if let Some(window) = cli.head.map(Window::Head).or(cli.tail.map(Window::Tail)).or(cli.lines) {
+        cleaner.set_window(window);
+    }
+    if let Some(format) = cli.tables {
+        cleaner.set_tables(format);
+    }
+    if let Some(format) = &cli.format {
+        cleaner.set_format(parse_value(format)?);
+    }
+    if let Some(format) = cli.header {
+        cleaner.set_header(format);
+    }
+    if let Some(screen) = cli.screen {
+        cleaner.set_screen(screen);
+    }
+    if let Some(mode) = cli.images {
+        cleaner.set_images(mode);
+    }
+    if let Some(line_endings) = cli.line_endings {
+        cleaner.set_line_endings(line_endings);
+    }
+    apply_settings(cleaner.config());
+    Ok(cleaner)
+}
+
+/// How a cleaning run reports its result.
+#[derive(Clone, Copy, Default)]
+struct Reporting {
+    explain: bool,
+    json: bool,
+    quiet: bool,
+    no_emoji: bool,
+    profile_stages: bool,
+}
+
+impl From<&Cli> for Reporting {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            explain: cli.explain,
+            json: cli.json,
+            quiet: cli.quiet,
+            no_emoji: cli.no_emoji,
+            profile_stages: cli.profile_stages,
+        }
+    }
+}
+
+impl Reporting {
+    fn emit(self, report: &Report) {
+        if self.json {
+            match serde_json::to_string(report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => tracing::error!("Failed to serialize report: {}", e),
+            }
+            return;
+        }
+        if self.explain {
+            eprintln!("{}", report.explain());
+        }
+        if self.profile_stages && !report.timings.is_empty() {
+            eprintln!("{}", timing::render(&report.timings));
+        }
+        if report.committed {
+            self.done();
+        }
+    }
 
-        println!("Cleaned Output:\n{}", cleaned);
+    /// Success feedback. Goes to stderr, so stdout only ever carries data
+    /// (`--json`, `--register`, `extract`) and stays safe to pipe.
+    fn done(self) {
+        if !self.quiet {
+            eprintln!("{}", if self.no_emoji { "cleaned" } else { "✨" });
+        }
+    }
+}
 
-        assert!(cleaned.contains(expected_contains), "Should contain content");
-        assert!(!cleaned.contains("Claude Code v2.0.47"), "Should remove titled top border");
-        assert!(!cleaned.contains("╰───"), "Should remove bottom border");
-        assert!(!cleaned.contains("│     Welcome"), "Should remove left border");
+/// Asks on the terminal whether to commit despite `message`. Without a
+/// terminal (a hotkey, `watch`) the answer is no.
+fn confirm(message: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(false);
     }
+    eprint!("{}. Commit anyway? [y/N] ", message);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
 
-    #[test]
-    fn test_ansi_stripping() {
-        let input = "\x1b[31mHello\x1b[0m World";
-        let cleaned = clean_text(input);
-        assert_eq!(cleaned, "Hello World");
+/// Reads the clipboard and returns it as is and cleaned, without writing it back.
+fn read_clipboard(cleaner: &Cleaner) -> Result<(String, Report)> {
+    let text = with_retry(get_clipboard).context("Failed to read clipboard")?;
+    anyhow::ensure!(!text.trim().is_empty(), "Clipboard is empty");
+    sensitive::ensure_safe(&text)?;
+    let report = cleaner.clean_checked(&text, None)?;
+    anyhow::ensure!(report.kind != ContentKind::Binary, "Clipboard looks like binary data");
+    Ok((text, report))
+}
+
+/// Reads the clipboard and returns it cleaned, without writing it back.
+fn read_cleaned(cleaner: &Cleaner) -> Result<Report> {
+    Ok(read_clipboard(cleaner)?.1)
+}
 
-        let input_nested = "\x1b[1;31mBold Red\x1b[0m";
-        let cleaned = clean_text(input_nested);
-        assert_eq!(cleaned, "Bold Red");
+/// `reprompt paths`: every file and directory reprompt reads or writes.
+fn print_paths(json: bool) -> Result<()> {
+    let entries = [
+        ("config", paths::config_file()?),
+        ("profiles", paths::profiles_dir()?),
+        ("plugins", paths::plugins_dir()?),
+        ("state", paths::state_dir()?),
+        ("stats", paths::stats_file()?),
+        ("slots", paths::slots_dir()?),
+        ("backup", backup::latest_path()?),
+        ("append", paths::append_file()?),
+        ("ignored", paths::ignore_file()?),
+        ("cache", paths::cache_dir()?),
+        ("daemon socket", daemon::socket_path()),
+    ];
+    if json {
+        let map: serde_json::Map<String, serde_json::Value> =
+            entries.iter().map(|(name, path)| (name.to_string(), path.display().to_string().into())).collect();
+        println!("{}", serde_json::to_string_pretty(&map)?);
+    } else {
+        for (name, path) in &entries {
+            println!("{:<14} {}", name, path.display());
+        }
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_code_with_pipes() {
-        let input = "│ let x = a | b; │";
-        let cleaned = clean_text(input);
-        assert_eq!(cleaned, "let x = a | b;");
+/// `reprompt urls`: lists the URLs in the cleaned clipboard, including OSC 8
+/// hyperlink targets, or with `copy` puts the list on the clipboard.
+fn print_urls(cleaner: &Cleaner, copy: bool, json: bool) -> Result<()> {
+    let _lock = InstanceLock::acquire_timeout("clipboard", LOCK_TIMEOUT)?;
+    let (raw, report) = read_clipboard(cleaner)?;
+    let found = urls::collect(&raw, &report.output);
+    anyhow::ensure!(!found.is_empty(), "No URLs found in the clipboard");
+    if copy {
+        with_retry(|| set_clipboard(&found.join("\n"))).context("Failed to write clipboard")?;
+        tracing::info!("Copied {} URLs to the clipboard", found.len());
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&found)?);
+    } else {
+        for url in &found {
+            println!("{}", url);
+        }
     }
+    Ok(())
 }
 
-fn main() -> Result<()> {
+/// Runs one snapshot → transform → validate → commit cycle on the clipboard.
+/// Failures are reported to stderr and leave the clipboard untouched. Returns
+/// the report, or `None` if the clipboard was left alone (empty, sensitive,
+/// over `max_mb`, or in watch mode unreadable or our own write).
+/// In watch mode, `recent` holds reprompt's own recent writes so they are skipped.
+fn clean_clipboard(
+    cleaner: &Cleaner,
+    recent: Option<&mut RecentWrites>,
+    reporting: Reporting,
+) -> Result<Option<(Report, Outcome)>> {
     // Phase 1: SNAPSHOT - Create transaction and backup clipboard
-    let mut transaction = match ClipboardTransaction::new() {
+    let started = Instant::now();
+    let transaction = match ClipboardTransaction::snapshot(cleaner) {
         Ok(tx) => tx,
+        // A one-off clean reports it (exit status 3); the watcher waits for the next copy
+        Err(e) if recent.is_none() => return Err(e),
         Err(e) => {
-            // If we cannot read clipboard, exit gracefully
-            eprintln!("Error reading clipboard: {}", e);
-            return Ok(());
+            tracing::error!("Error reading clipboard: {:#}", e);
+            return Ok(None);
         }
     };
+    let read_time = started.elapsed();
+    clean_transaction(cleaner, transaction, read_time, recent, reporting)
+}
 
-    let original_text = transaction.original();
+/// The rest of [`clean_clipboard`] once the clipboard has been read into `transaction`.
+fn clean_transaction(
+    cleaner: &Cleaner,
+    mut transaction: ClipboardTransaction,
+    read_time: Duration,
+    recent: Option<&mut RecentWrites>,
+    reporting: Reporting,
+) -> Result<Option<(Report, Outcome)>> {
+    let original_text = transaction.source();
 
     // Handle empty clipboard gracefully
     if original_text.trim().is_empty() {
-        return Ok(());
+        return Ok(None);
     }
 
-    // Phase 2: TRANSFORM - Clean the text (remove TUI artifacts)
-    let cleaned_text = clean_text(original_text);
+    // Don't re-process text we just wrote ourselves (watch mode loop prevention)
+    if recent.as_ref().is_some_and(|recent| recent.contains(original_text)) {
+        return Ok(None);
+    }
+    if let Some(reason) = sensitive::reason(original_text) {
+        // Routine for the watcher; worth a word when asked to clean by hand
+        if recent.is_some() {
+            tracing::info!("Leaving the clipboard alone: {}", reason);
+        } else {
+            tracing::warn!("Leaving the clipboard alone: {}", reason);
+        }
+        return Ok(None);
+    }
+    if recent.is_some() {
+        if let Some(reason) = ignore::reason(cleaner.config(), original_text) {
+            tracing::debug!("Leaving the clipboard alone: {}", reason);
+            return Ok(None);
+        }
+    }
+
+    // Phase 2: TRANSFORM - Clean the text (remove TUI artifacts), refusing
+    // oversized clipboards before spending time on them
+    let mut report = match cleaner.clean_checked(original_text, None) {
+        Ok(report) => report,
+        Err(e @ RepromptError::TooLarge { .. }) => {
+            tracing::error!("{e}");
+            stats::record(Outcome::TooLarge, original_text.len(), None);
+            return Ok(None);
+        }
+        // A one-off clean reports it; the watcher carries on with the next copy
+        Err(e) if recent.is_none() => return Err(e.into()),
+        Err(e) => {
+            tracing::error!("{e}; clipboard unchanged");
+            return Ok(None);
+        }
+    };
+    // The recovered text goes back even if it needed no cleaning
+    if transaction.recovered.is_some() {
+        report.recovered = Some("rtf");
+        report.changed = true;
+    }
+    if cleaner.timed() {
+        report.timings.insert(0, Timing::new("clipboard read", read_time));
+    }
 
     // Early exit if no changes (don't waste write cycles)
-    if cleaned_text == original_text {
-        return Ok(());
+    if !report.changed {
+        let outcome = if report.kind == ContentKind::Binary {
+            Outcome::Binary
+        } else if report.held_back {
+            Outcome::HeldBack
+        } else {
+            Outcome::Unchanged
+        };
+        stats::record(outcome, original_text.len(), Some(&report));
+        reporting.emit(&report);
+        return Ok(Some((report, outcome)));
     }
 
-    transaction.set_modified(cleaned_text);
+    transaction.set_modified(&report);
 
-    // Phase 3: VALIDATE - Check for corruption before committing
-    if let Err(e) = transaction.validate() {
-        eprintln!("Validation failed: {e}");
-        eprintln!("Aborting operation. Clipboard unchanged.");
-        return Ok(());
+    // Phase 3: VALIDATE - Check for corruption before committing, falling back
+    // to weaker cleaning if the full result is rejected
+    let mut policy = cleaner.config().validation.clone();
+    // A selected section or window is meant to be a fraction of the text
+    if report.cropped.is_some() {
+        policy.reduction = Action::Off;
+        policy.line_drop = Action::Off;
+    }
+    // Markdown mode treats a broken fence as corruption, not a warning
+    if cleaner.config().markdown && policy.fences != Action::Off {
+        policy.fences = Action::Abort;
+    }
+    let policy = &policy;
+    if let Err(e) = transaction.validate(policy) {
+        let fallbacks = if policy.partial { &Level::FALLBACKS[..] } else { &[] };
+        let partial = fallbacks.iter().find_map(|&level| {
+            let partial = cleaner.clean_partial(transaction.source(), &report, level);
+            if !partial.changed {
+                return None;
+            }
+            transaction.set_modified(&partial);
+            transaction.validate(policy).is_ok().then_some(partial)
+        });
+        match partial {
+            Some(partial) => {
+                tracing::warn!("Validation failed: {e}. Falling back to {}.", partial.level);
+                report = partial;
+            }
+            None => {
+                tracing::error!("Validation failed: {e}. Aborting operation; clipboard unchanged.");
+                stats::record(Outcome::ValidationFailed, transaction.source().len(), Some(&report));
+                reporting.emit(&report);
+                return Ok(Some((report, Outcome::ValidationFailed)));
+            }
+        }
+    }
+
+    // A clean that broke its own guarantees is logged, never committed
+    if !report.violations.is_empty() {
+        for violation in &report.violations {
+            tracing::error!("Invariant violated: {violation}");
+        }
+        tracing::error!("Aborting operation; clipboard unchanged.");
+        stats::record(Outcome::ValidationFailed, transaction.source().len(), Some(&report));
+        reporting.emit(&report);
+        return Ok(Some((report, Outcome::ValidationFailed)));
     }
 
     // Phase 4 & 5: COMMIT and VERIFY - Write with automatic verification and rollback
-    match transaction.commit() {
+    let bytes_in = transaction.source().len();
+    let started = Instant::now();
+    let committed = transaction.commit();
+    if cleaner.timed() {
+        report.timings.push(Timing::new("clipboard write", started.elapsed()));
+    }
+    match committed {
         Ok(()) => {
-            // Success feedback
-            println!("✨");
-            Ok(())
+            if let Some(recent) = recent {
+                recent.record(&report.output);
+            }
+            report.committed = true;
+            stats::record(Outcome::Cleaned, bytes_in, Some(&report));
+            reporting.emit(&report);
+            Ok(Some((report, Outcome::Cleaned)))
         }
         Err(e) => {
-            eprintln!("Transaction failed: {}", e);
+            tracing::error!("Transaction failed: {}", e);
             // The transaction already attempted rollback
-            Ok(())
+            stats::record(Outcome::CommitFailed, bytes_in, Some(&report));
+            reporting.emit(&report);
+            Ok(Some((report, Outcome::CommitFailed)))
         }
     }
 }
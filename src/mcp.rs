@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::clipboard::{get_clipboard, set_clipboard, with_retry};
+use reprompt::lock::InstanceLock;
+
+/// MCP revision this server speaks
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Tool descriptions returned by `tools/list`.
+fn tools() -> Value {
+    json!([
+        {
+            "name": "clean_text",
+            "description": "Strip terminal UI artifacts (box borders, ANSI codes, status lines, mojibake) from text copied out of a TUI. Returns the cleaned text, with the cleaning report as structured content.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to clean" },
+                    "profile": { "type": "string", "description": "Cleaning profile (e.g. claude-code); detected from the text if omitted" }
+                },
+                "required": ["text"]
+            }
+        },
+        {
+            "name": "get_clean_clipboard",
+            "description": "Read the user's clipboard and return it cleaned of terminal UI artifacts. The clipboard itself is not changed.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "profile": { "type": "string", "description": "Cleaning profile; detected from the text if omitted" }
+                }
+            }
+        },
+        {
+            "name": "set_clipboard",
+            "description": "Replace the user's clipboard with the given text.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to put on the clipboard" }
+                },
+                "required": ["text"]
+            }
+        }
+    ])
+}
+
+fn text_result(text: impl Into<String>) -> Value {
+    json!({ "content": [{ "type": "text", "text": text.into() }] })
+}
+
+/// A failed tool call is reported to the model as a result, not a protocol error.
+fn error_result(error: anyhow::Error) -> Value {
+    json!({ "content": [{ "type": "text", "text": format!("{:#}", error) }], "isError": true })
+}
+
+fn clean_result(cleaner: &Cleaner, text: &str, profile: Option<&str>) -> Result<Value> {
+    let report = cleaner.clean_checked(text, profile)?;
+    let mut result = text_result(report.output.clone());
+    result["structuredContent"] = serde_json::to_value(&report)?;
+    Ok(result)
+}
+
+fn call_tool(cleaner: &Cleaner, name: &str, arguments: &Value) -> Result<Value> {
+    let profile = arguments["profile"].as_str();
+    match name {
+        "clean_text" => {
+            let text = arguments["text"].as_str().context("clean_text needs a `text` string")?;
+            clean_result(cleaner, text, profile)
+        }
+        "get_clean_clipboard" => {
+            let text = with_retry(get_clipboard).context("Failed to read clipboard")?;
+            crate::sensitive::ensure_safe(&text)?;
+            clean_result(cleaner, &text, profile)
+        }
+        "set_clipboard" => {
+            let text = arguments["text"].as_str().context("set_clipboard needs a `text` string")?;
+            let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+            with_retry(|| set_clipboard(text)).context("Failed to write clipboard")?;
+            Ok(text_result(format!("Copied {} bytes to the clipboard", text.len())))
+        }
+        _ => anyhow::bail!("Unknown tool '{}'", name),
+    }
+}
+
+fn error(id: &Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+/// Answers one JSON-RPC message; notifications get no answer.
+fn handle(cleaner: &Cleaner, message: &Value) -> Option<Value> {
+    let id = message.get("id")?;
+    let params = &message["params"];
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "reprompt", "version": env!("CARGO_PKG_VERSION") }
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let Some(name) = params["name"].as_str() else {
+                return Some(error(id, INVALID_PARAMS, "tools/call needs a tool `name`"));
+            };
+            if !tools().as_array().is_some_and(|tools| tools.iter().any(|tool| tool["name"] == name)) {
+                return Some(error(id, INVALID_PARAMS, format!("Unknown tool '{}'", name)));
+            }
+            call_tool(cleaner, name, &params["arguments"]).unwrap_or_else(error_result)
+        }
+        method => return Some(error(id, METHOD_NOT_FOUND, format!("Method '{}' not found", method))),
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Serves MCP over newline-delimited JSON-RPC until `input` closes. Logs go
+/// to stderr, so `output` carries nothing but protocol messages.
+pub fn serve(cleaner: &Cleaner, input: impl BufRead, mut output: impl Write) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(cleaner, &message),
+            Err(e) => Some(error(&Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::config::Config;
+
+    #[test]
+    fn test_session() {
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"clean_text","arguments":{"text":"│ hello │","profile":"generic"}}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"clean_text","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"resources/list"}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        serve(&cleaner, input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Value> =
+            String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "reprompt");
+        assert_eq!(responses[1]["result"]["tools"].as_array().unwrap().len(), 3);
+        assert_eq!(responses[2]["result"]["content"][0]["text"], "hello");
+        assert_eq!(responses[2]["result"]["structuredContent"]["profile"], "generic");
+        assert_eq!(responses[3]["result"]["isError"], true);
+        assert_eq!(responses[4]["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,92 @@
+use serde::Deserialize;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::{RepromptError, Result};
+use crate::process::run_with_timeout;
+
+/// `[ocr]`: the command `reprompt ocr` reads a screenshot with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrSpec {
+    /// Program and arguments; the image is piped to stdin and the text read from stdout
+    pub command: Vec<String>,
+    /// Give up on the image after this long
+    pub timeout_ms: u64,
+}
+
+impl Default for OcrSpec {
+    fn default() -> Self {
+        // One uniform block of text, with the spaces that align terminal columns kept
+        let command = ["tesseract", "stdin", "stdout", "--psm", "6", "-c", "preserve_interword_spaces=1"];
+        Self { command: command.map(String::from).to_vec(), timeout_ms: 30_000 }
+    }
+}
+
+/// The text `spec`'s command recognizes in `image` (PNG, BMP, TIFF or
+/// anything else it reads).
+pub fn recognize(spec: &OcrSpec, image: &[u8]) -> Result<String> {
+    let Some((program, args)) = spec.command.split_first() else {
+        return Err(RepromptError::InvalidSetting("[ocr] command must name a program".to_string()));
+    };
+    let mut command = Command::new(program);
+    command.args(args);
+    let output = match run_with_timeout(&mut command, image, Duration::from_millis(spec.timeout_ms)) {
+        Err(RepromptError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Err(RepromptError::Command(format!("{} not found; install it or set [ocr] command", program)));
+        }
+        result => result?,
+    };
+    if !output.status.success() {
+        return Err(RepromptError::Command(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Turns a Windows device-independent bitmap (`CF_DIB`) into a BMP file by
+/// putting the file header in front. `None` if `dib` is too short to be one.
+pub fn dib_to_bmp(dib: &[u8]) -> Option<Vec<u8>> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(dib.get(at..at + 4)?.try_into().ok()?));
+    let header = u32_at(0)?;
+    let bits = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let (compression, colors_used) = (u32_at(16)?, u32_at(32)?);
+    let palette = match (bits, colors_used) {
+        (1..=8, 0) => 1 << bits,
+        (_, used) => used,
+    };
+    // BI_BITFIELDS masks follow a plain BITMAPINFOHEADER
+    let masks = if compression == 3 && header == 40 { 12 } else { 0 };
+    let offset = 14 + header + palette * 4 + masks;
+    let size = u32::try_from(14 + dib.len()).ok()?;
+
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Some(bmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dib_to_bmp_header() {
+        // 1x1, 8 bits per pixel with a full palette: pixels start after 14 + 40 + 256 * 4 bytes
+        let mut dib = vec![0u8; 40 + 1024 + 4];
+        dib[0] = 40;
+        dib[14] = 8;
+        let bmp = dib_to_bmp(&dib).unwrap();
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32::from_le_bytes(bmp[2..6].try_into().unwrap()) as usize, bmp.len());
+        assert_eq!(u32::from_le_bytes(bmp[10..14].try_into().unwrap()), 14 + 40 + 1024);
+        assert_eq!(dib_to_bmp(&[40, 0]), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::{RepromptError, Result};
+
+static STATE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Per-user configuration directory: `$XDG_CONFIG_HOME/reprompt`,
+/// `%APPDATA%\reprompt`, `~/Library/Application Support/reprompt` on macOS
+/// (unless `~/.config/reprompt` is already there), else `~/.config/reprompt`.
+pub fn config_dir() -> Result<PathBuf> {
+    if cfg!(windows) {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            return Ok(PathBuf::from(appdata).join("reprompt"));
+        }
+    }
+    if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Ok(PathBuf::from(xdg).join("reprompt"));
+    }
+    let home = home_dir()?;
+    Ok(on_macos(&home, "Application Support", home.join(".config").join("reprompt")))
+}
+
+/// Main configuration file.
+pub fn config_file() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Directory scanned for standalone profile files.
+pub fn profiles_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("profiles.d"))
+}
+
+/// Base directory for relative WASM plugin paths.
+pub fn plugins_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("plugins"))
+}
+
+/// Keeps state (and the cache, under `cache/`) in `dir` instead, for `--state-dir`;
+/// only the first call counts.
+pub fn set_state_dir(dir: PathBuf) {
+    let _ = STATE_DIR.set(dir);
+}
+
+/// Per-user state directory: `$XDG_STATE_HOME/reprompt`, `%LOCALAPPDATA%\reprompt`,
+/// `~/Library/Application Support/reprompt` on macOS (unless
+/// `~/.local/state/reprompt` is already there), else `~/.local/state/reprompt`.
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = STATE_DIR.get() {
+        return Ok(dir.clone());
+    }
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            return Ok(PathBuf::from(local).join("reprompt"));
+        }
+    }
+    if let Some(xdg) = std::env::var_os("XDG_STATE_HOME") {
+        return Ok(PathBuf::from(xdg).join("reprompt"));
+    }
+    let home = home_dir()?;
+    Ok(on_macos(&home, "Application Support", home.join(".local").join("state").join("reprompt")))
+}
+
+/// Running totals for `reprompt stats`.
+pub fn stats_file() -> Result<PathBuf> {
+    Ok(state_dir()?.join("stats.json"))
+}
+
+/// Named clipboard slots for `reprompt save` and `reprompt load`.
+pub fn slots_dir() -> Result<PathBuf> {
+    Ok(state_dir()?.join("slots"))
+}
+
+/// Fingerprints of clipboard contents `reprompt watch` leaves alone (`reprompt ignore`).
+pub fn ignore_file() -> Result<PathBuf> {
+    Ok(state_dir()?.join("ignored.txt"))
+}
+
+/// Text collected by `reprompt append` until `reprompt flush` (mode 0600), kept
+/// with the state so a collection survives logging out.
+pub fn append_file() -> Result<PathBuf> {
+    Ok(state_dir()?.join("append.txt"))
+}
+
+/// Per-user cache directory, for files that are rebuilt when missing:
+/// `$XDG_CACHE_HOME/reprompt`, `%LOCALAPPDATA%\reprompt\cache`,
+/// `~/Library/Caches/reprompt` on macOS, else `~/.cache/reprompt`.
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Some(dir) = STATE_DIR.get() {
+        return Ok(dir.join("cache"));
+    }
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            return Ok(PathBuf::from(local).join("reprompt").join("cache"));
+        }
+    }
+    if let Some(xdg) = std::env::var_os("XDG_CACHE_HOME") {
+        return Ok(PathBuf::from(xdg).join("reprompt"));
+    }
+    let home = home_dir()?;
+    Ok(on_macos(&home, "Caches", home.join(".cache").join("reprompt")))
+}
+
+/// A lock file or socket: in the per-user runtime directory when available,
+/// otherwise the temp directory with the user name mixed in to keep users apart.
+pub fn runtime_file(name: &str) -> PathBuf {
+    if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return PathBuf::from(runtime).join(format!("reprompt-{}", name));
+    }
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "default".to_string());
+    std::env::temp_dir().join(format!("reprompt-{}-{}", user, name))
+}
+
+/// `~/Library/<library>/reprompt` on macOS, unless `xdg` (where earlier
+/// versions kept these files there too) already exists; `xdg` elsewhere.
+fn on_macos(home: &Path, library: &str, xdg: PathBuf) -> PathBuf {
+    if cfg!(target_os = "macos") && !xdg.exists() {
+        home.join("Library").join(library).join("reprompt")
+    } else {
+        xdg
+    }
+}
+
+/// The user's home directory. Without one there's nowhere private to keep
+/// files, so this fails rather than fall back to the shared temp directory.
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            let error = std::io::Error::new(std::io::ErrorKind::NotFound, "HOME is not set");
+            RepromptError::Io {
+                context: "No home directory to keep reprompt's files in (set HOME, or XDG_CONFIG_HOME and XDG_STATE_HOME)"
+                    .to_string(),
+                error,
+            }
+        })
+}
@@ -0,0 +1,107 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reprompt::paths;
+
+use crate::stats::now;
+
+/// Where `reprompt pause` leaves word for the watcher: the Unix time to
+/// resume at, or nothing to stay paused until `reprompt resume`.
+fn pause_path() -> PathBuf {
+    paths::runtime_file("paused")
+}
+
+/// Reads a duration such as `90`, `30s`, `5m`, `2h` or `1d`; plain numbers are seconds.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().with_context(|| format!("'{}' is not a duration like 30s, 5m or 2h", text))?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("Unknown unit '{}' in '{}'; use s, m, h or d", unit, text),
+    };
+    Ok(Duration::from_secs(number.saturating_mul(seconds)))
+}
+
+/// The pause recorded in `path` as of `now` (Unix seconds): `None` when there
+/// is none, `Some(None)` until `reprompt resume`, `Some(Some(until))` otherwise.
+fn read(path: &Path, now: u64) -> Option<Option<u64>> {
+    let text = std::fs::read_to_string(path).ok()?;
+    match text.trim().parse::<u64>() {
+        Ok(until) => (now < until).then_some(Some(until)),
+        Err(_) => Some(None),
+    }
+}
+
+/// The current pause, as `read` describes it.
+pub fn state() -> Option<Option<u64>> {
+    read(&pause_path(), now())
+}
+
+/// Whether the watcher should leave the clipboard alone right now.
+pub fn is_paused() -> bool {
+    state().is_some()
+}
+
+/// Stops the watcher cleaning for `duration`, or until `resume` without one.
+/// It keeps running and picks up again by itself. Returns what happened.
+pub fn pause(duration: Option<Duration>) -> Result<String> {
+    let path = pause_path();
+    let until = duration.map(|duration| now().saturating_add(duration.as_secs()).to_string()).unwrap_or_default();
+    std::fs::write(&path, until).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(match duration {
+        Some(duration) => format!("Watch mode paused for {}s", duration.as_secs()),
+        None => "Watch mode paused until `reprompt resume`".to_string(),
+    })
+}
+
+/// Ends a pause early. Returns what happened.
+pub fn resume() -> Result<String> {
+    let path = pause_path();
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok("Watch mode resumed".to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok("Watch mode wasn't paused".to_string()),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+}
+
+/// `reprompt pause` and `reprompt resume`: sends `command` (`PAUSE [seconds]`
+/// or `RESUME`) to a running daemon, which applies it, or applies it here when
+/// none is listening.
+pub fn signal(command: &str, local: impl FnOnce() -> Result<String>) -> Result<()> {
+    let message = match crate::daemon::request(command) {
+        Ok(message) => message,
+        Err(e) if e.is::<crate::daemon::Unreachable>() => local()?,
+        Err(e) => return Err(e),
+    };
+    tracing::info!("{}", message);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_until_and_durations() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("99999999999999999d").unwrap(), Duration::from_secs(u64::MAX));
+        assert!(parse_duration("5 minutes").is_err() && parse_duration("m").is_err());
+
+        let path = std::env::temp_dir().join(format!("reprompt-pause-{}", std::process::id()));
+        let missing = read(&path, 100);
+        std::fs::write(&path, "150").unwrap();
+        let (during, after) = (read(&path, 149), read(&path, 150));
+        std::fs::write(&path, "").unwrap();
+        let indefinite = read(&path, u64::MAX);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((missing, during, after, indefinite), (None, Some(Some(150)), None, Some(None)));
+    }
+}
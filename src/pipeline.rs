@@ -0,0 +1,376 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::rules::Rule;
+use crate::stages::{
+    AnsiEmphasis, AnsiStrip, ArtStrip, AsciiGrid, AsciiPunctuation, BorderStrip, BracketedPaste, Coalesce, ConfusableFold, DecGraphics,
+    DiffGuard, ExecFilter, ExecSpec, GlyphSpec, GlyphStrip, ImageMode, ImageStrip, IndentNormalize, IndentSpec, InlineScrub,
+    InvisibleStrip, LogPrefixStrip, MarkdownGuard, MojibakeRepair, NfcNormalize, PagerStrip, QuoteStrip, RepeatCollapse, RuleFilter, Screen, ScreenSelect,
+    SideBySide, SpinnerDedup, StatusLineSpec, StatusLineStrip, Substitute, Substitution, TabExpand, TrailingTrim, VerbatimRegion,
+    VerbatimRegions, WasmFilter,
+};
+
+/// One line of text flowing through the pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    /// Protected content (e.g. from a `keep` rule) that heuristics must not touch
+    pub verbatim: bool,
+}
+
+impl Line {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), verbatim: false }
+    }
+}
+
+/// A single cleaning pass over the document. Stages are shared across
+/// threads while variants are cleaned in parallel.
+pub trait Stage: Send + Sync {
+    /// Name used in config files and `--stages`
+    fn name(&self) -> &'static str;
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line>;
+
+    /// Whether this stage hands the text to outside code (a filter command,
+    /// a plugin): slow and maybe with side effects, so it runs once per clean
+    /// rather than once per search variant.
+    fn external(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in stages, selectable by name from config or the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StageKind {
+    /// Undo UTF-8 text that was decoded as Windows-1252 ("â”‚" → "│")
+    Mojibake,
+    /// Keep the main screen, the alternate screen full-screen programs draw on, or both (`screen`)
+    Screen,
+    /// Remove bracketed-paste markers and protect the pasted text from the stages after it
+    Paste,
+    /// Remove inline images (sixel, iTerm2, kitty), or leave `[image omitted]` with `images = "placeholder"`
+    Images,
+    /// Translate DEC special graphics line drawing (`ESC(0` then `lqqk`) to box-drawing characters
+    Charset,
+    /// Turn bold/italic/underline and red/green diff lines into Markdown (opt-in, before `ansi`)
+    Emphasis,
+    /// Remove ANSI escape sequences
+    Ansi,
+    /// Protect fenced code blocks and `[[verbatim]]` regions from the stages after it
+    Verbatim,
+    /// Turn side-by-side diffs (delta, difftastic) back into unified diff hunks
+    Sidebyside,
+    /// Protect unified diffs and patches from the stages after it
+    Diff,
+    /// Remove more/less/man status lines and overstrike formatting
+    Pager,
+    /// Collapse lines that differ only in a spinner glyph (`⠋ Building…`, `⠙ Building…`)
+    Spinners,
+    /// Remove tmux/screen status bars at the top or bottom
+    Status,
+    /// Remove Nerd Font icons and Powerline separators
+    Glyphs,
+    /// Remove multi-line braille and block-element art (logos, charts)
+    Art,
+    /// Apply user-defined `[[rules]]`, after `verbatim` and before every heuristic
+    Rules,
+    /// Drop box borders and unwrap bordered content
+    Border,
+    /// Remove box-drawing column dividers left inside content
+    Inline,
+    /// Protect Markdown fences, tables, headings and list items from the stages after it (opt-in, after `inline`)
+    Markdown,
+    /// Run the configured WASM cleaner plugins
+    Wasm,
+    /// Pipe the text through the profile's external `[exec]` command
+    Exec,
+    /// Remove zero-width characters, soft hyphens and bidi controls
+    Invisible,
+    /// Strip leading timestamps, levels and pid/thread tags from log lines (opt-in)
+    Logprefix,
+    /// Fold runs of repeated lines into one with a `[repeated N times]` note (opt-in)
+    Repeats,
+    /// Normalize to Unicode NFC (opt-in)
+    Nfc,
+    /// Fold full-width ASCII, curly quotes and odd spaces to plain ASCII (opt-in)
+    Fold,
+    /// Spell smart quotes, dashes, ellipses and non-breaking spaces in ASCII (opt-in)
+    Punctuation,
+    /// Expand tabs to spaces at `[indent] tab_width` stops (opt-in)
+    Tabs,
+    /// Turn leading tabs into spaces in code blocks that mix the two (opt-in)
+    Indent,
+    /// Trim trailing whitespace from every line (opt-in)
+    Trailing,
+    /// Drop ASCII table frames (`+----+`, `| cell |`) from MySQL, psql and similar (opt-in)
+    Grid,
+    /// Remove `>` quote markers and re-flow the quoted paragraphs (opt-in)
+    Quotes,
+    /// Collapse blank-line runs and trailing whitespace
+    Coalesce,
+    /// Apply user `[[substitutions]]` to the cleaned text
+    Substitute,
+}
+
+impl StageKind {
+    pub const DEFAULT_ORDER: &'static [StageKind] = &[
+        StageKind::Mojibake,
+        StageKind::Screen,
+        StageKind::Paste,
+        StageKind::Images,
+        StageKind::Charset,
+        StageKind::Ansi,
+        StageKind::Verbatim,
+        StageKind::Rules,
+        StageKind::Sidebyside,
+        StageKind::Diff,
+        StageKind::Pager,
+        StageKind::Spinners,
+        StageKind::Status,
+        StageKind::Glyphs,
+        StageKind::Art,
+        StageKind::Border,
+        StageKind::Inline,
+        StageKind::Wasm,
+        StageKind::Exec,
+        StageKind::Invisible,
+        StageKind::Coalesce,
+        StageKind::Substitute,
+    ];
+
+    /// Adds an opt-in stage unless it's already there: just before `ansi` for
+    /// stages that need the escape codes, just after `inline` for `markdown`,
+    /// which needs the borders gone, otherwise before `coalesce` (or at the end).
+    pub fn enable(order: &mut Vec<StageKind>, kind: StageKind) {
+        if order.contains(&kind) {
+            return;
+        }
+        let position = |anchor: StageKind| order.iter().position(|k| *k == anchor);
+        let at = match kind {
+            StageKind::Emphasis => position(StageKind::Ansi),
+            StageKind::Markdown => position(StageKind::Inline).map(|at| at + 1),
+            _ => position(StageKind::Coalesce),
+        };
+        order.insert(at.unwrap_or(order.len()), kind);
+    }
+}
+
+/// Settings for stages that need more than their name.
+#[derive(Debug, Clone, Default)]
+pub struct StageOptions {
+    /// Line rules for the `rules` stage
+    pub rules: Vec<Rule>,
+    /// External command for the `exec` stage
+    pub exec: Option<ExecSpec>,
+    /// Module paths for the `wasm` stage
+    pub plugins: Vec<PathBuf>,
+    /// Rewrites for the `substitute` stage
+    pub substitutions: Vec<Substitution>,
+    /// Settings for the `status` stage
+    pub status_line: StatusLineSpec,
+    /// Allowlist for the `glyphs` stage
+    pub glyphs: GlyphSpec,
+    /// Protected regions for the `verbatim` stage
+    pub verbatim: Vec<VerbatimRegion>,
+    /// Tab stops for the `tabs` and `indent` stages
+    pub indent: IndentSpec,
+    /// Screen kept by the `screen` stage
+    pub screen: Screen,
+    /// What the `images` stage leaves in place of an image
+    pub images: ImageMode,
+}
+
+/// An ordered list of stages that turns raw clipboard text into clean text.
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new(StageKind::DEFAULT_ORDER, StageOptions::default())
+    }
+}
+
+impl Pipeline {
+    /// Builds a pipeline running `order` in sequence.
+    pub fn new(order: &[StageKind], options: StageOptions) -> Self {
+        let stages = order
+            .iter()
+            .map(|kind| -> Box<dyn Stage> {
+                match kind {
+                    StageKind::Mojibake => Box::new(MojibakeRepair),
+                    StageKind::Screen => Box::new(ScreenSelect::new(options.screen)),
+                    StageKind::Paste => Box::new(BracketedPaste),
+                    StageKind::Images => Box::new(ImageStrip::new(options.images)),
+                    StageKind::Charset => Box::new(DecGraphics),
+                    StageKind::Emphasis => Box::new(AnsiEmphasis),
+                    StageKind::Ansi => Box::new(AnsiStrip),
+                    StageKind::Verbatim => Box::new(VerbatimRegions::new(options.verbatim.clone())),
+                    StageKind::Sidebyside => Box::new(SideBySide),
+                    StageKind::Diff => Box::new(DiffGuard),
+                    StageKind::Pager => Box::new(PagerStrip),
+                    StageKind::Spinners => Box::new(SpinnerDedup),
+                    StageKind::Status => Box::new(StatusLineStrip::new(options.status_line.clone())),
+                    StageKind::Glyphs => Box::new(GlyphStrip::new(options.glyphs.clone())),
+                    StageKind::Art => Box::new(ArtStrip),
+                    StageKind::Rules => Box::new(RuleFilter::new(options.rules.clone())),
+                    StageKind::Border => Box::new(BorderStrip),
+                    StageKind::Inline => Box::new(InlineScrub),
+                    StageKind::Markdown => Box::new(MarkdownGuard),
+                    StageKind::Wasm => Box::new(WasmFilter::new(&options.plugins)),
+                    StageKind::Exec => Box::new(ExecFilter::new(options.exec.clone())),
+                    StageKind::Invisible => Box::new(InvisibleStrip),
+                    StageKind::Logprefix => Box::new(LogPrefixStrip),
+                    StageKind::Repeats => Box::new(RepeatCollapse),
+                    StageKind::Nfc => Box::new(NfcNormalize),
+                    StageKind::Fold => Box::new(ConfusableFold),
+                    StageKind::Punctuation => Box::new(AsciiPunctuation),
+                    StageKind::Tabs => Box::new(TabExpand::new(options.indent.clone())),
+                    StageKind::Indent => Box::new(IndentNormalize::new(options.indent.clone())),
+                    StageKind::Trailing => Box::new(TrailingTrim),
+                    StageKind::Grid => Box::new(AsciiGrid),
+                    StageKind::Quotes => Box::new(QuoteStrip),
+                    StageKind::Coalesce => Box::new(Coalesce),
+                    StageKind::Substitute => Box::new(Substitute::new(options.substitutions.clone())),
+                }
+            })
+            .collect();
+        Self { stages }
+    }
+
+    /// Names of the configured stages, in order.
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// The configured stages, in order.
+    pub fn stages(&self) -> &[Box<dyn Stage>] {
+        &self.stages
+    }
+
+    /// Cleans the input text by removing TUI artifacts (borders, ANSI codes).
+    pub fn run(&self, input: &str) -> String {
+        let mut lines: Vec<Line> = input.lines().map(Line::new).collect();
+        for stage in &self.stages {
+            lines = stage.apply(lines);
+            tracing::trace!(stage = stage.name(), lines = lines.len(), "stage applied");
+        }
+        Self::finish(&lines)
+    }
+
+    /// Joins processed lines into the final text with a single allocation.
+    pub fn finish(lines: &[Line]) -> String {
+        let size = lines.iter().map(|line| line.text.len() + 1).sum();
+        let mut text = String::with_capacity(size);
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(&line.text);
+        }
+        // Final cleanup: remove any trailing whitespace the TUI might have added
+        text.truncate(text.trim_end().len());
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean_text(input: &str) -> String {
+        Pipeline::default().run(input)
+    }
+
+    #[test]
+    fn test_claude_code_titled_border() {
+        let input = "╭─── Claude Code v2.0.47 ──────────────────────────────────────────────────────────────────────────╮\n\
+                     │                             │ Recent activity                                                    │\n\
+                     │     Welcome back Ainesh!    │ No recent activity                                                 │\n\
+                     │                             │ ────────────────────────────────────────────────────────────────── │\n\
+                     │           ▐▛███▜▌           │ What's new                                                         │\n\
+                     ╰──────────────────────────────────────────────────────────────────────────────────────────────────╯";
+
+        // The expected output should have the top and bottom lines removed,
+        // and the side borders removed from the content lines.
+
+        let expected_contains = "Welcome back Ainesh!";
+        let cleaned = clean_text(input);
+
+        println!("Cleaned Output:\n{}", cleaned);
+
+        assert!(cleaned.contains(expected_contains), "Should contain content");
+        assert!(!cleaned.contains("Claude Code v2.0.47"), "Should remove titled top border");
+        assert!(!cleaned.contains("╰───"), "Should remove bottom border");
+        assert!(!cleaned.contains("│     Welcome"), "Should remove left border");
+    }
+
+    #[test]
+    fn test_ansi_stripping() {
+        let input = "\x1b[31mHello\x1b[0m World";
+        let cleaned = clean_text(input);
+        assert_eq!(cleaned, "Hello World");
+
+        let input_nested = "\x1b[1;31mBold Red\x1b[0m";
+        let cleaned = clean_text(input_nested);
+        assert_eq!(cleaned, "Bold Red");
+    }
+
+    #[test]
+    fn test_code_with_pipes() {
+        let input = "│ let x = a | b; │";
+        let cleaned = clean_text(input);
+        assert_eq!(cleaned, "let x = a | b;");
+    }
+
+    #[test]
+    fn test_stage_order_is_configurable() {
+        let input = "│ \x1b[31mred\x1b[0m │";
+
+        // Without the ANSI pass the escape codes survive border stripping
+        let pipeline = Pipeline::new(&[StageKind::Border], StageOptions::default());
+        assert_eq!(pipeline.stage_names(), vec!["border"]);
+        assert_eq!(pipeline.run(input), "\x1b[31mred\x1b[0m");
+
+        assert_eq!(Pipeline::default().run(input), "red");
+    }
+
+    #[test]
+    fn test_cjk_box_unwrapped() {
+        let input = "╭──────────────────╮\n│ 設定ファイル │ 値 │\n｜ 日本語のテキスト ｜\n╰──────────────────╯";
+        assert_eq!(clean_text(input), "設定ファイル    値\n日本語のテキスト");
+    }
+
+    #[test]
+    fn test_diff_survives_cleaning() {
+        let patch = "@@ -1,5 +1,5 @@\n-// │ old │\n+// │ new │\n\n\n\n \tindented  ";
+        let input = format!("\x1b[1mdiff --git a/x b/x\x1b[0m\n--- a/x\n+++ b/x\n{patch}");
+        assert_eq!(clean_text(&input), format!("diff --git a/x b/x\n--- a/x\n+++ b/x\n{}", patch.trim_end()));
+    }
+
+    #[test]
+    fn test_keep_rules_run_before_heuristics() {
+        let rules = crate::config::Config::parse("[[rules]]\nmatch = '^icons:'\naction = \"keep\"\n").unwrap().rules;
+        let pipeline = Pipeline::new(StageKind::DEFAULT_ORDER, StageOptions { rules, ..StageOptions::default() });
+        assert_eq!(pipeline.run("icons: \u{F015} home\n\u{F015} home"), "icons: \u{F015} home\nhome");
+    }
+
+    #[test]
+    fn test_enable_inserts_at_anchor() {
+        let mut order = vec![StageKind::Border, StageKind::Coalesce];
+        StageKind::enable(&mut order, StageKind::Logprefix);
+        StageKind::enable(&mut order, StageKind::Logprefix);
+        assert_eq!(order, vec![StageKind::Border, StageKind::Logprefix, StageKind::Coalesce]);
+
+        let mut order = StageKind::DEFAULT_ORDER.to_vec();
+        StageKind::enable(&mut order, StageKind::Emphasis);
+        let at = order.iter().position(|kind| *kind == StageKind::Emphasis).unwrap();
+        assert_eq!(order[at + 1], StageKind::Ansi);
+        StageKind::enable(&mut order, StageKind::Markdown);
+        let at = order.iter().position(|kind| *kind == StageKind::Markdown).unwrap();
+        assert_eq!(order[at - 1], StageKind::Inline);
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::error::{RepromptError, Result};
+
+/// How often a running child is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn pipe_error(name: &str) -> RepromptError {
+    RepromptError::Command(format!("Failed to open child {}", name))
+}
+
+/// Runs `command` with `input` on stdin, killing it if it outlives `timeout`.
+/// Stdin/stdout/stderr are serviced on helper threads so large payloads
+/// can't deadlock on full pipes.
+pub fn run_with_timeout(command: &mut Command, input: &[u8], timeout: Duration) -> Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| RepromptError::Io { context: format!("Failed to run {}", command.get_program().to_string_lossy()), error })?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| pipe_error("stdin"))?;
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || {
+        // A child that exits without reading stdin is not an error
+        let _ = stdin.write_all(&input);
+    });
+
+    let mut stdout = child.stdout.take().ok_or_else(|| pipe_error("stdout"))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| pipe_error("stderr"))?;
+    let out_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let err_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(RepromptError::Command(format!("Timed out after {}ms", timeout.as_millis())));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let _ = writer.join();
+    let stdout = out_reader.join().unwrap_or_default();
+    let stderr = err_reader.join().unwrap_or_default();
+    Ok(Output { status, stdout, stderr })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_timeout_pipes_and_kills() {
+        let output = run_with_timeout(&mut Command::new("cat"), b"hello", Duration::from_secs(5)).unwrap();
+        assert_eq!(output.stdout, b"hello");
+
+        let mut sleeper = Command::new("sleep");
+        sleeper.arg("5");
+        let started = Instant::now();
+        assert!(run_with_timeout(&mut sleeper, b"", Duration::from_millis(100)).is_err());
+        assert!(started.elapsed() < Duration::from_secs(2), "Child should be killed at the deadline");
+    }
+}
@@ -0,0 +1,709 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Pattern;
+use crate::detect::Fingerprint;
+use crate::error::{RepromptError, Result};
+use crate::paths;
+use crate::pipeline::StageKind;
+use crate::rules::Rule;
+use crate::stages::{ExecSpec, StatusLineSpec, Substitution, VerbatimRegion};
+
+/// Profiles compiled into the binary; user files with the same name override them.
+const BUILTIN: &[&str] = &[
+    include_str!("../profiles/aider.toml"),
+    include_str!("../profiles/claude-code.toml"),
+    include_str!("../profiles/cli-tables.toml"),
+    include_str!("../profiles/gemini-cli.toml"),
+    include_str!("../profiles/htop.toml"),
+    include_str!("../profiles/k9s.toml"),
+    include_str!("../profiles/lazygit.toml"),
+];
+
+lazy_static! {
+    // Parsed once per process; clones share the compiled regexes and signature sets
+    static ref BUILTIN_PROFILES: Vec<Profile> = BUILTIN
+        .iter()
+        .map(|text| Profile::parse(text).expect("built-in profile is valid"))
+        .collect();
+}
+
+/// Dotted numeric version (`1.2.0`), compared component-wise.
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    pub fn parse(text: &str) -> Result<Self> {
+        let parts = text
+            .trim()
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| RepromptError::InvalidSetting(format!("Invalid version '{}' (expected e.g. 1.2.0)", text)))?;
+        Ok(Self(parts))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Missing trailing components count as zero, so 1.2 == 1.2.0
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| {
+                let a = self.0.get(i).copied().unwrap_or(0);
+                let b = other.0.get(i).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Version::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A named, versioned set of tool-specific cleaning rules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub name: String,
+    pub version: Version,
+    #[serde(default)]
+    pub description: String,
+    /// Profile this one builds on (a built-in one when it's this profile's own
+    /// name): its rules, substitutions, verbatim regions, plugins and status
+    /// patterns come first, and whatever this profile leaves unset is inherited
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Stage order while this profile is active, unless `--stages` is given
+    #[serde(default)]
+    pub stages: Option<Vec<StageKind>>,
+    /// Shorthand for `[[rules]]` entries with `action = "drop"`
+    #[serde(default)]
+    drop: Vec<Pattern>,
+    /// Line rules applied while this profile is active
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// External filter for the `exec` stage
+    #[serde(default)]
+    pub exec: Option<ExecSpec>,
+    /// WASM cleaner modules for the `wasm` stage
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+    /// Regex rewrites applied after cleaning
+    #[serde(default)]
+    pub substitutions: Vec<Substitution>,
+    /// tmux/screen status-line removal for the `status` stage
+    #[serde(default)]
+    pub status_line: StatusLineSpec,
+    /// Marker-delimited regions the `verbatim` stage protects
+    #[serde(default)]
+    pub verbatim: Vec<VerbatimRegion>,
+    /// How to recognise this tool's output when no `--profile` is given
+    #[serde(default)]
+    pub detect: Fingerprint,
+    /// File the profile was loaded from (None for built-in profiles)
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "generic".to_string(),
+            version: Version(vec![0]),
+            description: "Built-in heuristics only".to_string(),
+            extends: None,
+            stages: None,
+            drop: Vec::new(),
+            rules: Vec::new(),
+            exec: None,
+            plugins: Vec::new(),
+            substitutions: Vec::new(),
+            status_line: StatusLineSpec::default(),
+            verbatim: Vec::new(),
+            detect: Fingerprint::default(),
+            source: None,
+        }
+    }
+}
+
+impl Profile {
+    /// Parses a profile from TOML text, validating its name and patterns.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut profile: Profile = toml::from_str(text)
+            .map_err(|error| RepromptError::InvalidConfig { context: "Invalid profile".to_string(), error })?;
+        let valid_name = !profile.name.is_empty()
+            && profile.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(RepromptError::InvalidSetting(format!(
+                "Invalid profile name '{}' (use letters, digits, '-' and '_')",
+                profile.name
+            )));
+        }
+        for rule in &profile.rules {
+            rule.check()?;
+        }
+        if let Some(exec) = &profile.exec {
+            exec.check()?;
+        }
+
+        // Expand the `drop` shorthand into ordinary rules, ahead of explicit ones
+        let drops = std::mem::take(&mut profile.drop).into_iter().map(Rule::drop);
+        profile.rules.splice(0..0, drops);
+
+        Ok(profile)
+    }
+
+    /// This profile on top of `parent`: lists are the parent's followed by
+    /// this one's, settings this one leaves unset are the parent's. Detection
+    /// is only inherited under the same name, so a variant of a profile isn't
+    /// picked in its place; use it with `--profile`.
+    fn inherit(mut self, parent: &Profile) -> Self {
+        fn prepend<T: Clone>(list: &mut Vec<T>, parent: &[T]) {
+            list.splice(0..0, parent.iter().cloned());
+        }
+        prepend(&mut self.rules, &parent.rules);
+        prepend(&mut self.plugins, &parent.plugins);
+        prepend(&mut self.substitutions, &parent.substitutions);
+        prepend(&mut self.verbatim, &parent.verbatim);
+        prepend(&mut self.status_line.patterns, &parent.status_line.patterns);
+        self.status_line.enabled &= parent.status_line.enabled;
+        self.exec = self.exec.or_else(|| parent.exec.clone());
+        self.stages = self.stages.or_else(|| parent.stages.clone());
+        if self.name == parent.name && self.detect.signatures.is_empty() && self.detect.borders.is_empty() {
+            self.detect = parent.detect.clone();
+        }
+        if self.description.is_empty() {
+            self.description = parent.description.clone();
+        }
+        self
+    }
+}
+
+/// Loads every `*.toml` profile in `dir`, keeping the highest version of each name.
+/// Broken files are reported and skipped so one bad download can't break cleaning.
+pub fn discover_in(dir: &Path) -> Vec<Profile> {
+    let mut found: BTreeMap<String, Profile> = BTreeMap::new();
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let profile = std::fs::read_to_string(&path)
+            .map_err(RepromptError::from)
+            .and_then(|text| Profile::parse(&text));
+        match profile {
+            Ok(mut profile) => {
+                profile.source = Some(path);
+                let newer = found
+                    .get(&profile.name)
+                    .is_none_or(|existing| profile.version > existing.version);
+                if newer {
+                    found.insert(profile.name.clone(), profile);
+                }
+            }
+            Err(e) => tracing::warn!("Skipping profile {}: {}", path.display(), e),
+        }
+    }
+
+    found.into_values().collect()
+}
+
+/// Resolves `name` in `found` and everything it `extends`, caching results in
+/// `resolved`. `chain` holds the profiles being resolved, to catch cycles.
+fn resolve(name: &str, found: &BTreeMap<String, Profile>, resolved: &mut BTreeMap<String, Profile>, chain: &mut Vec<String>) -> Option<Profile> {
+    if let Some(profile) = resolved.get(name) {
+        return Some(profile.clone());
+    }
+    let profile = found.get(name)?.clone();
+    let Some(parent_name) = profile.extends.clone() else {
+        resolved.insert(name.to_string(), profile.clone());
+        return Some(profile);
+    };
+
+    let parent = if parent_name == name {
+        builtin().into_iter().find(|parent| parent.name == name)
+    } else if parent_name == "generic" {
+        Some(Profile::default())
+    } else if chain.contains(&parent_name) {
+        tracing::warn!("Profile '{}' extends '{}', which extends it back; ignoring `extends`", name, parent_name);
+        return Some(profile);
+    } else {
+        chain.push(name.to_string());
+        let parent = resolve(&parent_name, found, resolved, chain);
+        chain.pop();
+        parent
+    };
+    let profile = match parent {
+        Some(parent) => profile.inherit(&parent),
+        None => {
+            tracing::warn!("Profile '{}' extends unknown profile '{}'; using it on its own", name, parent_name);
+            profile
+        }
+    };
+    resolved.insert(name.to_string(), profile.clone());
+    Some(profile)
+}
+
+/// The profiles shipped with reprompt.
+pub fn builtin() -> Vec<Profile> {
+    BUILTIN_PROFILES.clone()
+}
+
+/// Built-in profiles plus those in the user's `profiles.d` directory.
+pub fn discover() -> Vec<Profile> {
+    let mut found: BTreeMap<String, Profile> = builtin()
+        .into_iter()
+        .map(|profile| (profile.name.clone(), profile))
+        .collect();
+    if let Ok(dir) = paths::profiles_dir() {
+        for profile in discover_in(&dir) {
+            found.insert(profile.name.clone(), profile);
+        }
+    }
+    let mut resolved = BTreeMap::new();
+    for name in found.keys() {
+        resolve(name, &found, &mut resolved, &mut Vec::new());
+    }
+    resolved.into_values().collect()
+}
+
+/// Resolves a profile by name ("generic" is always available).
+pub fn find(name: &str) -> Result<Profile> {
+    if name == "generic" {
+        return Ok(Profile::default());
+    }
+    discover()
+        .into_iter()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| RepromptError::UnknownProfile(name.to_string()))
+}
+
+/// Downloads (or reads) a profile, verifies it, and installs it into `profiles.d`.
+/// Without `expected_sha256` it refuses unless `insecure` says to skip the check.
+pub fn install(source: &str, expected_sha256: Option<&str>, insecure: bool, force: bool) -> Result<PathBuf> {
+    let bytes = fetch(source)?;
+
+    let actual = sha256_hex(&bytes);
+    match expected_sha256 {
+        Some(expected) if !expected.trim().eq_ignore_ascii_case(&actual) => {
+            return Err(RepromptError::InvalidSetting(format!(
+                "Checksum mismatch: expected {}, got {}",
+                expected.trim(),
+                actual
+            )));
+        }
+        Some(_) => {}
+        None if insecure => tracing::warn!("Installing an unverified profile; its SHA-256 is {}", actual),
+        None => {
+            return Err(RepromptError::InvalidSetting(format!(
+                "No checksum given; check the file and pass --sha256 {}, or --insecure to skip the check",
+                actual
+            )));
+        }
+    }
+
+    let text = String::from_utf8(bytes)
+        .map_err(|e| RepromptError::InvalidProfile(format!("Profile is not valid UTF-8: {}", e)))?;
+    let profile = Profile::parse(&text)?;
+
+    install_in(&paths::profiles_dir()?, &profile, &text, force)
+}
+
+/// Writes `profile` (whose source is `text`) to `dir` as `<name>.toml`,
+/// replacing any installed version unless that one is newer and not `force`.
+fn install_in(dir: &Path, profile: &Profile, text: &str, force: bool) -> Result<PathBuf> {
+    let existing = discover_in(dir).into_iter().find(|p| p.name == profile.name);
+    if let Some(existing) = &existing {
+        if existing.version > profile.version && !force {
+            return Err(RepromptError::InvalidSetting(format!(
+                "Profile '{}' {} is already installed; refusing to downgrade to {} (use --force)",
+                profile.name, existing.version, profile.version
+            )));
+        }
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| RepromptError::io("Failed to create", dir, e))?;
+    let path = dir.join(format!("{}.toml", profile.name));
+    // Write beside it and rename over it, so a failed write leaves the installed version in place
+    let temp = dir.join(format!(".{}.toml.tmp", profile.name));
+    std::fs::write(&temp, text)
+        .and_then(|()| std::fs::rename(&temp, &path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            RepromptError::io("Failed to write", &path, e)
+        })?;
+
+    // Then remove an old file under another name, so two versions never coexist
+    if let Some(old) = existing.and_then(|existing| existing.source).filter(|old| *old != path) {
+        std::fs::remove_file(&old).map_err(|e| RepromptError::io("Failed to remove", &old, e))?;
+    }
+    Ok(path)
+}
+
+/// Fetches profile bytes from an http(s) URL (via curl) or a local path.
+fn fetch(source: &str) -> Result<Vec<u8>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let output = Command::new("curl")
+            .args(["-fsSL", "--max-time", "30", source])
+            .output()
+            .map_err(|e| RepromptError::Command(format!("Failed to run curl (is it installed?): {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(RepromptError::Command(format!("Download failed: {}", stderr.trim())));
+        }
+        Ok(output.stdout)
+    } else {
+        std::fs::read(source).map_err(|e| RepromptError::io("Failed to read", Path::new(source), e))
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::pipeline::{Pipeline, StageKind};
+    use crate::rules::Action;
+
+    #[test]
+    fn test_version_ordering() {
+        let v = |s| Version::parse(s).unwrap();
+        assert!(v("1.10.0") > v("1.9.3"));
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert!(Version::parse("1.x").is_err());
+    }
+
+    #[test]
+    fn test_discover_keeps_highest_version() {
+        let dir = std::env::temp_dir().join(format!("reprompt-profiles-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.toml"), "name = \"k9s\"\nversion = \"1.0.0\"\ndrop = ['^<\\w+>']\n").unwrap();
+        std::fs::write(dir.join("b.toml"), "name = \"k9s\"\nversion = \"1.2.0\"\n").unwrap();
+        std::fs::write(dir.join("broken.toml"), "name = \"bad\"\nversion = \"1\"\ndrop = ['(']\n").unwrap();
+
+        let profiles = discover_in(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(profiles.len(), 1, "Broken profile should be skipped");
+        assert_eq!(profiles[0].version.to_string(), "1.2.0");
+    }
+
+    #[test]
+    fn test_install_replaces_older_version() {
+        let dir = std::env::temp_dir().join(format!("reprompt-install-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("custom-name.toml"), "name = \"k9s\"\nversion = \"1.0.0\"\n").unwrap();
+
+        let newer = "name = \"k9s\"\nversion = \"1.1.0\"\n";
+        let path = install_in(&dir, &Profile::parse(newer).unwrap(), newer, false).unwrap();
+        let older = "name = \"k9s\"\nversion = \"0.9.0\"\n";
+        let refused = install_in(&dir, &Profile::parse(older).unwrap(), older, false);
+        let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        files.sort();
+        let installed = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, ["k9s.toml"], "The old file and the temp file should be gone");
+        assert_eq!(installed, newer);
+        assert!(refused.is_err(), "Downgrades need --force");
+    }
+
+    #[test]
+    fn test_install_requires_a_matching_checksum() {
+        let path = std::env::temp_dir().join(format!("reprompt-unpinned-{}.toml", std::process::id()));
+        let text = "name = \"k9s\"\nversion = \"1.0.0\"\n";
+        std::fs::write(&path, text).unwrap();
+        let source = path.to_string_lossy();
+        let unpinned = install(&source, None, false, false);
+        let mismatched = install(&source, Some("00"), true, false);
+        std::fs::remove_file(&path).unwrap();
+
+        let hash = sha256_hex(text.as_bytes());
+        assert!(unpinned.unwrap_err().to_string().contains(&hash), "The error should suggest the hash to pin");
+        assert!(mismatched.is_err(), "--insecure doesn't excuse a wrong --sha256");
+    }
+
+    #[test]
+    fn test_profile_drop_shorthand_becomes_rules() {
+        let profile = Profile::parse("name = \"t\"\nversion = \"1\"\ndrop = ['^<[\\w-]+>']\n").unwrap();
+        assert_eq!(profile.rules.len(), 1);
+        assert_eq!(profile.rules[0].action, Action::Drop);
+        assert!(profile.rules[0].pattern.0.is_match("<ctrl-d> Delete"));
+        assert!(Profile::parse("name = \"../evil\"\nversion = \"1\"\n").is_err());
+    }
+
+    #[test]
+    fn test_extends_appends_and_inherits() {
+        let parse = |text: &str| Profile::parse(&format!("version = \"1\"\n{}", text)).unwrap();
+        let mut found: BTreeMap<String, Profile> = builtin().into_iter().map(|p| (p.name.clone(), p)).collect();
+        for profile in [
+            parse("name = \"base\"\nstages = [\"ansi\"]\ndrop = ['^base']\n[exec]\ncommand = [\"cat\"]\n"),
+            parse("name = \"child\"\nextends = \"base\"\ndrop = ['^child']\n"),
+            parse("name = \"claude-code\"\nextends = \"claude-code\"\ndrop = ['^mine']\n"),
+            parse("name = \"loop\"\nextends = \"loop\"\n"),
+            parse("name = \"a\"\nextends = \"b\"\n"),
+            parse("name = \"b\"\nextends = \"a\"\n"),
+        ] {
+            found.insert(profile.name.clone(), profile);
+        }
+        let mut resolved = BTreeMap::new();
+        let get = |name: &str, resolved: &mut BTreeMap<String, Profile>| resolve(name, &found, resolved, &mut Vec::new()).unwrap();
+
+        let child = get("child", &mut resolved);
+        let patterns: Vec<&str> = child.rules.iter().map(|rule| rule.pattern.0.as_str()).collect();
+        assert_eq!(patterns, ["^base", "^child"]);
+        assert_eq!(child.stages, Some(vec![StageKind::Ansi]));
+        assert!(child.exec.is_some() && child.detect.signatures.is_empty());
+
+        let original = builtin().into_iter().find(|p| p.name == "claude-code").unwrap();
+        let mine = get("claude-code", &mut resolved);
+        assert_eq!(mine.rules.len(), original.rules.len() + 1);
+        assert_eq!(mine.detect.signatures.len(), original.detect.signatures.len());
+        assert!(get("loop", &mut resolved).rules.is_empty());
+        assert!(get("a", &mut resolved).rules.is_empty());
+    }
+
+    fn clean_with_builtin(name: &str, text: &str) -> String {
+        let profile = builtin().into_iter().find(|p| p.name == name).unwrap();
+        Pipeline::new(StageKind::DEFAULT_ORDER, Config::default().stage_options(&profile)).run(text)
+    }
+
+    #[test]
+    fn test_claude_code_profile() {
+        let transcript = "\
+╭───────────────────────────────────────────────────╮
+│ ✻ Welcome to Claude Code!                         │
+│                                                   │
+│   /help for help, /status for your current setup  │
+│                                                   │
+│   cwd: /home/me/project                           │
+╰───────────────────────────────────────────────────╯
+
+> fix the failing test
+
+⏺ Bash(cargo test)
+  ⎿  running 3 tests
+     test result: FAILED. 2 passed; 1 failed
+
+╭───────────────────────────────────────────────────╮
+│ Edit file                                         │
+│ Do you want to make this edit to main.rs?         │
+│ ❯ 1. Yes                                          │
+│   2. No, and tell Claude what to do differently   │
+╰───────────────────────────────────────────────────╯
+
+⏺ The test expected a trailing newline. Here is the fix:
+
+```rust
+fn main() {}
+```
+
+✻ Thinking… (3s · esc to interrupt)
+
+╭───────────────────────────────────────────────────╮
+│ >                                                 │
+╰───────────────────────────────────────────────────╯
+  ? for shortcuts                 Context left until auto-compact: 9%
+Total cost:            $0.12
+";
+
+        assert_eq!(
+            clean_with_builtin("claude-code", transcript),
+            "The test expected a trailing newline. Here is the fix:\n\n```rust\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn test_aider_profile_keeps_search_replace() {
+        let transcript = "\
+Aider v0.86.1
+Main model: gpt-4o with diff edit format
+Git repo: .git with 42 files
+────────────────────────────────────────────────
+> rename the helper
+
+Renaming it in one place:
+
+utils.py
+<<<<<<< SEARCH
+def helper():
+=======
+def render():
+>>>>>>> REPLACE
+
+Tokens: 2.3k sent, 120 received. Cost: $0.01 message, $0.02 session.
+Applied edit to utils.py
+Commit 1a2b3c4 refactor: rename helper
+";
+
+        assert_eq!(
+            clean_with_builtin("aider", transcript),
+            "Renaming it in one place:\n\nutils.py\n<<<<<<< SEARCH\ndef helper():\n=======\ndef render():\n>>>>>>> REPLACE"
+        );
+    }
+
+    #[test]
+    fn test_gemini_cli_profile() {
+        let transcript = "\
+ ███            █████████  ██████████ ██████   ██████
+░░░███         ███░░░░░███░░███░░░░░█░░██████ ██████
+
+Tips for getting started:
+1. Ask questions, edit files, or run commands.
+2. /help for more information.
+
+╭────────────────────────────────────────╮
+│  > why does the build fail?            │
+╰────────────────────────────────────────╯
+
+╭────────────────────────────────────────╮
+│ ✔  ReadFile Cargo.toml                 │
+╰────────────────────────────────────────╯
+✦ The `serde` feature is missing. Add:
+
+```toml
+serde = { version = \"1\", features = [\"derive\"] }
+```
+
+⠏ Thinking... (esc to cancel, 3s)
+~/project (main*)     no sandbox (see /docs)     gemini-2.5-pro (97% context left)
+";
+
+        assert_eq!(
+            clean_with_builtin("gemini-cli", transcript),
+            "The `serde` feature is missing. Add:\n\n```toml\nserde = { version = \"1\", features = [\"derive\"] }\n```"
+        );
+    }
+
+    #[test]
+    fn test_lazygit_profile_keeps_main_panel() {
+        let screen = "\
+┌─Status──────────────────────┐┌─Unstaged changes─────────────────────────────┐
+│✓ reprompt → main            ││diff --git a/src/main.rs b/src/main.rs        │
+└─────────────────────────────┘│@@ -1,3 +1,4 @@                               │
+┌─Files - Submodules──────────┐│ fn main() {                                  │
+│ M src/main.rs               ││+    let x = 1;                               │
+│?? notes.md                  ││ }                                            │
+└─────────────────────────────┘│                                              │
+┌─Local branches - Remotes────┐│                                              │
+│* main      fix parser       ││                                              │
+│  feature/x wip              ││                                              │
+└─────────────────────────────┘│                                              │
+┌─Commits - Reflog────────────┐│                                              │
+│a1b2c3d AS fix parser        ││                                              │
+│e4f5a6b AS add tests         ││                                              │
+└────────────────────1 of 2───┘└──────────────────────────────────────────────┘
+Commit: c | Stash: s | Reset: D | Keybindings: ? | Cancel: <esc>     Donate Ask Question 0.44.1
+";
+
+        assert_eq!(
+            clean_with_builtin("lazygit", screen),
+            "diff --git a/src/main.rs b/src/main.rs\n@@ -1,3 +1,4 @@\n fn main() {\n+    let x = 1;\n }"
+        );
+    }
+
+    #[test]
+    fn test_k9s_profile_keeps_table() {
+        let screen = "\
+ Context: kind-dev                                 <0> all       <a>      Attach     <l>       Logs            ____  __.________
+ Cluster: kind-dev                                 <1> default   <ctrl-d> Delete     <p>       Logs Previous  |    |/ _/   __   \\______
+ User:    kind-dev                                               <d>      Describe   <shift-f> Port-Forward   |      < \\____    /  ___/
+ K9s Rev: v0.32.5                                                <e>      Edit       <s>       Shell          |    |  \\   /    /\\___ \\
+ K8s Rev: v1.29.2                                                <?>      Help       <n>       Show Node      |____|__ \\ /____//____  >
+ CPU:     12%                                                    <k>      Kill       <f>       Show PortForward       \\/            \\/
+ MEM:     40%
+┌────────────────────────────── Pods(default)[3] ──────────────────────────────┐
+│ NAME↑                     READY   STATUS            RESTARTS   AGE           │
+│ api-7d9f8b6c4-x2x9z       1/1     Running           0          2d            │
+│ worker-6b7c8d9e0-fghij    0/1     CrashLoopBackOff  14         1h            │
+│                                                                              │
+└──────────────────────────────────────────────────────────────────────────────┘
+  <pod>
+";
+
+        assert_eq!(
+            clean_with_builtin("k9s", screen),
+            "Pods(default)[3]\n\
+             NAME↑                     READY   STATUS            RESTARTS   AGE\n\
+             api-7d9f8b6c4-x2x9z       1/1     Running           0          2d\n\
+             worker-6b7c8d9e0-fghij    0/1     CrashLoopBackOff  14         1h"
+        );
+    }
+
+    #[test]
+    fn test_htop_profile_drops_meters() {
+        let screen = "\
+    0[||||||||||                 25.3%]   Tasks: 123, 456 thr; 2 running
+    1[|||||                      12.1%]   Load average: 0.52 0.58 0.59
+  Swp[                         0K/2.00G]
+
+    PID USER      PRI  NI  VIRT   RES   SHR S CPU% MEM%   TIME+  Command
+   1234 ain        20   0 1024M  200M 50000 S 12.0  1.3  1:23.45 cargo build
+F1Help  F2Setup F3SearchF4FilterF5Tree  F6SortByF7Nice -F8Nice +F9Kill  F10Quit
+";
+
+        assert_eq!(
+            clean_with_builtin("htop", screen),
+            "Tasks: 123, 456 thr; 2 running\nLoad average: 0.52 0.58 0.59\n\n\
+             \x20   PID USER      PRI  NI  VIRT   RES   SHR S CPU% MEM%   TIME+  Command\n\
+             \x20  1234 ain        20   0 1024M  200M 50000 S 12.0  1.3  1:23.45 cargo build"
+        );
+    }
+
+    #[test]
+    fn test_cli_tables_profile() {
+        let session = "\
+mydb=# select id, name from users;
+ id | name
+----+------
+  1 | ann
+(1 row)
+
+Time: 0.412 ms
+";
+
+        assert_eq!(clean_with_builtin("cli-tables", session), " id | name\n----+------\n  1 | ann");
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::pipeline::{Line, Stage};
+
+/// Lines looked at past the last matched one when placing an output line
+const WINDOW: usize = 32;
+
+/// Where one output line came from, for the `--json` report's `line_map`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LineOrigin {
+    /// 1-based input lines it was made from; empty for added lines (templates, notes)
+    pub input: Vec<usize>,
+    /// Stages that changed it, in pipeline order
+    pub stages: Vec<&'static str>,
+}
+
+/// Words with a letter or digit, their punctuation trimmed.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+}
+
+/// Whether the letters and digits of `after` appear in order in `before` and
+/// make up at least half of them, as when escape codes were cut out of a word.
+fn kept_in_order(before: &str, after: &str) -> bool {
+    let total = before.chars().filter(|c| c.is_alphanumeric()).count();
+    let mut remaining = before.chars().filter(|c| c.is_alphanumeric());
+    let mut kept = 0;
+    for c in after.chars().filter(|c| c.is_alphanumeric()) {
+        if !remaining.any(|b| b == c) {
+            return false;
+        }
+        kept += 1;
+    }
+    kept > 0 && kept * 2 >= total
+}
+
+/// Whether `after` reads as a cleaned version of `before`: one holds the
+/// other, most of its words are `before`'s, or its letters are most of
+/// `before`'s in order. A blank line comes from a line with nothing but a
+/// divider or two left in it.
+fn similar(before: &str, after: &str) -> bool {
+    let (before, after) = (before.trim(), after.trim());
+    if after.is_empty() {
+        return !before.chars().any(char::is_alphanumeric) && before.chars().filter(|c| !c.is_whitespace()).count() <= 2;
+    }
+    if before.is_empty() {
+        return false;
+    }
+    if before.contains(after) || after.contains(before) {
+        return true;
+    }
+    let known: HashSet<&str> = words(before).collect();
+    let (total, shared) = words(after).fold((0, 0), |(total, shared), word| (total + 1, shared + usize::from(known.contains(word))));
+    (shared > 0 && shared * 2 >= total) || kept_in_order(before, after)
+}
+
+/// Whether every word of `line` is in `merged`, as when a stage joined it
+/// onto the line before.
+fn absorbed(line: &str, merged: &str) -> bool {
+    let known: HashSet<&str> = words(merged).collect();
+    let mut words = words(line).peekable();
+    words.peek().is_some() && words.all(|word| known.contains(word))
+}
+
+/// Origins of `after`, the lines a stage (or the final formatting, for `None`)
+/// made from `before`. Output lines are matched in order: an identical line
+/// nearby, then a similar one, then an identical one anywhere further on.
+/// A changed line that grew also takes in the following lines it absorbed.
+fn align(before: &[String], origins: &[LineOrigin], after: &[&str], stage: Option<&'static str>) -> Vec<LineOrigin> {
+    let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (k, text) in before.iter().enumerate() {
+        positions.entry(text.as_str()).or_default().push(k);
+    }
+
+    let mut next = 0;
+    let mut mapped = Vec::with_capacity(after.len());
+    for &text in after {
+        let window = next..(next + WINDOW).min(before.len());
+        let found = window
+            .clone()
+            .find(|&k| before[k] == text)
+            .map(|k| (k, false))
+            .or_else(|| {
+                // A line split in two maps both halves to the line just used
+                window.chain(next.checked_sub(1)).find(|&k| similar(&before[k], text)).map(|k| (k, true))
+            })
+            .or_else(|| {
+                let list = positions.get(text)?;
+                list.get(list.partition_point(|&k| k < next)).map(|&k| (k, false))
+            });
+        let Some((k, changed)) = found else {
+            mapped.push(LineOrigin { input: Vec::new(), stages: stage.into_iter().collect() });
+            continue;
+        };
+
+        let mut origin = origins[k].clone();
+        next = next.max(k + 1);
+        if changed {
+            if let Some(stage) = stage.filter(|stage| origin.stages.last() != Some(stage)) {
+                origin.stages.push(stage);
+            }
+            if text.trim().len() > before[k].trim().len() {
+                while before.get(next).is_some_and(|line| absorbed(line, text)) {
+                    origin.input.extend(&origins[next].input);
+                    next += 1;
+                }
+            }
+        }
+        mapped.push(origin);
+    }
+    mapped
+}
+
+fn texts(lines: &[Line]) -> Vec<String> {
+    lines.iter().map(|line| line.text.clone()).collect()
+}
+
+/// Maps each line of `output` back to the `input` lines it came from and the
+/// stages that changed it, by running `stages` again one at a time and
+/// matching each stage's output to its input. The match is a heuristic: lines
+/// are followed by content, so a line no stage kept anything of has no origin.
+pub fn trace(stages: &[&dyn Stage], input: &str, output: &str) -> Vec<LineOrigin> {
+    let mut lines: Vec<Line> = input.lines().map(Line::new).collect();
+    let mut origins: Vec<LineOrigin> =
+        (1..=lines.len()).map(|n| LineOrigin { input: vec![n], stages: Vec::new() }).collect();
+    for stage in stages {
+        let after = stage.apply(lines.clone());
+        if after != lines {
+            let texts_after = texts(&after);
+            let texts_after: Vec<&str> = texts_after.iter().map(String::as_str).collect();
+            origins = align(&texts(&lines), &origins, &texts_after, Some(stage.name()));
+        }
+        lines = after;
+    }
+    let output: Vec<&str> = output.lines().collect();
+    align(&texts(&lines), &origins, &output, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Pipeline;
+    use crate::stages::QuoteStrip;
+
+    fn map(stages: &[&dyn Stage], input: &str, output: &str) -> Vec<(Vec<usize>, Vec<&'static str>)> {
+        trace(stages, input, output).into_iter().map(|origin| (origin.input, origin.stages)).collect()
+    }
+
+    #[test]
+    fn test_traces_border_and_ansi() {
+        let pipeline = Pipeline::default();
+        let stages: Vec<&dyn Stage> = pipeline.stages().iter().map(|stage| stage.as_ref()).collect();
+        let input = "╭────────╮\n│ \x1b[1mhello\x1b[0m │\n│        │\n│ world  │\n╰────────╯\nplain";
+        let output = pipeline.run(input);
+        assert_eq!(output, "hello\n\nworld\nplain");
+        assert_eq!(
+            map(&stages, input, &format!("```\n{output}\n```")),
+            [
+                (vec![], vec![]),
+                (vec![2], vec!["ansi", "border"]),
+                (vec![3], vec!["border"]),
+                (vec![4], vec!["border"]),
+                (vec![6], vec![]),
+                (vec![], vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_traces_merged_lines() {
+        let input = "> Can we ship\n> on Friday?\n>\n> Sure.";
+        assert_eq!(
+            map(&[&QuoteStrip], input, "Can we ship on Friday?\n\nSure."),
+            [(vec![1, 2], vec!["quotes"]), (vec![3], vec!["quotes"]), (vec![4], vec!["quotes"])]
+        );
+    }
+}
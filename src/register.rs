@@ -0,0 +1,106 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::config::LineEndings;
+use reprompt::report::Report;
+
+/// What an editor needs to know about a cleaned register.
+fn metadata(report: &Report) -> Value {
+    json!({
+        "profile": report.profile,
+        "kind": report.kind,
+        "changed": report.changed,
+        "lines_in": report.lines_in,
+        "lines_out": report.lines_out,
+        "removed_lines": report.lines_in.saturating_sub(report.lines_out),
+    })
+}
+
+/// Cleans `input` for an editor register. Never fails on the text itself: input
+/// that can't be cleaned comes back unchanged, with the reason as `error` in the
+/// metadata, so a paste never loses what was yanked. A trailing newline (a
+/// linewise register) is kept. Lines end in LF whatever `line_endings` says,
+/// since that's what editor buffers hold.
+pub fn clean(cleaner: &Cleaner, input: &str) -> (String, Value) {
+    let report = match cleaner.clean_checked(input, None) {
+        Ok(report) => report,
+        Err(e) => return (input.to_string(), json!({ "changed": false, "error": format!("{:#}", e) })),
+    };
+    let mut output = LineEndings::Lf.apply(report.html.clone().unwrap_or_else(|| report.output.clone()));
+    if input.ends_with('\n') && !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    (output, metadata(&report))
+}
+
+/// Reads a register from `input` and writes it cleaned to `output`. Metadata goes
+/// to `meta` as one JSON line when given, otherwise as a JSON header line on `output`.
+pub fn run(cleaner: &Cleaner, mut input: impl Read, mut output: impl Write, meta: Option<impl Write>) -> Result<()> {
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    let (cleaned, metadata) = clean(cleaner, &text);
+    match meta {
+        Some(mut meta) => {
+            writeln!(meta, "{}", metadata)?;
+            meta.flush()?;
+        }
+        None => writeln!(output, "{}", metadata)?,
+    }
+    output.write_all(cleaned.as_bytes())?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Opens an inherited file descriptor (e.g. fd 3 set up by the editor) for the metadata.
+#[cfg(unix)]
+pub fn open_fd(fd: i32) -> Result<std::fs::File> {
+    use std::os::fd::FromRawFd;
+
+    anyhow::ensure!(fd > 2, "--meta-fd must not be stdin, stdout or stderr");
+    anyhow::ensure!(
+        std::path::Path::new(&format!("/dev/fd/{}", fd)).exists(),
+        "File descriptor {} is not open",
+        fd
+    );
+    // SAFETY: the descriptor was checked to be open, and this process owns it for its lifetime
+    Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+pub fn open_fd(_fd: i32) -> Result<std::fs::File> {
+    anyhow::bail!("--meta-fd is only supported on Unix; omit it to get a JSON header line")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::config::Config;
+
+    #[test]
+    fn test_header_and_linewise_newline() {
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let mut output = Vec::new();
+        run(&cleaner, "╭───────╮\n│ hello │\n╰───────╯\n".as_bytes(), &mut output, None::<Vec<u8>>).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let (header, text) = output.split_once('\n').unwrap();
+        assert_eq!(text, "hello\n");
+        let header: Value = serde_json::from_str(header).unwrap();
+        assert_eq!(header["removed_lines"], 2);
+        assert_eq!(header["changed"], true);
+
+        let mut output = Vec::new();
+        let mut meta = Vec::new();
+        run(&cleaner, "plain".as_bytes(), &mut output, Some(&mut meta)).unwrap();
+        assert_eq!(output, b"plain");
+        assert_eq!(serde_json::from_slice::<Value>(&meta).unwrap()["changed"], false);
+    }
+
+    #[test]
+    fn test_register_ignores_line_endings() {
+        let mut cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        cleaner.set_line_endings(LineEndings::Crlf);
+        assert_eq!(clean(&cleaner, "│ x │\n│ y │\n").0, "x\ny\n");
+    }
+}
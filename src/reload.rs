@@ -0,0 +1,135 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::paths;
+
+/// Contents of the config file and each profile file, by path.
+type Files = BTreeMap<PathBuf, String>;
+
+/// Reads `config` and every `*.toml` in `profiles`; missing ones are left out.
+fn read_files(config: &Path, profiles: &Path) -> Files {
+    let mut files = Files::new();
+    let mut paths = vec![config.to_path_buf()];
+    if let Ok(entries) = std::fs::read_dir(profiles) {
+        paths.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.extension().is_some_and(|ext| ext == "toml")));
+    }
+    for path in paths {
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            files.insert(path, text);
+        }
+    }
+    files
+}
+
+/// Top-level config keys whose values differ between two versions of the file.
+fn changed_keys(old: &str, new: &str) -> Vec<String> {
+    let parse = |text: &str| text.parse::<toml::Table>().unwrap_or_default();
+    let (old, new) = (parse(old), parse(new));
+    let mut keys: Vec<String> = old.keys().chain(new.keys()).filter(|key| old.get(*key) != new.get(*key)).cloned().collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// One line per file that was added, removed or edited, naming the config
+/// keys that changed when it's the config file.
+fn describe(config: &Path, old: &Files, new: &Files) -> Vec<String> {
+    let mut changes = Vec::new();
+    let paths: std::collections::BTreeSet<&PathBuf> = old.keys().chain(new.keys()).collect();
+    for path in paths {
+        let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        match (old.get(path), new.get(path)) {
+            (None, Some(_)) => changes.push(format!("{} added", name)),
+            (Some(_), None) => changes.push(format!("{} removed", name)),
+            (Some(before), Some(after)) if before != after => {
+                let keys = if path == config { changed_keys(before, after) } else { Vec::new() };
+                if keys.is_empty() {
+                    changes.push(format!("{} changed", name));
+                } else {
+                    changes.push(format!("{} changed ({})", name, keys.join(", ")));
+                }
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// Keeps a long-running command's cleaner in step with `config.toml` and
+/// `profiles.d/`: before each use the files are re-read and, when they
+/// differ from last time, the cleaner is rebuilt. A config that fails to load
+/// is reported and the previous cleaner kept. This polls rather than asking
+/// the OS for change events (inotify, FSEvents): a reload can only matter
+/// when the cleaner is about to be used, the files are small, and reading
+/// them works the same on every platform and file system.
+pub struct Reloader<'a> {
+    build: Box<dyn Fn() -> Result<Cleaner> + 'a>,
+    cleaner: Cleaner,
+    config: PathBuf,
+    profiles: PathBuf,
+    files: Files,
+}
+
+impl<'a> Reloader<'a> {
+    /// Builds the first cleaner with `build`, watching the user's config.
+    pub fn new(build: impl Fn() -> Result<Cleaner> + 'a) -> Result<Self> {
+        // Without a home directory there are no files to watch, only defaults
+        Self::watching(paths::config_file().unwrap_or_default(), paths::profiles_dir().unwrap_or_default(), build)
+    }
+
+    fn watching(config: PathBuf, profiles: PathBuf, build: impl Fn() -> Result<Cleaner> + 'a) -> Result<Self> {
+        // Read before building, so an edit in between is picked up next time
+        let files = read_files(&config, &profiles);
+        let cleaner = build()?;
+        Ok(Self { build: Box::new(build), cleaner, config, profiles, files })
+    }
+
+    /// The cleaner for the config on disk now, rebuilding it first if the
+    /// config or a profile changed since the last call.
+    pub fn current(&mut self) -> &Cleaner {
+        let files = read_files(&self.config, &self.profiles);
+        if files != self.files {
+            let changes = describe(&self.config, &self.files, &files).join("; ");
+            self.files = files;
+            match (self.build)() {
+                Ok(cleaner) => {
+                    self.cleaner = cleaner;
+                    tracing::info!("Reloaded config: {}", changes);
+                }
+                Err(e) => tracing::error!("Config changed ({}) but failed to load, keeping the previous one: {:#}", changes, e),
+            }
+        }
+        &self.cleaner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::config::Config;
+
+    #[test]
+    fn test_reloads_on_change_and_keeps_last_good() {
+        let dir = std::env::temp_dir().join(format!("reprompt-reload-{}", std::process::id()));
+        let (config, profiles) = (dir.join("config.toml"), dir.join("profiles.d"));
+        std::fs::create_dir_all(&profiles).unwrap();
+        let build = || Ok(Cleaner::new(Config::load_from(&dir.join("config.toml"))?, Some("generic"), None, false)?);
+        let mut reloader = Reloader::watching(config.clone(), profiles.clone(), build).unwrap();
+        let before = reloader.current().clean("colour").output;
+
+        let old = read_files(&config, &profiles);
+        std::fs::write(&config, "[[substitutions]]\npattern = \"colour\"\nreplacement = \"color\"\n").unwrap();
+        std::fs::write(profiles.join("tool.toml"), "name = \"tool\"\n").unwrap();
+        let changes = describe(&config, &old, &read_files(&config, &profiles));
+        let after = reloader.current().clean("colour").output;
+        std::fs::write(&config, "substitutions = 3\n").unwrap();
+        let broken = reloader.current().clean("colour").output;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(changes, ["config.toml added", "tool.toml added"]);
+        assert_eq!((before.as_str(), after.as_str(), broken.as_str()), ("colour", "color", "color"));
+        assert_eq!(changed_keys("a = 1\nb = 2", "a = 1\nb = 3\nc = 4"), ["b", "c"]);
+    }
+}
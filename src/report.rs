@@ -0,0 +1,192 @@
+use serde::Serialize;
+
+use crate::classify::ContentKind;
+use crate::detect::Detection;
+use crate::invariants::Violation;
+use crate::provenance::LineOrigin;
+use crate::score::Score;
+use crate::stages::InvisibleCounts;
+use crate::timing::Timing;
+
+/// How the active profile was chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Selection {
+    /// Named with `--profile`
+    Flag,
+    /// Picked by fingerprinting the text
+    Detected,
+    /// Nothing matched; the generic profile was used
+    Default,
+}
+
+/// How much of the pipeline the output went through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Every stage, as chosen by the search
+    #[default]
+    Full,
+    /// Only ANSI escapes stripped, after the full result failed validation
+    Ansi,
+    /// Only mojibake repaired, after the stronger levels failed validation
+    Mojibake,
+}
+
+impl Level {
+    /// Weaker levels to try, strongest first, when validation rejects a result
+    pub const FALLBACKS: [Level; 2] = [Level::Ansi, Level::Mojibake];
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Level::Full => "full",
+            Level::Ansi => "ANSI strip only",
+            Level::Mojibake => "mojibake repair only",
+        })
+    }
+}
+
+/// A cleaning variant that was scored, e.g. with and without mojibake repair.
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub name: String,
+    pub score: f64,
+}
+
+/// What one cleaning run did, for `--explain` and `--json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub profile: String,
+    pub selection: Selection,
+    pub detection: Option<Detection>,
+    pub stages: Vec<&'static str>,
+    /// What the cleaned text looks like (code, prose, table, log)
+    pub kind: ContentKind,
+    /// Invisible characters removed by the `invisible` stage
+    pub invisible: InvisibleCounts,
+    /// Bytes each stage removed, in pipeline order
+    pub removed: Vec<(&'static str, usize)>,
+    /// Score of the chosen variant, with its breakdown
+    pub score: Score,
+    /// Every variant tried, best first
+    pub candidates: Vec<Candidate>,
+    /// How much the chosen variant outscores the original (ANSI-stripped with `fallback = "ansi"`)
+    pub margin: f64,
+    /// Whether `--conservative` kept the original (or only stripped ANSI) because the margin was too small
+    pub held_back: bool,
+    /// Weaker than `full` when full cleaning failed validation
+    pub level: Level,
+    /// What `--select` or `--head`/`--tail`/`--lines` kept of the cleaned text, if they narrowed it
+    pub cropped: Option<String>,
+    pub lines_in: usize,
+    pub lines_out: usize,
+    pub changed: bool,
+    /// Whether the cleaned text was written back to the clipboard
+    pub committed: bool,
+    pub output: String,
+    /// Rich-text flavor committed next to `output` with `--format html`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// For each output line, the input lines it came from and the stages that changed it (`--json`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_map: Option<Vec<LineOrigin>>,
+    /// Guarantees this clean broke, when invariant checks are on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+    /// Time spent in each stage, scoring and clipboard access (`--profile-stages`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+    /// Clipboard flavor the input was rebuilt from because its plain text was damaged (`rtf`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovered: Option<&'static str>,
+}
+
+impl Report {
+    /// Report for input that looks like binary data and was passed through as is.
+    pub fn binary(profile: &str, input: &str) -> Self {
+        Self {
+            profile: profile.to_string(),
+            selection: Selection::Default,
+            detection: None,
+            stages: Vec::new(),
+            kind: ContentKind::Binary,
+            invisible: InvisibleCounts::default(),
+            removed: Vec::new(),
+            score: Score::default(),
+            candidates: Vec::new(),
+            margin: 0.0,
+            held_back: false,
+            level: Level::Full,
+            cropped: None,
+            lines_in: input.lines().count(),
+            lines_out: input.lines().count(),
+            changed: false,
+            committed: false,
+            output: input.to_string(),
+            html: None,
+            line_map: None,
+            violations: Vec::new(),
+            timings: Vec::new(),
+            recovered: None,
+        }
+    }
+
+    /// Human-readable summary for `--explain`.
+    pub fn explain(&self) -> String {
+        let why = match (&self.selection, &self.detection) {
+            (Selection::Flag, _) => "from --profile".to_string(),
+            (Selection::Detected, Some(detection)) => format!(
+                "detected, score {}: {}",
+                detection.score,
+                detection.signals.join(", ")
+            ),
+            _ => "no tool detected".to_string(),
+        };
+
+        let mut lines = vec![
+            format!("profile: {} ({})", self.profile, why),
+            format!("stages: {}", self.stages.join(" → ")),
+            format!("content: {}", self.kind),
+            format!("score: {:.2} ({})", self.score.total, self.score.breakdown()),
+            format!("margin: {:+.2} over the original", self.margin),
+            format!("lines: {} → {}", self.lines_in, self.lines_out),
+        ];
+        if self.candidates.len() > 1 {
+            let candidates: Vec<String> =
+                self.candidates.iter().map(|c| format!("{} {:.2}", c.name, c.score)).collect();
+            lines.push(format!("candidates: {}", candidates.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            let removed: Vec<String> =
+                self.removed.iter().map(|(stage, bytes)| format!("{} {} B", stage, bytes)).collect();
+            lines.push(format!("removed: {}", removed.join(", ")));
+        }
+        if self.invisible.total() > 0 {
+            lines.push(format!(
+                "invisible: removed {} zero-width, {} soft hyphen, {} bidi control",
+                self.invisible.zero_width, self.invisible.soft_hyphens, self.invisible.bidi
+            ));
+        }
+        if self.level != Level::Full {
+            lines.push(format!("level: {} (full cleaning failed validation)", self.level));
+        }
+        if let Some(kept) = &self.cropped {
+            lines.push(format!("cropped: kept {}", kept));
+        }
+        if let Some(flavor) = self.recovered {
+            lines.push(format!("input: rebuilt from the {} flavor (the plain text was damaged)", flavor));
+        }
+        if self.kind == ContentKind::Binary {
+            lines.push("result: skipped, looks like binary data".to_string());
+        } else if self.held_back {
+            lines.push("result: held back by --conservative".to_string());
+        } else if !self.changed {
+            lines.push("result: unchanged".to_string());
+        } else if !self.committed {
+            lines.push("result: not written to the clipboard".to_string());
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,228 @@
+use crate::stages::{from_cp1252_byte, repair_line};
+
+/// Groups whose content is formatting or metadata, never document text
+const DESTINATIONS: &[&str] = &[
+    "colorschememapping",
+    "colortbl",
+    "datastore",
+    "filetbl",
+    "fldinst",
+    "fonttbl",
+    "footer",
+    "footerf",
+    "footerl",
+    "footerr",
+    "generator",
+    "header",
+    "headerf",
+    "headerl",
+    "headerr",
+    "info",
+    "latentstyles",
+    "listoverridetable",
+    "listtable",
+    "object",
+    "pict",
+    "pn",
+    "revtbl",
+    "rsidtbl",
+    "stylesheet",
+    "themedata",
+    "xmlnstbl",
+];
+
+/// What a `{` saves and the matching `}` restores.
+#[derive(Clone, Copy)]
+struct Group {
+    /// Inside a destination that holds no text
+    skip: bool,
+    /// Fallback characters that follow each `\uN` (`\ucN`)
+    uc: usize,
+}
+
+/// Builds the text while a group or a `\uN` fallback may be swallowing it.
+struct Text {
+    text: String,
+    /// Fallback characters still to drop after a `\uN`
+    fallback: usize,
+    /// High half of a UTF-16 surrogate pair sent as two `\uN`
+    high: Option<u32>,
+}
+
+impl Text {
+    fn push(&mut self, group: &Group, c: char) {
+        if group.skip {
+            return;
+        }
+        if self.fallback > 0 {
+            self.fallback -= 1;
+            return;
+        }
+        self.text.push(c);
+    }
+
+    fn unicode(&mut self, group: &Group, code: i32) {
+        // Code points over 32767 are written as negative 16-bit numbers
+        let unit = code.rem_euclid(65536) as u32;
+        let c = match (self.high.take(), unit) {
+            (None, 0xD800..=0xDBFF) => {
+                self.high = Some(unit);
+                None
+            }
+            (Some(high), 0xDC00..=0xDFFF) => char::from_u32(0x10000 + ((high - 0xD800) << 10) + (unit - 0xDC00)),
+            (_, unit) => Some(char::from_u32(unit).unwrap_or('\u{FFFD}')),
+        };
+        if let Some(c) = c {
+            self.fallback = 0;
+            self.push(group, c);
+        }
+        self.fallback = group.uc;
+    }
+}
+
+/// The text of an RTF document, as a reader would see it: paragraphs and
+/// line breaks become newlines, cells tabs, and font tables, pictures and
+/// field instructions are dropped. `\'hh` escapes are read as Windows-1252.
+/// `None` if `rtf` isn't RTF.
+pub fn to_text(rtf: &[u8]) -> Option<String> {
+    let rtf = String::from_utf8_lossy(rtf);
+    let rtf = rtf.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{FEFF}');
+    if !rtf.starts_with("{\\rtf") {
+        return None;
+    }
+    let mut out = Text { text: String::new(), fallback: 0, high: None };
+    let mut group = Group { skip: false, uc: 1 };
+    let mut saved = Vec::new();
+    let mut group_start = false;
+    let mut chars = rtf.chars().peekable();
+    while let Some(c) = chars.next() {
+        let starts = std::mem::take(&mut group_start);
+        match c {
+            '{' => {
+                saved.push(group);
+                group_start = true;
+            }
+            '}' => {
+                group = saved.pop().unwrap_or(group);
+                out.fallback = 0;
+            }
+            // Raw line breaks only wrap the source
+            '\r' | '\n' => {}
+            '\\' => match chars.next() {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    let mut word = String::from(letter);
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                        word.push(c);
+                        chars.next();
+                    }
+                    let mut digits = String::new();
+                    if chars.peek() == Some(&'-') {
+                        digits.push('-');
+                        chars.next();
+                    }
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                        chars.next();
+                    }
+                    // One space ends a control word and is part of it
+                    if chars.peek() == Some(&' ') {
+                        chars.next();
+                    }
+                    let param = digits.parse::<i32>().ok();
+                    match word.as_str() {
+                        "par" | "line" | "row" | "sect" | "page" => out.push(&group, '\n'),
+                        "tab" | "cell" => out.push(&group, '\t'),
+                        "emdash" => out.push(&group, '—'),
+                        "endash" => out.push(&group, '–'),
+                        "bullet" => out.push(&group, '•'),
+                        "lquote" => out.push(&group, '‘'),
+                        "rquote" => out.push(&group, '’'),
+                        "ldblquote" => out.push(&group, '“'),
+                        "rdblquote" => out.push(&group, '”'),
+                        "emspace" | "enspace" | "qmspace" => out.push(&group, ' '),
+                        "u" => {
+                            if let Some(code) = param {
+                                out.unicode(&group, code);
+                            }
+                        }
+                        "uc" => group.uc = param.map_or(1, |uc| uc.max(0) as usize),
+                        word if starts && DESTINATIONS.contains(&word) => group.skip = true,
+                        _ => {}
+                    }
+                }
+                Some('\'') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                        out.push(&group, from_cp1252_byte(byte));
+                    }
+                }
+                Some(c @ ('\\' | '{' | '}')) => out.push(&group, c),
+                Some('~') => out.push(&group, '\u{A0}'),
+                Some('_') => out.push(&group, '-'),
+                Some('\r' | '\n') => out.push(&group, '\n'),
+                // `{\*\dest …}`: a destination this reader may not know, so no text
+                Some('*') if starts => group.skip = true,
+                _ => {}
+            },
+            c => out.push(&group, c),
+        }
+    }
+    Some(out.text)
+}
+
+/// Whether plain text may have lost characters that an RTF flavor could
+/// have kept: replacement characters, `?` standing in for letters, or
+/// Windows-1252 mojibake. Cheap, so fetching the RTF only happens for these.
+pub fn suspect(plain: &str) -> bool {
+    plain.contains('\u{FFFD}')
+        || plain.as_bytes().windows(2).any(|pair| pair[0] == b'?' && (pair[1] == b'?' || pair[1].is_ascii_alphanumeric()))
+        || plain.lines().any(|line| repair_line(line).is_some())
+}
+
+/// Whether `recovered` is `plain` with its lost characters back: the same
+/// text except where `plain` has `?` or U+FFFD for a non-ASCII character,
+/// or once its mojibake is undone. Anything else means the flavors disagree
+/// and the plain text wins.
+pub fn repairs(plain: &str, recovered: &str) -> bool {
+    let plain = plain.replace("\r\n", "\n");
+    let recovered = recovered.replace("\r\n", "\n");
+    let (plain, recovered) = (plain.trim_end(), recovered.trim_end());
+    if plain == recovered {
+        return false;
+    }
+    let substituted = plain.chars().count() == recovered.chars().count()
+        && plain.chars().zip(recovered.chars()).all(|(lost, kept)| lost == kept || (matches!(lost, '?' | '\u{FFFD}') && !kept.is_ascii()));
+    let unmangled = plain.lines().map(|line| {
+        let mut line = line.to_string();
+        while let Some(repaired) = repair_line(&line) {
+            line = repaired;
+        }
+        line
+    });
+    substituted || unmangled.eq(recovered.lines())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtf_to_text() {
+        let rtf = br"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fmodern Cascadia Mono;}}{\colortbl ;\red255\green255\blue255;}
+{\*\generator Riched20;}\f0\fs20 \u9484?\u9472?\u9472?\u9488?\par
+\u9474? caf\'e9 \{x\} \u9474?\par
+\ldblquote done\rdblquote\tab \u-10179?\u-8704?}";
+        assert_eq!(to_text(rtf).as_deref(), Some("┌──┐\n│ café {x} │\n“done”\t😀"));
+        assert_eq!(to_text(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_repairs_only_lost_characters() {
+        assert!(suspect("?????\n? caf? ?"));
+        assert!(!suspect("Why? Because."));
+        assert!(repairs("??\r\n? caf? ?\r\n", "──\n│ café │"));
+        assert!(repairs("cafÃ©", "café"));
+        assert!(!repairs("caf? ?", "caf? ?"));
+        assert!(!repairs("caf? ok", "another text"));
+    }
+}
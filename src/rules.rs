@@ -0,0 +1,188 @@
+use regex::RegexSet;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::config::Pattern;
+use crate::error::{RepromptError, Result};
+
+/// What a matching rule does to a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Remove the line entirely
+    Drop,
+    /// Keep the line verbatim, bypassing the built-in heuristics
+    Keep,
+    /// Rewrite the match with `replacement`, then keep evaluating
+    Replace,
+    /// Keep only the `content` (or first) capture group, verbatim
+    Unwrap,
+}
+
+/// A user-defined line filter from `[[rules]]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(rename = "match")]
+    pub pattern: Pattern,
+    pub action: Action,
+    /// Replacement text for `replace` (supports `$1` / `${name}`)
+    #[serde(default)]
+    pub replacement: Option<String>,
+    /// Only apply while this profile is active
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// For `drop`/`keep`: extend the action to following lines, through the
+    /// first line matching this pattern (e.g. a whole tool-output block)
+    #[serde(default)]
+    pub until: Option<Pattern>,
+}
+
+impl Rule {
+    /// Builds an unscoped drop rule (used for the profile `drop` shorthand).
+    pub fn drop(pattern: Pattern) -> Self {
+        Self { pattern, action: Action::Drop, replacement: None, profile: None, until: None }
+    }
+
+    /// Rejects rules whose fields don't fit their action.
+    pub fn check(&self) -> Result<()> {
+        if self.action == Action::Replace && self.replacement.is_none() {
+            return Err(RepromptError::InvalidSetting(format!("Rule '{}' uses action \"replace\" but has no replacement", self.pattern.0)));
+        }
+        if self.until.is_some() && !matches!(self.action, Action::Drop | Action::Keep) {
+            return Err(RepromptError::InvalidSetting(format!("Rule '{}' uses `until`, which only works with drop or keep", self.pattern.0)));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of running the rules over one line.
+#[derive(Debug, PartialEq)]
+pub enum Verdict<'a> {
+    /// Remove the line
+    Drop,
+    /// Emit this text as-is, skipping the heuristics
+    Keep(Cow<'a, str>),
+    /// No terminal rule matched; continue with (possibly rewritten) text
+    Continue(Cow<'a, str>),
+}
+
+/// Rules with their patterns compiled into one `RegexSet`, so each line is
+/// matched against all of them in a single pass and only the rules that
+/// match are evaluated. Per-line cost stays flat as the rule count grows.
+#[derive(Debug)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    /// Built on first use; `None` when the patterns are too big for one set
+    set: OnceLock<Option<RegexSet>>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self { rules, set: OnceLock::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn set(&self) -> Option<&RegexSet> {
+        self.set
+            .get_or_init(|| match RegexSet::new(self.rules.iter().map(|rule| rule.pattern.0.as_str())) {
+                Ok(set) => Some(set),
+                Err(e) => {
+                    tracing::debug!("Rules don't fit in one regex set ({}); matching them one by one", e);
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Evaluates the rules in order. `drop`, `keep` and `unwrap` stop at the first
+    /// match; `replace` rewrites the line and lets later rules see the result.
+    /// Also returns the matching rule's `until` pattern when it opens a region.
+    pub fn apply<'r, 'a>(&'r self, line: &'a str) -> (Verdict<'a>, Option<&'r Pattern>) {
+        let set = self.set();
+        // Rules that can match the current text; with no set, every rule is tried
+        let mut candidates = set.map(|set| set.matches(line));
+        if candidates.as_ref().is_some_and(|matches| !matches.matched_any()) {
+            return (Verdict::Continue(Cow::Borrowed(line)), None);
+        }
+        let mut text = Cow::Borrowed(line);
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            if candidates.as_ref().is_some_and(|matches| !matches.matched(i)) {
+                continue;
+            }
+            let re = &rule.pattern.0;
+            match rule.action {
+                Action::Drop if re.is_match(&text) => return (Verdict::Drop, rule.until.as_ref()),
+                Action::Keep if re.is_match(&text) => return (Verdict::Keep(text), rule.until.as_ref()),
+                Action::Unwrap => {
+                    if let Some(caps) = re.captures(&text) {
+                        let inner = caps.name("content")
+                            .or_else(|| caps.get(1))
+                            .map_or("", |m| m.as_str())
+                            .to_string();
+                        return (Verdict::Keep(Cow::Owned(inner)), None);
+                    }
+                }
+                Action::Replace => {
+                    let replacement = rule.replacement.as_deref().unwrap_or_default();
+                    if let Cow::Owned(rewritten) = re.replace_all(&text, replacement) {
+                        candidates = set.map(|set| set.matches(&rewritten));
+                        text = Cow::Owned(rewritten);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (Verdict::Continue(text), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn rules(toml: &str) -> RuleSet {
+        RuleSet::new(Config::parse(toml).unwrap().rules)
+    }
+
+    #[test]
+    fn test_rule_actions() {
+        let rules = rules(r#"
+            [[rules]]
+            match = '^⏺ '
+            action = "replace"
+            replacement = ""
+
+            [[rules]]
+            match = '^Bash\('
+            action = "drop"
+
+            [[rules]]
+            match = '^\s*┃ (?P<content>.*?)\s*┃$'
+            action = "unwrap"
+
+            [[rules]]
+            match = '^\s*\|'
+            action = "keep"
+        "#);
+
+        assert_eq!(rules.apply("⏺ Bash(ls)").0, Verdict::Drop, "Replace should feed later rules");
+        assert_eq!(rules.apply("⏺ Done.").0, Verdict::Continue("Done.".into()));
+        assert_eq!(rules.apply("  ┃ inner text ┃").0, Verdict::Keep("inner text".into()));
+        assert_eq!(rules.apply("| a | b |").0, Verdict::Keep("| a | b |".into()));
+        assert_eq!(rules.apply("plain").0, Verdict::Continue("plain".into()));
+    }
+
+    #[test]
+    fn test_until_requires_region_action() {
+        assert!(Config::parse("[[rules]]\nmatch = 'a'\naction = \"drop\"\nuntil = '^$'\n").is_ok());
+        assert!(Config::parse("[[rules]]\nmatch = 'a'\naction = \"unwrap\"\nuntil = '^$'\n").is_err());
+    }
+}
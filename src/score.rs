@@ -0,0 +1,233 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+lazy_static! {
+    static ref DICTIONARY: HashSet<&'static str> = include_str!("../data/words.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty() && !word.starts_with('#'))
+        .collect();
+}
+
+/// Longer words can't be in the dictionary, so they're never looked up
+const MAX_WORD_LEN: usize = 16;
+
+/// The most frequent English letter pairs
+const COMMON_BIGRAMS: &[&str] = &[
+    "th", "he", "in", "er", "an", "re", "on", "at", "en", "nd", "ti", "es", "or", "te", "of", "ed", "is", "it",
+    "al", "ar", "st", "to", "nt", "ng", "se", "ha", "as", "ou", "io", "le",
+];
+
+/// How much each character class and word-level signal is worth, per
+/// character of text. Set under `[scoring]` in `config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub letter: f64,
+    pub digit: f64,
+    pub whitespace: f64,
+    pub punctuation: f64,
+    /// Other printable symbols (emoji, math, arrows)
+    pub symbol: f64,
+    pub box_drawing: f64,
+    /// Control characters other than tab
+    pub control: f64,
+    /// U+FFFD, left behind by failed decoding
+    pub replacement: f64,
+    /// Windows-1252 mojibake pairs such as `Ã©` or `â€`
+    pub mojibake: f64,
+    /// Bonus for characters inside dictionary words
+    pub word: f64,
+    /// Bonus per common English letter pair
+    pub bigram: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            letter: 1.0,
+            digit: 0.5,
+            whitespace: 0.2,
+            punctuation: 0.3,
+            symbol: 0.1,
+            box_drawing: -1.0,
+            control: -5.0,
+            replacement: -10.0,
+            mojibake: -4.0,
+            word: 1.0,
+            bigram: 0.5,
+        }
+    }
+}
+
+/// What `--conservative` does when cleaning isn't clearly an improvement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fallback {
+    /// Leave the text exactly as it was
+    #[default]
+    None,
+    /// Only remove ANSI escape codes
+    Ansi,
+}
+
+/// `[conservative]` settings: commit the cleaned text only when it beats the
+/// original's score by at least `threshold`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conservative {
+    /// Always on, as if `--conservative` were passed
+    pub enabled: bool,
+    pub threshold: f64,
+    pub fallback: Fallback,
+}
+
+impl Default for Conservative {
+    fn default() -> Self {
+        Self { enabled: false, threshold: 0.1, fallback: Fallback::None }
+    }
+}
+
+/// A score and the parts it was summed from, in `Weights` field order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Score {
+    pub total: f64,
+    pub parts: Vec<(&'static str, f64)>,
+}
+
+impl Score {
+    /// Non-zero parts as `name value`, largest first, for `--explain`.
+    pub fn breakdown(&self) -> String {
+        let mut parts: Vec<_> = self.parts.iter().filter(|(_, value)| *value != 0.0).collect();
+        parts.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        let parts: Vec<String> = parts.iter().map(|(name, value)| format!("{} {:+.2}", name, value)).collect();
+        parts.join(", ")
+    }
+}
+
+/// Start of a UTF-8 sequence misread as Windows-1252 (`Ã`, `Â`, `â`, `Ð`, …)
+fn is_mojibake_lead(c: char) -> bool {
+    matches!(c, 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ð' | 'Ñ' | 'â' | 'ã' | 'ï')
+}
+
+/// Continuation bytes (0x80–0xBF) as Windows-1252 shows them.
+fn is_mojibake_tail(c: char) -> bool {
+    matches!(c, '\u{0080}'..='\u{00BF}' | '€' | '‚' | 'ƒ' | '„' | '…' | '†' | '‡' | 'ˆ' | '‰' | 'Š' | '‹' | 'Œ'
+        | 'Ž' | '‘' | '’' | '“' | '”' | '•' | '–' | '—' | '˜' | '™' | 'š' | '›' | 'œ' | 'ž' | 'Ÿ')
+}
+
+/// Looks `word` up case-insensitively without allocating.
+fn is_dictionary_word(word: &str) -> bool {
+    if word.len() > MAX_WORD_LEN || !word.is_ascii() {
+        return false;
+    }
+    let mut lower = [0u8; MAX_WORD_LEN];
+    let lower = &mut lower[..word.len()];
+    lower.copy_from_slice(word.as_bytes());
+    lower.make_ascii_lowercase();
+    std::str::from_utf8(lower).is_ok_and(|lower| DICTIONARY.contains(lower))
+}
+
+/// Rates how much a text looks like clean, readable content, so that repair
+/// and cleaning variants can be compared. Scores are per character, so
+/// removing junk raises them while removing content lowers them.
+#[derive(Debug, Clone, Default)]
+pub struct Scorer {
+    weights: Weights,
+}
+
+impl Scorer {
+    pub fn new(weights: Weights) -> Self {
+        Self { weights }
+    }
+
+    /// Scores `text` in a single pass without copying it, so huge inputs
+    /// cost no more memory than small ones.
+    pub fn score(&self, text: &str) -> Score {
+        let w = &self.weights;
+        let mut chars = text.chars().filter(|&c| c != '\n' && c != '\r').peekable();
+        let mut len = 0usize;
+        let mut counts = [0usize; 9];
+        while let Some(c) = chars.next() {
+            len += 1;
+            if is_mojibake_lead(c) && chars.next_if(|&next| is_mojibake_tail(next)).is_some() {
+                len += 1;
+                counts[8] += 2;
+                continue;
+            }
+            let class = match c {
+                '\u{FFFD}' => 7,
+                '\u{2500}'..='\u{257F}' => 5,
+                c if c.is_alphabetic() => 0,
+                c if c.is_numeric() => 1,
+                c if c.is_whitespace() => 2,
+                c if c.is_control() => 6,
+                c if c.is_ascii_punctuation() || matches!(c, '‘' | '’' | '“' | '”' | '–' | '—' | '…') => 3,
+                _ => 4,
+            };
+            counts[class] += 1;
+        }
+        if len == 0 {
+            return Score::default();
+        }
+
+        // Dictionary words are ASCII, so bytes == chars
+        let word_chars: usize = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| is_dictionary_word(word))
+            .map(str::len)
+            .sum();
+        let bigrams = text
+            .as_bytes()
+            .windows(2)
+            .filter(|pair| {
+                let pair = [pair[0].to_ascii_lowercase(), pair[1].to_ascii_lowercase()];
+                COMMON_BIGRAMS.iter().any(|bigram| bigram.as_bytes() == pair)
+            })
+            .count();
+
+        let weighted = [
+            ("letter", w.letter, counts[0]),
+            ("digit", w.digit, counts[1]),
+            ("whitespace", w.whitespace, counts[2]),
+            ("punctuation", w.punctuation, counts[3]),
+            ("symbol", w.symbol, counts[4]),
+            ("box_drawing", w.box_drawing, counts[5]),
+            ("control", w.control, counts[6]),
+            ("replacement", w.replacement, counts[7]),
+            ("mojibake", w.mojibake, counts[8]),
+            ("word", w.word, word_chars),
+            ("bigram", w.bigram, bigrams),
+        ];
+        let len = len as f64;
+        let parts: Vec<(&'static str, f64)> =
+            weighted.iter().map(|&(name, weight, count)| (name, weight * count as f64 / len)).collect();
+        Score { total: parts.iter().map(|(_, value)| value).sum(), parts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repaired_text_outscores_mojibake() {
+        let scorer = Scorer::default();
+        let repaired = scorer.score("The café is open");
+        let broken = scorer.score("The cafÃ© is open");
+        assert!(repaired.total > broken.total, "{:?} vs {:?}", repaired, broken);
+        assert!(broken.parts.contains(&("mojibake", -4.0 * 2.0 / 17.0)));
+    }
+
+    #[test]
+    fn test_words_and_weights() {
+        let scorer = Scorer::default();
+        assert!(scorer.score("the value is set").total > scorer.score("xqz vlkw pq jjt").total);
+        assert!(scorer.score("│ text │").total < scorer.score("text").total);
+
+        let weights = Weights { box_drawing: 0.0, ..Weights::default() };
+        let lenient = Scorer::new(weights).score("│ text │");
+        assert_eq!(lenient.parts.iter().find(|(name, _)| *name == "box_drawing"), Some(&("box_drawing", 0.0)));
+    }
+}
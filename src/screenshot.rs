@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::clipboard::{get_clipboard, set_clipboard, with_retry};
+use reprompt::error::RepromptError;
+use reprompt::lock::InstanceLock;
+use reprompt::ocr::recognize;
+
+use crate::stats::{self, Outcome};
+use crate::Reporting;
+
+/// `reprompt ocr`: reads the screenshot on the clipboard with `[ocr] command`,
+/// cleans the text like any copy, and puts it on the clipboard in place of
+/// the image. Only ever run by hand, never by `watch`.
+pub fn run(cleaner: &Cleaner, reporting: Reporting) -> Result<()> {
+    // Apps that copy text and an image together meant the text
+    if get_clipboard().is_ok_and(|text| !text.trim().is_empty()) {
+        anyhow::bail!("The clipboard holds text, not just an image; clean it with `reprompt` instead");
+    }
+    let image = crate::formats::fetch_image().map_err(|e| RepromptError::ClipboardRead(format!("{:#}", e)))?;
+    let image = image.ok_or_else(|| RepromptError::ClipboardRead("The clipboard holds no image".to_string()))?;
+    let text = recognize(&cleaner.config().ocr, &image).context("Failed to read the image")?;
+    anyhow::ensure!(!text.trim().is_empty(), "No text found in the image");
+
+    let mut report = cleaner.clean_checked(&text, None)?;
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    with_retry(|| set_clipboard(&report.output)).map_err(|e| RepromptError::ClipboardWrite(e.to_string()))?;
+    report.committed = true;
+    tracing::info!("Replaced a {} byte image with {} recognized lines", image.len(), report.lines_out);
+    stats::record(Outcome::Cleaned, text.len(), Some(&report));
+    reporting.emit(&report);
+    Ok(())
+}
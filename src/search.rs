@@ -0,0 +1,267 @@
+use rayon::prelude::*;
+use std::io::BufRead;
+
+use crate::pipeline::{Line, Pipeline, Stage};
+use crate::score::{Score, Scorer};
+use crate::timing::Timings;
+
+/// Stages whose effect is a judgement call: each variant is explored both
+/// with and without them (encoding repair × border strategy × column strategy,
+/// and ASCII tables when `grid` is enabled).
+const BRANCHES: &[&str] = &["mojibake", "border", "inline", "grid"];
+
+/// One path through the pipeline: its current lines and the stages it skipped.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub lines: Vec<Line>,
+    pub skipped: Vec<&'static str>,
+    /// Bytes each stage removed, in pipeline order
+    pub removed: Vec<(&'static str, usize)>,
+    pub score: Score,
+}
+
+impl Variant {
+    /// `input` as it is, before any stage.
+    pub fn new(input: &str) -> Self {
+        let lines = input.lines().map(Line::new).collect();
+        Self { lines, skipped: Vec::new(), removed: Vec::new(), score: Score::default() }
+    }
+
+    fn apply(&mut self, stage: &dyn Stage, timings: &Timings) {
+        let before = size(&self.lines);
+        self.lines = timings.time(stage.name(), || stage.apply(std::mem::take(&mut self.lines)));
+        self.note_removed(stage.name(), before);
+    }
+
+    fn note_removed(&mut self, stage: &'static str, before: usize) {
+        self.add_removed(stage, before.saturating_sub(size(&self.lines)));
+    }
+
+    fn add_removed(&mut self, stage: &'static str, bytes: usize) {
+        if bytes == 0 {
+            return;
+        }
+        match self.removed.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += bytes,
+            None => self.removed.push((stage, bytes)),
+        }
+    }
+
+    /// `all stages`, or which branch stages were left out.
+    pub fn name(&self) -> String {
+        if self.skipped.is_empty() {
+            "all stages".to_string()
+        } else {
+            format!("without {}", self.skipped.join(", "))
+        }
+    }
+}
+
+/// Line-aligned pieces of at least `chunk_bytes` (bar the last) read from
+/// `reader` one line at a time, so only one piece is ever in memory.
+pub struct Pieces<R> {
+    reader: R,
+    chunk_bytes: usize,
+}
+
+/// Splits `reader` into [`Pieces`].
+pub fn pieces<R: BufRead>(reader: R, chunk_bytes: usize) -> Pieces<R> {
+    Pieces { reader, chunk_bytes: chunk_bytes.max(1) }
+}
+
+impl<R: BufRead> Iterator for Pieces<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut piece = String::new();
+        while piece.len() < self.chunk_bytes {
+            match self.reader.read_line(&mut piece) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        (!piece.is_empty()).then_some(Ok(piece))
+    }
+}
+
+/// Like [`search`], but over `chunk_bytes`-sized, line-aligned pieces of
+/// `input`, each searched on its own and joined from its best variant, so
+/// the beam only ever holds copies of one piece. Stages that look at context
+/// (blank-line runs, art blocks, regions) see each piece separately. The
+/// result skips the stages every piece skipped.
+pub fn search_chunked(pipeline: &Pipeline, input: &str, scorer: &Scorer, width: usize, chunk_bytes: usize, timings: &Timings) -> Variant {
+    let mut whole = Variant::new("");
+    let mut skipped: Option<Vec<&'static str>> = None;
+    // Reading from a `&str` can't fail or meet invalid UTF-8
+    for piece in pieces(input.as_bytes(), chunk_bytes).map_while(std::io::Result::ok) {
+        let Some(mut part) = search(pipeline, &piece, scorer, width, timings).into_iter().next() else {
+            continue;
+        };
+        for (stage, bytes) in std::mem::take(&mut part.removed) {
+            whole.add_removed(stage, bytes);
+        }
+        skipped = Some(match skipped {
+            None => part.skipped,
+            Some(all) => all.into_iter().filter(|stage| part.skipped.contains(stage)).collect(),
+        });
+        whole.lines.append(&mut part.lines);
+    }
+    whole.skipped = skipped.unwrap_or_default();
+    whole.score = timings.time("scoring", || scorer.score(&Pipeline::finish(&whole.lines)));
+    whole
+}
+
+/// Bytes `lines` would take joined with newlines
+fn size(lines: &[Line]) -> usize {
+    lines.iter().map(|line| line.text.len() + 1).sum()
+}
+
+/// Runs `pipeline` over `input` as a beam search: at every branch stage each
+/// variant splits into "applied" and "skipped", and only the `width`
+/// best-scoring distinct variants go on. Returns the survivors, best first;
+/// on equal scores, variants that skipped fewer stages come first. Variants
+/// are independent, so they're cleaned and scored in parallel. The beam
+/// narrows to its best variant before a stage that is [`Stage::external`], so
+/// that runs once.
+pub fn search(pipeline: &Pipeline, input: &str, scorer: &Scorer, width: usize, timings: &Timings) -> Vec<Variant> {
+    let mut variant = Variant::new(input);
+    variant.score = timings.time("scoring", || scorer.score(&Pipeline::finish(&variant.lines)));
+    let mut beam = vec![variant];
+
+    for stage in pipeline.stages() {
+        if stage.external() && beam.len() > 1 {
+            beam.par_iter_mut().for_each(|variant| {
+                variant.score = timings.time("scoring", || scorer.score(&Pipeline::finish(&variant.lines)));
+            });
+            beam.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+            beam.truncate(1);
+        }
+        if !BRANCHES.contains(&stage.name()) {
+            beam.par_iter_mut().for_each(|variant| variant.apply(stage.as_ref(), timings));
+            continue;
+        }
+
+        // Order is kept, so "applied" still precedes its "skipped" twin
+        let mut next: Vec<Variant> = beam
+            .into_par_iter()
+            .flat_map_iter(|variant| {
+                let applied = timings.time(stage.name(), || stage.apply(variant.lines.clone()));
+                if applied == variant.lines {
+                    return vec![variant];
+                }
+                let score = timings.time("scoring", || scorer.score(&Pipeline::finish(&applied)));
+                let mut with = Variant {
+                    lines: applied,
+                    skipped: variant.skipped.clone(),
+                    removed: variant.removed.clone(),
+                    score,
+                };
+                with.note_removed(stage.name(), size(&variant.lines));
+                let mut without = variant;
+                without.skipped.push(stage.name());
+                vec![with, without]
+            })
+            .collect();
+        // Stable sort keeps "applied" ahead of "skipped" on ties
+        next.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+        let mut kept: Vec<Variant> = Vec::with_capacity(width);
+        for variant in next {
+            if kept.len() < width.max(1) && !kept.iter().any(|k| k.lines == variant.lines) {
+                kept.push(variant);
+            }
+        }
+        tracing::debug!(
+            stage = stage.name(),
+            scores = ?kept.iter().map(|v| v.score.total).collect::<Vec<_>>(),
+            "beam after branch"
+        );
+        beam = kept;
+    }
+
+    beam.par_iter_mut().for_each(|variant| {
+        variant.score = timings.time("scoring", || scorer.score(&Pipeline::finish(&variant.lines)));
+    });
+    beam.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+    beam
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{StageKind, StageOptions};
+    use crate::score::Weights;
+
+    #[test]
+    fn test_beam_keeps_best_combinations() {
+        let pipeline = Pipeline::default();
+        // A box whose border characters were themselves mangled by a cp1252 decode
+        let input = "â•\u{AD}â”€â”€â”€â”€â•®\nâ”‚ cafÃ© â”‚\nâ•°â”€â”€â”€â”€â•¯";
+        let beam = search(&pipeline, input, &Scorer::default(), 4, &Timings::default());
+        assert_eq!(Pipeline::finish(&beam[0].lines), "café");
+        assert_eq!(beam[0].name(), "all stages");
+        assert!(beam.len() > 1 && beam.len() <= 4);
+        assert!(beam.windows(2).all(|pair| pair[0].score.total >= pair[1].score.total));
+
+        // A weight that likes box drawing makes keeping the border the better strategy
+        let scorer = Scorer::new(Weights { box_drawing: 5.0, ..Weights::default() });
+        let beam = search(&pipeline, input, &scorer, 1, &Timings::default());
+        assert_eq!(beam.len(), 1);
+        assert!(beam[0].skipped.contains(&"border"), "{:?}", beam[0].skipped);
+    }
+
+    #[test]
+    fn test_chunks_split_on_lines() {
+        let pieces: Vec<String> = pieces("one\ntwo\r\nthree".as_bytes(), 4).map(Result::unwrap).collect();
+        assert_eq!(pieces, ["one\n", "two\r\n", "three"]);
+
+        let pipeline = Pipeline::default();
+        let input = "│ first line │\n│ second line │\n│ third line │";
+        let timings = Timings::default();
+        let variant = search_chunked(&pipeline, input, &Scorer::default(), 4, 4, &timings);
+        assert_eq!(Pipeline::finish(&variant.lines), "first line\nsecond line\nthird line");
+        assert_eq!(variant.removed, vec![("border", 24)]);
+        let timed: Vec<&str> = timings.into_vec().iter().map(|timing| timing.name).collect();
+        assert!(timed.contains(&"border") && timed.contains(&"scoring"), "{:?}", timed);
+    }
+
+    #[test]
+    fn test_chunks_keep_the_search() {
+        // Each piece makes the same judgement calls a search of the whole text does
+        let pipeline = Pipeline::default();
+        let input = "â•\u{AD}â”€â”€â”€â”€â•®\nâ”‚ cafÃ© â”‚\nâ•°â”€â”€â”€â”€â•¯\n".repeat(3);
+        let whole = search(&pipeline, &input, &Scorer::default(), 4, &Timings::default());
+        let chunked = search_chunked(&pipeline, &input, &Scorer::default(), 4, 8, &Timings::default());
+        assert_eq!(Pipeline::finish(&chunked.lines), Pipeline::finish(&whole[0].lines));
+
+        let scorer = Scorer::new(Weights { box_drawing: 5.0, ..Weights::default() });
+        let chunked = search_chunked(&pipeline, &input, &scorer, 1, 8, &Timings::default());
+        assert!(chunked.skipped.contains(&"border"), "{:?}", chunked.skipped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_stages_run_once() {
+        use crate::stages::ExecSpec;
+
+        let log = std::env::temp_dir().join(format!("reprompt-exec-runs-{}", std::process::id()));
+        let script = format!("echo run >> '{}'; cat", log.display());
+        let exec = ExecSpec { command: vec!["sh".to_string(), "-c".to_string(), script], timeout_ms: 5000 };
+        let options = StageOptions { exec: Some(exec), ..StageOptions::default() };
+        let pipeline = Pipeline::new(&[StageKind::Mojibake, StageKind::Border, StageKind::Exec], options);
+        let input = "â•\u{AD}â”€â”€â”€â”€â•®\nâ”‚ cafÃ© â”‚\nâ•°â”€â”€â”€â”€â•¯";
+        let beam = search(&pipeline, input, &Scorer::default(), 4, &Timings::default());
+        let runs = std::fs::read_to_string(&log).unwrap_or_default().lines().count();
+        let _ = std::fs::remove_file(&log);
+        assert_eq!(runs, 1);
+        assert_eq!(beam.len(), 1);
+        assert_eq!(Pipeline::finish(&beam[0].lines), "café");
+    }
+
+    #[test]
+    fn test_plain_text_has_one_variant() {
+        let pipeline = Pipeline::new(StageKind::DEFAULT_ORDER, StageOptions::default());
+        let beam = search(&pipeline, "just some text", &Scorer::default(), 4, &Timings::default());
+        assert_eq!(beam.len(), 1);
+    }
+}
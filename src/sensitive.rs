@@ -0,0 +1,68 @@
+use reprompt::classify;
+
+/// Why `text`, just read from the clipboard, must be neither cleaned nor
+/// written anywhere (backups, slots, the append buffer), if it must not: the
+/// app that copied it marked it as a password, or it looks like one.
+pub fn reason(text: &str) -> Option<&'static str> {
+    if hinted() {
+        Some("the app that copied it marked it as a password")
+    } else if classify::looks_secret(text) {
+        Some("it looks like a password, token or one-time code")
+    } else {
+        None
+    }
+}
+
+/// Fails with `reason` when the clipboard's `text` is sensitive.
+pub fn ensure_safe(text: &str) -> anyhow::Result<()> {
+    match reason(text) {
+        Some(reason) => anyhow::bail!("Not reading the clipboard: {}", reason),
+        None => Ok(()),
+    }
+}
+
+/// Whether the clipboard carries a password manager's "don't keep this" flag:
+/// `x-kde-passwordManagerHint` on Linux (KeePassXC, KDE), nspasteboard.org's
+/// `ConcealedType` on macOS, and `ExcludeClipboardContentFromMonitorProcessing`
+/// on Windows. WSL can't see the Windows flag without another PowerShell call.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn hinted() -> bool {
+    const HINT: &str = "x-kde-passwordManagerHint";
+    if reprompt::clipboard::is_wsl_custom() {
+        return false;
+    }
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let types = std::process::Command::new("wl-paste")
+            .arg("--list-types")
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output();
+        return types.is_ok_and(|output| String::from_utf8_lossy(&output.stdout).lines().any(|line| line.trim() == HINT));
+    }
+    std::env::var_os("DISPLAY").is_some() && crate::formats::x11::offers(HINT).unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+fn hinted() -> bool {
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::NSString;
+
+    let concealed = NSString::from_str("org.nspasteboard.ConcealedType");
+    NSPasteboard::generalPasteboard().types().is_some_and(|types| types.containsObject(&concealed))
+}
+
+#[cfg(windows)]
+fn hinted() -> bool {
+    use windows_sys::Win32::System::DataExchange::{IsClipboardFormatAvailable, RegisterClipboardFormatW};
+
+    let name: Vec<u16> = "ExcludeClipboardContentFromMonitorProcessing".encode_utf16().chain([0]).collect();
+    unsafe {
+        let format = RegisterClipboardFormatW(name.as_ptr());
+        format != 0 && IsClipboardFormatAvailable(format) != 0
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn hinted() -> bool {
+    false
+}
@@ -0,0 +1,317 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use reprompt::cleaner::Cleaner;
+use reprompt::error::RepromptError;
+use reprompt::lock::InstanceLock;
+use crate::Reporting;
+
+/// A client that stalls longer than this is dropped so it can't block others
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line or header accepted
+const MAX_HEADER_LINE: usize = 8192;
+
+/// Most header lines accepted in one request
+const MAX_HEADERS: usize = 100;
+
+/// Largest body accepted whatever `[input]` allows, so a client can't make
+/// the server allocate what a Content-Length claims
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Where the bearer token comes from when there's no `--token-file`
+const TOKEN_VAR: &str = "REPROMPT_TOKEN";
+
+/// Origin schemes of browser extensions, which a web page can't pose as
+const EXTENSION_SCHEMES: &[&str] = &["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+
+/// A parsed HTTP request: method, path, query string, headers and body.
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of `name` in the query string (no percent-decoding; profile names don't need it).
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+    }
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Self { status, content_type: "application/json", body }
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self::json(status, serde_json::json!({ "error": message.to_string() }).to_string())
+    }
+
+    /// An error response that also names the `RepromptError::kind`.
+    fn failure(status: u16, message: impl std::fmt::Display, kind: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": message.to_string(), "kind": kind }).to_string())
+    }
+
+    fn write_to(&self, mut stream: impl Write) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Reads one line, refusing one that doesn't end within `MAX_HEADER_LINE`
+/// bytes rather than read its rest as the next line.
+fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+    let mut line = String::new();
+    reader.take(MAX_HEADER_LINE as u64).read_line(&mut line).map_err(|e| Response::error(400, e))?;
+    if line.len() == MAX_HEADER_LINE && !line.ends_with('\n') {
+        return Err(Response::error(431, format!("A header line is over {} bytes", MAX_HEADER_LINE)));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Reads one request; bodies over `max_body` bytes (or `MAX_BODY`) are refused unread.
+fn read_request(reader: &mut impl BufRead, max_body: usize) -> Result<Request, Response> {
+    let max_body = max_body.min(MAX_BODY);
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request =
+        Request { method: method.to_string(), path: path.to_string(), query: query.to_string(), ..Default::default() };
+
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if request.headers.len() == MAX_HEADERS {
+            return Err(Response::error(431, format!("More than {} header lines", MAX_HEADERS)));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request.headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length: usize = match request.header("Content-Length") {
+        Some(length) => length.parse().map_err(|_| Response::error(400, "Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > max_body {
+        return Err(Response::error(413, format!("Body is over the {} byte limit", max_body)));
+    }
+    // Grows with what arrives instead of trusting Content-Length up front
+    reader.take(length as u64).read_to_end(&mut request.body).map_err(|e| Response::error(400, e))?;
+    if request.body.len() < length {
+        return Err(Response::error(400, "Body is shorter than Content-Length"));
+    }
+    Ok(request)
+}
+
+/// Whether `request` was addressed to this server by a local client: its
+/// `Host` must be `127.0.0.1:<port>` or `localhost:<port>`, and an `Origin`,
+/// if sent, the same or a browser extension. This turns away pages that post
+/// here from a browser (CSRF) or reach it through a domain re-pointed at
+/// 127.0.0.1 (DNS rebinding).
+fn local(request: &Request, port: u16) -> bool {
+    let hosts = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    let host_ok = request.header("Host").is_some_and(|host| hosts.iter().any(|h| host.eq_ignore_ascii_case(h)));
+    let origin_ok = request.header("Origin").is_none_or(|origin| {
+        EXTENSION_SCHEMES.iter().any(|scheme| origin.starts_with(scheme))
+            || origin.strip_prefix("http://").is_some_and(|host| hosts.iter().any(|h| host.eq_ignore_ascii_case(h)))
+    });
+    host_ok && origin_ok
+}
+
+/// Routes a request. `token`, when set, must arrive as `Authorization: Bearer <token>`.
+fn respond(cleaner: &Cleaner, port: u16, token: Option<&str>, reporting: Reporting, request: &Request) -> Response {
+    if !local(request, port) {
+        return Response::error(403, "Host or Origin is not this local server");
+    }
+    if let Some(token) = token {
+        let given = request.header("Authorization").and_then(|value| value.strip_prefix("Bearer "));
+        if given != Some(token) {
+            return Response::error(401, "Missing or wrong bearer token");
+        }
+    }
+
+    let report = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => return Response::json(200, r#"{"status":"ok"}"#.to_string()),
+        ("POST", "/clean") => {
+            let Ok(text) = std::str::from_utf8(&request.body) else {
+                return Response::error(400, "Body is not UTF-8 text");
+            };
+            match cleaner.clean_checked(text, request.param("profile")) {
+                Ok(report) => report,
+                Err(e @ RepromptError::TooLarge { .. }) => return Response::failure(413, &e, e.kind()),
+                Err(e @ RepromptError::UnknownProfile(_)) => return Response::failure(400, &e, e.kind()),
+                Err(e) => return Response::failure(500, &e, e.kind()),
+            }
+        }
+        ("POST", "/clipboard/clean") => {
+            let result = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)
+                .map_err(anyhow::Error::from)
+                .and_then(|_lock| crate::clean_clipboard(cleaner, None, reporting));
+            match result {
+                Ok(Some((report, _))) => report,
+                // Left alone on purpose: empty, sensitive, ignored or too large
+                Ok(None) => return Response::json(200, r#"{"status":"skipped"}"#.to_string()),
+                Err(e) => return Response::failure(500, &e, crate::error_kind(&e)),
+            }
+        }
+        (_, "/health" | "/clean" | "/clipboard/clean") => return Response::error(405, "Method not allowed"),
+        _ => return Response::error(404, "No such endpoint"),
+    };
+    match serde_json::to_string(&report) {
+        Ok(json) => Response::json(200, json),
+        Err(e) => Response::error(500, e),
+    }
+}
+
+fn handle(cleaner: &Cleaner, port: u16, token: Option<&str>, reporting: Reporting, stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let response = match read_request(&mut reader, cleaner.max_input_bytes().unwrap_or(usize::MAX)) {
+        Ok(request) => {
+            let response = respond(cleaner, port, token, reporting, &request);
+            tracing::info!("{} {} → {}", request.method, request.path, response.status);
+            response
+        }
+        Err(response) => response,
+    };
+    response.write_to(&stream)?;
+    Ok(())
+}
+
+/// The bearer token: the first line of `file` when given, else `REPROMPT_TOKEN`
+/// if set. Never a command-line argument, which other users can read from the
+/// process list.
+pub fn token(file: Option<&Path>) -> Result<Option<String>> {
+    let token = match file {
+        Some(file) => {
+            let text = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+            let token = text.lines().next().unwrap_or_default().trim().to_string();
+            anyhow::ensure!(!token.is_empty(), "{} holds no token", file.display());
+            Some(token)
+        }
+        None => std::env::var(TOKEN_VAR).ok().filter(|token| !token.trim().is_empty()),
+    };
+    Ok(token)
+}
+
+/// Serves the HTTP API on `127.0.0.1:port`, one request at a time, until killed.
+pub fn run(cleaner: &Cleaner, port: u16, token: Option<&str>, reporting: Reporting) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("Failed to listen on 127.0.0.1:{}", port))?;
+    tracing::info!(
+        "Listening on http://{}{}",
+        listener.local_addr()?,
+        if token.is_some() { " (bearer token required)" } else { "" }
+    );
+    for stream in listener.incoming() {
+        let result = stream.map_err(anyhow::Error::from).and_then(|stream| handle(cleaner, port, token, reporting, stream));
+        if let Err(e) = result {
+            tracing::warn!("Request failed: {:#}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::config::Config;
+
+    fn send(cleaner: &Cleaner, token: Option<&str>, raw: &str) -> Response {
+        let reporting = Reporting::default();
+        match read_request(&mut raw.as_bytes(), 1024) {
+            Ok(request) => respond(cleaner, 7457, token, reporting, &request),
+            Err(response) => response,
+        }
+    }
+
+    #[test]
+    fn test_clean_endpoint() {
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let body = "│ hello │";
+        let raw = format!("POST /clean?profile=generic HTTP/1.1\r\nHost: 127.0.0.1:7457\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let response = send(&cleaner, None, &raw);
+        assert_eq!(response.status, 200);
+        let report: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(report["output"], "hello");
+        assert_eq!(report["profile"], "generic");
+
+        assert_eq!(send(&cleaner, None, "GET /clean HTTP/1.1\r\nHost: localhost:7457\r\n\r\n").status, 405);
+        assert_eq!(send(&cleaner, None, "GET /nope HTTP/1.1\r\nHost: localhost:7457\r\n\r\n").status, 404);
+        assert_eq!(send(&cleaner, None, "POST /clean HTTP/1.1\r\nContent-Length: 5000\r\n\r\n").status, 413);
+        let headers = "X-Filler: 1\r\n".repeat(MAX_HEADERS + 1);
+        assert_eq!(send(&cleaner, None, &format!("GET /health HTTP/1.1\r\n{}\r\n", headers)).status, 431);
+        let long = format!("GET /health HTTP/1.1\r\nX-Long: {}\r\nHost: 127.0.0.1:7457\r\n\r\n", "a".repeat(MAX_HEADER_LINE));
+        assert_eq!(send(&cleaner, None, &long).status, 431);
+        let huge = format!("POST /clean HTTP/1.1\r\nHost: 127.0.0.1:7457\r\nContent-Length: {}\r\n\r\n", usize::MAX);
+        assert_eq!(read_request(&mut huge.as_bytes(), usize::MAX).unwrap_err().status, 413);
+        let short = "POST /clean HTTP/1.1\r\nHost: 127.0.0.1:7457\r\nContent-Length: 10\r\n\r\nabc";
+        assert_eq!(send(&cleaner, None, short).status, 400);
+    }
+
+    #[test]
+    fn test_foreign_hosts_and_origins() {
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        let get = |headers: &str| send(&cleaner, None, &format!("GET /health HTTP/1.1\r\n{}\r\n", headers)).status;
+        assert_eq!(get("Host: 127.0.0.1:7457\r\n"), 200);
+        assert_eq!(get("Host: localhost:7457\r\nOrigin: http://localhost:7457\r\n"), 200);
+        assert_eq!(get("Host: 127.0.0.1:7457\r\nOrigin: chrome-extension://abcdef\r\n"), 200);
+        assert_eq!(get(""), 403);
+        assert_eq!(get("Host: rebound.example.com:7457\r\n"), 403);
+        assert_eq!(get("Host: 127.0.0.1:7457\r\nOrigin: https://evil.example.com\r\n"), 403);
+    }
+
+    #[test]
+    fn test_token_required() {
+        let cleaner = Cleaner::new(Config::default(), None, None, false).unwrap();
+        assert_eq!(send(&cleaner, Some("s3cret"), "GET /health HTTP/1.1\r\nHost: 127.0.0.1:7457\r\n\r\n").status, 401);
+        let raw = "GET /health HTTP/1.1\r\nHost: 127.0.0.1:7457\r\nauthorization: Bearer s3cret\r\n\r\n";
+        assert_eq!(send(&cleaner, Some("s3cret"), raw).status, 200);
+    }
+}
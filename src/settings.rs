@@ -0,0 +1,248 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use reprompt::config::Config;
+use reprompt::error::RepromptError;
+use reprompt::paths;
+use reprompt::profiles::{self, Profile};
+
+/// What `reprompt config init` writes: every setting, commented out at its default
+const TEMPLATE: &str = include_str!("../data/config.toml");
+
+/// Something wrong in a config or profile file.
+#[derive(Debug)]
+struct Problem {
+    path: PathBuf,
+    /// 1-based, when the parser could tell
+    line: Option<usize>,
+    message: String,
+    /// An error stops the file from loading; a warning is probably a mistake
+    error: bool,
+}
+
+impl Problem {
+    fn error(path: &Path, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self { path: path.to_path_buf(), line, message: message.into(), error: true }
+    }
+
+    fn warning(path: &Path, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self { path: path.to_path_buf(), line, message: message.into(), error: false }
+    }
+
+    /// Where `text` failed to parse, from the TOML span when there is one.
+    fn parse(path: &Path, text: &str, e: RepromptError) -> Self {
+        match e {
+            RepromptError::InvalidConfig { error, .. } => {
+                let line = error.span().map(|span| text[..span.start.min(text.len())].matches('\n').count() + 1);
+                Self::error(path, line, error.message())
+            }
+            e => Self::error(path, None, e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = if self.error { "error" } else { "warning" };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.path.display(), line, level, self.message),
+            None => write!(f, "{}: {}: {}", self.path.display(), level, self.message),
+        }
+    }
+}
+
+/// Line of the first `<key> = "<value>"` in `text`.
+fn line_of(text: &str, key: &str, value: &str) -> Option<usize> {
+    text.lines()
+        .position(|line| line.trim_start().starts_with(key) && line.contains(value))
+        .map(|at| at + 1)
+}
+
+/// Warns about plugins in `plugins` that aren't there to load.
+fn check_plugins(path: &Path, plugins: &[PathBuf], problems: &mut Vec<Problem>) {
+    for plugin in plugins {
+        let full = paths::plugins_dir().map_or_else(|_| plugin.clone(), |dir| dir.join(plugin));
+        if !full.is_file() {
+            problems.push(Problem::warning(path, None, format!("plugin {} not found", full.display())));
+        } else if !cfg!(feature = "wasm-plugins") {
+            problems.push(Problem::warning(path, None, format!("plugin {} is ignored: built without `wasm-plugins`", plugin.display())));
+        }
+    }
+}
+
+/// Parses the profiles in `profiles_dir` and the config at `config_path` the
+/// way cleaning would, and also reports profile names nothing defines and
+/// profiles another file shadows. Returns the problems and the profiles read.
+fn check_files(config_path: &Path, profiles_dir: &Path) -> (Vec<Problem>, usize) {
+    let mut problems = Vec::new();
+    let mut files: Vec<PathBuf> = std::fs::read_dir(profiles_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+    files.sort();
+
+    let mut installed: BTreeMap<String, Vec<(PathBuf, Profile)>> = BTreeMap::new();
+    for path in &files {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                problems.push(Problem::error(path, None, e.to_string()));
+                continue;
+            }
+        };
+        match Profile::parse(&text) {
+            Ok(profile) => {
+                check_plugins(path, &profile.plugins, &mut problems);
+                installed.entry(profile.name.clone()).or_default().push((path.clone(), profile));
+            }
+            Err(e) => problems.push(Problem::parse(path, &text, e)),
+        }
+    }
+    for versions in installed.values_mut() {
+        versions.sort_by(|(_, a), (_, b)| b.version.partial_cmp(&a.version).unwrap_or(std::cmp::Ordering::Equal));
+        let (kept, profile) = &versions[0];
+        for (path, shadowed) in &versions[1..] {
+            let message = format!("'{}' {} is shadowed by {} ({})", shadowed.name, shadowed.version, kept.display(), profile.version);
+            problems.push(Problem::warning(path, None, message));
+        }
+    }
+
+    let known = |name: &str| {
+        name == "generic" || installed.contains_key(name) || profiles::builtin().iter().any(|profile| profile.name == name)
+    };
+    for (path, profile) in installed.values().map(|versions| &versions[0]) {
+        let Some(parent) = profile.extends.as_deref() else { continue };
+        let found = if parent == profile.name {
+            profiles::builtin().iter().any(|builtin| builtin.name == parent)
+        } else {
+            known(parent)
+        };
+        if !found {
+            let line = std::fs::read_to_string(path).ok().and_then(|text| line_of(&text, "extends", parent));
+            problems.push(Problem::warning(path, line, format!("extends unknown profile '{}'", parent)));
+        }
+    }
+
+    let text = match std::fs::read_to_string(config_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (problems, files.len()),
+        Err(e) => {
+            problems.push(Problem::error(config_path, None, e.to_string()));
+            return (problems, files.len());
+        }
+    };
+    let config = match Config::parse(&text) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(Problem::parse(config_path, &text, e));
+            return (problems, files.len());
+        }
+    };
+    if let Err(e) = Config::load_from(config_path) {
+        problems.push(Problem::error(config_path, None, format!("{:#}", e)));
+    }
+
+    if let Some(name) = config.profile.as_deref().filter(|name| !known(name)) {
+        problems.push(Problem::error(config_path, line_of(&text, "profile", name), format!("unknown profile '{}'", name)));
+    }
+    let scoped = config
+        .rules
+        .iter()
+        .map(|rule| rule.profile.as_deref())
+        .chain(config.substitutions.iter().map(|sub| sub.profile.as_deref()))
+        .chain(config.verbatim.iter().map(|region| region.profile.as_deref()));
+    let mut unknown: Vec<&str> = scoped.flatten().filter(|name| !known(name)).collect();
+    unknown.sort_unstable();
+    unknown.dedup();
+    for name in unknown {
+        let message = format!("scoped to unknown profile '{}', so it never applies", name);
+        problems.push(Problem::warning(config_path, line_of(&text, "profile", name), message));
+    }
+    check_plugins(config_path, &config.plugins, &mut problems);
+    (problems, files.len())
+}
+
+/// `reprompt config check`: prints every problem in the config and profiles,
+/// and fails if any of them would stop reprompt from loading them.
+pub fn check() -> Result<()> {
+    let config_path = paths::config_file()?;
+    let (problems, profiles) = check_files(&config_path, &paths::profiles_dir()?);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    let errors = problems.iter().filter(|problem| problem.error).count();
+    if errors > 0 {
+        bail!("{} error(s) in the config or profiles", errors);
+    }
+    let config = if config_path.exists() { config_path.display().to_string() } else { "default config".to_string() };
+    println!("✓ {} and {} profile file(s) are valid", config, profiles);
+    Ok(())
+}
+
+/// `reprompt config init`: writes a commented config with every setting at
+/// its default, refusing to replace an existing one unless `force`.
+pub fn init(force: bool) -> Result<()> {
+    let path = paths::config_file()?;
+    if path.exists() && !force {
+        bail!("{} already exists; pass --force to replace it", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, TEMPLATE).with_context(|| format!("Failed to write {}", path.display()))?;
+    tracing::info!("Wrote {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_is_a_valid_config() {
+        assert!(Config::parse(TEMPLATE).is_ok());
+        // With every setting uncommented it still loads, so the defaults shown are real
+        let settings = regex::Regex::new(r"^# (\[|[a-z_]+ = )").unwrap();
+        let uncommented: String = TEMPLATE
+            .lines()
+            .map(|line| if settings.is_match(line) { &line[2..] } else { line })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert!(uncommented.contains("\n[backup]\n"));
+        Config::parse(&uncommented).unwrap();
+    }
+
+    #[test]
+    fn test_check_reports_problems_with_lines() {
+        let dir = std::env::temp_dir().join(format!("reprompt-settings-{}", std::process::id()));
+        let profiles = dir.join("profiles.d");
+        std::fs::create_dir_all(&profiles).unwrap();
+        std::fs::write(dir.join("config.toml"), "beam_width = 2\n\n[[rules]]\nmatch = '('\naction = \"drop\"\n").unwrap();
+        std::fs::write(profiles.join("bad.toml"), "name = \"bad\"\nversion = 1\n").unwrap();
+        std::fs::write(profiles.join("old.toml"), "name = \"tool\"\nversion = \"1.0\"\n").unwrap();
+        std::fs::write(profiles.join("new.toml"), "name = \"tool\"\nversion = \"1.1\"\n").unwrap();
+        let (broken, count) = check_files(&dir.join("config.toml"), &profiles);
+
+        std::fs::write(dir.join("config.toml"), "[[substitutions]]\npattern = 'a'\nreplacement = 'b'\nprofile = \"nope\"\n").unwrap();
+        let (scoped, _) = check_files(&dir.join("config.toml"), &profiles);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<(String, Option<usize>, bool)> = broken
+            .iter()
+            .map(|problem| (problem.path.file_name().unwrap().to_string_lossy().into_owned(), problem.line, problem.error))
+            .collect();
+        assert_eq!(count, 3);
+        assert_eq!(
+            lines,
+            [
+                ("bad.toml".to_string(), Some(2), true),
+                ("old.toml".to_string(), None, false),
+                ("config.toml".to_string(), Some(4), true),
+            ]
+        );
+        let scoped = scoped.last().unwrap();
+        assert_eq!((scoped.line, scoped.error), (Some(4), false));
+    }
+}
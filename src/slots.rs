@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use reprompt::cleaner::Cleaner;
+use reprompt::clipboard::{set_clipboard, with_retry};
+use reprompt::files::write_private;
+use reprompt::lock::InstanceLock;
+use reprompt::paths;
+
+/// Longest slot name, so a name always makes a valid file name
+const MAX_NAME: usize = 64;
+
+/// File holding slot `name` in `dir`. Names are letters, digits, `-` and `_`.
+fn slot_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && name.len() <= MAX_NAME
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Invalid slot name '{}': use up to {} letters, digits, '-' or '_'",
+        name,
+        MAX_NAME
+    );
+    Ok(dir.join(format!("{}.txt", name)))
+}
+
+/// Names of the saved slots in `dir`, sorted.
+fn names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".txt").map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+fn read_slot(dir: &Path, name: &str) -> Result<String> {
+    let path = slot_path(dir, name)?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let saved = names(dir);
+            if saved.is_empty() {
+                anyhow::bail!("No slot '{}'; nothing has been saved yet", name)
+            }
+            anyhow::bail!("No slot '{}' (saved: {})", name, saved.join(", "))
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Cleans the clipboard and stores it in slot `name`, replacing what was there.
+pub fn save(cleaner: &Cleaner, name: &str) -> Result<()> {
+    let path = slot_path(&paths::slots_dir()?, name)?;
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    let report = crate::read_cleaned(cleaner)?;
+    write_private(&path, &report.output)?;
+    tracing::info!("Saved {} bytes to slot '{}'", report.output.len(), name);
+    Ok(())
+}
+
+/// Puts slot `name` on the clipboard; the slot is kept.
+pub fn load(name: &str) -> Result<()> {
+    let text = read_slot(&paths::slots_dir()?, name)?;
+    let _lock = InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT)?;
+    with_retry(|| set_clipboard(&text)).context("Failed to write clipboard")?;
+    tracing::info!("Loaded slot '{}' ({} bytes)", name, text.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_files() {
+        let dir = std::env::temp_dir().join(format!("reprompt-slots-{}", std::process::id()));
+        assert!(slot_path(&dir, "../escape").is_err());
+        assert!(slot_path(&dir, "").is_err());
+
+        write_private(&slot_path(&dir, "task-1").unwrap(), "fix the parser").unwrap();
+        write_private(&slot_path(&dir, "error_log").unwrap(), "stack trace").unwrap();
+        assert_eq!(read_slot(&dir, "task-1").unwrap(), "fix the parser");
+        let missing = read_slot(&dir, "other").unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(missing, "No slot 'other' (saved: error_log, task-1)");
+    }
+}
@@ -0,0 +1,61 @@
+mod ansi;
+mod art;
+mod border;
+mod charset;
+mod coalesce;
+mod diff;
+mod emphasis;
+mod exec;
+mod glyphs;
+mod grid;
+mod images;
+mod indent;
+mod inline;
+mod invisible;
+mod logprefix;
+mod markdown;
+mod mojibake;
+mod normalize;
+mod pager;
+mod paste;
+mod quotes;
+mod repeats;
+mod rules;
+mod screen;
+mod sidebyside;
+mod spinners;
+mod status;
+mod substitute;
+mod verbatim;
+mod wasm;
+
+pub use ansi::AnsiStrip;
+pub use art::ArtStrip;
+pub use border::BorderStrip;
+pub use charset::DecGraphics;
+pub use coalesce::Coalesce;
+pub use diff::DiffGuard;
+pub use emphasis::AnsiEmphasis;
+pub use exec::{ExecFilter, ExecSpec};
+pub use glyphs::{GlyphSpec, GlyphStrip};
+pub use grid::AsciiGrid;
+pub use images::{ImageMode, ImageStrip};
+pub use indent::{IndentNormalize, IndentSpec, TabExpand, TrailingTrim};
+pub use inline::{is_borderish, is_vertical_divider, InlineScrub};
+pub use invisible::{count_invisible, InvisibleCounts, InvisibleStrip};
+pub use logprefix::LogPrefixStrip;
+pub use markdown::MarkdownGuard;
+pub use mojibake::{from_cp1252_byte, repair_line, MojibakeRepair};
+pub use normalize::{AsciiPunctuation, ConfusableFold, NfcNormalize};
+pub use pager::PagerStrip;
+pub use paste::BracketedPaste;
+pub use quotes::QuoteStrip;
+pub use repeats::RepeatCollapse;
+pub use rules::RuleFilter;
+pub use screen::{Screen, ScreenSelect};
+pub use sidebyside::SideBySide;
+pub use spinners::SpinnerDedup;
+pub use status::{StatusLineSpec, StatusLineStrip};
+pub use substitute::{Substitute, Substitution};
+pub use verbatim::{VerbatimRegion, VerbatimRegions};
+pub use wasm::WasmFilter;
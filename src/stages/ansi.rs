@@ -0,0 +1,158 @@
+use crate::pipeline::{Line, Stage};
+
+/// 8-bit C1 controls that open the same sequences as their `ESC` forms
+const C1_CSI: char = '\u{9b}';
+const C1_OSC: char = '\u{9d}';
+const C1_DCS: char = '\u{90}';
+const C1_SOS: char = '\u{98}';
+const C1_PM: char = '\u{9e}';
+const C1_APC: char = '\u{9f}';
+const C1_ST: char = '\u{9c}';
+
+fn starts_sequence(c: char) -> bool {
+    matches!(c, '\x1b' | C1_CSI | C1_OSC | C1_DCS | C1_SOS | C1_PM | C1_APC)
+}
+
+/// Where the scanner is inside an escape sequence.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Text,
+    /// Just after ESC
+    Escape,
+    /// `ESC (`, `ESC #` and the like: intermediates, then one final byte
+    Intermediate,
+    /// `ESC [` / CSI: parameters and intermediates up to a final byte
+    Csi,
+    /// `ESC ]` / OSC: up to BEL or ST
+    Osc,
+    /// DCS, APC, PM and SOS strings: up to ST
+    String,
+    /// ESC inside an OSC or string, where `\` makes it ST
+    StringEscape { osc: bool },
+}
+
+/// Removes every escape sequence from `text` in one pass, or `None` when there
+/// are none. CSI sequences end at their final byte (`0x40`–`0x7e`); OSC at
+/// BEL or ST (`ESC \` or `0x9c`); DCS, APC, PM and SOS at ST. A sequence still
+/// open at the end of the line is dropped, as a terminal would swallow it, and
+/// a malformed CSI ends at the first byte that can't be part of it.
+fn strip(text: &str) -> Option<String> {
+    let first = text.find(starts_sequence)?;
+    let mut output = String::with_capacity(text.len());
+    output.push_str(&text[..first]);
+
+    let mut state = State::Text;
+    for c in text[first..].chars() {
+        state = match (state, c) {
+            (State::Text, '\x1b') => State::Escape,
+            (State::Text, C1_CSI) => State::Csi,
+            (State::Text, C1_OSC) => State::Osc,
+            (State::Text, C1_DCS | C1_SOS | C1_PM | C1_APC) => State::String,
+            (State::Text, c) => {
+                output.push(c);
+                State::Text
+            }
+
+            (State::Escape, '[') => State::Csi,
+            (State::Escape, ']') => State::Osc,
+            (State::Escape, 'P' | 'X' | '^' | '_') => State::String,
+            (State::Escape, '\x1b') => State::Escape,
+            (State::Escape, '\x20'..='\x2f') => State::Intermediate,
+            (State::Escape, '\x30'..='\x7e') => State::Text,
+
+            (State::Intermediate, '\x20'..='\x2f') => State::Intermediate,
+            (State::Intermediate, '\x30'..='\x7e') => State::Text,
+
+            (State::Csi, '\x20'..='\x3f') => State::Csi,
+            (State::Csi, '\x40'..='\x7e') => State::Text,
+
+            (State::Osc, '\x07' | C1_ST) => State::Text,
+            (State::Osc, '\x1b') => State::StringEscape { osc: true },
+            (State::Osc, _) => State::Osc,
+
+            (State::String, C1_ST) => State::Text,
+            (State::String, '\x1b') => State::StringEscape { osc: false },
+            (State::String, _) => State::String,
+
+            (State::StringEscape { .. }, '\\') => State::Text,
+            (State::StringEscape { osc: true }, _) => State::Osc,
+            (State::StringEscape { osc: false }, _) => State::String,
+
+            // A byte that can't continue the sequence ends it and is kept
+            (State::Escape | State::Intermediate | State::Csi, c) => {
+                if starts_sequence(c) {
+                    match c {
+                        '\x1b' => State::Escape,
+                        C1_CSI => State::Csi,
+                        C1_OSC => State::Osc,
+                        _ => State::String,
+                    }
+                } else {
+                    output.push(c);
+                    State::Text
+                }
+            }
+        };
+    }
+    Some(output)
+}
+
+/// Strips ANSI escape codes (colors, cursor movement, etc.)
+/// Many TUI applications add these for visual formatting.
+/// A hand-rolled scanner rather than a regex, so megabyte captures go in one
+/// pass and OSC hyperlinks, DCS and APC payloads come out whole.
+pub struct AnsiStrip;
+
+impl Stage for AnsiStrip {
+    fn name(&self) -> &'static str {
+        "ansi"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in &mut lines {
+            if let Some(stripped) = strip(&line.text) {
+                line.text = stripped;
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(text: &str) -> String {
+        strip(text).unwrap_or_else(|| text.to_string())
+    }
+
+    #[test]
+    fn test_strips_each_kind() {
+        assert_eq!(clean("\x1b[1;38;5;196mred\x1b[0m \x1b[?25lhidden\x1b[2K"), "red hidden");
+        assert_eq!(clean("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07 \x1b]0;title\u{9c}ok"), "link ok");
+        assert_eq!(clean("a\x1bP1$r0m\x1b\\b\x1b_Gi=1;AAAA\x1b\\c\x1b(0\x1b)B\x1b=\x1b7d"), "abcd");
+        assert_eq!(clean("\u{9b}31mc1\u{9b}0m"), "c1");
+        assert!(strip("plain │ text").is_none());
+    }
+
+    #[test]
+    fn test_malformed_and_unterminated() {
+        // A CSI cut short keeps the text after it; an open OSC runs to the end of the line
+        assert_eq!(clean("\x1b[31\u{e9}t\x1b"), "\u{e9}t");
+        assert_eq!(clean("before \x1b]0;no terminator"), "before ");
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_ansi_strip_million_lines() {
+        let samples = [
+            "\x1b[1;32m✓\x1b[0m test stages::ansi::tests \x1b[2m(0.01s)\x1b[0m",
+            "  fn main() { println!(\"hello\"); }",
+            "\x1b]8;;file:///src/main.rs\x1b\\src/main.rs\x1b]8;;\x1b\\:12:5 \x1b[33mwarning\x1b[39m",
+        ];
+        let lines: Vec<Line> = samples.iter().cycle().take(1_000_000).map(|&text| Line::new(text)).collect();
+        let start = std::time::Instant::now();
+        let output = AnsiStrip.apply(lines);
+        println!("ansi: {} lines in {:?}", output.len(), start.elapsed());
+    }
+}
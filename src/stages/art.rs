@@ -0,0 +1,79 @@
+use crate::pipeline::{Line, Stage};
+
+/// Art characters a line needs before it can be part of an art block
+const MIN_ART_CHARS: usize = 2;
+/// Consecutive art lines needed to call it art rather than e.g. a progress bar
+const MIN_ART_LINES: usize = 2;
+
+/// Braille patterns, block elements and legacy-computing sextants used for
+/// logos and charts.
+fn is_art_char(c: char) -> bool {
+    matches!(c, '\u{2800}'..='\u{28FF}' | '\u{2580}'..='\u{259F}' | '\u{1FB00}'..='\u{1FBAF}')
+}
+
+/// True when at least 60% of the visible characters are art characters.
+fn is_mostly_art(text: &str) -> bool {
+    let visible = text.chars().filter(|c| !c.is_whitespace()).count();
+    let art = text.chars().filter(|&c| is_art_char(c)).count();
+    visible > 0 && art * 10 >= visible * 6
+}
+
+/// Removes multi-line braille/block-element art such as tool logos. Lines that
+/// are mostly art are dropped; text printed beside the art (`▐▛███▜▌ Claude Code`)
+/// is kept without it. A single art line on its own is left alone.
+pub struct ArtStrip;
+
+impl Stage for ArtStrip {
+    fn name(&self) -> &'static str {
+        "art"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let is_art_line = |line: &Line| {
+            !line.verbatim && line.text.chars().filter(|&c| is_art_char(c)).count() >= MIN_ART_CHARS
+        };
+
+        let mut output = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let run = lines[i..].iter().take_while(|line| is_art_line(line)).count();
+            if run < MIN_ART_LINES {
+                output.extend(lines[i..i + run.max(1)].iter().cloned());
+                i += run.max(1);
+                continue;
+            }
+
+            for line in &lines[i..i + run] {
+                if is_mostly_art(&line.text) {
+                    continue;
+                }
+                let text: String = line.text.chars().filter(|&c| !is_art_char(c)).collect();
+                output.push(Line { text: text.trim().to_string(), verbatim: false });
+            }
+            i += run;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(input: &str) -> Vec<String> {
+        let lines = input.lines().map(Line::new).collect();
+        ArtStrip.apply(lines).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_strips_logo_beside_text() {
+        let banner = " ▐▛███▜▌   Claude Code v2.0.0\n▝▜█████▛▘  Sonnet 4.5\n  ▘▘ ▝▝    ~/project\n\nHello";
+        assert_eq!(strip(banner), vec!["Claude Code v2.0.0", "Sonnet 4.5", "~/project", "", "Hello"]);
+    }
+
+    #[test]
+    fn test_strips_braille_art_keeps_single_bars() {
+        assert_eq!(strip("⣿⣿⡿⠿⢿⣿\n⣿⡇⠀⠀⢸⣿\ndone"), vec!["done"]);
+        assert_eq!(strip("████░░░░ 50%\nnext"), vec!["████░░░░ 50%", "next"]);
+    }
+}
@@ -0,0 +1,67 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    static ref RE_BORDER_LINE: Regex = Regex::new(r"^[\s╭╮╰╯─═━┌┐└┘]+$").expect("Invalid Border Line Regex");
+
+    // Handles borders that have text embedded, e.g., "╭─── Title ───╮"
+    static ref RE_TITLED_BORDER: Regex = Regex::new(r"(?x)
+        ^[\s╭┌╰└]           # Start with corner or space
+        (?:.*?)             # Content (title, etc.)
+        [─═━]{3,}           # Must contain at least 3 horizontal bars
+        (?:.*?)             # More content
+        [╮┐╯┘]\s*$          # End with corner
+    ").expect("Invalid Titled Border Regex");
+
+    static ref RE_CONTENT_WRAPPER: Regex = Regex::new(r"(?x)
+        ^
+        \s*           # Start of line, optional indentation
+        [│║｜]        # The border character (full-width in CJK TUIs)
+        \x20?         # Optional single padding space
+        (?P<content>.*?) # Lazy capture of the actual content
+        \x20?         # Optional single padding space
+        [│║｜]?       # Optional trailing border
+        \s*           # End of line
+        $
+    ").expect("Invalid Content Wrapper Regex");
+}
+
+/// Removes box borders: pure border lines and titled borders are dropped,
+/// content wrapped in `│ … │` is unwrapped.
+pub struct BorderStrip;
+
+impl Stage for BorderStrip {
+    fn name(&self) -> &'static str {
+        "border"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        lines
+            .into_iter()
+            .filter_map(|mut line| {
+                if line.verbatim {
+                    return Some(line);
+                }
+
+                // Check if this is a pure border line (top/bottom of box)
+                // or a titled border line (top/bottom with text)
+                if RE_BORDER_LINE.is_match(&line.text) || RE_TITLED_BORDER.is_match(&line.text) {
+                    return None;
+                }
+
+                // Check if this is a content line wrapped in borders
+                if let Some(content) = RE_CONTENT_WRAPPER.captures(&line.text).and_then(|caps| caps.name("content")) {
+                    // Only trim trailing spaces (TUI padding), preserve leading spaces (indentation)
+                    // trim_end() removes the padding spaces that TUIs add to reach the right border
+                    line.text = content.as_str().trim_end().to_string();
+                }
+
+                // Lines that don't match any TUI pattern are preserved as-is
+                // This handles regular text, markdown, code, etc.
+                Some(line)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,154 @@
+use crate::pipeline::{Line, Stage};
+
+/// The DEC special graphics character for `c` (`q` → `─`), for the bytes the
+/// charset redefines.
+fn dec_graphic(c: char) -> Option<char> {
+    Some(match c {
+        '`' => '◆',
+        'a' => '▒',
+        'f' => '°',
+        'g' => '±',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        _ => return None,
+    })
+}
+
+/// Which character sets are designated and which one is in use.
+#[derive(Default)]
+struct Shift {
+    /// G0 (`ESC ( 0`) holds the graphics set
+    g0: bool,
+    /// G1 (`ESC ) 0`) holds the graphics set
+    g1: bool,
+    /// Shift Out (`^N`) selected G1
+    shifted: bool,
+}
+
+impl Shift {
+    fn graphics(&self) -> bool {
+        if self.shifted {
+            self.g1
+        } else {
+            self.g0
+        }
+    }
+}
+
+/// Copies the escape sequence starting at `chars` (just after ESC) into `output`
+/// untranslated, so the letters ending a CSI (`ESC[0m`) aren't taken for lines.
+fn copy_escape(chars: &mut std::iter::Peekable<std::str::Chars>, output: &mut String) {
+    output.push('\x1b');
+    match chars.next() {
+        Some('[') => {
+            output.push('[');
+            for c in chars.by_ref() {
+                output.push(c);
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+        Some(']') => {
+            output.push(']');
+            while let Some(c) = chars.next() {
+                output.push(c);
+                if c == '\x07' {
+                    break;
+                }
+                if let Some(terminator) = chars.next_if(|next| c == '\x1b' && *next == '\\') {
+                    output.push(terminator);
+                    break;
+                }
+            }
+        }
+        Some(c) => output.push(c),
+        None => {}
+    }
+}
+
+/// Translates text drawn in the DEC special graphics charset (`ESC(0` then
+/// `lqqqk`, as some curses programs do) to Unicode box drawing, so the border
+/// stages see the frame instead of literal letters. Charset switches are
+/// tracked across lines, including `ESC)0` with Shift Out/In.
+pub struct DecGraphics;
+
+impl Stage for DecGraphics {
+    fn name(&self) -> &'static str {
+        "charset"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let mut shift = Shift::default();
+        for line in &mut lines {
+            let text = &line.text;
+            if !shift.graphics() && !text.contains(['\x1b', '\x0e', '\x0f']) {
+                continue;
+            }
+            let mut output = String::with_capacity(text.len());
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\x1b' => match chars.peek() {
+                        Some(&designate @ ('(' | ')')) => {
+                            chars.next();
+                            let graphics = chars.next() == Some('0');
+                            if designate == '(' {
+                                shift.g0 = graphics;
+                            } else {
+                                shift.g1 = graphics;
+                            }
+                        }
+                        _ => copy_escape(&mut chars, &mut output),
+                    },
+                    '\x0e' => shift.shifted = true,
+                    '\x0f' => shift.shifted = false,
+                    c if shift.graphics() => output.push(dec_graphic(c).unwrap_or(c)),
+                    c => output.push(c),
+                }
+            }
+            line.text = output;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<String> {
+        DecGraphics.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_translates_line_drawing() {
+        let input = "\x1b(0lqqqqk\x1b(B\n\x1b(0x\x1b(B hi \x1b(0x\x1b(B\n\x1b(0mqqqqj\x1b(B";
+        assert_eq!(run(input), ["┌────┐", "│ hi │", "└────┘"]);
+    }
+
+    #[test]
+    fn test_shift_out_and_escapes() {
+        // Shift state carries over the line break; CSI letters stay untouched
+        assert_eq!(run("\x1b)0\x0e\x1b[1mtq\nqu\x1b[0m\x0f ok"), ["\x1b[1m├─", "─┤\x1b[0m ok"]);
+    }
+}
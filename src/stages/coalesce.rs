@@ -0,0 +1,53 @@
+use crate::pipeline::{Line, Stage};
+
+/// Maximum run of blank lines kept; TUI spacing often produces many more
+const MAX_CONSECUTIVE_EMPTY: usize = 2;
+
+/// Limits consecutive empty lines to avoid bloat and drops leading/trailing blank lines.
+pub struct Coalesce;
+
+impl Stage for Coalesce {
+    fn name(&self) -> &'static str {
+        "coalesce"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output = Vec::with_capacity(lines.len());
+        let mut consecutive_empty = 0;
+
+        for line in lines {
+            if line.text.trim().is_empty() {
+                consecutive_empty += 1;
+                if consecutive_empty > MAX_CONSECUTIVE_EMPTY && !line.verbatim {
+                    continue; // Skip excessive empty lines
+                }
+            } else {
+                consecutive_empty = 0;
+            }
+            output.push(line);
+        }
+
+        while output.last().is_some_and(|line| !line.verbatim && line.text.trim().is_empty()) {
+            output.pop();
+        }
+        // Chrome removed from the top (banners, panels) leaves blank lines behind
+        let leading = output
+            .iter()
+            .take_while(|line| !line.verbatim && line.text.trim().is_empty())
+            .count();
+        output.drain(..leading);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_runs_are_capped() {
+        let lines = ["", "a", "", "", "", "", "b", "", ""].map(Line::new).to_vec();
+        let texts: Vec<String> = Coalesce.apply(lines).into_iter().map(|l| l.text).collect();
+        assert_eq!(texts, vec!["a", "", "", "b"]);
+    }
+}
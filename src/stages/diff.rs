@@ -0,0 +1,123 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    static ref RE_HUNK: Regex =
+        Regex::new(r"^@@ -\d+(?:,(\d+))? \+\d+(?:,(\d+))? @@").expect("Invalid Hunk Regex");
+
+    // Lines between `diff --git` and the first hunk
+    static ref RE_FILE_HEADER: Regex = Regex::new(
+        r"^(?:diff |index [0-9a-f]+\.\.[0-9a-f]+|(?:old|new|deleted file|new file) mode |similarity index |dissimilarity index |rename (?:from|to) |copy (?:from|to) |Binary files |--- |\+\+\+ )"
+    ).expect("Invalid File Header Regex");
+}
+
+/// Old and new line counts from a hunk header; an omitted count means 1.
+fn hunk_counts(text: &str) -> Option<(usize, usize)> {
+    let caps = RE_HUNK.captures(text)?;
+    let count = |i| caps.get(i).map_or(Ok(1), |m| m.as_str().parse()).ok();
+    Some((count(1)?, count(2)?))
+}
+
+/// Length of the hunk body starting at `lines[0]`, following the header's counts.
+fn hunk_len(lines: &[Line], (mut old, mut new): (usize, usize)) -> usize {
+    let mut len = 0;
+    for line in lines {
+        match line.text.chars().next() {
+            // Blank context lines often lose their leading space when copied
+            Some(' ') | None if old > 0 && new > 0 => {
+                old -= 1;
+                new -= 1;
+            }
+            Some('-') if old > 0 => old -= 1,
+            Some('+') if new > 0 => new -= 1,
+            Some('\\') => {}
+            _ => break,
+        }
+        len += 1;
+        if old == 0 && new == 0 {
+            // `\ No newline at end of file` after the last line
+            len += usize::from(lines[len..].first().is_some_and(|line| line.text.starts_with('\\')));
+            break;
+        }
+    }
+    len
+}
+
+/// Marks unified diffs (`git diff`, `diff -u`, `.patch` files) verbatim so
+/// later stages don't strip `+`/`-`/`@@` lines, reflow them, or touch their
+/// whitespace. Hunks are followed by their header's line counts, so text after
+/// a patch is still cleaned. Runs after `ansi` so colored diffs are found.
+pub struct DiffGuard;
+
+impl Stage for DiffGuard {
+    fn name(&self) -> &'static str {
+        "diff"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let mut i = 0;
+        while i < lines.len() {
+            // File headers only count when a hunk (or binary notice) follows them
+            let header = lines[i..]
+                .iter()
+                .take_while(|line| RE_FILE_HEADER.is_match(&line.text))
+                .count();
+            let headed = &lines[i..i + header];
+            let complete = headed.iter().any(|line| line.text.starts_with("Binary files "))
+                || lines.get(i + header).is_some_and(|line| RE_HUNK.is_match(&line.text));
+            if header > 0 && !complete {
+                i += header;
+                continue;
+            }
+
+            let mut end = i + header;
+            while let Some(counts) = lines.get(end).and_then(|line| hunk_counts(&line.text)) {
+                end += 1 + hunk_len(&lines[end + 1..], counts);
+            }
+            if end == i {
+                i += 1;
+                continue;
+            }
+            for line in &mut lines[i..end] {
+                line.verbatim = true;
+            }
+            i = end;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protected(input: &str) -> Vec<bool> {
+        let lines = input.lines().map(Line::new).collect();
+        DiffGuard.apply(lines).into_iter().map(|line| line.verbatim).collect()
+    }
+
+    #[test]
+    fn test_marks_git_diff() {
+        let diff = "Here's the fix:\n\
+                    diff --git a/src/main.rs b/src/main.rs\n\
+                    index 1a2b3c4..5d6e7f8 100644\n\
+                    --- a/src/main.rs\n\
+                    +++ b/src/main.rs\n\
+                    @@ -1,3 +1,3 @@\n \
+                    fn main() {\n\
+                    -    println!(\"hi\");\n\
+                    +    println!(\"hello\");\n\
+                    \n\
+                    That should do it.";
+        let expected = [false, true, true, true, true, true, true, true, true, true, false];
+        assert_eq!(protected(diff), expected);
+    }
+
+    #[test]
+    fn test_ignores_lookalikes() {
+        assert_eq!(protected("--- a list\n- item\n+ plus"), vec![false, false, false]);
+        assert_eq!(protected("@@ -1 +1 @@\n-old\n+new\n+extra"), vec![true, true, true, false]);
+    }
+}
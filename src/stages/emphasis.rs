@@ -0,0 +1,168 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    static ref RE_SGR: Regex = Regex::new(r"\x1b\[([0-9;]*)m").expect("Invalid SGR Regex");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Green,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    /// Italic or underline, both rendered as `_…_`
+    emphasis: bool,
+    color: Option<Color>,
+}
+
+impl Style {
+    fn update(&mut self, params: &str) {
+        let mut codes = params.split(';').map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                3 | 4 => self.emphasis = true,
+                23 | 24 => self.emphasis = false,
+                31 | 91 => self.color = Some(Color::Red),
+                32 | 92 => self.color = Some(Color::Green),
+                30..=37 | 90..=97 => self.color = Some(Color::Other),
+                39 => self.color = None,
+                38 | 48 => {
+                    // Extended colors: skip `5;n` or `2;r;g;b`
+                    let skip = match codes.next() {
+                        Some(5) => 1,
+                        Some(2) => 3,
+                        _ => 0,
+                    };
+                    for _ in 0..skip {
+                        codes.next();
+                    }
+                    if code == 38 {
+                        self.color = Some(Color::Other);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Splits a line into runs of text sharing one style.
+fn spans(text: &str) -> Vec<(Style, &str)> {
+    let mut style = Style::default();
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for caps in RE_SGR.captures_iter(text) {
+        let sequence = caps.get(0).expect("match");
+        if sequence.start() > last {
+            spans.push((style, &text[last..sequence.start()]));
+        }
+        style.update(&caps[1]);
+        last = sequence.end();
+    }
+    if last < text.len() {
+        spans.push((style, &text[last..]));
+    }
+    spans
+}
+
+/// The color every visible character of the line shares, if any.
+fn line_color(spans: &[(Style, &str)]) -> Option<Color> {
+    let mut visible = spans.iter().filter(|(_, text)| !text.trim().is_empty());
+    let color = visible.next()?.0.color?;
+    visible.all(|(style, _)| style.color == Some(color)).then_some(color)
+}
+
+/// Wraps `text` in `marker`, keeping surrounding whitespace outside it.
+fn wrap(output: &mut String, text: &str, marker: &str) {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        output.push_str(text);
+        return;
+    }
+    let start = text.len() - text.trim_start().len();
+    output.push_str(&text[..start]);
+    output.push_str(marker);
+    output.push_str(trimmed);
+    output.push_str(marker);
+    output.push_str(&text[start + trimmed.len()..]);
+}
+
+fn render(spans: &[(Style, &str)]) -> String {
+    let mut output = String::new();
+    for (style, text) in spans {
+        match (style.bold, style.emphasis) {
+            (true, true) => wrap(&mut output, text, "***"),
+            (true, false) => wrap(&mut output, text, "**"),
+            (false, true) => wrap(&mut output, text, "_"),
+            (false, false) => output.push_str(text),
+        }
+    }
+    output
+}
+
+/// Opt-in (`--ansi-markdown`), runs before `ansi`: turns bold into `**…**`,
+/// italic/underline into `_…_`, and, when the text has both wholly red and
+/// wholly green lines, marks those lines `-`/`+` like a diff.
+pub struct AnsiEmphasis;
+
+impl Stage for AnsiEmphasis {
+    fn name(&self) -> &'static str {
+        "emphasis"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let colors: Vec<Option<Color>> = lines.iter().map(|line| line_color(&spans(&line.text))).collect();
+        let is_diff = colors.contains(&Some(Color::Red)) && colors.contains(&Some(Color::Green));
+
+        for (line, color) in lines.iter_mut().zip(colors) {
+            if line.verbatim || !line.text.contains('\x1b') {
+                continue;
+            }
+            let mut text = render(&spans(&line.text));
+            let prefix = match color {
+                Some(Color::Red) if is_diff => "-",
+                Some(Color::Green) if is_diff => "+",
+                _ => "",
+            };
+            if !prefix.is_empty() && !text.starts_with(['+', '-']) {
+                text.insert_str(0, prefix);
+            }
+            line.text = text;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(input: &str) -> Vec<String> {
+        let lines = input.lines().map(Line::new).collect();
+        AnsiEmphasis.apply(lines).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_emphasis_becomes_markdown() {
+        assert_eq!(convert("a \x1b[1mbold \x1b[0mand \x1b[3mitalic\x1b[23m."), vec!["a **bold** and _italic_."]);
+        assert_eq!(convert("\x1b[4;1mboth\x1b[0m"), vec!["***both***"]);
+    }
+
+    #[test]
+    fn test_colored_diff_lines_get_prefixes() {
+        let diff = "\x1b[31mold line\x1b[0m\n\x1b[32mnew line\x1b[0m\n\x1b[32m+kept\x1b[0m";
+        assert_eq!(convert(diff), vec!["-old line", "+new line", "+kept"]);
+        // A lone red error message is not a diff
+        assert_eq!(convert("\x1b[31merror: failed\x1b[0m"), vec!["error: failed"]);
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::{RepromptError, Result};
+use crate::pipeline::{Line, Stage};
+use crate::process::run_with_timeout;
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+/// An external filter command from a profile's (or the config's) `[exec]` table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecSpec {
+    /// Program and arguments; the text is piped to stdin and read back from stdout
+    pub command: Vec<String>,
+    /// Kill the filter and keep the unfiltered text after this long
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl ExecSpec {
+    pub fn check(&self) -> Result<()> {
+        if self.command.is_empty() {
+            return Err(RepromptError::InvalidSetting("[exec] command must name a program".to_string()));
+        }
+        Ok(())
+    }
+
+    fn run(&self, input: &str) -> Result<String> {
+        let mut command = Command::new(&self.command[0]);
+        command.args(&self.command[1..]);
+
+        let output = run_with_timeout(&mut command, input.as_bytes(), Duration::from_millis(self.timeout_ms))?;
+        if !output.status.success() {
+            return Err(RepromptError::Command(format!("exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        String::from_utf8(output.stdout).map_err(|e| RepromptError::Command(format!("wrote invalid UTF-8: {}", e)))
+    }
+}
+
+/// Pipes the text through a user-specified command (e.g. a de-identification
+/// script). Any failure leaves the text as it was before this stage.
+pub struct ExecFilter {
+    spec: Option<ExecSpec>,
+}
+
+impl ExecFilter {
+    pub fn new(spec: Option<ExecSpec>) -> Self {
+        Self { spec }
+    }
+}
+
+impl Stage for ExecFilter {
+    fn name(&self) -> &'static str {
+        "exec"
+    }
+
+    fn external(&self) -> bool {
+        self.spec.is_some()
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let Some(spec) = &self.spec else {
+            return lines;
+        };
+
+        let input: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        match spec.run(&input.join("\n")) {
+            Ok(filtered) => filtered.lines().map(Line::new).collect(),
+            Err(e) => {
+                tracing::warn!("exec filter '{}' failed: {}. Continuing with unfiltered text.", spec.command[0], e);
+                lines
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn run(command: &[&str], input: &str) -> String {
+        let spec = ExecSpec { command: command.iter().map(|s| s.to_string()).collect(), timeout_ms: 2000 };
+        let lines = input.lines().map(Line::new).collect();
+        let texts: Vec<String> = ExecFilter::new(Some(spec)).apply(lines).into_iter().map(|l| l.text).collect();
+        texts.join("\n")
+    }
+
+    #[test]
+    fn test_exec_filters_text() {
+        assert_eq!(run(&["tr", "a-z", "A-Z"], "host: db01\nok"), "HOST: DB01\nOK");
+    }
+
+    #[test]
+    fn test_exec_failure_keeps_input() {
+        assert_eq!(run(&["false"], "keep me"), "keep me");
+        assert_eq!(run(&["/nonexistent/filter"], "keep me"), "keep me");
+    }
+}
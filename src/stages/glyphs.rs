@@ -0,0 +1,95 @@
+use serde::Deserialize;
+
+use crate::pipeline::{Line, Stage};
+
+/// Nerd Font symbols that live outside the Private Use Area (power icons)
+const NERD_FONT_EXTRAS: &[char] = &['\u{23FB}', '\u{23FC}', '\u{23FD}', '\u{23FE}', '\u{2B58}'];
+
+/// Settings for the `glyphs` stage (`[glyphs]` in `config.toml`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GlyphSpec {
+    /// Characters to keep even though they are icon glyphs (e.g. "" for the Apple logo)
+    #[serde(default)]
+    pub keep: String,
+}
+
+/// Private-use glyphs from Nerd Fonts and Powerline, plus the few Nerd Font
+/// icons outside the PUA. Ordinary emoji are never matched.
+fn is_icon_glyph(c: char) -> bool {
+    matches!(c, '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}')
+        || NERD_FONT_EXTRAS.contains(&c)
+}
+
+/// Powerline segment separators (, , , , rounded and slanted variants)
+fn is_separator(c: char) -> bool {
+    matches!(c, '\u{E0B0}'..='\u{E0D4}')
+}
+
+/// Removes Nerd Font icons and turns Powerline separators into plain spaces,
+/// so fancy prompts copy as `~/project main` instead of private-use noise.
+pub struct GlyphStrip {
+    keep: String,
+}
+
+impl GlyphStrip {
+    pub fn new(spec: GlyphSpec) -> Self {
+        Self { keep: spec.keep }
+    }
+
+    fn strip(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if !is_icon_glyph(c) || self.keep.contains(c) {
+                output.push(c);
+                continue;
+            }
+            // Icons are padded with a space in prompts; drop it along with the glyph
+            if chars.peek() == Some(&' ') {
+                chars.next();
+            }
+            if is_separator(c) && !output.is_empty() && !output.ends_with(' ') {
+                output.push(' ');
+            }
+        }
+        output.trim_end().to_string()
+    }
+}
+
+impl Stage for GlyphStrip {
+    fn name(&self) -> &'static str {
+        "glyphs"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in &mut lines {
+            if !line.verbatim && line.text.chars().any(is_icon_glyph) {
+                line.text = self.strip(&line.text);
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(keep: &str, text: &str) -> String {
+        let stage = GlyphStrip::new(GlyphSpec { keep: keep.to_string() });
+        stage.apply(vec![Line::new(text)]).remove(0).text
+    }
+
+    #[test]
+    fn test_strips_powerline_prompt() {
+        assert_eq!(strip("", "\u{F31B} ~/project\u{E0B0} \u{E0A0} main\u{E0B0} "), "~/project main");
+        assert_eq!(strip("", "\u{F07C} src \u{E0B1} lib"), "src lib");
+    }
+
+    #[test]
+    fn test_emoji_and_allowlist_survive() {
+        assert_eq!(strip("", "done ✅ 🚀 ⚡"), "done ✅ 🚀 ⚡");
+        assert_eq!(strip("\u{F8FF}", "\u{F8FF} macOS \u{F179} "), "\u{F8FF} macOS");
+    }
+}
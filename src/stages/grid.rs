@@ -0,0 +1,119 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // `+----+------+`, `+====+`, or psql's `----+------`: dashes joined by `+`
+    static ref RE_FRAME: Regex = Regex::new(r"^\s*\+?[-=]{2,}(?:\+[-=]{2,})*\+?\s*$").expect("Invalid ASCII Frame Regex");
+
+    // A plain `-----` or `=====` rule, only taken as part of a framed table
+    static ref RE_RULE: Regex = Regex::new(r"^\s*(?:-{3,}|={3,})\s*$").expect("Invalid ASCII Rule Regex");
+
+    // Markdown's `|---|:---:|` header separator; tables with one are left alone
+    static ref RE_MARKDOWN_SEPARATOR: Regex = Regex::new(r"^\s*\|?\s*:?-{3,}:?\s*(?:\|\s*:?-{3,}:?\s*)+\|?\s*$").expect("Invalid Markdown Separator Regex");
+}
+
+/// Shortest table worth unframing: a frame plus a row
+const MIN_TABLE_LINES: usize = 2;
+
+fn is_frame(text: &str) -> bool {
+    RE_FRAME.is_match(text) && text.contains('+')
+}
+
+fn is_table_line(text: &str) -> bool {
+    RE_FRAME.is_match(text) || RE_RULE.is_match(text) || text.contains('|')
+}
+
+/// Blanks the `|` column dividers that stand free (space or line edge on both
+/// sides), so cells stay aligned; a `|` inside a cell's text is kept.
+fn blank_dividers(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let free = |i: usize| chars.get(i).is_none_or(|c| c.is_whitespace());
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| if c == '|' && (i == 0 || free(i - 1)) && free(i + 1) { ' ' } else { c })
+        .collect()
+}
+
+/// Opt-in (`--ascii-borders`): unframes classic ASCII tables from MySQL,
+/// psql, SQLite and the like. `+----+` and psql's `----+----` frames are
+/// dropped, `|` dividers are blanked in place so columns stay aligned, and
+/// `====` rules inside the table go too. A run of lines only counts as a table
+/// when it has a `+` frame, so Markdown tables (whose separator is `|---|`) and
+/// code with pipes are left alone. It's a branch of the search, so the
+/// scorer decides whether the unframed table reads better.
+pub struct AsciiGrid;
+
+impl Stage for AsciiGrid {
+    fn name(&self) -> &'static str {
+        "grid"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let run = lines[i..].iter().take_while(|line| !line.verbatim && is_table_line(&line.text)).count();
+            let table = &lines[i..i + run];
+            let framed = run >= MIN_TABLE_LINES
+                && table.iter().any(|line| is_frame(&line.text))
+                && table.iter().any(|line| !RE_FRAME.is_match(&line.text) && !RE_RULE.is_match(&line.text))
+                && !table.iter().any(|line| RE_MARKDOWN_SEPARATOR.is_match(&line.text));
+            if !framed {
+                output.extend(table.iter().cloned());
+                output.extend(lines.get(i + run).cloned());
+                i += run + 1;
+                continue;
+            }
+
+            let rows: Vec<&Line> =
+                table.iter().filter(|line| !RE_FRAME.is_match(&line.text) && !RE_RULE.is_match(&line.text)).collect();
+            let indent = |text: &str| text.len() - text.trim_start().len();
+            let original = rows.iter().map(|row| indent(&row.text)).min().unwrap_or(0);
+            let rows: Vec<Line> =
+                rows.iter().map(|row| Line::new(blank_dividers(&row.text).trim_end().to_string())).collect();
+            // Blanking the outer `| ` indents every row alike; take that back
+            let added = rows
+                .iter()
+                .filter(|row| !row.text.trim().is_empty())
+                .map(|row| indent(&row.text))
+                .min()
+                .unwrap_or(0)
+                .saturating_sub(original);
+            output.extend(rows.into_iter().map(|mut row| {
+                row.text.drain(..added.min(indent(&row.text)));
+                row
+            }));
+            i += run;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<String> {
+        AsciiGrid.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_unframes_mysql_and_psql() {
+        let mysql = "+----+-------+\n| id | name  |\n+----+-------+\n|  1 | a|b   |\n+----+-------+\n1 row in set";
+        assert_eq!(run(mysql), ["id   name", " 1   a|b", "1 row in set"]);
+
+        let psql = " id | name\n----+------\n  1 | ann\n(1 row)";
+        assert_eq!(run(psql), [" id   name", "  1   ann", "(1 row)"]);
+    }
+
+    #[test]
+    fn test_leaves_markdown_and_pipes_alone() {
+        let markdown = "| id | name |\n|----|------|\n| 1  | ann  |";
+        assert_eq!(run(markdown), markdown.lines().collect::<Vec<_>>());
+        let shell = "cat log | grep error\n---\nls | wc -l";
+        assert_eq!(run(shell), shell.lines().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,126 @@
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Start of an inline image: sixel (DCS … q), iTerm2 (OSC 1337 File=, and
+    // its multipart form) or kitty graphics (APC G)
+    static ref RE_IMAGE_START: Regex = Regex::new(r"\x1bP[0-9;]*q|\x1b\]1337;(?:File|MultipartFile|FilePart|FileEnd)|\x1b_G").expect("Invalid Image Start Regex");
+
+    // End of the image sequence: ST, or BEL for OSC
+    static ref RE_IMAGE_END: Regex = Regex::new(r"\x1b\\|\x07").expect("Invalid Image End Regex");
+}
+
+/// Stands in for a removed image with `images = "placeholder"`
+const PLACEHOLDER: &str = "[image omitted]";
+
+/// What to leave where an inline image was (`images`, `--images`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageMode {
+    /// Nothing
+    #[default]
+    Strip,
+    /// An `[image omitted]` note
+    Placeholder,
+}
+
+/// Removes inline-image payloads (sixel, iTerm2 `OSC 1337 File=`, kitty
+/// graphics), which can be megabytes of encoded pixels in a terminal capture.
+/// Payloads may span lines; lines left blank by the removal are dropped.
+/// Back-to-back sequences (kitty sends big images in chunks) count as one image.
+pub struct ImageStrip {
+    mode: ImageMode,
+}
+
+impl ImageStrip {
+    pub fn new(mode: ImageMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl Stage for ImageStrip {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        // Inside an image sequence that hasn't ended yet
+        let mut inside = false;
+        // An image was the last thing seen, so a chunk right after it is the same image
+        let mut after_image = false;
+
+        let mut output = Vec::with_capacity(lines.len());
+        for mut line in lines {
+            if !inside && !RE_IMAGE_START.is_match(&line.text) {
+                after_image &= line.text.trim().is_empty();
+                output.push(line);
+                continue;
+            }
+
+            let text = &line.text;
+            let mut kept = String::new();
+            let mut at = 0;
+            while at < text.len() {
+                if inside {
+                    match RE_IMAGE_END.find_at(text, at) {
+                        Some(end) => {
+                            inside = false;
+                            at = end.end();
+                        }
+                        None => at = text.len(),
+                    }
+                    continue;
+                }
+                let (before, start) = match RE_IMAGE_START.find_at(text, at) {
+                    Some(start) => (&text[at..start.start()], Some(start)),
+                    None => (&text[at..], None),
+                };
+                after_image &= before.trim().is_empty();
+                kept.push_str(before);
+                let Some(start) = start else {
+                    break;
+                };
+                if self.mode == ImageMode::Placeholder && !after_image {
+                    kept.push_str(PLACEHOLDER);
+                }
+                after_image = true;
+                inside = true;
+                at = start.end();
+            }
+
+            if !kept.trim().is_empty() {
+                line.text = kept;
+                output.push(line);
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mode: ImageMode, input: &str) -> Vec<String> {
+        ImageStrip::new(mode).apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_strips_each_protocol() {
+        let input = "before\n\
+                     \x1bPq#0;2;0;0;0#0!10~-\n\
+                     #1!10@\x1b\\\n\
+                     icon: \x1b]1337;File=name=YS5wbmc=;inline=1:iVBORw0KGgo=\x07 done\n\
+                     \x1b_Ga=T,f=100,m=1;iVBORw0K\x1b\\\x1b_Gm=0;GgoAAAA\x1b\\\n\
+                     after";
+        assert_eq!(run(ImageMode::Strip, input), ["before", "icon:  done", "after"]);
+        assert_eq!(
+            run(ImageMode::Placeholder, input),
+            ["before", "[image omitted]", "icon: [image omitted] done", "[image omitted]", "after"]
+        );
+    }
+}
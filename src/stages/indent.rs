@@ -0,0 +1,169 @@
+use serde::Deserialize;
+
+use crate::pipeline::{Line, Stage};
+
+/// `[indent]`: how the `tabs` and `indent` stages measure tabs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndentSpec {
+    /// Columns between tab stops
+    pub tab_width: usize,
+}
+
+impl Default for IndentSpec {
+    fn default() -> Self {
+        Self { tab_width: 4 }
+    }
+}
+
+/// Replaces each tab in `text` with spaces up to the next multiple of `width`.
+fn expand(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut output = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        if c == '\t' {
+            let pad = width - column % width;
+            output.extend(std::iter::repeat_n(' ', pad));
+            column += pad;
+        } else {
+            output.push(c);
+            column += 1;
+        }
+    }
+    output
+}
+
+/// Opt-in (`--expand-tabs`): expands every tab to spaces at `tab_width` stops,
+/// so indentation no longer depends on how the pane (or the reader) renders tabs.
+/// Verbatim lines keep their tabs.
+pub struct TabExpand {
+    width: usize,
+}
+
+impl TabExpand {
+    pub fn new(spec: IndentSpec) -> Self {
+        Self { width: spec.tab_width }
+    }
+}
+
+impl Stage for TabExpand {
+    fn name(&self) -> &'static str {
+        "tabs"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim && line.text.contains('\t')) {
+            line.text = expand(&line.text, self.width);
+        }
+        lines
+    }
+}
+
+fn is_fence(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with("```") || text.starts_with("~~~")
+}
+
+fn indent_of(text: &str) -> &str {
+    &text[..text.len() - text.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Opt-in (`--normalize-indent`): in a code block whose lines mix tab and space
+/// indentation, rewrites the leading tabs as spaces at `tab_width` stops. Blocks
+/// indented consistently, with either, are left alone. Code blocks are the
+/// fenced ones; text without fences counts as a single block.
+pub struct IndentNormalize {
+    width: usize,
+}
+
+impl IndentNormalize {
+    pub fn new(spec: IndentSpec) -> Self {
+        Self { width: spec.tab_width }
+    }
+
+    fn normalize(&self, block: &mut [Line]) {
+        let indents = || block.iter().map(|line| indent_of(&line.text)).filter(|indent| !indent.is_empty());
+        // Tab indents followed by alignment spaces (`\t * doc`) still count as tabs
+        let tabs = indents().any(|indent| indent.contains('\t'));
+        let spaces = indents().any(|indent| indent.starts_with(' '));
+        if !(tabs && spaces) {
+            return;
+        }
+        for line in block.iter_mut() {
+            let indent = indent_of(&line.text);
+            if indent.contains('\t') {
+                line.text = format!("{}{}", expand(indent, self.width), &line.text[indent.len()..]);
+            }
+        }
+    }
+}
+
+impl Stage for IndentNormalize {
+    fn name(&self) -> &'static str {
+        "indent"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let fences: Vec<usize> = (0..lines.len()).filter(|&i| is_fence(&lines[i].text)).collect();
+        if fences.len() < 2 {
+            self.normalize(&mut lines);
+            return lines;
+        }
+        for pair in fences.chunks_exact(2) {
+            self.normalize(&mut lines[pair[0] + 1..pair[1]]);
+        }
+        lines
+    }
+}
+
+/// Opt-in (`--strip-trailing-whitespace`): trims trailing spaces and tabs from
+/// every line but verbatim ones. The pipeline only trims the end of the whole
+/// text by default.
+pub struct TrailingTrim;
+
+impl Stage for TrailingTrim {
+    fn name(&self) -> &'static str {
+        "trailing"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            line.text.truncate(line.text.trim_end().len());
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_tabs_expand_to_stops() {
+        let stage = TabExpand::new(IndentSpec { tab_width: 4 });
+        let lines = stage.apply(vec![Line::new("\tif x:"), Line::new("ab\tc")]);
+        assert_eq!(texts(lines), ["    if x:", "ab  c"]);
+    }
+
+    #[test]
+    fn test_mixed_indent_only_in_mixed_blocks() {
+        let stage = IndentNormalize::new(IndentSpec { tab_width: 2 });
+        let input = ["```", "def f():", "\tif x:", "    \treturn\t1", "```", "\tkept", "  prose", "```", "\tall tabs", "\t * aligned", "```"];
+        let lines = stage.apply(input.map(Line::new).to_vec());
+        assert_eq!(
+            texts(lines),
+            ["```", "def f():", "  if x:", "      return\t1", "```", "\tkept", "  prose", "```", "\tall tabs", "\t * aligned", "```"]
+        );
+    }
+
+    #[test]
+    fn test_trailing_whitespace_trimmed() {
+        let lines = TrailingTrim.apply(vec![Line::new("a  \t"), Line::new("  b ")]);
+        assert_eq!(texts(lines), ["a", "  b"]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+use crate::pipeline::{Line, Stage};
+use crate::width::{char_width, has_wide_chars};
+
+/// Removes box-drawing fragments left inside content after border stripping:
+/// column dividers between side-by-side panes, inline rules, and lines made
+/// up only of junction pieces (`├──┼──┤`) that the border regexes don't cover.
+pub struct InlineScrub;
+
+impl Stage for InlineScrub {
+    fn name(&self) -> &'static str {
+        "inline"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        // Blanked pieces must keep their cell width or CJK columns drift
+        let cjk = lines.iter().any(|line| has_wide_chars(&line.text));
+        lines
+            .into_iter()
+            .filter_map(|mut line| {
+                if line.verbatim || !line.text.chars().any(is_borderish) {
+                    return Some(line);
+                }
+                if is_mostly_borderish(&line.text) {
+                    return None;
+                }
+                let scrubbed = match scrub_inline_borderish(&line.text, cjk) {
+                    Cow::Owned(scrubbed) => Some(scrubbed),
+                    Cow::Borrowed(_) => None,
+                };
+                match scrubbed {
+                    Some(scrubbed) => line.text = scrubbed,
+                    None => line.text.truncate(line.text.trim_end().len()),
+                }
+                Some(line)
+            })
+            .collect()
+    }
+}
+
+/// Box-drawing characters plus the half/full blocks TUIs use as frame edges.
+pub fn is_borderish(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{257F}' | '▀' | '▄' | '█' | '▌' | '▐' | '｜')
+}
+
+pub fn is_vertical_divider(c: char) -> bool {
+    matches!(c, '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' | '｜')
+}
+
+fn is_horizontal_rule(c: char) -> bool {
+    matches!(c, '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍')
+}
+
+/// True for lines with no letters or digits that are mostly frame pieces,
+/// measured in terminal cells so full-width punctuation weighs what it shows.
+/// One pass: cells are tallied both ways until it's known whether the line is CJK.
+pub fn is_mostly_borderish(text: &str) -> bool {
+    let mut wide = false;
+    // (visible, borderish) cells, measured narrow and CJK-wide
+    let mut narrow = (0, 0);
+    let mut cjk = (0, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_alphanumeric() {
+            return false;
+        }
+        let (width, cjk_width) = (char_width(c, false), char_width(c, true));
+        wide |= width == 2;
+        narrow.0 += width;
+        cjk.0 += cjk_width;
+        if is_borderish(c) {
+            narrow.1 += width;
+            cjk.1 += cjk_width;
+        }
+    }
+    let (visible, borderish) = if wide { cjk } else { narrow };
+    visible > 0 && borderish * 10 >= visible * 6
+}
+
+/// Blanks out free-standing dividers and rules (those with whitespace or the
+/// line edge on both sides), keeping column alignment of the remaining text.
+/// With `cjk`, ambiguous-width pieces are replaced by two spaces each.
+/// Borrows the (end-trimmed) input unless something was actually blanked.
+fn scrub_inline_borderish(text: &str, cjk: bool) -> Cow<'_, str> {
+    let gap_before = |i: usize| text[..i].chars().next_back().is_none_or(char::is_whitespace);
+    let gap_at = |i: usize| text[i..].chars().next().is_none_or(char::is_whitespace);
+
+    // Built only once the first piece is blanked; `copied` is how far into `text` it has got
+    let mut output: Option<String> = None;
+    let mut copied = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let blank = if is_vertical_divider(c) {
+            gap_before(start) && gap_at(end)
+        } else if is_horizontal_rule(c) {
+            let mut run = 1;
+            while let Some((i, c)) = chars.next_if(|&(_, c)| is_horizontal_rule(c)) {
+                end = i + c.len_utf8();
+                run += 1;
+            }
+            run >= 3 && gap_before(start) && gap_at(end)
+        } else {
+            false
+        };
+        if blank {
+            let output = output.get_or_insert_with(|| String::with_capacity(text.len()));
+            output.push_str(&text[copied..start]);
+            let cells = text[start..end].chars().map(|c| char_width(c, cjk)).sum();
+            output.extend(std::iter::repeat_n(' ', cells));
+            copied = end;
+        }
+    }
+
+    match output {
+        None => Cow::Borrowed(text.trim_end()),
+        Some(mut output) => {
+            output.push_str(&text[copied..]);
+            output.truncate(output.trim_end().len());
+            Cow::Owned(output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrubs_pane_dividers() {
+        assert_eq!(
+            scrub_inline_borderish("    Welcome back!    │ No recent activity", false),
+            "    Welcome back!      No recent activity"
+        );
+        assert_eq!(scrub_inline_borderish("             │ ──────────── ", false), "");
+        // Attached box characters and short runs are content, not chrome
+        assert_eq!(scrub_inline_borderish("a─b │x ──", false), "a─b │x ──");
+    }
+
+    #[test]
+    fn test_mostly_borderish_lines() {
+        assert!(is_mostly_borderish("  ├──────┼──────┤"));
+        assert!(!is_mostly_borderish("├── src/main.rs"));
+        assert!(!is_mostly_borderish("   "));
+    }
+
+    /// `cargo test --release -- --ignored --nocapture bench_` (≈330ms → ≈200ms
+    /// once lines stopped being copied into a `Vec<char>` and rebuilt)
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_inline_scrub_million_lines() {
+        let samples = [
+            "    Welcome back!    │ No recent activity",
+            "  fn main() { println!(\"hello\"); }",
+            "             │ ──────────── ",
+            "├── src/main.rs",
+        ];
+        let lines: Vec<Line> = samples.iter().cycle().take(1_000_000).map(|&text| Line::new(text)).collect();
+        let start = std::time::Instant::now();
+        let output = InlineScrub.apply(lines);
+        println!("inline: {} lines in {:?}", output.len(), start.elapsed());
+    }
+
+    #[test]
+    fn test_cjk_columns_keep_alignment() {
+        // Ambiguous-width dividers are two cells in CJK terminals
+        assert_eq!(scrub_inline_borderish("設定 │ 値", true), "設定    値");
+        assert_eq!(scrub_inline_borderish("名前 ｜ 田中", false), "名前    田中");
+        assert!(is_mostly_borderish("──┼── ｜ ──┼──"));
+    }
+}
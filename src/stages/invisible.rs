@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+use crate::pipeline::{Line, Stage};
+
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+const SOFT_HYPHEN: char = '\u{00AD}';
+
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200B}' | ZWNJ | ZWJ | '\u{2060}' | '\u{FEFF}' | '\u{180E}')
+}
+
+/// Embedding, override and isolate controls plus the directional marks
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{200E}' | '\u{200F}' | '\u{061C}')
+}
+
+/// Joiners between two non-ASCII characters shape emoji sequences and
+/// Indic/Persian text, so those are kept.
+fn is_meaningful_joiner(prev: Option<char>, c: char, next: Option<char>) -> bool {
+    let shaping = |c: Option<char>| c.is_some_and(|c| !c.is_ascii() && !c.is_whitespace());
+    matches!(c, ZWJ | ZWNJ) && shaping(prev) && shaping(next)
+}
+
+/// How many invisible characters were removed, by kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct InvisibleCounts {
+    pub zero_width: usize,
+    pub soft_hyphens: usize,
+    pub bidi: usize,
+}
+
+impl InvisibleCounts {
+    pub fn total(&self) -> usize {
+        self.zero_width + self.soft_hyphens + self.bidi
+    }
+
+    /// The characters counted here but not in `kept`, kind by kind.
+    pub fn minus(self, kept: Self) -> Self {
+        Self {
+            zero_width: self.zero_width.saturating_sub(kept.zero_width),
+            soft_hyphens: self.soft_hyphens.saturating_sub(kept.soft_hyphens),
+            bidi: self.bidi.saturating_sub(kept.bidi),
+        }
+    }
+}
+
+/// Removes `text`'s invisible characters, tallying them into `counts`.
+fn sanitize(text: &str, counts: &mut InvisibleCounts) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        if is_bidi_control(c) {
+            counts.bidi += 1;
+        } else if c == SOFT_HYPHEN {
+            counts.soft_hyphens += 1;
+        } else if is_zero_width(c) && !is_meaningful_joiner(prev, c, next) {
+            counts.zero_width += 1;
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Counts the invisible characters the `invisible` stage removes outside verbatim lines.
+pub fn count_invisible(text: &str) -> InvisibleCounts {
+    let mut counts = InvisibleCounts::default();
+    for line in text.lines() {
+        sanitize(line, &mut counts);
+    }
+    counts
+}
+
+/// Strips zero-width characters, soft hyphens and bidi controls. Besides
+/// confusing diffs and searches, hidden bidi overrides can smuggle text into
+/// a prompt that reads differently from what is displayed.
+pub struct InvisibleStrip;
+
+impl Stage for InvisibleStrip {
+    fn name(&self) -> &'static str {
+        "invisible"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let mut counts = InvisibleCounts::default();
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            if line.text.chars().any(|c| is_zero_width(c) || is_bidi_control(c) || c == SOFT_HYPHEN) {
+                line.text = sanitize(&line.text, &mut counts);
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removes_invisible_characters() {
+        let mut counts = InvisibleCounts::default();
+        let text = "\u{FEFF}ad\u{200B}min\u{00AD}istrator \u{202E}gnp.exe\u{202C}";
+        assert_eq!(sanitize(text, &mut counts), "administrator gnp.exe");
+        assert_eq!(counts, InvisibleCounts { zero_width: 2, soft_hyphens: 1, bidi: 2 });
+    }
+
+    #[test]
+    fn test_keeps_emoji_and_script_joiners() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        assert_eq!(count_invisible(family).total(), 0);
+        assert_eq!(count_invisible("می\u{200C}خواهم").total(), 0);
+        assert_eq!(count_invisible("a\u{200D}b").zero_width, 1);
+    }
+}
@@ -0,0 +1,89 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Leading timestamp / level / pid / thread tokens, in any order, each optionally
+    // bracketed and followed by a `-`, `|` or `:` separator
+    static ref RE_LOG_PREFIX: Regex = Regex::new(r"(?x)
+        ^\s*
+        (?:
+            [\[(<]?
+            (?:
+                \d{4}[-/]\d{2}[-/]\d{2}(?:[T\x20]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?
+              | \d{2}:\d{2}:\d{2}(?:[.,]\d+)?
+              | (?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)\x20+\d{1,2}\x20\d{2}:\d{2}:\d{2}
+              | (?i:trace|debug|info|notice|warn|warning|error|fatal|critical)
+              | (?:pid|tid)[=:\x20]?\d+
+              | \d+(?::\d+)?
+              | [\w.-]*[Tt]hread[\w.-]*
+              | main
+            )
+            [\])>]?
+            :?
+            (?:\s+[-|]\s+|\s+|$)
+        )+
+    ").expect("Invalid Log Prefix Regex");
+
+    // A prefix only counts when it has a timestamp or a level in it
+    static ref RE_PREFIX_ANCHOR: Regex = Regex::new(r"(?x)
+        \d{2}:\d{2}:\d{2}
+      | \d{4}[-/]\d{2}[-/]\d{2}
+      | \b(?:TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|FATAL|CRITICAL)\b
+      | (?i:\[(?:trace|debug|info|notice|warn|warning|error|fatal|critical)\])
+    ").expect("Invalid Prefix Anchor Regex");
+}
+
+/// Opt-in (`--strip-log-prefixes`): drops leading timestamps, levels and
+/// pid/thread tags from log lines, keeping the message.
+pub struct LogPrefixStrip;
+
+impl LogPrefixStrip {
+    fn strip(text: &str) -> Option<&str> {
+        let prefix = RE_LOG_PREFIX.find(text)?.as_str();
+        let message = &text[prefix.len()..];
+        (RE_PREFIX_ANCHOR.is_match(prefix) && !message.trim().is_empty()).then_some(message)
+    }
+}
+
+impl Stage for LogPrefixStrip {
+    fn name(&self) -> &'static str {
+        "logprefix"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            if let Some(message) = Self::strip(&line.text) {
+                line.text = message.to_string();
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(text: &str) -> String {
+        LogPrefixStrip.apply(vec![Line::new(text)]).remove(0).text
+    }
+
+    #[test]
+    fn test_strips_common_log_prefixes() {
+        assert_eq!(strip("2024-05-01T12:00:00.123Z  INFO app::db: connected"), "app::db: connected");
+        assert_eq!(strip("2024-05-01 12:00:00,123 [main] WARN  com.foo.Bar - slow query"), "com.foo.Bar - slow query");
+        assert_eq!(strip("2024-05-01 12:00:00,123 - ERROR - boom"), "boom");
+        assert_eq!(strip("[INFO] Building project"), "Building project");
+        assert_eq!(strip("Oct 15 12:00:01 [1234] [worker-thread-2] started"), "started");
+    }
+
+    #[test]
+    fn test_leaves_other_lines_alone() {
+        assert_eq!(strip("    at com.foo.Bar.run(Bar.java:42)"), "    at com.foo.Bar.run(Bar.java:42)");
+        assert_eq!(strip("3 files changed"), "3 files changed");
+        assert_eq!(strip("main: entry point"), "main: entry point");
+        assert_eq!(strip("2024-05-01"), "2024-05-01");
+    }
+}
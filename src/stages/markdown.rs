@@ -0,0 +1,90 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // An opening or closing ``` / ~~~ fence; the marker must be repeated to close it
+    static ref RE_FENCE: Regex = Regex::new(r"^\s{0,3}(`{3,}|~{3,})").expect("Invalid Markdown Fence Regex");
+
+    // `# Heading` through `###### Heading`
+    static ref RE_HEADING: Regex = Regex::new(r"^\s{0,3}#{1,6}(?:\s|$)").expect("Invalid Markdown Heading Regex");
+
+    // `- item`, `* item`, `+ item`, `1. item`, `1) item`, task items included
+    static ref RE_LIST_ITEM: Regex = Regex::new(r"^\s*(?:[-*+]|\d{1,9}[.)])\s+\S").expect("Invalid Markdown List Regex");
+
+    // The `|---|:---:|` row under a table's header
+    static ref RE_TABLE_SEPARATOR: Regex = Regex::new(r"^\s*\|?\s*:?-{3,}:?\s*(?:\|\s*:?-{3,}:?\s*)+\|?\s*$").expect("Invalid Markdown Separator Regex");
+}
+
+/// Opt-in (`--markdown`): marks the Markdown structure of the text verbatim so
+/// the stages after it can't break it. Fenced code is protected through its
+/// closing fence, or to the end when it has none, as Markdown reads it; tables
+/// (a header row over a `|---|` separator, and the rows under it), headings
+/// and list items are protected line by line, so no reflow joins them and
+/// `repeats` doesn't fold identical items. Runs after the border stages, so
+/// fences and tables drawn inside a box are found once it's unwrapped.
+pub struct MarkdownGuard;
+
+impl Stage for MarkdownGuard {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        let mut i = 0;
+        while i < lines.len() {
+            if let Some(fence) = RE_FENCE.captures(&lines[i].text).map(|caps| caps[1].to_string()) {
+                // Closed by a bare run of the same character, at least as long
+                let marker = if fence.starts_with('~') { '~' } else { '`' };
+                let closing = |line: &Line| {
+                    let text = line.text.trim();
+                    text.len() >= fence.len() && text.chars().all(|c| c == marker)
+                };
+                let end = lines[i + 1..].iter().position(closing).map_or(lines.len(), |at| i + 2 + at);
+                for line in &mut lines[i..end] {
+                    line.verbatim = true;
+                }
+                i = end;
+                continue;
+            }
+
+            let separated = lines.get(i + 1).is_some_and(|next| RE_TABLE_SEPARATOR.is_match(&next.text));
+            if lines[i].text.contains('|') && separated {
+                let rows = lines[i + 2..].iter().take_while(|line| line.text.contains('|')).count();
+                for line in &mut lines[i..i + 2 + rows] {
+                    line.verbatim = true;
+                }
+                i += 2 + rows;
+                continue;
+            }
+
+            let line = &mut lines[i];
+            line.verbatim |= RE_HEADING.is_match(&line.text) || RE_LIST_ITEM.is_match(&line.text);
+            i += 1;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protected(input: &str) -> Vec<bool> {
+        MarkdownGuard.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.verbatim).collect()
+    }
+
+    #[test]
+    fn test_protects_structure() {
+        let input = "## Plan\nSome prose\n- one\n- one\n\n| a | b |\n|---|---|\n| 1 | 2 |\nafter";
+        assert_eq!(protected(input), [true, false, true, true, false, true, true, true, false]);
+    }
+
+    #[test]
+    fn test_fences_run_to_their_close() {
+        // A shorter fence inside doesn't close a longer one; an unclosed fence runs to the end
+        let input = "````md\n```\n````\ntext\n~~~\n- not a list\n## nor a heading";
+        assert_eq!(protected(input), [true, true, true, false, true, true, true]);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::pipeline::{Line, Stage};
+
+/// Mojibake can be stacked when text passes through several bad decoders
+const MAX_REPAIR_ROUNDS: usize = 3;
+
+/// Repairs UTF-8 text that was decoded as Windows-1252 somewhere along the way
+/// (classic WSL/PowerShell code page damage, e.g. "â”‚" instead of "│").
+/// Works per line, so correctly decoded lines in the same clipboard are untouched.
+pub struct MojibakeRepair;
+
+impl Stage for MojibakeRepair {
+    fn name(&self) -> &'static str {
+        "mojibake"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in &mut lines {
+            for _ in 0..MAX_REPAIR_ROUNDS {
+                match repair_line(&line.text) {
+                    Some(repaired) => line.text = repaired,
+                    None => break,
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Re-encodes the line as Windows-1252 and decodes it as UTF-8. Returns `None`
+/// unless every character maps back to a byte and the bytes form valid UTF-8,
+/// which legitimate Latin-1 or Unicode text essentially never does.
+pub fn repair_line(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    let bytes = text.chars().map(to_cp1252_byte).collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok().filter(|repaired| repaired != text)
+}
+
+/// Windows-1252 for 0x80..=0x9F; the five undefined bytes stay C1 controls
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•',
+    '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Decodes a Windows-1252 byte, e.g. an RTF `\'hh` escape.
+pub fn from_cp1252_byte(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+/// Inverse of the Windows-1252 decoder (undefined bytes decode to C1 controls).
+fn to_cp1252_byte(c: char) -> Option<u8> {
+    let byte = match c {
+        '\u{0000}'..='\u{00FF}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => return None,
+    };
+    Some(byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_cp1252_mojibake() {
+        assert_eq!(repair_line("â”‚ cafÃ© â”‚").as_deref(), Some("│ café │"));
+        assert_eq!(repair_line("â€œquotedâ€\u{9d}").as_deref(), Some("“quoted”"));
+    }
+
+    #[test]
+    fn test_leaves_legitimate_text_alone() {
+        assert_eq!(repair_line("plain ascii"), None);
+        assert_eq!(repair_line("café"), None, "Latin-1 text is not valid UTF-8 once re-encoded");
+        assert_eq!(repair_line("│ box │"), None, "Box drawing has no Windows-1252 byte");
+    }
+}
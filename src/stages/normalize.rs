@@ -0,0 +1,120 @@
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::pipeline::{Line, Stage};
+
+/// Opt-in (`--nfc`): composes text to Unicode NFC, so `e` + combining acute
+/// and `é` compare (and tokenize) the same.
+pub struct NfcNormalize;
+
+impl Stage for NfcNormalize {
+    fn name(&self) -> &'static str {
+        "nfc"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            if !is_nfc(&line.text) {
+                line.text = line.text.nfc().collect();
+            }
+        }
+        lines
+    }
+}
+
+/// Plain ASCII stand-in for a confusable character, if it has one.
+fn fold_char(c: char) -> Option<char> {
+    match c {
+        // Full-width ASCII variants (！ through ～) sit at a fixed offset
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0),
+        '\u{3000}' | '\u{00A0}' | '\u{2007}' | '\u{202F}' | '\u{2000}'..='\u{200A}' => Some(' '),
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => Some('\''),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => Some('"'),
+        _ => None,
+    }
+}
+
+/// Opt-in (`--fold-confusables`): replaces full-width ASCII, curly quotes and
+/// non-breaking/typographic spaces with their plain ASCII equivalents, which
+/// CJK-locale terminals and rich-text sources mix into code.
+pub struct ConfusableFold;
+
+impl Stage for ConfusableFold {
+    fn name(&self) -> &'static str {
+        "fold"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            if line.text.chars().any(|c| fold_char(c).is_some()) {
+                line.text = line.text.chars().map(|c| fold_char(c).unwrap_or(c)).collect();
+            }
+        }
+        lines
+    }
+}
+
+/// ASCII spelling of typographic punctuation, if it has one.
+fn ascii_punctuation(c: char) -> Option<&'static str> {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some("'"),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some("\""),
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2212}' => Some("-"),
+        '\u{2014}' | '\u{2015}' => Some("--"),
+        '\u{2026}' => Some("..."),
+        '\u{00A0}' | '\u{202F}' | '\u{2007}' => Some(" "),
+        _ => None,
+    }
+}
+
+/// Opt-in (`--ascii-punctuation`): spells smart quotes, dashes, ellipses and
+/// non-breaking spaces in ASCII, since LLM output copied from a terminal is full
+/// of them and they break shell commands and source code. Verbatim lines are
+/// left as they are.
+pub struct AsciiPunctuation;
+
+impl Stage for AsciiPunctuation {
+    fn name(&self) -> &'static str {
+        "punctuation"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        for line in lines.iter_mut().filter(|line| !line.verbatim) {
+            if line.text.chars().any(|c| ascii_punctuation(c).is_some()) {
+                let mut text = String::with_capacity(line.text.len());
+                for c in line.text.chars() {
+                    match ascii_punctuation(c) {
+                        Some(ascii) => text.push_str(ascii),
+                        None => text.push(c),
+                    }
+                }
+                line.text = text;
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfc_composes() {
+        let lines = NfcNormalize.apply(vec![Line::new("cafe\u{0301}")]);
+        assert_eq!(lines[0].text, "café");
+    }
+
+    #[test]
+    fn test_folds_confusables() {
+        let lines = ConfusableFold.apply(vec![Line::new("ｐｒｉｎｔ（“ｈｉ”）；\u{00A0}‘x’　日本語")]);
+        assert_eq!(lines[0].text, "print(\"hi\"); 'x' 日本語");
+    }
+
+    #[test]
+    fn test_ascii_punctuation() {
+        let fenced = Line { text: "“as pasted”".to_string(), verbatim: true };
+        let lines = AsciiPunctuation.apply(vec![Line::new("echo “it’s ok” – really—done…\u{00A0}ｘ"), fenced]);
+        assert_eq!(lines[0].text, "echo \"it's ok\" - really--done... ｘ");
+        assert_eq!(lines[1].text, "“as pasted”");
+    }
+}
@@ -0,0 +1,85 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Status and prompt lines from more/less/man (reverse video is lost on copy)
+    static ref RE_PAGER_STATUS: Regex = Regex::new(r"(?x)
+        ^\s*(?:
+            --More--(?:\(\d+%\))?
+          | \(END\)
+          | \.\.\.skipping\.\.\.
+          | (?:HELP\x20--\x20)?Press\x20RETURN\x20for\x20more,.*
+          | .*\(press\x20RETURN\)
+          | Manual\x20page\x20.*\x20line\x20\d+.*
+          | (?:.*\s)?lines\x20\d+-\d+(?:/\d+)?
+                (?:\s+\(?byte\x20\d+(?:/\d+)?\)?)?
+                (?:\s+\d+%)?
+                (?:\s+\(END\))?
+                (?:\s+\(press\x20h\x20for\x20help\x20or\x20q\x20to\x20quit\))?
+          | byte\x20\d+(?:/\d+)?(?:\s+\d+%)?
+        )\s*$
+    ").expect("Invalid Pager Status Regex");
+
+    // man's overstrike formatting: "N\bN" (bold) and "_\bN" (underline)
+    static ref RE_OVERSTRIKE: Regex = Regex::new(r"[^\x08]\x08").expect("Invalid Overstrike Regex");
+}
+
+/// Removes pager chrome copied out of more, less and man: status lines,
+/// the bare `:` prompt and `~` filler at the end, and overstrike formatting.
+pub struct PagerStrip;
+
+impl Stage for PagerStrip {
+    fn name(&self) -> &'static str {
+        "pager"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output: Vec<Line> = lines
+            .into_iter()
+            .filter(|line| line.verbatim || !RE_PAGER_STATUS.is_match(&line.text))
+            .map(|mut line| {
+                if !line.verbatim && line.text.contains('\x08') {
+                    line.text = RE_OVERSTRIKE.replace_all(&line.text, "").into_owned();
+                }
+                line
+            })
+            .collect();
+
+        // The prompt and past-EOF filler only ever sit at the bottom of the screen
+        while let Some(last) = output.last() {
+            let text = last.text.trim();
+            let prompt = text == ":" || text == "~" || text.is_empty();
+            if last.verbatim || !prompt {
+                break;
+            }
+            output.pop();
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(input: &str) -> Vec<String> {
+        let lines = input.lines().map(Line::new).collect();
+        PagerStrip.apply(lines).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_strips_pager_status_lines() {
+        assert_eq!(strip("alpha\n--More--(42%)\nbeta\n(END)"), vec!["alpha", "beta"]);
+        assert_eq!(strip("alpha\nlines 1-24/240 (byte 512/8192) 10%\n:"), vec!["alpha"]);
+        assert_eq!(strip("NAME\n Manual page ls(1) line 1 (press h for help or q to quit)"), vec!["NAME"]);
+        assert_eq!(strip("end\n~\n~\n(END)"), vec!["end"]);
+    }
+
+    #[test]
+    fn test_keeps_lookalike_content() {
+        assert_eq!(strip("key:\n: value\nread 3 lines"), vec!["key:", ": value", "read 3 lines"]);
+        assert_eq!(strip("N\x08NA\x08AM\x08ME\x08E"), vec!["NAME"]);
+    }
+}
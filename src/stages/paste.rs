@@ -0,0 +1,83 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Bracketed-paste guards: the terminal wraps pasted text in ESC[200~ … ESC[201~
+    static ref RE_PASTE_MARKER: Regex = Regex::new(r"\x1b\[20([01])~").expect("Invalid Paste Marker Regex");
+}
+
+/// Removes bracketed-paste guards (`ESC[200~` / `ESC[201~`) and protects the
+/// text between them from the stages after it: it was pasted by the user, so
+/// a `|` or `│` in it is content, not TUI chrome. Lines that also hold text
+/// from outside the paste (a prompt before it) are only unguarded.
+pub struct BracketedPaste;
+
+impl Stage for BracketedPaste {
+    fn name(&self) -> &'static str {
+        "paste"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        if !lines.iter().any(|line| RE_PASTE_MARKER.is_match(&line.text)) {
+            return lines;
+        }
+
+        let mut pasting = false;
+        lines
+            .into_iter()
+            .map(|mut line| {
+                if !RE_PASTE_MARKER.is_match(&line.text) {
+                    line.verbatim |= pasting;
+                    return line;
+                }
+                let mut text = String::with_capacity(line.text.len());
+                // Whether any visible text on the line came from outside the paste
+                let mut outside = false;
+                let mut start = 0;
+                for caps in RE_PASTE_MARKER.captures_iter(&line.text) {
+                    let marker = caps.get(0).expect("match");
+                    let piece = &line.text[start..marker.start()];
+                    outside |= !pasting && !piece.trim().is_empty();
+                    text.push_str(piece);
+                    pasting = &caps[1] == "0";
+                    start = marker.end();
+                }
+                let rest = &line.text[start..];
+                outside |= !pasting && !rest.trim().is_empty();
+                text.push_str(rest);
+                line.text = text;
+                line.verbatim |= !outside;
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<(String, bool)> {
+        BracketedPaste.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| (line.text, line.verbatim)).collect()
+    }
+
+    #[test]
+    fn test_pasted_lines_are_protected() {
+        let input = "│ > \x1b[200~| id | name |\n| 1  | ann  |\x1b[201~\n│ ok │";
+        assert_eq!(
+            run(input),
+            [
+                ("│ > | id | name |".to_string(), false),
+                ("| 1  | ann  |".to_string(), true),
+                ("│ ok │".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_without_markers_is_untouched() {
+        assert_eq!(run("│ box │"), [("│ box │".to_string(), false)]);
+    }
+}
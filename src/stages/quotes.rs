@@ -0,0 +1,110 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // `> `, `>> ` or `> > ` quote markers; one space after each is part of the marker
+    static ref RE_QUOTE_MARKER: Regex = Regex::new(r"^\s*((?:>[ \t]?)+)").expect("Invalid Quote Marker Regex");
+
+    // Lines that start their own block and never join the line above
+    static ref RE_BLOCK_START: Regex = Regex::new(r"^(?:[-*+]\s|\d+[.)]\s|#{1,6}\s|```|~~~|\|)").expect("Invalid Block Start Regex");
+}
+
+/// Opt-in (`--strip-quotes`): removes `>` quote markers (nested ones too) from
+/// text copied out of mail clients and chat TUIs, and re-flows each quoted
+/// paragraph, hard-wrapped at the client's width, back onto one line. List
+/// items, headings, indented lines and fenced code keep their own lines, and a
+/// change of quote depth starts a new paragraph.
+pub struct QuoteStrip;
+
+impl Stage for QuoteStrip {
+    fn name(&self) -> &'static str {
+        "quotes"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output: Vec<Line> = Vec::with_capacity(lines.len());
+        // Quote depth of the paragraph the last output line belongs to, while it can grow
+        let mut paragraph: Option<usize> = None;
+        let mut in_fence = false;
+
+        for mut line in lines {
+            let marker = RE_QUOTE_MARKER.captures(&line.text).filter(|_| !line.verbatim).and_then(|caps| caps.get(1));
+            let Some(marker) = marker else {
+                paragraph = None;
+                output.push(line);
+                continue;
+            };
+            let depth = marker.as_str().matches('>').count();
+            let content = line.text[marker.end()..].trim_end().to_string();
+
+            let fence = content.starts_with("```") || content.starts_with("~~~");
+            let joins = !in_fence
+                && !fence
+                && paragraph == Some(depth)
+                && !content.is_empty()
+                && !content.starts_with(char::is_whitespace)
+                && !RE_BLOCK_START.is_match(&content);
+            if fence {
+                in_fence = !in_fence;
+            }
+
+            let blank = match output.last_mut() {
+                Some(last) if joins => {
+                    last.text.push(' ');
+                    last.text.push_str(&content);
+                    false
+                }
+                _ => {
+                    let blank = content.is_empty();
+                    line.text = content;
+                    output.push(line);
+                    blank
+                }
+            };
+            paragraph = (!in_fence && !fence && !blank).then_some(depth);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<String> {
+        QuoteStrip.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_strips_and_reflows_quotes() {
+        let input = "On Monday, Bob wrote:\n\
+                     >> Can we ship\n\
+                     >> on Friday?\n\
+                     > Yes, if the tests\n\
+                     > pass.\n\
+                     >\n\
+                     > - fix the parser\n\
+                     > - update docs\n\
+                     Thanks";
+        assert_eq!(
+            run(input),
+            [
+                "On Monday, Bob wrote:",
+                "Can we ship on Friday?",
+                "Yes, if the tests pass.",
+                "",
+                "- fix the parser",
+                "- update docs",
+                "Thanks"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keeps_quoted_code_lines() {
+        let input = "> ```\n> let a = 1;\n> let b = 2;\n> ```\n>     indented\n>     code";
+        assert_eq!(run(input), ["```", "let a = 1;", "let b = 2;", "```", "    indented", "    code"]);
+    }
+}
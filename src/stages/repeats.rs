@@ -0,0 +1,88 @@
+use crate::pipeline::{Line, Stage};
+
+/// Shortest run worth collapsing
+const MIN_RUN: usize = 3;
+/// Lines shorter than this (trimmed) are never collapsed, e.g. closing braces
+const MIN_LINE_CHARS: usize = 4;
+/// Maximum edit distance, as a fraction of the longer line, for "near-identical"
+const MAX_DISTANCE_RATIO: f64 = 0.2;
+
+/// Opt-in (`--collapse-repeats`): folds runs of identical or near-identical
+/// consecutive lines (retry loops, polling output) into the first line plus
+/// a `[repeated N times]` note.
+pub struct RepeatCollapse;
+
+impl Stage for RepeatCollapse {
+    fn name(&self) -> &'static str {
+        "repeats"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output: Vec<Line> = Vec::with_capacity(lines.len());
+        let mut lines = lines.into_iter().peekable();
+
+        while let Some(first) = lines.next() {
+            let mut run = vec![first];
+            let head = &run[0];
+            if !head.verbatim && head.text.trim().chars().count() >= MIN_LINE_CHARS {
+                let head = head.text.clone();
+                while let Some(next) = lines.next_if(|next| !next.verbatim && is_repeat(&head, &next.text)) {
+                    run.push(next);
+                }
+            }
+
+            if run.len() >= MIN_RUN {
+                let count = run.len();
+                let mut first = run.swap_remove(0);
+                first.text = format!("{} [repeated {} times]", first.text.trim_end(), count);
+                output.push(first);
+            } else {
+                output.extend(run);
+            }
+        }
+        output
+    }
+}
+
+fn is_repeat(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let a: Vec<char> = a.trim_end().chars().collect();
+    let b: Vec<char> = b.trim_end().chars().collect();
+    let max_distance = (a.len().max(b.len()) as f64 * MAX_DISTANCE_RATIO) as usize;
+    a.len().abs_diff(b.len()) <= max_distance && edit_distance(&a, &b) <= max_distance
+}
+
+/// Levenshtein distance over characters.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collapse(input: &str) -> Vec<String> {
+        let lines = input.lines().map(Line::new).collect();
+        RepeatCollapse.apply(lines).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_collapses_near_identical_runs() {
+        let input = "start\nRetrying (1/9)...\nRetrying (2/9)...\nRetrying (3/9)...\ndone";
+        assert_eq!(collapse(input), vec!["start", "Retrying (1/9)... [repeated 3 times]", "done"]);
+        assert_eq!(collapse("ok ok\nok ok\nfailed"), vec!["ok ok", "ok ok", "failed"], "Pairs stay as they are");
+        assert_eq!(collapse("}\n}\n}"), vec!["}", "}", "}"]);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::config::Pattern;
+use crate::pipeline::{Line, Stage};
+use crate::rules::{Rule, RuleSet, Verdict};
+
+/// Runs user-defined `[[rules]]` over each line. Lines kept or unwrapped by a
+/// rule are marked verbatim so later heuristics leave them alone. Rules with
+/// `until` keep dropping (or keeping) lines through the closing match.
+pub struct RuleFilter {
+    rules: RuleSet,
+}
+
+impl RuleFilter {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self { rules: RuleSet::new(rules) }
+    }
+}
+
+impl Stage for RuleFilter {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        if self.rules.is_empty() {
+            return lines;
+        }
+
+        // Open region: whether it keeps its lines, and the pattern that closes it
+        let mut region: Option<(bool, &Pattern)> = None;
+
+        lines
+            .into_iter()
+            .filter_map(|line| {
+                if let Some((keep, until)) = region {
+                    if until.0.is_match(&line.text) {
+                        region = None;
+                    }
+                    return keep.then_some(Line { verbatim: true, ..line });
+                }
+                if line.verbatim {
+                    return Some(line);
+                }
+
+                let (verdict, until) = self.rules.apply(&line.text);
+                match verdict {
+                    Verdict::Drop => {
+                        region = until.map(|until| (false, until));
+                        None
+                    }
+                    Verdict::Keep(text) => {
+                        region = until.map(|until| (true, until));
+                        Some(Line { text: text.into_owned(), verbatim: true })
+                    }
+                    Verdict::Continue(text) => Some(Line { text: text.into_owned(), verbatim: false }),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_until_regions() {
+        let rules = Config::parse(r#"
+            [[rules]]
+            match = '^BEGIN'
+            action = "drop"
+            until = '^END'
+
+            [[rules]]
+            match = '^```'
+            action = "keep"
+            until = '^```'
+        "#).unwrap().rules;
+
+        let lines = ["a", "BEGIN", "x", "END", "```", "│ y │", "```", "b"].map(Line::new).to_vec();
+        let output = RuleFilter::new(rules).apply(lines);
+        let texts: Vec<&str> = output.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "```", "│ y │", "```", "b"]);
+        assert!(output[2].verbatim, "Lines inside a keep region should be protected");
+    }
+}
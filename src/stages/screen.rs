@@ -0,0 +1,106 @@
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Switch to (`h`) or back from (`l`) the alternate screen; 1049 is what
+    // current terminals use, 1047 and 47 are the older forms
+    static ref RE_ALT_SCREEN: Regex = Regex::new(r"\x1b\[\?(?:1049|1047|47)([hl])").expect("Invalid Alternate Screen Regex");
+}
+
+/// Which screen's output to keep when a capture switches to the terminal's
+/// alternate screen, where full-screen programs draw (`screen`, `--screen`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Screen {
+    /// Only the shell session around the full-screen program
+    Main,
+    /// Only what the full-screen program drew
+    Alt,
+    /// Everything, in the order it was written
+    #[default]
+    Both,
+}
+
+/// Keeps the main-screen or alternate-screen part of a capture, split at the
+/// `ESC[?1049h` / `ESC[?1049l` switches. Text without any switch passes
+/// through, so `alt` doesn't empty an ordinary copy.
+pub struct ScreenSelect {
+    screen: Screen,
+}
+
+impl ScreenSelect {
+    pub fn new(screen: Screen) -> Self {
+        Self { screen }
+    }
+}
+
+impl Stage for ScreenSelect {
+    fn name(&self) -> &'static str {
+        "screen"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        if self.screen == Screen::Both || !lines.iter().any(|line| RE_ALT_SCREEN.is_match(&line.text)) {
+            return lines;
+        }
+        let keep_alt = self.screen == Screen::Alt;
+
+        let mut alt = false;
+        let mut output = Vec::with_capacity(lines.len());
+        for mut line in lines {
+            if !RE_ALT_SCREEN.is_match(&line.text) {
+                if alt == keep_alt {
+                    output.push(line);
+                }
+                continue;
+            }
+            // Keep the pieces of the line written to the wanted screen
+            let mut kept = String::new();
+            let mut start = 0;
+            for caps in RE_ALT_SCREEN.captures_iter(&line.text) {
+                let switch = caps.get(0).expect("match");
+                if alt == keep_alt {
+                    kept.push_str(&line.text[start..switch.start()]);
+                }
+                alt = &caps[1] == "h";
+                start = switch.end();
+            }
+            if alt == keep_alt {
+                kept.push_str(&line.text[start..]);
+            }
+            // A line holding only the switch was never part of either screen
+            if !kept.trim().is_empty() {
+                line.text = kept;
+                output.push(line);
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPTURE: &str = "$ less notes.md\n\x1b[?1049h\x1b[Hnotes line 1\nnotes line 2\n\x1b[?1049l$ echo done\ndone";
+
+    fn run(screen: Screen, input: &str) -> Vec<String> {
+        ScreenSelect::new(screen).apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_keeps_chosen_screen() {
+        assert_eq!(run(Screen::Main, CAPTURE), ["$ less notes.md", "$ echo done", "done"]);
+        assert_eq!(run(Screen::Alt, CAPTURE), ["\x1b[Hnotes line 1", "notes line 2"]);
+        assert_eq!(run(Screen::Both, CAPTURE), CAPTURE.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_text_without_switches_passes_through() {
+        assert_eq!(run(Screen::Alt, "plain\ntext"), ["plain", "text"]);
+    }
+}
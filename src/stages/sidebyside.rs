@@ -0,0 +1,251 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // A line-number gutter and the content after it: `│ 12 │text` (delta),
+    // `12 text` (difftastic) or a blank gutter on the side without a line
+    static ref RE_GUTTER: Regex = Regex::new(r"^\s*(?:[│┃]\s*)?(\d+)?(?:\s*[│┃]| |$)").expect("Invalid Gutter Regex");
+}
+
+/// Rows a layout needs before it is taken for a side-by-side diff
+const MIN_ROWS: usize = 3;
+
+fn is_bar(c: char) -> bool {
+    matches!(c, '│' | '┃')
+}
+
+/// Where the right-hand gutter starts: at a bar, or (for right-aligned numbers
+/// without one) just after the number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Split {
+    Bar(usize),
+    NumberEnd(usize),
+}
+
+impl Split {
+    /// Places in `chars` where a right-hand gutter could start.
+    fn candidates(chars: &[char]) -> Vec<Split> {
+        let mut splits = Vec::new();
+        for p in 1..chars.len() {
+            if !(chars[p - 1].is_whitespace() || is_bar(chars[p - 1])) {
+                continue;
+            }
+            if is_bar(chars[p]) {
+                let rest: String = chars[p..].iter().collect();
+                if RE_GUTTER.captures(&rest).is_some_and(|caps| caps.get(1).is_some()) {
+                    splits.push(Split::Bar(p));
+                }
+            } else if chars[p].is_ascii_digit() && chars[p - 1].is_whitespace() {
+                let end = p + chars[p..].iter().take_while(|c| c.is_ascii_digit()).count();
+                if chars.get(end).is_none_or(|c| *c == ' ' || is_bar(*c)) {
+                    splits.push(Split::NumberEnd(end));
+                }
+            }
+        }
+        splits
+    }
+
+    /// Splits `chars` into its left and right halves.
+    fn apply(self, chars: &[char]) -> Option<(String, String)> {
+        let at = match self {
+            Split::Bar(at) => {
+                if chars.len() > at && !is_bar(chars[at]) {
+                    return None;
+                }
+                at
+            }
+            Split::NumberEnd(end) => {
+                let digits = chars[..end.min(chars.len())].iter().rev().take_while(|c| c.is_ascii_digit()).count();
+                if digits == 0 {
+                    // No number: the right side must be blank at this column
+                    let start = end.saturating_sub(1).min(chars.len());
+                    if chars[start..].iter().any(|c| !c.is_whitespace()) {
+                        return None;
+                    }
+                    start
+                } else {
+                    end - digits
+                }
+            }
+        };
+        let at = at.min(chars.len());
+        Some((chars[..at].iter().collect(), chars[at..].iter().collect()))
+    }
+}
+
+/// Line number and text of one half of a row; `None` for the side without a line.
+fn parse_side(text: &str) -> Option<Option<(usize, String)>> {
+    let caps = RE_GUTTER.captures(text)?;
+    let content = text[caps.get(0).map_or(0, |m| m.end())..].trim_end();
+    match caps.get(1) {
+        Some(number) => Some(Some((number.as_str().parse().ok()?, content.to_string()))),
+        None if content.is_empty() => Some(None),
+        None => None,
+    }
+}
+
+type Row = (Option<(usize, String)>, Option<(usize, String)>);
+
+fn parse_row(text: &str, split: Split) -> Option<Row> {
+    let chars: Vec<char> = text.chars().collect();
+    let (left, right) = split.apply(&chars)?;
+    let row = (parse_side(&left)?, parse_side(&right)?);
+    (row.0.is_some() || row.1.is_some()).then_some(row)
+}
+
+/// Whether `rows` really look like a diff: line numbers rise on both sides,
+/// and at least one unchanged line appears on both.
+fn is_diff(rows: &[Row]) -> bool {
+    let rising = |side: fn(&Row) -> Option<usize>| {
+        let numbers: Vec<usize> = rows.iter().filter_map(side).collect();
+        numbers.windows(2).all(|pair| pair[0] < pair[1])
+    };
+    rows.len() >= MIN_ROWS
+        && rising(|row| row.0.as_ref().map(|side| side.0))
+        && rising(|row| row.1.as_ref().map(|side| side.0))
+        && rows.iter().any(|row| matches!(row, (Some(left), Some(right)) if left.1 == right.1 && !left.1.is_empty()))
+}
+
+/// Unified diff hunks for `rows`, starting a new hunk wherever the numbering jumps.
+fn unified(rows: &[Row]) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut i = 0;
+    while i < rows.len() {
+        let (mut next_old, mut next_new) = (None::<usize>, None::<usize>);
+        let mut end = i;
+        while end < rows.len() {
+            let (left, right) = &rows[end];
+            let jumps = |side: &Option<(usize, String)>, next: Option<usize>| {
+                matches!((side, next), (Some((number, _)), Some(next)) if *number != next)
+            };
+            if end > i && (jumps(left, next_old) || jumps(right, next_new)) {
+                break;
+            }
+            if let Some((number, _)) = left {
+                next_old = Some(number + 1);
+            }
+            if let Some((number, _)) = right {
+                next_new = Some(number + 1);
+            }
+            end += 1;
+        }
+        let hunk = &rows[i..end];
+        let range = |side: fn(&Row) -> Option<usize>| {
+            let numbers: Vec<usize> = hunk.iter().filter_map(side).collect();
+            match numbers.first() {
+                Some(first) => format!("{},{}", first, numbers.len()),
+                None => "0,0".to_string(),
+            }
+        };
+        output.push(format!(
+            "@@ -{} +{} @@",
+            range(|row| row.0.as_ref().map(|side| side.0)),
+            range(|row| row.1.as_ref().map(|side| side.0))
+        ));
+        let (mut removed, mut added) = (Vec::new(), Vec::new());
+        for (left, right) in hunk {
+            if let (Some(left), Some(right)) = (left, right) {
+                if left.1 == right.1 {
+                    output.append(&mut removed);
+                    output.append(&mut added);
+                    output.push(format!(" {}", left.1));
+                    continue;
+                }
+            }
+            removed.extend(left.iter().map(|side| format!("-{}", side.1)));
+            added.extend(right.iter().map(|side| format!("+{}", side.1)));
+        }
+        output.append(&mut removed);
+        output.append(&mut added);
+        i = end;
+    }
+    output
+}
+
+/// Turns side-by-side diffs (delta's `side-by-side` mode, difftastic) back into
+/// unified diff hunks, instead of letting later stages interleave both columns.
+/// The layout is recognized by a second line-number gutter at the same column
+/// on every row; numbers must rise on both sides and some line must be unchanged.
+/// Runs before `diff`, which then protects the hunks it writes.
+pub struct SideBySide;
+
+impl Stage for SideBySide {
+    fn name(&self) -> &'static str {
+        "sidebyside"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut counts: HashMap<Split, usize> = HashMap::new();
+        for line in lines.iter().filter(|line| !line.verbatim) {
+            let chars: Vec<char> = line.text.chars().collect();
+            for split in Split::candidates(&chars) {
+                *counts.entry(split).or_default() += 1;
+            }
+        }
+        let Some(split) = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= MIN_ROWS)
+            // A bar and the number inside it are found together; split at the bar
+            .max_by_key(|(split, count)| (*count, matches!(split, Split::Bar(_)), *split))
+            .map(|(split, _)| split)
+        else {
+            return lines;
+        };
+
+        let mut output = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let rows: Vec<Row> = lines[i..]
+                .iter()
+                .map_while(|line| (!line.verbatim).then(|| parse_row(&line.text, split)).flatten())
+                .collect();
+            if is_diff(&rows) {
+                output.extend(unified(&rows).into_iter().map(Line::new));
+            } else {
+                // Rescanning from each row of a long non-diff run would be quadratic
+                output.extend(lines[i..i + rows.len().max(1)].iter().cloned());
+            }
+            i += rows.len().max(1);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<String> {
+        SideBySide.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_delta_layout() {
+        let input = "src/main.rs\n\
+                     │ 1  │fn main() {              │ 1  │fn main() {\n\
+                     │ 2  │    let x = 1;           │ 2  │    let x = 2;\n\
+                     │    │                         │ 3  │    let y = x;\n\
+                     │ 3  │}                        │ 4  │}";
+        assert_eq!(
+            run(input),
+            ["src/main.rs", "@@ -1,3 +1,4 @@", " fn main() {", "-    let x = 1;", "+    let x = 2;", "+    let y = x;", " }"]
+        );
+    }
+
+    #[test]
+    fn test_difftastic_layout() {
+        let input = " 9 if ok {                     9 if ok {\n\
+                     10     run();                 10     run(true);\n\
+                     11 }                          11 }";
+        assert_eq!(run(input), ["@@ -9,3 +9,3 @@", " if ok {", "-    run();", "+    run(true);", " }"]);
+    }
+
+    #[test]
+    fn test_ignores_numbered_tables() {
+        let input = "1 apple     4 dates\n2 banana    5 elderberry\n3 cherry    6 fig";
+        assert_eq!(run(input), input.lines().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::pipeline::{Line, Stage};
+
+/// Frames of the common spinner styles: braille dots, ASCII bars, circle
+/// quarters, clocks and moon phases.
+fn is_spinner_glyph(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2800}'..='\u{28FF}'
+            | '|' | '/' | '-' | '\\'
+            | '◐' | '◓' | '◑' | '◒' | '◴' | '◷' | '◶' | '◵' | '◰' | '◳' | '◲' | '◱'
+            | '◜' | '◝' | '◞' | '◟' | '▖' | '▘' | '▝' | '▗'
+            | '✶' | '✸' | '✹' | '✺' | '✻' | '✽' | '✢' | '✳'
+            | '🕐'..='🕧' | '🌑'..='🌘'
+    )
+}
+
+/// The line with its spinner glyph cut out, and the glyph. The glyph must be a
+/// word of its own at the start or end of the line (`⠋ Building…`, `Building… ⠙`).
+fn split_spinner(text: &str) -> Option<(String, char)> {
+    let trimmed = text.trim();
+    let indent = &text[..text.len() - text.trim_start().len()];
+    let first = trimmed.chars().next()?;
+    let after = &trimmed[first.len_utf8()..];
+    if is_spinner_glyph(first) && after.starts_with(char::is_whitespace) {
+        return Some((format!("{}\u{0}{}", indent, after), first));
+    }
+    let last = trimmed.chars().next_back()?;
+    let before = &trimmed[..trimmed.len() - last.len_utf8()];
+    if is_spinner_glyph(last) && before.ends_with(char::is_whitespace) {
+        return Some((format!("{}{}\u{0}", indent, before), last));
+    }
+    None
+}
+
+/// Collapses runs of consecutive lines that differ only in a spinner glyph
+/// (`⠋ Building…`, `⠙ Building…`, …), as captured from logs written without a
+/// TTY, into the last frame. A run needs at least two different glyphs, so a
+/// list of `- item` lines repeated on purpose is left alone.
+pub struct SpinnerDedup;
+
+impl Stage for SpinnerDedup {
+    fn name(&self) -> &'static str {
+        "spinners"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let keys: Vec<Option<(String, char)>> =
+            lines.iter().map(|line| if line.verbatim { None } else { split_spinner(&line.text) }).collect();
+
+        let mut output = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let Some((key, glyph)) = &keys[i] else {
+                output.push(lines[i].clone());
+                i += 1;
+                continue;
+            };
+            let run = keys[i..].iter().take_while(|next| next.as_ref().is_some_and(|(next, _)| next == key)).count();
+            let animated = keys[i..i + run].iter().flatten().any(|(_, next)| next != glyph);
+            if animated {
+                output.push(lines[i + run - 1].clone());
+            } else {
+                output.extend(lines[i..i + run].iter().cloned());
+            }
+            i += run;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> Vec<String> {
+        SpinnerDedup.apply(input.lines().map(Line::new).collect()).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_collapses_spinner_frames() {
+        let input = "start\n⠋ Building…\n⠙ Building…\n⠹ Building…\n  Fetching /\n  Fetching -\n  Fetching \\\ndone";
+        assert_eq!(run(input), ["start", "⠹ Building…", "  Fetching \\", "done"]);
+    }
+
+    #[test]
+    fn test_keeps_unanimated_and_changing_lines() {
+        let input = "- one\n- one\n⠋ Building 1/3\n⠙ Building 2/3\n| a | b |";
+        assert_eq!(run(input), input.lines().collect::<Vec<_>>());
+    }
+}
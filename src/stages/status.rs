@@ -0,0 +1,117 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Pattern;
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // tmux default status bar: `[0] 0:zsh* 1:vim-  "host" 12:34 15-Oct-26`
+    static ref RE_TMUX_STATUS: Regex = Regex::new(r#"(?x)
+        ^\s*\[[^\]]+\]                  # session name
+        (?:\s+\d+:\S+?[-*\#!~MZ]*)+     # window list with flags
+        (?:\s+".*")?                    # pane title / host
+        (?:\s+\d{1,2}:\d{2}(?:\s+\d{1,2}-\w{3}-\d{2,4})?)?  # clock
+        \s*$
+    "#).expect("Invalid Tmux Status Regex");
+
+    // screen caption/hardstatus window list: `0$ bash  1*$ vim  2-$ top`
+    static ref RE_SCREEN_WINDOWS: Regex = Regex::new(r"^(?:\s*\d+[-*!@Z$]*\s+\S+)+\s*$").expect("Invalid Screen Windows Regex");
+    static ref RE_SCREEN_CURRENT: Regex = Regex::new(r"(?:^|\s)\d+[-!@Z$]*\*").expect("Invalid Screen Current Regex");
+
+    // Bracketed hardstatus: `[ host ][ (0*$ bash) 1$ vim ][ 2026-10-15 12:34 ]`
+    static ref RE_SCREEN_HARDSTATUS: Regex = Regex::new(r"^\s*\[[^\]]*\]\s*\[[^\]]*\d+\*[^\]]*\].*\]\s*$").expect("Invalid Screen Hardstatus Regex");
+}
+
+/// Per-profile control over status-line removal (`[status_line]`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatusLineSpec {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Extra patterns for custom status bars
+    #[serde(default)]
+    pub patterns: Vec<Pattern>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for StatusLineSpec {
+    fn default() -> Self {
+        Self { enabled: true, patterns: Vec::new() }
+    }
+}
+
+/// Drops tmux/screen status bars captured with scrollback. Only the first and
+/// last non-blank lines are checked, since that's where the bars are drawn.
+pub struct StatusLineStrip {
+    spec: StatusLineSpec,
+}
+
+impl StatusLineStrip {
+    pub fn new(spec: StatusLineSpec) -> Self {
+        Self { spec }
+    }
+
+    fn is_status_line(&self, text: &str) -> bool {
+        RE_TMUX_STATUS.is_match(text)
+            || (RE_SCREEN_WINDOWS.is_match(text) && RE_SCREEN_CURRENT.is_match(text))
+            || RE_SCREEN_HARDSTATUS.is_match(text)
+            || self.spec.patterns.iter().any(|pattern| pattern.0.is_match(text))
+    }
+}
+
+impl Stage for StatusLineStrip {
+    fn name(&self) -> &'static str {
+        "status"
+    }
+
+    fn apply(&self, mut lines: Vec<Line>) -> Vec<Line> {
+        if !self.spec.enabled {
+            return lines;
+        }
+
+        let is_content = |line: &Line| !line.text.trim().is_empty();
+        if let Some(last) = lines.iter().rposition(is_content) {
+            if !lines[last].verbatim && self.is_status_line(&lines[last].text) {
+                lines.remove(last);
+            }
+        }
+        if let Some(first) = lines.iter().position(is_content) {
+            if !lines[first].verbatim && self.is_status_line(&lines[first].text) {
+                lines.remove(first);
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(spec: StatusLineSpec, input: &str) -> Vec<String> {
+        let lines = input.lines().map(Line::new).collect();
+        StatusLineStrip::new(spec).apply(lines).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_strips_tmux_and_screen_bars() {
+        let spec = StatusLineSpec::default;
+        assert_eq!(strip(spec(), "$ ls\na.txt\n[0] 0:zsh* 1:vim-  \"host\" 12:34 15-Oct-26"), vec!["$ ls", "a.txt"]);
+        assert_eq!(strip(spec(), "0$ bash  1*$ vim  2-$ top\n$ ls"), vec!["$ ls"]);
+        assert_eq!(strip(spec(), "$ ls\n[ host ][ (0*$ bash) 1$ vim ][ 2026-10-15 12:34 ]"), vec!["$ ls"]);
+        assert_eq!(strip(spec(), "1 apple 2 banana\n[note] see above"), vec!["1 apple 2 banana", "[note] see above"]);
+    }
+
+    #[test]
+    fn test_status_lines_configurable() {
+        let disabled = StatusLineSpec { enabled: false, patterns: Vec::new() };
+        assert_eq!(strip(disabled, "x\n[0] 0:zsh*").len(), 2);
+
+        let custom = StatusLineSpec { enabled: true, patterns: vec![Pattern(Regex::new(r"^NORMAL \|").unwrap())] };
+        assert_eq!(strip(custom, "x\nNORMAL | main.rs"), vec!["x"]);
+    }
+}
@@ -0,0 +1,124 @@
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::pipeline::{Line, Stage};
+
+/// A `[[substitutions]]` entry: a regex rewrite applied to the cleaned text.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawSubstitution")]
+pub struct Substitution {
+    pub regex: Regex,
+    pub replacement: String,
+    /// Only apply while this profile is active
+    pub profile: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSubstitution {
+    pattern: String,
+    replacement: String,
+    /// Regex flags: `i` (case-insensitive), `m` (multi-line), `s` (dot matches newline), `x` (verbose)
+    #[serde(default)]
+    flags: String,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+impl TryFrom<RawSubstitution> for Substitution {
+    type Error = String;
+
+    fn try_from(raw: RawSubstitution) -> Result<Self, Self::Error> {
+        let mut builder = RegexBuilder::new(&raw.pattern);
+        for flag in raw.flags.chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                other => return Err(format!("Unknown substitution flag '{}' (expected i, m, s, x)", other)),
+            };
+        }
+        let regex = builder.build().map_err(|e| e.to_string())?;
+        Ok(Self { regex, replacement: raw.replacement, profile: raw.profile })
+    }
+}
+
+/// Applies user substitutions, in order, to the whole cleaned text so patterns
+/// may span lines (e.g. collapsing internal hostnames or rewriting paths).
+pub struct Substitute {
+    substitutions: Vec<Substitution>,
+}
+
+impl Substitute {
+    pub fn new(substitutions: Vec<Substitution>) -> Self {
+        Self { substitutions }
+    }
+}
+
+impl Stage for Substitute {
+    fn name(&self) -> &'static str {
+        "substitute"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        if self.substitutions.is_empty() {
+            return lines;
+        }
+
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        let original = texts.join("\n");
+        let mut text = original.clone();
+        for substitution in &self.substitutions {
+            text = substitution.regex.replace_all(&text, substitution.replacement.as_str()).into_owned();
+        }
+        if text == original {
+            return lines;
+        }
+
+        let rewritten: Vec<&str> = text.lines().collect();
+        if rewritten.len() == lines.len() {
+            // Same shape: keep each line's verbatim flag
+            lines
+                .into_iter()
+                .zip(rewritten)
+                .map(|(line, text)| Line { text: text.to_string(), ..line })
+                .collect()
+        } else {
+            rewritten.into_iter().map(Line::new).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substitution(pattern: &str, replacement: &str, flags: &str) -> Substitution {
+        RawSubstitution {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            flags: flags.to_string(),
+            profile: None,
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_substitutions_apply_in_order_with_flags() {
+        let stage = Substitute::new(vec![
+            substitution(r"\b[\w-]+\.corp\.example\.com\b", "<host>", "i"),
+            substitution(r"/home/\w+", "~", ""),
+        ]);
+        let lines = vec![Line::new("ssh DB01.CORP.EXAMPLE.COM"), Line::new("cd /home/alice/src")];
+        let texts: Vec<String> = stage.apply(lines).into_iter().map(|l| l.text).collect();
+        assert_eq!(texts, vec!["ssh <host>", "cd ~/src"]);
+    }
+
+    #[test]
+    fn test_unknown_flag_rejected() {
+        let raw = RawSubstitution { pattern: "a".into(), replacement: "b".into(), flags: "q".into(), profile: None };
+        assert!(Substitution::try_from(raw).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Pattern;
+use crate::pipeline::{Line, Stage};
+
+lazy_static! {
+    // Markdown code fences, always protected
+    static ref FENCES: Vec<VerbatimRegion> = [r"^\s*```", r"^\s*~~~"]
+        .into_iter()
+        .map(|marker| {
+            let pattern = Pattern(Regex::new(marker).expect("Invalid Fence Regex"));
+            VerbatimRegion { start: pattern.clone(), end: pattern, strip_markers: false, profile: None }
+        })
+        .collect();
+}
+
+/// A `[[verbatim]]` entry: lines from a `start` match through the next `end`
+/// match pass through cleaning untouched.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerbatimRegion {
+    pub start: Pattern,
+    pub end: Pattern,
+    /// Drop the marker lines themselves, keeping only what's between them
+    #[serde(default)]
+    pub strip_markers: bool,
+    /// Only apply while this profile is active
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// Protects ``` / ~~~ fenced blocks and user `[[verbatim]]` regions so that
+/// ASCII diagrams and table literals in code keep their box-drawing characters.
+/// A region without a closing marker is left to the other stages.
+pub struct VerbatimRegions {
+    regions: Vec<VerbatimRegion>,
+}
+
+impl VerbatimRegions {
+    pub fn new(regions: Vec<VerbatimRegion>) -> Self {
+        Self { regions }
+    }
+}
+
+impl Stage for VerbatimRegions {
+    fn name(&self) -> &'static str {
+        "verbatim"
+    }
+
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        let mut output = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let start = &lines[i];
+            let opened = (!start.verbatim)
+                .then(|| self.regions.iter().chain(FENCES.iter()).find(|r| r.start.0.is_match(&start.text)))
+                .flatten();
+            let closed = opened.and_then(|region| {
+                let end = lines[i + 1..].iter().position(|line| region.end.0.is_match(&line.text))?;
+                Some((region, i + 1 + end))
+            });
+            let Some((region, end)) = closed else {
+                output.push(lines[i].clone());
+                i += 1;
+                continue;
+            };
+
+            let inner = if region.strip_markers { i + 1..end } else { i..end + 1 };
+            output.extend(lines[inner].iter().map(|line| Line { text: line.text.clone(), verbatim: true }));
+            i = end + 1;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn protect(stage: &VerbatimRegions, input: &str) -> Vec<(String, bool)> {
+        let lines = input.lines().map(Line::new).collect();
+        stage.apply(lines).into_iter().map(|line| (line.text, line.verbatim)).collect()
+    }
+
+    #[test]
+    fn test_fences_are_protected() {
+        let stage = VerbatimRegions::new(Vec::new());
+        let output = protect(&stage, "a\n```\n┌─┐\n└─┘\n```\n```\nunclosed");
+        let verbatim: Vec<bool> = output.iter().map(|(_, verbatim)| *verbatim).collect();
+        assert_eq!(verbatim, vec![false, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_custom_markers() {
+        let regions = Config::parse(r#"
+            [[verbatim]]
+            start = '^<<<$'
+            end = '^>>>$'
+            strip_markers = true
+        "#).unwrap().verbatim;
+        let output = protect(&VerbatimRegions::new(regions), "<<<\n| a | b |\n>>>\nc");
+        assert_eq!(output, vec![("| a | b |".to_string(), true), ("c".to_string(), false)]);
+    }
+}
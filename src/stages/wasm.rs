@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use crate::pipeline::{Line, Stage};
+
+/// Runs user-provided WASM cleaners (`plugins = [...]` in a profile or the config).
+///
+/// Plugin ABI (core WASM, no imports):
+/// - export `memory`
+/// - export `alloc(len: i32) -> i32` returning a buffer for the input
+/// - export `clean(ptr: i32, len: i32) -> i64` returning `(out_ptr << 32) | out_len`
+///
+/// Input and output are UTF-8. A failing plugin is skipped with a warning.
+pub struct WasmFilter {
+    #[cfg(feature = "wasm-plugins")]
+    plugins: Vec<host::Plugin>,
+}
+
+impl WasmFilter {
+    #[cfg(feature = "wasm-plugins")]
+    pub fn new(paths: &[PathBuf]) -> Self {
+        let plugins = paths
+            .iter()
+            .filter_map(|path| match host::Plugin::load(path) {
+                Ok(plugin) => Some(plugin),
+                Err(e) => {
+                    tracing::warn!("Skipping WASM plugin {}: {:#}", path.display(), e);
+                    None
+                }
+            })
+            .collect();
+        Self { plugins }
+    }
+
+    #[cfg(not(feature = "wasm-plugins"))]
+    pub fn new(paths: &[PathBuf]) -> Self {
+        if !paths.is_empty() {
+            tracing::warn!("reprompt was built without the `wasm-plugins` feature; ignoring {} plugin(s).", paths.len());
+        }
+        Self {}
+    }
+}
+
+impl Stage for WasmFilter {
+    fn name(&self) -> &'static str {
+        "wasm"
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    fn external(&self) -> bool {
+        !self.plugins.is_empty()
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        if self.plugins.is_empty() {
+            return lines;
+        }
+
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        let mut text = texts.join("\n");
+        for plugin in &self.plugins {
+            match plugin.clean(&text) {
+                Ok(cleaned) => text = cleaned,
+                Err(e) => tracing::warn!("WASM plugin {} failed: {:#}", plugin.name(), e),
+            }
+        }
+        text.lines().map(Line::new).collect()
+    }
+
+    #[cfg(not(feature = "wasm-plugins"))]
+    fn apply(&self, lines: Vec<Line>) -> Vec<Line> {
+        lines
+    }
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod host {
+    use sha2::{Digest, Sha256};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::path::Path;
+    use wasmtime::{Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+    use crate::error::{RepromptError, Result};
+    use crate::paths;
+
+    /// Instruction budget per call, so a buggy plugin can't hang the hotkey
+    const FUEL: u64 = 2_000_000_000;
+    /// Linear memory cap per instance
+    const MAX_MEMORY: usize = 256 * 1024 * 1024;
+
+    /// A wasmtime error, with its causes, as a plugin failure.
+    fn failed(e: impl std::fmt::Display) -> RepromptError {
+        RepromptError::Plugin(format!("{:#}", e))
+    }
+
+    fn missing(what: &str) -> RepromptError {
+        RepromptError::Plugin(format!("Plugin does not export `{}`", what))
+    }
+
+    pub struct Plugin {
+        name: String,
+        engine: Engine,
+        module: Module,
+    }
+
+    /// Compiles the module at `path`, reusing the native code cached in
+    /// `plugins/` under the cache directory when neither the module nor this
+    /// engine's settings changed, so a hotkey press doesn't wait on Cranelift.
+    fn compile(engine: &Engine, path: &Path) -> Result<Module> {
+        let bytes = std::fs::read(path).map_err(|e| RepromptError::io("Failed to read", path, e))?;
+        let Ok(cache) = paths::cache_dir() else {
+            return Module::new(engine, &bytes).map_err(failed);
+        };
+        let mut engine_hash = DefaultHasher::new();
+        engine.precompile_compatibility_hash().hash(&mut engine_hash);
+        let digest: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+        let cached = cache.join("plugins").join(format!("{}-{:016x}.cwasm", digest, engine_hash.finish()));
+
+        if cached.exists() {
+            // SAFETY: the file is one we serialized for this module and engine
+            // configuration, in the user's own cache directory
+            match unsafe { Module::deserialize_file(engine, &cached) } {
+                Ok(module) => return Ok(module),
+                Err(e) => tracing::debug!("Ignoring cached plugin {}: {:#}", cached.display(), e),
+            }
+        }
+        let module = Module::new(engine, &bytes).map_err(failed)?;
+        let saved = module.serialize().and_then(|compiled| {
+            if let Some(dir) = cached.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            Ok(std::fs::write(&cached, compiled)?)
+        });
+        if let Err(e) = saved {
+            tracing::debug!("Could not cache compiled plugin {}: {:#}", cached.display(), e);
+        }
+        Ok(module)
+    }
+
+    impl Plugin {
+        pub fn load(path: &Path) -> Result<Self> {
+            let mut config = wasmtime::Config::new();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config).map_err(failed)?;
+            let module = compile(&engine, path)?;
+            let name = path.file_stem().map_or_else(
+                || path.display().to_string(),
+                |stem| stem.to_string_lossy().into_owned(),
+            );
+            Ok(Self { name, engine, module })
+        }
+
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Runs the plugin on `text` in a fresh instance.
+        pub fn clean(&self, text: &str) -> Result<String> {
+            let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build();
+            let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(FUEL).map_err(failed)?;
+
+            let instance = Instance::new(&mut store, &self.module, &[]).map_err(failed)?;
+            let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| missing("memory"))?;
+            let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc").map_err(failed)?;
+            let clean = instance.get_typed_func::<(i32, i32), i64>(&mut store, "clean").map_err(failed)?;
+
+            let len = i32::try_from(text.len()).map_err(|_| failed("Input too large for a WASM plugin"))?;
+            let ptr = alloc.call(&mut store, len).map_err(failed)?;
+            memory.write(&mut store, ptr as u32 as usize, text.as_bytes()).map_err(failed)?;
+
+            let packed = clean.call(&mut store, (ptr, len)).map_err(failed)? as u64;
+            let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xFFFF_FFFF) as usize);
+            let mut output = vec![0u8; out_len];
+            memory.read(&store, out_ptr, &mut output).map_err(failed)?;
+
+            String::from_utf8(output).map_err(|_| failed("Plugin returned invalid UTF-8"))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wasm-plugins"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_plugin_round_trip() {
+        // Drops the first byte of its input
+        let wat = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "clean") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (i32.add (local.get $ptr) (i32.const 1))) (i64.const 32))
+                    (i64.extend_i32_u (i32.sub (local.get $len) (i32.const 1))))))"#;
+        let path = std::env::temp_dir().join(format!("reprompt-plugin-{}.wat", std::process::id()));
+        std::fs::write(&path, wat).unwrap();
+
+        let filter = WasmFilter::new(std::slice::from_ref(&path));
+        let texts: Vec<String> = filter.apply(vec![Line::new("xhello")]).into_iter().map(|l| l.text).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(texts, vec!["hello"]);
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reprompt::paths;
+use reprompt::report::Report;
+
+/// Runs kept for "recent activity"
+const RECENT_RUNS: usize = 20;
+
+/// How a cleaning run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Cleaned text was written to the clipboard
+    Cleaned,
+    Unchanged,
+    /// `--conservative` kept the original
+    HeldBack,
+    /// Looked like binary data and was skipped
+    Binary,
+    /// Over `[input] max_mb` and refused
+    TooLarge,
+    ValidationFailed,
+    /// The write or its readback failed and was rolled back
+    CommitFailed,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Outcome::Cleaned => "cleaned",
+            Outcome::Unchanged => "unchanged",
+            Outcome::HeldBack => "held back",
+            Outcome::Binary => "binary",
+            Outcome::TooLarge => "too large",
+            Outcome::ValidationFailed => "validation failed",
+            Outcome::CommitFailed => "commit failed",
+        })
+    }
+}
+
+/// One run in the recent-activity list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub outcome: Outcome,
+    pub profile: String,
+    pub bytes_in: usize,
+    pub bytes_out: usize,
+}
+
+/// Running totals kept in the state directory. Only sizes and counts are
+/// stored, never clipboard text.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub runs: u64,
+    pub cleanups: u64,
+    pub bytes_saved: u64,
+    pub validation_failures: u64,
+    pub commit_failures: u64,
+    /// Bytes removed per stage, over all cleanups
+    pub removed: BTreeMap<String, u64>,
+    /// Latest runs, oldest first
+    pub recent: VecDeque<Run>,
+}
+
+impl Stats {
+    /// Reads the stats file; a missing or unreadable file starts from zero.
+    pub fn load(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                tracing::warn!("Could not read {}: {}; starting over", path.display(), e);
+                return Self::default();
+            }
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!("Could not parse {}: {}; starting over", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Writes the stats file through a temporary file, so a crash can't leave it half-written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string(self)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// Counts one run over `bytes_in` bytes of input; `report` is `None` when cleaning never started.
+    pub fn add(&mut self, outcome: Outcome, bytes_in: usize, report: Option<&Report>, at: u64) {
+        let bytes_out = match (outcome, report) {
+            (Outcome::Cleaned, Some(report)) => report.output.len(),
+            _ => bytes_in,
+        };
+        self.runs += 1;
+        match outcome {
+            Outcome::Cleaned => {
+                self.cleanups += 1;
+                self.bytes_saved += bytes_in.saturating_sub(bytes_out) as u64;
+                for (stage, bytes) in report.map_or(&[][..], |report| &report.removed) {
+                    *self.removed.entry(stage.to_string()).or_default() += *bytes as u64;
+                }
+            }
+            Outcome::ValidationFailed => self.validation_failures += 1,
+            Outcome::CommitFailed => self.commit_failures += 1,
+            _ => {}
+        }
+        self.recent.push_back(Run {
+            at,
+            outcome,
+            profile: report.map_or_else(String::new, |report| report.profile.clone()),
+            bytes_in,
+            bytes_out,
+        });
+        while self.recent.len() > RECENT_RUNS {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Totals and recent runs for `reprompt stats`, with ages relative to `now`.
+    pub fn render(&self, now: u64) -> String {
+        let mut lines = vec![
+            format!("runs: {} ({} cleaned)", self.runs, self.cleanups),
+            format!("saved: {}", format_bytes(self.bytes_saved)),
+            format!(
+                "failures: {} validation, {} commit",
+                self.validation_failures, self.commit_failures
+            ),
+        ];
+        if !self.removed.is_empty() {
+            let mut removed: Vec<_> = self.removed.iter().collect();
+            removed.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let removed: Vec<String> =
+                removed.iter().map(|(stage, bytes)| format!("{} {}", stage, format_bytes(**bytes))).collect();
+            lines.push(format!("removed: {}", removed.join(", ")));
+        }
+        if !self.recent.is_empty() {
+            lines.push("recent:".to_string());
+            for run in self.recent.iter().rev() {
+                let profile = if run.profile.is_empty() { "-" } else { &run.profile };
+                lines.push(format!(
+                    "  {:>8}  {:<17}  {:<12}  {} → {}",
+                    format_age(now.saturating_sub(run.at)),
+                    run.outcome.to_string(),
+                    profile,
+                    format_bytes(run.bytes_in as u64),
+                    format_bytes(run.bytes_out as u64)
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s ago", seconds),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86_400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Adds one run to the stats file. Failures only warn, since statistics
+/// must never get in the way of cleaning.
+pub fn record(outcome: Outcome, bytes_in: usize, report: Option<&Report>) {
+    let path = match paths::stats_file() {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!("Could not update stats: {}", e);
+            return;
+        }
+    };
+    let mut stats = Stats::load(&path);
+    stats.add(outcome, bytes_in, report, now());
+    if let Err(e) = stats.save(&path) {
+        tracing::warn!("Could not update {}: {:#}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reprompt::cleaner::Cleaner;
+    use reprompt::config::Config;
+
+    #[test]
+    fn test_totals_and_recent_runs() {
+        let cleaner = Cleaner::new(Config::default(), Some("generic"), None, false).unwrap();
+        let input = "╭──────────╮\n│ See this │\n╰──────────╯";
+        let report = cleaner.clean(input);
+
+        let mut stats = Stats::default();
+        stats.add(Outcome::Cleaned, input.len(), Some(&report), 1000);
+        stats.add(Outcome::ValidationFailed, input.len(), Some(&report), 1100);
+        stats.add(Outcome::TooLarge, 30 * 1024 * 1024, None, 1200);
+        assert_eq!((stats.runs, stats.cleanups, stats.validation_failures), (3, 1, 1));
+        assert_eq!(stats.bytes_saved, (input.len() - "See this".len()) as u64);
+        assert!(stats.removed["border"] > 0);
+
+        let path = std::env::temp_dir().join(format!("reprompt-stats-{}.json", std::process::id()));
+        stats.save(&path).unwrap();
+        let loaded = Stats::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.recent, stats.recent);
+
+        let text = loaded.render(1260);
+        assert!(text.starts_with("runs: 3 (1 cleaned)\n"), "{}", text);
+        assert!(text.contains("1m ago  too large"), "{}", text);
+    }
+}
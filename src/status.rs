@@ -0,0 +1,144 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use reprompt::clipboard;
+use reprompt::lock::{self, InstanceLock};
+use reprompt::paths;
+
+use crate::stats::{self, Stats};
+
+/// The watcher's pid if one is running. Taking its lock for a moment is the
+/// only reliable test, since a crashed watcher leaves its lock file behind.
+fn watcher() -> Option<Option<u32>> {
+    match InstanceLock::try_acquire("watch") {
+        Ok(Some(_free)) => None,
+        _ => Some(lock::holder_pid("watch")),
+    }
+}
+
+/// The later of the daemon's last clean and the last run in the stats file.
+fn last_clean(daemon: Option<&Value>) -> Value {
+    let recorded = paths::stats_file().ok().and_then(|path| Stats::load(&path).recent.back().cloned()).map(|run| {
+        json!({ "at": run.at, "result": run.outcome.to_string(), "profile": run.profile })
+    });
+    let served = daemon.map(|daemon| daemon["last_clean"].clone()).filter(|last| !last.is_null());
+    match (served, recorded) {
+        (Some(served), Some(recorded)) if recorded["at"].as_u64() > served["at"].as_u64() => recorded,
+        (Some(served), _) => served,
+        (None, recorded) => recorded.unwrap_or(Value::Null),
+    }
+}
+
+/// Everything `reprompt status` shows, as JSON. The daemon is asked over its
+/// socket; without one, the profile is the configured one.
+fn collect(profile: Option<&str>) -> Value {
+    let daemon: Option<Value> = crate::daemon::request("STATUS").ok().and_then(|reply| serde_json::from_str(&reply).ok());
+    let profile = match &daemon {
+        Some(daemon) => daemon["profile"].clone(),
+        None => profile.map_or(Value::Null, Value::from),
+    };
+    json!({
+        "daemon": daemon.as_ref().map(|daemon| json!({
+            "pid": daemon["pid"],
+            "uptime_secs": daemon["uptime_secs"],
+            "requests": daemon["requests"],
+            "undo": daemon["undo"],
+        })),
+        "watcher": watcher().map(|pid| json!({ "pid": pid })),
+        "backend": clipboard::backend(),
+        "profile": profile,
+        "last_clean": last_clean(daemon.as_ref()),
+        "paused": crate::pause::state().map(|until| json!({ "until": until })),
+    })
+}
+
+fn format_uptime(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m", seconds / 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+/// One `name  value` line per item of `status`, for people.
+fn render(status: &Value, now: u64) -> String {
+    let daemon = match &status["daemon"] {
+        Value::Null => "not running".to_string(),
+        daemon => format!(
+            "running (pid {}, up {}, {} requests)",
+            daemon["pid"],
+            format_uptime(daemon["uptime_secs"].as_u64().unwrap_or(0)),
+            daemon["requests"]
+        ),
+    };
+    let watcher = match &status["watcher"] {
+        Value::Null => "not running".to_string(),
+        watcher => match watcher["pid"].as_u64() {
+            Some(pid) => format!("running (pid {})", pid),
+            None => "running".to_string(),
+        },
+    };
+    let profile = status["profile"].as_str().unwrap_or("detected from the text").to_string();
+    let last = match &status["last_clean"] {
+        Value::Null => "none yet".to_string(),
+        last => {
+            let at = last["at"].as_u64().unwrap_or(now);
+            let result = last["result"].as_str().unwrap_or("?");
+            match last["profile"].as_str() {
+                Some(profile) => format!("{}, {} ({})", stats::format_age(now.saturating_sub(at)), result, profile),
+                None => format!("{}, {}", stats::format_age(now.saturating_sub(at)), result),
+            }
+        }
+    };
+    let paused = match &status["paused"] {
+        Value::Null => "no".to_string(),
+        paused => match paused["until"].as_u64() {
+            Some(until) => format!("yes, {}s more", until.saturating_sub(now)),
+            None => "yes, until `reprompt resume`".to_string(),
+        },
+    };
+    let backend = status["backend"].as_str().unwrap_or("?").to_string();
+    [("daemon", daemon), ("watcher", watcher), ("backend", backend), ("profile", profile), ("last clean", last), ("paused", paused)]
+        .iter()
+        .map(|(name, value)| format!("{:<11} {}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `reprompt status`: whether the daemon and watcher run, and what they're doing.
+pub fn run(profile: Option<&str>, json: bool) -> Result<()> {
+    let status = collect(profile);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        println!("{}", render(&status, stats::now()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_status() {
+        let status = json!({
+            "daemon": { "pid": 42, "uptime_secs": 7500, "requests": 3, "undo": false },
+            "watcher": null,
+            "backend": "X11",
+            "profile": null,
+            "last_clean": { "at": 1000, "result": "cleaned", "profile": "claude-code" },
+            "paused": { "until": 1090 },
+        });
+        let text = render(&status, 1060);
+        assert_eq!(
+            text,
+            "daemon      running (pid 42, up 2h 05m, 3 requests)\n\
+             watcher     not running\n\
+             backend     X11\n\
+             profile     detected from the text\n\
+             last clean  1m ago, cleaned (claude-code)\n\
+             paused      yes, 30s more"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+lazy_static! {
+    // `+----+------+` frames and psql's `----+------` header rule
+    static ref RE_FRAME: Regex = Regex::new(r"^\s*\+?[-=]{2,}(?:\+[-=]{2,})*\+?\s*$").expect("Invalid Table Frame Regex");
+
+    // Row counts and timings printed after a result
+    static ref RE_FOOTER: Regex = Regex::new(r"^\s*(?:\(\d+ rows?\)|\d+ rows? in set\b.*|Empty set\b.*|Time: \d.*)\s*$").expect("Invalid Table Footer Regex");
+}
+
+/// Output format for `--tables`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+    /// An array of objects keyed by the header
+    Json,
+    /// A `| a | b |` table with a `| --- |` separator
+    Markdown,
+}
+
+/// A parsed table: the header row and the data rows, cells trimmed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Cells of a `| a | b |` row (psql rows have no outer pipes).
+fn pipe_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split(" | ").map(|cell| cell.trim().trim_matches('|').trim().to_string()).collect()
+}
+
+/// Columns start where the header has text after two or more spaces (so
+/// `CONTAINER ID` stays one column), and every row must have a space (or
+/// nothing) just before each start.
+fn aligned_starts(header: &str, rows: &[&str]) -> Option<Vec<usize>> {
+    let chars: Vec<char> = header.chars().collect();
+    let starts: Vec<usize> = (0..chars.len())
+        .filter(|&i| !chars[i].is_whitespace() && (i == 0 || (i >= 2 && chars[i - 1] == ' ' && chars[i - 2] == ' ')))
+        .collect();
+    let leading = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let mut starts: Vec<usize> = starts.into_iter().filter(|&i| i >= leading).collect();
+    *starts.first_mut()? = 0;
+    let fits = |row: &&str| {
+        let row: Vec<char> = row.chars().collect();
+        starts[1..].iter().all(|&start| row.get(start - 1).is_none_or(|c| *c == ' '))
+    };
+    (starts.len() >= 2 && rows.iter().all(fits)).then_some(starts)
+}
+
+fn aligned_cells(line: &str, starts: &[usize]) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(chars.len()).min(chars.len());
+            chars[start.min(end)..end].iter().collect::<String>().trim().to_string()
+        })
+        .collect()
+}
+
+/// Parses the first table in `text`: a `|`-separated result (MySQL, psql,
+/// SQLite) or whitespace-aligned columns (`docker ps`, `kubectl get`). The
+/// first row is the header; frames and row-count footers are skipped.
+pub fn parse(text: &str) -> Option<Table> {
+    let block: Vec<&str> = text
+        .lines()
+        .skip_while(|line| line.trim().is_empty() || RE_FRAME.is_match(line))
+        .take_while(|line| !line.trim().is_empty())
+        .filter(|line| !RE_FRAME.is_match(line) && !RE_FOOTER.is_match(line))
+        .collect();
+    let (header, rows) = block.split_first()?;
+    if rows.is_empty() {
+        return None;
+    }
+
+    let (header, rows) = if header.contains(" | ") || header.trim_start().starts_with('|') {
+        let header = pipe_cells(header);
+        let rows: Vec<Vec<String>> = rows.iter().map(|row| pipe_cells(row)).collect();
+        if rows.iter().any(|row| row.len() != header.len()) {
+            return None;
+        }
+        (header, rows)
+    } else {
+        let starts = aligned_starts(header, rows)?;
+        (aligned_cells(header, &starts), rows.iter().map(|row| aligned_cells(row, &starts)).collect())
+    };
+    Some(Table { header, rows })
+}
+
+/// Quotes a CSV field when it holds a separator, quote, line break or edge space.
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) || cell.starts_with(' ') || cell.ends_with(' ') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+impl Table {
+    pub fn render(&self, format: TableFormat) -> String {
+        let rows = std::iter::once(&self.header).chain(&self.rows);
+        match format {
+            TableFormat::Csv => {
+                let lines: Vec<String> =
+                    rows.map(|row| row.iter().map(|cell| csv_field(cell)).collect::<Vec<_>>().join(",")).collect();
+                lines.join("\n")
+            }
+            TableFormat::Tsv => {
+                let lines: Vec<String> = rows
+                    .map(|row| row.iter().map(|cell| cell.replace(['\t', '\n'], " ")).collect::<Vec<_>>().join("\t"))
+                    .collect();
+                lines.join("\n")
+            }
+            TableFormat::Json => {
+                // Built by hand so keys keep the table's column order
+                let string = |text: &str| serde_json::Value::from(text).to_string();
+                let objects: Vec<String> = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let fields: Vec<String> = self
+                            .header
+                            .iter()
+                            .zip(row)
+                            .map(|(key, value)| format!("{}: {}", string(key), string(value)))
+                            .collect();
+                        format!("  {{{}}}", fields.join(", "))
+                    })
+                    .collect();
+                format!("[\n{}\n]", objects.join(",\n"))
+            }
+            TableFormat::Markdown => {
+                let line = |row: &[String]| {
+                    let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    format!("| {} |", cells.join(" | "))
+                };
+                let separator = vec!["---".to_string(); self.header.len()];
+                let lines: Vec<String> = std::iter::once(line(&self.header))
+                    .chain(std::iter::once(line(&separator)))
+                    .chain(self.rows.iter().map(|row| line(row)))
+                    .collect();
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_pipe_tables() {
+        let mysql = "+----+-------------+\n| id | name        |\n+----+-------------+\n|  1 | Smith, Ann  |\n+----+-------------+\n1 row in set (0.00 sec)";
+        let table = parse(mysql).unwrap();
+        assert_eq!(table.render(TableFormat::Csv), "id,name\n1,\"Smith, Ann\"");
+
+        let psql = " id | name\n----+------\n  1 | ann\n  2 | bob\n(2 rows)";
+        assert_eq!(parse(psql).unwrap().render(TableFormat::Tsv), "id\tname\n1\tann\n2\tbob");
+    }
+
+    #[test]
+    fn test_parses_aligned_columns() {
+        let docker = "CONTAINER ID   IMAGE          STATUS         NAMES\n\
+                      4f2a9c1b7e3d   nginx:1.27     Up 2 hours     web\n\
+                      9b8c7d6e5f4a   postgres:16    Exited (0)     db";
+        let table = parse(docker).unwrap();
+        assert_eq!(table.header, ["CONTAINER ID", "IMAGE", "STATUS", "NAMES"]);
+        assert_eq!(
+            table.render(TableFormat::Json),
+            "[\n  {\"CONTAINER ID\": \"4f2a9c1b7e3d\", \"IMAGE\": \"nginx:1.27\", \"STATUS\": \"Up 2 hours\", \"NAMES\": \"web\"},\n  \
+             {\"CONTAINER ID\": \"9b8c7d6e5f4a\", \"IMAGE\": \"postgres:16\", \"STATUS\": \"Exited (0)\", \"NAMES\": \"db\"}\n]"
+        );
+        assert_eq!(parse("Just a sentence.\nAnd another one."), None);
+    }
+}
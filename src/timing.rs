@@ -0,0 +1,75 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Time spent in one part of a clean, for `--profile-stages`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timing {
+    /// A stage name, or `detection`, `scoring`, `clipboard read`, `clipboard write`
+    pub name: &'static str,
+    pub ms: f64,
+}
+
+impl Timing {
+    pub fn new(name: &'static str, elapsed: Duration) -> Self {
+        Self { name, ms: elapsed.as_secs_f64() * 1000.0 }
+    }
+}
+
+/// Adds up time per name while variants are cleaned on several threads, so a
+/// stage's time is its total over every variant, not wall-clock time.
+#[derive(Debug, Default)]
+pub struct Timings(Mutex<Vec<(&'static str, Duration)>>);
+
+impl Timings {
+    pub fn add(&self, name: &'static str, elapsed: Duration) {
+        let mut totals = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match totals.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, total)) => *total += elapsed,
+            None => totals.push((name, elapsed)),
+        }
+    }
+
+    /// Runs `f`, adding the time it took under `name`.
+    pub fn time<T>(&self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let value = f();
+        self.add(name, started.elapsed());
+        value
+    }
+
+    /// The totals, in the order each name was first timed.
+    pub fn into_vec(self) -> Vec<Timing> {
+        let totals = self.0.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        totals.into_iter().map(|(name, elapsed)| Timing::new(name, elapsed)).collect()
+    }
+}
+
+/// `--profile-stages` output: one `name  12.34 ms` line per timing.
+pub fn render(timings: &[Timing]) -> String {
+    let width = timings.iter().map(|timing| timing.name.len()).max().unwrap_or(0);
+    timings
+        .iter()
+        .map(|timing| format!("{:<width$}  {:>9.2} ms", timing.name, timing.ms))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings_add_up_per_name() {
+        let timings = Timings::default();
+        timings.add("ansi", Duration::from_millis(2));
+        timings.add("scoring", Duration::from_millis(1));
+        timings.add("ansi", Duration::from_millis(3));
+        assert_eq!(timings.time("border", || 7), 7);
+        let totals = timings.into_vec();
+        let names: Vec<&str> = totals.iter().map(|timing| timing.name).collect();
+        assert_eq!(names, ["ansi", "scoring", "border"]);
+        assert_eq!(totals[0].ms, 5.0);
+        assert_eq!(render(&totals[..2]), "ansi          5.00 ms\nscoring       1.00 ms");
+    }
+}
@@ -0,0 +1,117 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref RE_URL: Regex = Regex::new(r#"(?i)\b(?:https?://|ftp://|www\.)[^\s<>"'`]+"#).expect("Invalid URL Regex");
+
+    // OSC 8 hyperlink opener: `ESC ] 8 ; params ; URI` ended by BEL or ST
+    static ref RE_OSC8: Regex = Regex::new(r"\x1b\]8;[^;\x07\x1b]*;([^\x07\x1b]+)(?:\x07|\x1b\\)").expect("Invalid OSC 8 Regex");
+
+    // Start of a line that could be the rest of a wrapped URL
+    static ref RE_CONTINUATION: Regex = Regex::new(r#"^[^\s<>"'`]+"#).expect("Invalid URL Continuation Regex");
+}
+
+/// Lines shorter than this are never treated as wrapped
+const MIN_WRAP_WIDTH: usize = 40;
+
+/// Drops sentence punctuation after a URL, and closing brackets it didn't open.
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let unbalanced = |open: char| url.matches(open).count() < url.matches(last).count();
+        let drop = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' | '*' => true,
+            ')' => unbalanced('('),
+            ']' => unbalanced('['),
+            '}' => unbalanced('{'),
+            _ => false,
+        };
+        if !drop {
+            return url;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+}
+
+/// Targets of OSC 8 hyperlinks in raw terminal text, in order.
+pub fn hyperlinks(raw: &str) -> Vec<String> {
+    RE_OSC8.captures_iter(raw).map(|caps| caps[1].to_string()).collect()
+}
+
+/// URLs in `text`, in order. A URL that runs to the end of a line as wide as
+/// the widest one continues onto the next line when that starts with URL
+/// characters, since the pane probably wrapped it.
+pub fn find(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let mut urls = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let mut next = i + 1;
+        for found in RE_URL.find_iter(line) {
+            let mut url = found.as_str().to_string();
+            if found.end() == line.len() {
+                let wrapped = |line: &str| line.chars().count() >= width.max(MIN_WRAP_WIDTH);
+                let mut at = i;
+                while wrapped(lines[at]) && at + 1 < lines.len() {
+                    let Some(rest) = RE_CONTINUATION.find(lines[at + 1]) else {
+                        break;
+                    };
+                    url.push_str(rest.as_str());
+                    at += 1;
+                    // The next line only held the rest of this URL
+                    if rest.end() == lines[at].len() {
+                        next = at + 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            urls.push(trim_url(&url).to_string());
+        }
+        i = next;
+    }
+    urls
+}
+
+/// URLs in the cleaned text followed by hyperlink targets from the raw text
+/// that it doesn't show, without duplicates.
+pub fn collect(raw: &str, cleaned: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for url in find(cleaned).into_iter().chain(hyperlinks(raw)) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_and_trims_urls() {
+        let text = "See https://example.com/docs. Also (https://en.wikipedia.org/wiki/Rust_(language)), www.rust-lang.org!";
+        assert_eq!(
+            find(text),
+            ["https://example.com/docs", "https://en.wikipedia.org/wiki/Rust_(language)", "www.rust-lang.org"]
+        );
+    }
+
+    #[test]
+    fn test_rejoins_wrapped_urls() {
+        let text = "Open https://github.com/ain3sh/reprompt/blob/main/sr\nc/main.rs to see it\nshort https://a.io/x\nnext";
+        assert_eq!(find(text), ["https://github.com/ain3sh/reprompt/blob/main/src/main.rs", "https://a.io/x"]);
+    }
+
+    #[test]
+    fn test_hyperlink_targets() {
+        let raw = "\x1b]8;;https://example.com/a\x1b\\docs\x1b]8;;\x1b\\ and \x1b]8;id=1;https://example.com/b\x07b\x1b]8;;\x07";
+        assert_eq!(collect(raw, "docs and b https://example.com/b"), ["https://example.com/b", "https://example.com/a"]);
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Deserialize;
+
+/// What happens when a validation check fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Keep the original clipboard
+    Abort,
+    /// Log a warning and commit anyway
+    Warn,
+    /// Ask on the terminal; abort when there is none (e.g. `watch`)
+    Prompt,
+    /// Skip the check
+    Off,
+}
+
+/// `[validation]`: checks run on the cleaned text before it is committed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// U+FFFD in the output, a sign of encoding corruption
+    pub replacement_char: Action,
+    /// Output is empty though the input had more than 10 characters
+    pub emptied: Action,
+    /// Output is over `reduction_percent` smaller than a 200+ byte input
+    pub reduction: Action,
+    pub reduction_percent: u8,
+    /// Fewer than `min_lines_percent` of a 10+ line input's lines are left
+    pub line_drop: Action,
+    pub min_lines_percent: u8,
+    /// Code fences that were balanced no longer are
+    pub fences: Action,
+    /// When the full result is rejected, try ANSI-strip only, then mojibake-repair only
+    pub partial: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            replacement_char: Action::Abort,
+            emptied: Action::Abort,
+            reduction: Action::Warn,
+            reduction_percent: 90,
+            line_drop: Action::Warn,
+            min_lines_percent: 10,
+            fences: Action::Warn,
+            partial: true,
+        }
+    }
+}
+
+/// A failed check and what the policy says to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub check: &'static str,
+    pub message: String,
+    pub action: Action,
+}
+
+/// Whether every ``` or ~~~ fence has a partner. `None` without any fences.
+fn fences_balanced(text: &str) -> Option<bool> {
+    let count = text
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| line.starts_with("```") || line.starts_with("~~~"))
+        .count();
+    (count > 0).then_some(count % 2 == 0)
+}
+
+impl Policy {
+    /// Runs every check that isn't `off` on `cleaned`, returning the ones that failed.
+    pub fn check(&self, original: &str, cleaned: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut fail = |check: &'static str, action: Action, message: String| {
+            if action != Action::Off {
+                violations.push(Violation { check, message, action });
+            }
+        };
+
+        if cleaned.contains('\u{FFFD}') {
+            fail("replacement_char", self.replacement_char, "Unicode replacement character (U+FFFD) detected".to_string());
+        }
+        if original.trim().len() > 10 && cleaned.trim().is_empty() {
+            fail("emptied", self.emptied, "Cleaning removed all content (likely false positive)".to_string());
+        }
+        let kept = 100 - self.reduction_percent.min(100) as usize;
+        if original.len() > 200 && cleaned.len() * 100 < original.len() * kept {
+            fail(
+                "reduction",
+                self.reduction,
+                format!(
+                    "Cleaning reduced content by >{}% ({} -> {} bytes); this might indicate over-aggressive cleaning",
+                    self.reduction_percent,
+                    original.len(),
+                    cleaned.len()
+                ),
+            );
+        }
+        let (lines_in, lines_out) = (original.lines().count(), cleaned.lines().count());
+        if lines_in >= 10 && lines_out * 100 < lines_in * self.min_lines_percent as usize {
+            fail(
+                "line_drop",
+                self.line_drop,
+                format!("Only {} of {} lines are left (under {}%)", lines_out, lines_in, self.min_lines_percent),
+            );
+        }
+        if fences_balanced(original) == Some(true) && fences_balanced(cleaned) == Some(false) {
+            fail("fences", self.fences, "Code fences were balanced before cleaning but aren't after".to_string());
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_and_actions() {
+        let policy = Policy::default();
+        assert!(policy.check("hello world, again", "hello world").is_empty());
+
+        let violations = policy.check("some substantial text", "  ");
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].check, violations[0].action), ("emptied", Action::Abort));
+
+        let original = "```rust\nfn main() {}\n```\n";
+        let violations = policy.check(original, "```rust\nfn main() {}\n");
+        assert_eq!((violations[0].check, violations[0].action), ("fences", Action::Warn));
+
+        let original = "line\n".repeat(20);
+        let checks: Vec<_> = policy.check(&original, "line").iter().map(|v| v.check).collect();
+        assert_eq!(checks, ["line_drop"]);
+
+        let policy = Policy { replacement_char: Action::Off, ..Policy::default() };
+        assert!(policy.check("abc", "ab\u{FFFD}").is_empty());
+    }
+}
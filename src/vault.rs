@@ -0,0 +1,218 @@
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use reprompt::config::Encrypt;
+use reprompt::files::write_private;
+use reprompt::paths;
+use reprompt::process::run_with_timeout;
+
+/// How long `age`, `age-keygen` or a keychain tool may take without a prompt
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Keychain service and account the secret key is stored under
+const SERVICE: &str = "reprompt";
+const ACCOUNT: &str = "backup-key";
+
+/// Public half of the backup key, which backups are encrypted to. Writing a
+/// backup never needs the secret half.
+fn recipient_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join("backup-key.pub"))
+}
+
+/// Secret half with `encrypt = "passphrase"`, itself locked with `age -p`.
+fn identity_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join("backup-key.age"))
+}
+
+/// Stdout of `program args` fed `input`, failing with its stderr.
+fn run(program: &str, args: &[&str], input: &[u8]) -> Result<String> {
+    let output = run_with_timeout(Command::new(program).args(args), input, TIMEOUT)
+        .with_context(|| format!("Failed to run {}; is it installed?", program))?;
+    if !output.status.success() {
+        bail!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("{} wrote invalid UTF-8", program))
+}
+
+/// Like `run`, but with the terminal left to `program` for a passphrase
+/// prompt and no time limit.
+fn run_interactive(program: &str, args: &[&str], input: &[u8]) -> Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run {}; is it installed?", program))?;
+    child.stdin.take().context("No stdin")?.write_all(input)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("{} failed", program);
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("{} wrote invalid UTF-8", program))
+}
+
+/// Encrypts `text` to the backup key as ASCII-armored age.
+pub fn encrypt(text: &str) -> Result<String> {
+    let path = recipient_path()?;
+    let recipient = std::fs::read_to_string(&path)
+        .with_context(|| format!("No backup key at {}; run `reprompt history key`", path.display()))?;
+    run("age", &["--armor", "--recipient", recipient.trim()], text.as_bytes())
+}
+
+/// Decrypts the backup at `path` with the secret key from the keychain, or
+/// from the passphrase-locked key file when there is one.
+pub fn decrypt(path: &Path) -> Result<String> {
+    let file = path.to_string_lossy();
+    let identity = identity_path()?;
+    if identity.exists() {
+        run_interactive("age", &["--decrypt", "--identity", &identity.to_string_lossy(), &file], b"")
+    } else {
+        let secret = keychain::load().context("Failed to get the backup key from the keychain")?;
+        run("age", &["--decrypt", "--identity", "-", &file], secret.as_bytes())
+    }
+}
+
+/// `reprompt history key`: makes a new age key for `encrypt`, keeping its
+/// secret half in the keychain or locked with a passphrase. Refuses to
+/// replace an existing key unless `force`, since older backups need it.
+pub fn create_key(encrypt: Encrypt, force: bool) -> Result<()> {
+    if encrypt == Encrypt::Off {
+        bail!("Set [backup] encrypt = \"keychain\" or \"passphrase\" first");
+    }
+    let path = recipient_path()?;
+    if path.exists() && !force {
+        bail!("A backup key already exists; --force replaces it, and backups made with it can't be read afterwards");
+    }
+    let generated = run("age-keygen", &[], b"")?;
+    let recipient = generated
+        .lines()
+        .find_map(|line| line.strip_prefix("# public key: "))
+        .context("age-keygen printed no public key")?
+        .to_string();
+    let secret = generated
+        .lines()
+        .find(|line| line.starts_with("AGE-SECRET-KEY-"))
+        .context("age-keygen printed no secret key")?;
+
+    let identity = identity_path()?;
+    if encrypt == Encrypt::Keychain {
+        keychain::store(secret)?;
+        if identity.exists() {
+            std::fs::remove_file(&identity)?;
+        }
+    } else {
+        eprintln!("Choose the passphrase that will unlock backups:");
+        let locked = run_interactive("age", &["--passphrase", "--armor"], generated.as_bytes())?;
+        write_private(&identity, &locked)?;
+    }
+    write_private(&path, &format!("{}\n", recipient))?;
+    tracing::info!("Created backup key {}; new backups are encrypted to it", recipient);
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod keychain {
+    use super::{run, ACCOUNT, SERVICE};
+    use anyhow::{bail, Result};
+
+    /// Secret Service (GNOME Keyring, KWallet) through `secret-tool`.
+    pub fn store(secret: &str) -> Result<()> {
+        let label = "--label=reprompt backup key";
+        run("secret-tool", &["store", label, "service", SERVICE, "account", ACCOUNT], secret.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load() -> Result<String> {
+        let secret = run("secret-tool", &["lookup", "service", SERVICE, "account", ACCOUNT], b"")?;
+        if secret.trim().is_empty() {
+            bail!("No backup key in the keychain; run `reprompt history key`");
+        }
+        Ok(secret.trim().to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use super::{run, ACCOUNT, SERVICE};
+    use anyhow::{bail, ensure, Result};
+
+    /// The login keychain through `security`. The command goes to `security -i`
+    /// on stdin, so the secret never appears in an argument list `ps` can read.
+    pub fn store(secret: &str) -> Result<()> {
+        ensure!(
+            !secret.contains(['"', '\\', '\n', '\r']),
+            "The backup key can't be quoted for `security`"
+        );
+        let command = format!("add-generic-password -U -s {} -a {} -w \"{}\"\n", SERVICE, ACCOUNT, secret);
+        run("security", &["-i"], command.as_bytes())?;
+        // `security -i` exits 0 even when a command in it fails
+        if load().ok().as_deref() != Some(secret) {
+            bail!("Failed to store the backup key in the login keychain");
+        }
+        Ok(())
+    }
+
+    pub fn load() -> Result<String> {
+        Ok(run("security", &["find-generic-password", "-s", SERVICE, "-a", ACCOUNT, "-w"], b"")?.trim().to_string())
+    }
+}
+
+#[cfg(windows)]
+mod keychain {
+    use super::{ACCOUNT, SERVICE};
+    use anyhow::{bail, Result};
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    fn target() -> Vec<u16> {
+        format!("{}/{}", SERVICE, ACCOUNT).encode_utf16().chain([0]).collect()
+    }
+
+    /// Windows Credential Manager.
+    pub fn store(secret: &str) -> Result<()> {
+        let mut target = target();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            CredentialBlobSize: secret.len() as u32,
+            CredentialBlob: secret.as_ptr() as *mut u8,
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            ..Default::default()
+        };
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            bail!("CredWriteW failed: {}", std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn load() -> Result<String> {
+        let target = target();
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+            bail!("No backup key in Credential Manager ({}); run `reprompt history key`", std::io::Error::last_os_error());
+        }
+        unsafe {
+            let blob = std::slice::from_raw_parts((*credential).CredentialBlob, (*credential).CredentialBlobSize as usize);
+            let secret = String::from_utf8_lossy(blob).into_owned();
+            CredFree(credential as *const _);
+            Ok(secret)
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod keychain {
+    use anyhow::{bail, Result};
+
+    pub fn store(_secret: &str) -> Result<()> {
+        bail!("No keychain on this platform; use encrypt = \"passphrase\"")
+    }
+
+    pub fn load() -> Result<String> {
+        bail!("No keychain on this platform; use encrypt = \"passphrase\"")
+    }
+}
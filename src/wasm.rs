@@ -0,0 +1,66 @@
+//! JavaScript interface for the WASM build, e.g. a browser paste helper. Build
+//! with `--no-default-features --features wasm` for `wasm32-unknown-unknown`.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::cleaner::Cleaner;
+use crate::config::Config;
+use crate::error::Result;
+use crate::pipeline::StageKind;
+
+/// The `options` object of `clean`; every field may be left out.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Options {
+    /// Profile name, or detect it from the text
+    profile: Option<String>,
+    /// Stage order, like `--stages`
+    stages: Option<Vec<StageKind>>,
+    conservative: bool,
+    no_substitutions: bool,
+    /// Contents of a `config.toml`, since there is no file system to load it from
+    config: Option<String>,
+}
+
+fn clean_with(text: &str, options: Options) -> Result<String> {
+    let config = match &options.config {
+        Some(config) => Config::parse(config)?,
+        None => Config::default(),
+    };
+    let mut cleaner =
+        Cleaner::new(config, options.profile.as_deref(), options.stages.as_deref(), options.no_substitutions)?;
+    if options.conservative {
+        cleaner.set_conservative();
+    }
+    Ok(cleaner.clean_checked(text, None)?.output)
+}
+
+/// Cleans `text` and returns the result. `options` may be `undefined` or an object
+/// with `profile`, `stages`, `conservative`, `noSubstitutions` and `config`.
+#[wasm_bindgen]
+pub fn clean(text: &str, options: JsValue) -> Result<String, JsError> {
+    let options = if options.is_undefined() || options.is_null() {
+        Options::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)?
+    };
+    clean_with(text, options).map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        let options = Options { profile: Some("generic".to_string()), ..Default::default() };
+        assert_eq!(clean_with("│ hello │", options).unwrap(), "hello");
+
+        let options = Options { config: Some("stages = [\"ansi\"]".to_string()), ..Default::default() };
+        assert_eq!(clean_with("\x1b[1mhi\x1b[0m", options).unwrap(), "hi");
+
+        let options = Options { config: Some("nonsense = 1".to_string()), ..Default::default() };
+        assert!(clean_with("hi", options).is_err());
+    }
+}
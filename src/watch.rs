@@ -0,0 +1,616 @@
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Lines};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use reprompt::clipboard::{get_clipboard, is_wsl_custom};
+use reprompt::config::WatchScope;
+use reprompt::lock::{self, InstanceLock};
+use reprompt::report::Report;
+use crate::focus;
+use crate::stats::Outcome;
+use crate::reload::Reloader;
+use crate::Reporting;
+
+/// How many of our own writes are remembered for loop prevention
+const RECENT_WRITES: usize = 16;
+
+/// Hashes of the last few texts reprompt wrote to the clipboard. Every write
+/// triggers a change event, so without this the daemon would re-read (and
+/// potentially re-clean) its own output.
+pub struct RecentWrites {
+    hashes: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl RecentWrites {
+    pub fn new(capacity: usize) -> Self {
+        Self { hashes: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Remembers a text we are about to own, evicting the oldest entry when full.
+    pub fn record(&mut self, text: &str) {
+        if self.hashes.len() == self.capacity {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back(write_hash(text));
+    }
+
+    /// Checks whether the clipboard currently holds one of our own writes.
+    pub fn contains(&self, text: &str) -> bool {
+        self.hashes.contains(&write_hash(text))
+    }
+}
+
+/// Backends may add or strip trailing newlines on readback, and hand back LF
+/// for CRLF (WSL), so ignore both.
+fn write_hash(text: &str) -> u64 {
+    content_hash(text.replace("\r\n", "\n").trim_end())
+}
+
+/// Whether the clipboard reading `current` still holds the `written` text,
+/// by the same rules as [`RecentWrites::contains`].
+#[cfg(unix)]
+pub fn same_write(current: &str, written: &str) -> bool {
+    write_hash(current) == write_hash(written)
+}
+
+/// Keeps the watcher from fighting an app that rewrites the clipboard in a
+/// loop: past `max_per_minute` cleans, and for `cooldown_secs` after a clean
+/// fails validation, changes are left alone.
+struct Throttle {
+    /// When each clean in the last minute that wrote, or tried to write, the clipboard ran
+    cleans: VecDeque<Instant>,
+    paused_until: Option<Instant>,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Self { cleans: VecDeque::new(), paused_until: None }
+    }
+
+    /// Whether a change seen at `now` may be cleaned. Logs once when a pause starts.
+    fn allows(&mut self, scope: &WatchScope, now: Instant) -> bool {
+        if self.paused_until.is_some_and(|until| now < until) {
+            return false;
+        }
+        self.paused_until = None;
+        while self.cleans.front().is_some_and(|&at| now.duration_since(at) >= Duration::from_secs(60)) {
+            self.cleans.pop_front();
+        }
+        if scope.max_per_minute > 0 && self.cleans.len() >= scope.max_per_minute {
+            let until = self.cleans[0] + Duration::from_secs(60);
+            tracing::warn!(
+                "{} cleans in the last minute ([watch] max_per_minute = {}); is an app rewriting the clipboard? Pausing for {}s",
+                self.cleans.len(),
+                scope.max_per_minute,
+                until.duration_since(now).as_secs().max(1)
+            );
+            self.paused_until = Some(until);
+            return false;
+        }
+        true
+    }
+
+    /// Counts a clean at `now` toward `max_per_minute` if it wrote, or tried to
+    /// write, the clipboard: unchanged and held-back copies don't feed a loop.
+    /// Pauses for the cooldown if it changed the text but couldn't commit it.
+    fn record(&mut self, scope: &WatchScope, report: &Report, outcome: Outcome, now: Instant) {
+        if matches!(outcome, Outcome::Cleaned | Outcome::CommitFailed) {
+            self.cleans.push_back(now);
+        }
+        if report.changed && !report.committed && scope.cooldown_secs > 0 {
+            tracing::warn!("Leaving the clipboard alone for {}s after a failed clean ([watch] cooldown_secs)", scope.cooldown_secs);
+            self.paused_until = Some(now + Duration::from_secs(scope.cooldown_secs));
+        }
+    }
+}
+
+/// A source of "the clipboard has changed" notifications.
+/// Events may be spurious; the cleaning cycle is a no-op on already-clean text.
+trait ChangeSource {
+    /// Blocks until the clipboard changes.
+    fn wait(&mut self) -> Result<()>;
+
+    /// Short description shown when the watcher starts.
+    fn name(&self) -> &'static str;
+}
+
+/// Runs the watch daemon until killed, cleaning each new clipboard copy with
+/// the cleaner for the config as it is at that moment.
+pub fn run(interval: Duration, reloader: &mut Reloader, reporting: Reporting) -> Result<()> {
+    // Only one watcher per user: two daemons would ping-pong writes forever
+    let _daemon = match InstanceLock::try_acquire("watch")? {
+        Some(lock) => lock,
+        None => {
+            let holder = lock::holder_pid("watch")
+                .map(|pid| format!(" (pid {})", pid))
+                .unwrap_or_default();
+            anyhow::bail!("Another reprompt watcher is already running{}", holder);
+        }
+    };
+
+    let mut source = change_source(interval);
+    let mut recent = RecentWrites::new(RECENT_WRITES);
+    tracing::info!(
+        "Watching clipboard ({}; stages: {})...",
+        source.name(),
+        reloader.current().default_pipeline().stage_names().join(" → ")
+    );
+
+    let mut focus_unknown = false;
+    let mut throttle = Throttle::new();
+    loop {
+        if let Err(e) = source.wait() {
+            tracing::warn!("{} failed: {}. Falling back to polling every {}ms.", source.name(), e, interval.as_millis());
+            source = Box::new(Polling::new(interval));
+            continue;
+        }
+
+        if crate::pause::is_paused() {
+            tracing::debug!("Skipping change: paused by `reprompt pause`");
+            continue;
+        }
+
+        // Look the focus up first, while it's still the window the copy came from
+        let cleaner = reloader.current();
+        let scope = &cleaner.config().watch;
+        if scope.is_set() {
+            match focus::focused() {
+                Some(focus) if !scope.allows(&focus.app, &focus.title) => {
+                    tracing::debug!("Skipping copy from {} ({:?}): outside [watch] scope", focus.app, focus.title);
+                    continue;
+                }
+                Some(_) => {}
+                // A copy from an unknown window might be one `only`/`skip` excludes
+                None => {
+                    if !focus_unknown {
+                        tracing::warn!("Can't tell which window is focused; leaving copies alone while [watch] only/skip is set");
+                        focus_unknown = true;
+                    }
+                    tracing::debug!("Skipping copy: focused window unknown");
+                    continue;
+                }
+            }
+        }
+
+        if !throttle.allows(scope, Instant::now()) {
+            tracing::debug!("Skipping change: paused");
+            continue;
+        }
+        match InstanceLock::acquire_timeout("clipboard", crate::LOCK_TIMEOUT) {
+            Ok(_lock) => {
+                if let Some((report, outcome)) = crate::clean_clipboard(cleaner, Some(&mut recent), reporting)? {
+                    throttle.record(scope, &report, outcome, Instant::now());
+                }
+            }
+            Err(e) => tracing::warn!("Skipping change: {}", e),
+        }
+    }
+}
+
+/// Picks the cheapest change notification mechanism for this environment,
+/// falling back to polling where nothing native is available.
+fn change_source(interval: Duration) -> Box<dyn ChangeSource> {
+    if is_wsl_custom() {
+        match SequenceWatch::spawn() {
+            Ok(source) => return Box::new(source),
+            Err(e) => tracing::warn!("Windows clipboard listener unavailable: {}", e),
+        }
+        return Box::new(Polling::new(interval));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match WlPasteWatch::spawn() {
+                Ok(source) => return Box::new(source),
+                Err(e) => tracing::warn!("wl-paste --watch unavailable: {}", e),
+            }
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            match x11::XFixesWatch::connect() {
+                Ok(source) => return Box::new(source),
+                Err(e) => tracing::warn!("XFixes selection events unavailable: {}", e),
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    match windows::ClipboardListener::new() {
+        Ok(source) => return Box::new(source),
+        Err(e) => tracing::warn!("AddClipboardFormatListener failed: {}", e),
+    }
+
+    #[cfg(target_os = "macos")]
+    return Box::new(macos::ChangeCount::new());
+
+    #[allow(unreachable_code)]
+    Box::new(Polling::new(interval))
+}
+
+fn content_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Last resort: re-read the clipboard on a timer and compare hashes.
+struct Polling {
+    interval: Duration,
+    last: Option<u64>,
+}
+
+impl Polling {
+    fn new(interval: Duration) -> Self {
+        // Seed with the current content so pre-existing text isn't treated as a copy
+        let last = get_clipboard().ok().map(|text| content_hash(&text));
+        Self { interval, last }
+    }
+}
+
+impl ChangeSource for Polling {
+    fn wait(&mut self) -> Result<()> {
+        loop {
+            std::thread::sleep(self.interval);
+            let Ok(text) = get_clipboard() else { continue };
+            let hash = content_hash(&text);
+            if self.last.replace(hash) != Some(hash) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "polling"
+    }
+}
+
+/// Reads one line per change event from a long-lived helper process.
+fn next_event(lines: &mut Lines<BufReader<ChildStdout>>, helper: &str) -> Result<()> {
+    match lines.next() {
+        Some(line) => {
+            line.with_context(|| format!("Failed to read from {}", helper))?;
+            Ok(())
+        }
+        None => anyhow::bail!("{} exited", helper),
+    }
+}
+
+/// WSL: a single long-lived PowerShell process watches the Windows clipboard
+/// sequence number and prints a line whenever it changes. This avoids spawning
+/// PowerShell (and reading the whole clipboard) on every tick.
+struct SequenceWatch {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl SequenceWatch {
+    const SCRIPT: &'static str = "Add-Type -Namespace Reprompt -Name User32 -MemberDefinition '[DllImport(\"user32.dll\")] public static extern uint GetClipboardSequenceNumber();'; \
+        $last = [Reprompt.User32]::GetClipboardSequenceNumber(); \
+        while ($true) { Start-Sleep -Milliseconds 200; $n = [Reprompt.User32]::GetClipboardSequenceNumber(); \
+        if ($n -ne $last) { $last = $n; [Console]::Out.WriteLine('changed'); [Console]::Out.Flush() } }";
+
+    fn spawn() -> Result<Self> {
+        let mut child = Command::new("powershell.exe")
+            .args(["-NoProfile", "-Command", Self::SCRIPT])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start powershell.exe")?;
+        let stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open stdout for PowerShell"))?;
+        Ok(Self { child, lines: BufReader::new(stdout).lines() })
+    }
+}
+
+impl ChangeSource for SequenceWatch {
+    fn wait(&mut self) -> Result<()> {
+        next_event(&mut self.lines, "PowerShell clipboard listener")
+    }
+
+    fn name(&self) -> &'static str {
+        "Windows clipboard sequence via PowerShell"
+    }
+}
+
+impl Drop for SequenceWatch {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Wayland: `wl-paste --watch` uses wlr-data-control and runs a command on every
+/// selection change; `echo` turns each change into one line on its stdout.
+#[cfg(all(unix, not(target_os = "macos")))]
+struct WlPasteWatch {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl WlPasteWatch {
+    fn spawn() -> Result<Self> {
+        let mut child = Command::new("wl-paste")
+            .args(["--watch", "echo"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start wl-paste (is wl-clipboard installed?)")?;
+        let stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open stdout for wl-paste"))?;
+        let mut lines = BufReader::new(stdout).lines();
+
+        // The compositor reports the current selection right after binding;
+        // swallow that so pre-existing content isn't treated as a new copy
+        next_event(&mut lines, "wl-paste")?;
+
+        Ok(Self { child, lines })
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl ChangeSource for WlPasteWatch {
+    fn wait(&mut self) -> Result<()> {
+        next_event(&mut self.lines, "wl-paste")
+    }
+
+    fn name(&self) -> &'static str {
+        "wlr-data-control via wl-paste"
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Drop for WlPasteWatch {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11 {
+    use super::ChangeSource;
+    use anyhow::Result;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+    use x11rb::protocol::xproto::ConnectionExt as _;
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+
+    /// X11: the XFixes extension delivers an event whenever the CLIPBOARD owner changes.
+    pub struct XFixesWatch {
+        conn: RustConnection,
+    }
+
+    impl XFixesWatch {
+        pub fn connect() -> Result<Self> {
+            let (conn, screen) = x11rb::connect(None)?;
+            conn.xfixes_query_version(5, 0)?.reply()?;
+
+            let root = conn.setup().roots[screen].root;
+            let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+            conn.xfixes_select_selection_input(root, clipboard, SelectionEventMask::SET_SELECTION_OWNER)?;
+            conn.flush()?;
+
+            Ok(Self { conn })
+        }
+    }
+
+    impl ChangeSource for XFixesWatch {
+        fn wait(&mut self) -> Result<()> {
+            loop {
+                if let Event::XfixesSelectionNotify(_) = self.conn.wait_for_event()? {
+                    return Ok(());
+                }
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "X11 XFixes selection events"
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::ChangeSource;
+    use anyhow::Result;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::System::DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener};
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, PostMessageW,
+        RegisterClassW, TranslateMessage, HWND_MESSAGE, MSG, WM_APP, WM_CLIPBOARDUPDATE, WNDCLASSW,
+    };
+
+    /// Posted by the window procedure so the change surfaces from `GetMessageW`
+    const WM_REPROMPT_CHANGED: u32 = WM_APP + 1;
+
+    unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if msg == WM_CLIPBOARDUPDATE {
+            // WM_CLIPBOARDUPDATE is sent, not posted, so GetMessageW never returns it
+            PostMessageW(hwnd, WM_REPROMPT_CHANGED, 0, 0);
+            return 0;
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    /// Windows: a message-only window registered with AddClipboardFormatListener.
+    pub struct ClipboardListener {
+        hwnd: HWND,
+    }
+
+    impl ClipboardListener {
+        pub fn new() -> Result<Self> {
+            let class_name: Vec<u16> = "RepromptClipboardListener\0".encode_utf16().collect();
+            unsafe {
+                let instance = GetModuleHandleW(std::ptr::null());
+                let class = WNDCLASSW {
+                    lpfnWndProc: Some(wndproc),
+                    hInstance: instance,
+                    lpszClassName: class_name.as_ptr(),
+                    ..std::mem::zeroed()
+                };
+                RegisterClassW(&class);
+
+                let hwnd = CreateWindowExW(
+                    0,
+                    class_name.as_ptr(),
+                    std::ptr::null(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    HWND_MESSAGE,
+                    std::ptr::null_mut(),
+                    instance,
+                    std::ptr::null(),
+                );
+                if hwnd.is_null() {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+                if AddClipboardFormatListener(hwnd) == 0 {
+                    let err = std::io::Error::last_os_error();
+                    DestroyWindow(hwnd);
+                    return Err(err.into());
+                }
+                Ok(Self { hwnd })
+            }
+        }
+    }
+
+    impl ChangeSource for ClipboardListener {
+        fn wait(&mut self) -> Result<()> {
+            unsafe {
+                let mut msg: MSG = std::mem::zeroed();
+                loop {
+                    match GetMessageW(&mut msg, self.hwnd, 0, 0) {
+                        -1 => return Err(std::io::Error::last_os_error().into()),
+                        0 => anyhow::bail!("Message loop terminated"),
+                        _ if msg.message == WM_REPROMPT_CHANGED => return Ok(()),
+                        _ => {
+                            TranslateMessage(&msg);
+                            DispatchMessageW(&msg);
+                        }
+                    }
+                }
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "AddClipboardFormatListener"
+        }
+    }
+
+    impl Drop for ClipboardListener {
+        fn drop(&mut self) {
+            unsafe {
+                RemoveClipboardFormatListener(self.hwnd);
+                DestroyWindow(self.hwnd);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::ChangeSource;
+    use anyhow::Result;
+    use objc2_app_kit::NSPasteboard;
+    use std::time::Duration;
+
+    /// NSPasteboard has no change notification, but its changeCount is a cheap
+    /// in-process integer read, so checking it often costs next to nothing.
+    const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// macOS: watch NSPasteboard's changeCount instead of reading contents.
+    pub struct ChangeCount {
+        last: isize,
+    }
+
+    impl ChangeCount {
+        pub fn new() -> Self {
+            Self { last: Self::current() }
+        }
+
+        fn current() -> isize {
+            NSPasteboard::generalPasteboard().changeCount()
+        }
+    }
+
+    impl ChangeSource for ChangeCount {
+        fn wait(&mut self) -> Result<()> {
+            loop {
+                std::thread::sleep(CHECK_INTERVAL);
+                let count = Self::current();
+                if count != self.last {
+                    self.last = count;
+                    return Ok(());
+                }
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "NSPasteboard changeCount"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_distinguishes_text() {
+        assert_eq!(content_hash("abc"), content_hash("abc"));
+        assert_ne!(content_hash("abc"), content_hash("abd"));
+    }
+
+    #[test]
+    fn test_recent_writes_recognizes_own_output() {
+        let mut recent = RecentWrites::new(2);
+        recent.record("first");
+        assert!(recent.contains("first\r\n"), "Trailing newline from readback should not matter");
+        assert!(!recent.contains("something else"));
+        recent.record("one\r\ntwo\r\n");
+        assert!(recent.contains("one\ntwo"), "An LF read of a CRLF write is still ours");
+        #[cfg(unix)]
+        assert!(same_write("one\ntwo", "one\r\ntwo\r\n") && !same_write("copied since", "one\ntwo"));
+
+        recent.record("third");
+        assert!(!recent.contains("first"), "Oldest entry should be evicted");
+        assert!(recent.contains("one\ntwo") && recent.contains("third"));
+    }
+
+    #[test]
+    fn test_throttle_limits_and_cools_down() {
+        let scope = WatchScope { max_per_minute: 2, cooldown_secs: 5, ..WatchScope::default() };
+        let cleaner = reprompt::cleaner::Cleaner::new(reprompt::config::Config::default(), Some("generic"), None, false).unwrap();
+        let report = |changed, committed| Report { changed, committed, ..cleaner.clean("text") };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut throttle = Throttle::new();
+
+        assert!(throttle.allows(&scope, at(0)));
+        throttle.record(&scope, &report(true, true), Outcome::Cleaned, at(0));
+        throttle.record(&scope, &report(false, false), Outcome::Unchanged, at(1));
+        throttle.record(&scope, &report(false, false), Outcome::HeldBack, at(1));
+        assert!(throttle.allows(&scope, at(1)), "Copies left alone don't count toward the limit");
+        throttle.record(&scope, &report(true, true), Outcome::Cleaned, at(1));
+        assert!(!throttle.allows(&scope, at(2)));
+        assert!(!throttle.allows(&scope, at(59)));
+        assert!(throttle.allows(&scope, at(60)));
+
+        throttle.record(&scope, &report(true, false), Outcome::CommitFailed, at(60));
+        assert!(!throttle.allows(&scope, at(64)));
+        assert!(throttle.allows(&scope, at(65)));
+    }
+}
@@ -0,0 +1,27 @@
+use unicode_width::UnicodeWidthChar;
+
+/// True when the text has double-width (CJK) characters. Terminals in CJK
+/// locales usually draw East Asian "ambiguous" characters, box drawing
+/// included, two cells wide as well.
+pub fn has_wide_chars(text: &str) -> bool {
+    text.chars().any(|c| c.width() == Some(2))
+}
+
+/// Terminal cells taken by `c`; with `cjk`, ambiguous-width characters count as wide.
+pub fn char_width(c: char, cjk: bool) -> usize {
+    if cjk { c.width_cjk() } else { c.width() }.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widths() {
+        assert!(has_wide_chars("設定 │ 値"));
+        assert!(!has_wide_chars("─── plain │ ascii"));
+        assert_eq!(char_width('─', false), 1);
+        assert_eq!(char_width('─', true), 2);
+        assert_eq!(char_width('日', false), 2);
+    }
+}